@@ -0,0 +1,76 @@
+//! Compares sequential evaluation with batched, lane-wise and compiled evaluation of the same
+//! circuit. Compiled programs only compute the outputs.
+//!
+//! Run with `cargo bench`.
+use arithmetic_circuit::testing::{random_circuit, CircuitConfig, TestRng};
+use std::hint::black_box;
+use std::time::Instant;
+
+const LANES: usize = 8;
+const BATCHES: usize = 200;
+
+fn main() {
+    let mut rng = TestRng::new(42);
+    let config = CircuitConfig {
+        inputs: 8,
+        depth: 40,
+        width: 16,
+        ..CircuitConfig::default()
+    };
+    let random = random_circuit(&mut rng, &config);
+    let (circuit, inputs) = (random.circuit, random.inputs.len());
+    let sets: Vec<Vec<u64>> = (0..LANES * BATCHES)
+        .map(|_| (0..inputs).map(|_| rng.below(4)).collect())
+        .collect();
+
+    let start = Instant::now();
+    let mut filled = circuit.clone();
+    for set in &sets {
+        filled.fill_nodes(set.clone());
+        black_box(filled.witness());
+    }
+    let sequential = start.elapsed();
+
+    let start = Instant::now();
+    black_box(circuit.fill_many(&sets));
+    let batched = start.elapsed();
+
+    let lanes: Vec<Vec<[u64; LANES]>> = sets
+        .chunks(LANES)
+        .map(|chunk| {
+            (0..inputs)
+                .map(|input| std::array::from_fn(|lane| chunk[lane][input]))
+                .collect()
+        })
+        .collect();
+    let start = Instant::now();
+    for batch in &lanes {
+        black_box(circuit.fill_lanes(batch));
+    }
+    let lane_wise = start.elapsed();
+
+    let compiled = circuit.compile();
+    let start = Instant::now();
+    for set in &sets {
+        black_box(compiled.evaluate(set));
+    }
+    let program = start.elapsed();
+
+    let function = compiled.to_fn();
+    let start = Instant::now();
+    for set in &sets {
+        black_box(function(set));
+    }
+    let closures = start.elapsed();
+
+    println!(
+        "{} evaluations of {} gates",
+        sets.len(),
+        config.depth * config.width
+    );
+    println!("fill_nodes:        {:?}", sequential);
+    println!("fill_many:         {:?}", batched);
+    println!("fill_lanes ({}):    {:?}", LANES, lane_wise);
+    println!("compile:           {:?}", program);
+    println!("compile().to_fn(): {:?}", closures);
+}
@@ -0,0 +1,356 @@
+//! STARK-style execution traces.
+//!
+//! An AIR (algebraic intermediate representation) describes a computation as a table: every row
+//! is the state of the computation at one step and every column is one register of that state.
+//! Here a step is a `Builder` circuit whose inputs are the registers of a row and whose outputs,
+//! marked with `Builder::output`, are the registers of the next row. Applying the circuit over
+//! and over fills the trace.
+//!
+//! ```
+//! use arithmetic_circuit::air::TraceTable;
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! // Fibonacci: (a, b) -> (b, a + b)
+//! let mut step = Builder::new();
+//! let a = step.init();
+//! let b = step.init();
+//! let sum = step.add(a, b.clone());
+//! step.output(b);
+//! step.output(sum);
+//!
+//! let trace = TraceTable::generate(&step, vec![1, 1], 6);
+//! assert_eq!(trace.column(0), vec![1, 1, 2, 3, 5, 8]);
+//! assert_eq!(trace.row(5), &[8, 13]);
+//! ```
+//!
+//! An `Air` states what a valid trace looks like without computing it. Transition constraints are
+//! circuits relating every row `i` to row `i + 1` and boundary constraints fix single cells, such
+//! as the initial state or the claimed result.
+//!
+//! ```
+//! # use arithmetic_circuit::air::TraceTable;
+//! # use arithmetic_circuit::builder::Builder;
+//! # let mut step = Builder::new();
+//! # let a = step.init();
+//! # let b = step.init();
+//! # let sum = step.add(a, b.clone());
+//! # step.output(b);
+//! # step.output(sum);
+//! # let trace = TraceTable::generate(&step, vec![1, 1], 6);
+//! use arithmetic_circuit::air::Air;
+//!
+//! let mut air = Air::new(2);
+//! air.add_step_transition(&step);
+//! air.add_boundary(0, 0, 1);
+//! air.add_boundary(0, 1, 1);
+//! air.add_boundary(5, 1, 13);
+//! assert!(air.is_valid(&trace));
+//! ```
+use crate::Builder;
+use std::fmt::{Display, Formatter};
+use tracing::{debug, info};
+
+/// An execution trace: `num_rows` rows of `width` registers each.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceTable {
+    width: usize,
+    rows: Vec<Vec<u64>>,
+}
+
+impl TraceTable {
+    /// Creates a trace from rows given by the user.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rows do not all have the same length.
+    pub fn from_rows(rows: Vec<Vec<u64>>) -> Self {
+        let width = rows.first().map_or(0, |row| row.len());
+        if rows.iter().any(|row| row.len() != width) {
+            panic!("every row of a trace must have the same number of columns");
+        }
+        TraceTable { width, rows }
+    }
+
+    /// Generates a trace by applying a step circuit repeatedly.
+    ///
+    /// # Arguments
+    ///
+    /// * `step`: A circuit with as many outputs as inputs, mapping a row to the next one.
+    /// * `initial`: The first row.
+    /// * `num_rows`: Total number of rows, including the first one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the step circuit's inputs, outputs and `initial` do not have the same length.
+    pub fn generate(step: &Builder, initial: Vec<u64>, num_rows: usize) -> Self {
+        if step.inputs.len() != step.outputs.len() || step.inputs.len() != initial.len() {
+            panic!("a step circuit needs one input and one output per column of the trace");
+        }
+        let mut rows = vec![initial];
+        while rows.len() < num_rows {
+            let next = step.evaluate(rows[rows.len() - 1].clone());
+            debug!("trace row {}: {:?}", rows.len(), next);
+            rows.push(next);
+        }
+        rows.truncate(num_rows);
+        TraceTable::from_rows(rows)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn row(&self, row: usize) -> &[u64] {
+        &self.rows[row]
+    }
+
+    pub fn rows(&self) -> &[Vec<u64>] {
+        &self.rows
+    }
+
+    /// Returns the values of a register over every step.
+    pub fn column(&self, column: usize) -> Vec<u64> {
+        self.rows.iter().map(|row| row[column]).collect()
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> u64 {
+        self.rows[row][column]
+    }
+}
+
+impl Display for TraceTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "step")?;
+        for column in 0..self.width {
+            write!(f, "\tc{}", column)?;
+        }
+        for (index, row) in self.rows.iter().enumerate() {
+            write!(f, "\n{}", index)?;
+            for value in row {
+                write!(f, "\t{}", value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fixes the value of a single cell of the trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundaryConstraint {
+    pub row: usize,
+    pub column: usize,
+    pub value: u64,
+}
+
+/// A reason a trace is rejected by an `Air`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AirViolation {
+    /// The trace has a different number of columns than the AIR.
+    Width(usize),
+    /// Transition constraint `constraint` does not hold between `row` and `row + 1`.
+    Transition { constraint: usize, row: usize },
+    /// Boundary constraint `constraint` does not hold or refers to a cell outside the trace.
+    Boundary { constraint: usize },
+}
+
+/// Transition and boundary constraints describing the valid traces of a computation.
+#[derive(Clone)]
+pub struct Air {
+    width: usize,
+    transitions: Vec<Builder>,
+    boundaries: Vec<BoundaryConstraint>,
+}
+
+impl Air {
+    /// Creates an AIR without constraints over traces with `width` columns.
+    pub fn new(width: usize) -> Self {
+        Air {
+            width,
+            transitions: vec![],
+            boundaries: vec![],
+        }
+    }
+
+    /// Adds a transition constraint.
+    ///
+    /// # Arguments
+    ///
+    /// * `transition`: A circuit with `2 * width` inputs: the registers of row `i` followed by the
+    ///   registers of row `i + 1`. It holds if all of its constraints hold.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit does not have `2 * width` inputs.
+    pub fn add_transition(&mut self, transition: Builder) {
+        if transition.inputs.len() != 2 * self.width {
+            panic!("a transition circuit needs an input for every register of two rows");
+        }
+        self.transitions.push(transition);
+    }
+
+    /// Adds the transition constraint "row `i + 1` is the step circuit applied to row `i`".
+    ///
+    /// # Arguments
+    ///
+    /// * `step`: A circuit with `width` inputs and `width` outputs, as used by `TraceTable::generate`.
+    pub fn add_step_transition(&mut self, step: &Builder) {
+        let mut transition = Builder::new();
+        let current = (0..self.width).map(|_| transition.init()).collect();
+        let next: Vec<_> = (0..self.width).map(|_| transition.init()).collect();
+        let outputs = transition.call(step, current);
+        if outputs.len() != self.width {
+            panic!("a step circuit needs one output per column of the trace");
+        }
+        outputs
+            .into_iter()
+            .zip(next)
+            .for_each(|(output, next)| transition.assert_equal(output, next));
+        self.add_transition(transition);
+    }
+
+    /// Adds a boundary constraint fixing the cell at `row` and `column` to `value`.
+    pub fn add_boundary(&mut self, row: usize, column: usize, value: u64) {
+        self.boundaries
+            .push(BoundaryConstraint { row, column, value });
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn boundaries(&self) -> &[BoundaryConstraint] {
+        &self.boundaries
+    }
+
+    /// Checks every constraint against a trace.
+    ///
+    /// # Returns
+    ///
+    /// Every violated constraint, empty if the trace is valid. A transition whose gates overflow
+    /// a `u64` on a pair of rows is violated there.
+    pub fn violations(&self, trace: &TraceTable) -> Vec<AirViolation> {
+        if trace.width() != self.width && trace.num_rows() > 0 {
+            return vec![AirViolation::Width(trace.width())];
+        }
+        let mut violations = vec![];
+        for (index, boundary) in self.boundaries.iter().enumerate() {
+            let holds = boundary.column < self.width
+                && trace
+                    .rows()
+                    .get(boundary.row)
+                    .is_some_and(|row| row[boundary.column] == boundary.value);
+            if !holds {
+                violations.push(AirViolation::Boundary { constraint: index });
+            }
+        }
+        for (index, transition) in self.transitions.iter().enumerate() {
+            let mut transition = transition.clone();
+            for (row, pair) in trace.rows().windows(2).enumerate() {
+                // a gate overflowing a u64 cannot be checked, so it violates the constraint
+                let filled = transition
+                    .checked_fill_nodes([pair[0].clone(), pair[1].clone()].concat())
+                    .is_some();
+                if !filled || !transition.check_constraints() {
+                    violations.push(AirViolation::Transition {
+                        constraint: index,
+                        row,
+                    });
+                }
+            }
+        }
+        info!("trace has {} AIR violations", violations.len());
+        violations
+    }
+
+    /// Returns `true` if the trace satisfies every constraint.
+    pub fn is_valid(&self, trace: &TraceTable) -> bool {
+        self.violations(trace).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_counter() {
+        // (i, i^2) -> (i + 1, (i + 1)^2)
+        let mut step = Builder::new();
+        let i = step.init();
+        let _ = step.init();
+        let one = step.constant(1);
+        let next = step.add(i, one);
+        let square = step.mul(next.clone(), next.clone());
+        step.output(next);
+        step.output(square);
+
+        let trace = TraceTable::generate(&step, vec![0, 0], 4);
+        assert_eq!(trace.width(), 2);
+        assert_eq!(trace.num_rows(), 4);
+        assert_eq!(trace.column(1), vec![0, 1, 4, 9]);
+        assert_eq!(
+            trace.to_string(),
+            "step\tc0\tc1\n0\t0\t0\n1\t1\t1\n2\t2\t4\n3\t3\t9"
+        );
+    }
+
+    #[test]
+    fn test_air_violations() {
+        // x_{i+1} = x_i^2, starting at 2
+        let mut transition = Builder::new();
+        let x = transition.init();
+        let next = transition.init();
+        let square = transition.mul(x.clone(), x);
+        transition.assert_equal(square, next);
+        let mut air = Air::new(1);
+        air.add_transition(transition);
+        air.add_boundary(0, 0, 2);
+
+        assert!(air.is_valid(&TraceTable::from_rows(vec![vec![2], vec![4], vec![16]])));
+        let bad = TraceTable::from_rows(vec![vec![3], vec![9], vec![80]]);
+        assert_eq!(
+            air.violations(&bad),
+            vec![
+                AirViolation::Boundary { constraint: 0 },
+                AirViolation::Transition {
+                    constraint: 0,
+                    row: 1
+                }
+            ]
+        );
+        assert_eq!(
+            air.violations(&TraceTable::from_rows(vec![vec![2, 2]])),
+            vec![AirViolation::Width(2)]
+        );
+    }
+
+    #[test]
+    fn test_overflowing_transition_is_a_violation() {
+        let mut transition = Builder::new();
+        let x = transition.init();
+        let next = transition.init();
+        let square = transition.mul(x.clone(), x);
+        transition.assert_equal(square, next);
+        let mut air = Air::new(1);
+        air.add_transition(transition);
+
+        let trace = TraceTable::from_rows(vec![vec![1 << 32], vec![0], vec![0]]);
+        assert_eq!(
+            air.violations(&trace),
+            vec![AirViolation::Transition {
+                constraint: 0,
+                row: 0
+            }]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ragged_rows() {
+        TraceTable::from_rows(vec![vec![1, 2], vec![3]]);
+    }
+}
@@ -0,0 +1,263 @@
+//! Constraint backends.
+//!
+//! A circuit is defined once with the builder, and `Builder::emit` hands its constraints to a
+//! `Backend`, which turns them into one particular representation. Three backends come with the
+//! crate:
+//!
+//! - `ConstraintList`, the builder's own list of constraints, as returned by
+//!   `Builder::constraints`.
+//! - `R1csBackend` in the `r1cs` module, a rank-1 constraint system over the witness vector.
+//! - `PlonkRows`, rows of a PLONK-style gate table with selectors
+//!   `q_L a + q_R b + q_O c + q_M a b + q_C = 0`.
+//!
+//! ```
+//! use arithmetic_circuit::backend::{ConstraintList, PlonkRows};
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//!
+//! let field = Field::new(97);
+//! let mut builder = Builder::new_in_field(field);
+//! let x = builder.init();
+//! let three = builder.constant(3);
+//! let product = builder.mul(x.clone(), three);
+//! builder.assert_equal(product, x);
+//!
+//! let mut list = ConstraintList::default();
+//! builder.emit(&mut list);
+//! assert_eq!(list.constraints, builder.constraints().collect::<Vec<_>>());
+//!
+//! let mut plonk = PlonkRows::new(field);
+//! builder.emit(&mut plonk);
+//! // the multiplication, the equality and the row pinning the constant
+//! assert_eq!(plonk.rows().len(), 3);
+//! assert!(plonk.is_satisfied(&[0, 3, 0]));
+//! assert!(!plonk.is_satisfied(&[1, 3, 3]));
+//! ```
+use crate::enums::{ConstraintView, Constraints, Operation};
+use crate::field::Field;
+use crate::{Builder, NodeId};
+
+/// A target representation of the constraints of a circuit, see `Builder::emit`.
+///
+/// Wires are referred to by node id.
+pub trait Backend {
+    /// Pins wire `out` to a constant value.
+    fn constant(&mut self, out: NodeId, value: u64);
+
+    /// Emits `a + b = out`.
+    fn add_gate(&mut self, a: NodeId, b: NodeId, out: NodeId);
+
+    /// Emits `a * b = out`.
+    fn mul_gate(&mut self, a: NodeId, b: NodeId, out: NodeId);
+
+    /// Emits `a = b`.
+    fn equality(&mut self, a: NodeId, b: NodeId);
+
+    /// Emits that `wire` lies in `0..2^bits`, checked against a range table.
+    ///
+    /// The gates of the table's argument are emitted as well, so backends without native
+    /// lookups ignore this by default.
+    fn lookup(&mut self, _wire: NodeId, _bits: usize) {}
+}
+
+impl Builder {
+    /// Emits the circuit through a backend: every constraint in order of creation, then every
+    /// constant and finally every range table lookup.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend`: The representation to emit into.
+    pub fn emit(&self, backend: &mut impl Backend) {
+        for constraint in &self.constraints {
+            match *constraint {
+                Constraints::Add(a, b, out) => backend.add_gate(a, b, out),
+                Constraints::Mul(a, b, out) => backend.mul_gate(a, b, out),
+                Constraints::Eq(a, b) => backend.equality(a, b),
+            }
+        }
+        for node in &self.full_graph {
+            if let Operation::Constant(value) = node.operation {
+                backend.constant(node.id, value);
+            }
+        }
+        for (wire, bits) in &self.lookups {
+            backend.lookup(*wire, *bits);
+        }
+    }
+}
+
+/// The builder's own representation: a list of constraints.
+///
+/// Constants are not constraints of the builder, they are fixed by the graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConstraintList {
+    pub constraints: Vec<ConstraintView>,
+}
+
+impl Backend for ConstraintList {
+    fn constant(&mut self, _out: NodeId, _value: u64) {}
+
+    fn add_gate(&mut self, a: NodeId, b: NodeId, out: NodeId) {
+        self.constraints.push(ConstraintView::Add { a, b, out });
+    }
+
+    fn mul_gate(&mut self, a: NodeId, b: NodeId, out: NodeId) {
+        self.constraints.push(ConstraintView::Mul { a, b, out });
+    }
+
+    fn equality(&mut self, a: NodeId, b: NodeId) {
+        self.constraints.push(ConstraintView::Eq { a, b });
+    }
+}
+
+/// A row of a PLONK gate table, satisfied when
+/// `q_l a + q_r b + q_o c + q_m a b + q_c = 0` for the values of its wires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlonkRow {
+    pub q_l: u64,
+    pub q_r: u64,
+    pub q_o: u64,
+    pub q_m: u64,
+    pub q_c: u64,
+    pub a: NodeId,
+    pub b: NodeId,
+    pub c: NodeId,
+}
+
+/// A PLONK-style gate table. Rows refer to wires by node id, so the copy constraints between
+/// rows are implicit in the ids rather than a permutation argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlonkRows {
+    field: Field,
+    rows: Vec<PlonkRow>,
+    lookups: Vec<(NodeId, usize)>,
+}
+
+impl PlonkRows {
+    /// Creates an empty table over `field`, which the selectors live in.
+    pub fn new(field: Field) -> Self {
+        PlonkRows {
+            field,
+            rows: vec![],
+            lookups: vec![],
+        }
+    }
+
+    /// Returns the rows in order of emission.
+    pub fn rows(&self) -> &[PlonkRow] {
+        &self.rows
+    }
+
+    /// Returns the lookups into range tables, wire and number of bits.
+    pub fn lookups(&self) -> &[(NodeId, usize)] {
+        &self.lookups
+    }
+
+    /// Checks every row and lookup.
+    ///
+    /// # Arguments
+    ///
+    /// * `values`: The value of every wire, indexed by node id.
+    pub fn is_satisfied(&self, values: &[u64]) -> bool {
+        let field = self.field;
+        let rows = self.rows.iter().all(|row| {
+            let (a, b, c) = (values[row.a], values[row.b], values[row.c]);
+            let sum = [
+                field.mul(row.q_l, a),
+                field.mul(row.q_r, b),
+                field.mul(row.q_o, c),
+                field.mul(row.q_m, field.mul(a, b)),
+                row.q_c,
+            ]
+            .into_iter()
+            .fold(0, |sum, term| field.add(sum, term));
+            sum == 0
+        });
+        rows && self
+            .lookups
+            .iter()
+            .all(|(wire, bits)| u128::from(values[*wire]) < 1 << bits)
+    }
+
+    fn push(&mut self, selectors: [u64; 5], wires: [NodeId; 3]) {
+        let [q_l, q_r, q_o, q_m, q_c] = selectors;
+        let [a, b, c] = wires;
+        self.rows.push(PlonkRow {
+            q_l,
+            q_r,
+            q_o,
+            q_m,
+            q_c,
+            a,
+            b,
+            c,
+        });
+    }
+}
+
+impl Backend for PlonkRows {
+    fn constant(&mut self, out: NodeId, value: u64) {
+        let minus_value = self.field.neg(self.field.reduce(value));
+        self.push([1, 0, 0, 0, minus_value], [out, out, out]);
+    }
+
+    fn add_gate(&mut self, a: NodeId, b: NodeId, out: NodeId) {
+        let minus_one = self.field.neg(1);
+        self.push([1, 1, minus_one, 0, 0], [a, b, out]);
+    }
+
+    fn mul_gate(&mut self, a: NodeId, b: NodeId, out: NodeId) {
+        let minus_one = self.field.neg(1);
+        self.push([0, 0, minus_one, 1, 0], [a, b, out]);
+    }
+
+    fn equality(&mut self, a: NodeId, b: NodeId) {
+        let minus_one = self.field.neg(1);
+        self.push([1, minus_one, 0, 0, 0], [a, b, b]);
+    }
+
+    fn lookup(&mut self, wire: NodeId, bits: usize) {
+        self.lookups.push((wire, bits));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plonk_rows_agree_with_check_constraints() {
+        let field = Field::new(1_000_003);
+        let mut builder = Builder::new_in_field(field);
+        let mut table = builder.range_table(3);
+        let x = builder.init();
+        let y = builder.init();
+        let sum = builder.add(x.clone(), y.clone());
+        let product = builder.mul(sum.clone(), x.clone());
+        let seven = builder.constant(7);
+        builder.assert_equal(product, seven);
+        table.lookup(&sum);
+        let challenge = builder.init();
+        table.finish(&mut builder, &challenge);
+
+        let mut plonk = PlonkRows::new(field);
+        builder.emit(&mut plonk);
+        assert_eq!(plonk.lookups(), &[(sum.id, 3)]);
+        // a sum of 7 / 8 passes the equality but not the range check
+        let outside = field.sub(field.div(7, 8).unwrap(), 8);
+        for inputs in [
+            vec![1, 6, 5],
+            vec![7, 1_000_003 - 6, 5],
+            vec![2, 3, 9],
+            vec![8, outside, 5],
+        ] {
+            builder.fill_nodes(inputs);
+            let values: Vec<u64> = builder
+                .full_graph
+                .iter()
+                .map(|node| node.evaluated().unwrap())
+                .collect();
+            assert_eq!(plonk.is_satisfied(&values), builder.check_constraints());
+        }
+    }
+}
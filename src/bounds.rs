@@ -0,0 +1,149 @@
+//! Interval analysis of the values in a circuit.
+//!
+//! `Builder::analyze_bounds` starts from a range for every input and computes, for every node,
+//! an interval containing all values it can take. Gates whose interval does not fit into a
+//! `u64` can overflow and make `fill_nodes` panic. Over a field, gates whose interval reaches
+//! the prime wrap around, which is often unintended in gadgets emulating integer arithmetic.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let square = builder.mul(x.clone(), x);
+//! let fourth = builder.mul(square.clone(), square.clone());
+//!
+//! // bytes can be squared twice, 32 bit values cannot
+//! assert!(builder.analyze_bounds(&[0..=255]).is_safe());
+//! let report = builder.analyze_bounds(&[0..=u32::MAX as u64]);
+//! assert_eq!(report.overflowing, vec![fourth.id]);
+//! assert_eq!(report.interval(&square).max, (u32::MAX as u64).pow(2));
+//! ```
+use crate::enums::Operation;
+use crate::{Builder, Node};
+use std::ops::RangeInclusive;
+use tracing::info;
+
+/// The smallest and largest value a node can take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub min: u64,
+    pub max: u64,
+}
+
+/// The result of `Builder::analyze_bounds`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundsReport {
+    /// The interval of every node, indexed by id.
+    pub intervals: Vec<Interval>,
+    /// Ids of the gates that can overflow a `u64`, or reach the prime over a field.
+    pub overflowing: Vec<usize>,
+}
+
+impl BoundsReport {
+    /// Returns `true` if no gate can overflow.
+    pub fn is_safe(&self) -> bool {
+        self.overflowing.is_empty()
+    }
+
+    pub fn interval(&self, node: &Node) -> Interval {
+        self.intervals[node.id]
+    }
+}
+
+impl Builder {
+    /// Propagates ranges of the inputs through every gate.
+    ///
+    /// Every node is a sum of products of non-negative values, so it is smallest when every input
+    /// is at the start of its range and largest when every input is at the end, which makes the
+    /// intervals exact. Once a gate
+    /// overflows, its interval is clamped to `u64::MAX`, or widened to the whole field, and the
+    /// intervals depending on it are only upper bounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ranges`: The range of every input in order of creation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of ranges does not match the number of inputs.
+    pub fn analyze_bounds(&self, input_ranges: &[RangeInclusive<u64>]) -> BoundsReport {
+        if input_ranges.len() != self.inputs.len() {
+            panic!(
+                "number of input arguments supplied does not match number of inputs for function"
+            );
+        }
+        let limit = self
+            .field
+            .map_or(u64::MAX as u128, |field| field.modulus() as u128 - 1);
+        let mut intervals: Vec<Interval> = Vec::with_capacity(self.full_graph.len());
+        let mut overflowing = vec![];
+        for node in &self.full_graph {
+            let (min, max) = match node.operation {
+                Operation::Input => {
+                    let position = self.inputs.iter().position(|id| *id == node.id).unwrap();
+                    let range = &input_ranges[position];
+                    (*range.start() as u128, *range.end() as u128)
+                }
+                Operation::Constant(value) => (value as u128, value as u128),
+                Operation::Add(a, b) => (
+                    intervals[a].min as u128 + intervals[b].min as u128,
+                    intervals[a].max as u128 + intervals[b].max as u128,
+                ),
+                Operation::Mul(a, b) => (
+                    intervals[a].min as u128 * intervals[b].min as u128,
+                    intervals[a].max as u128 * intervals[b].max as u128,
+                ),
+                // a hint can take any value, only the constraints using it narrow it down
+                Operation::Hint(_) => (0, limit),
+            };
+            let interval = match max > limit {
+                false => Interval {
+                    min: min as u64,
+                    max: max as u64,
+                },
+                true => {
+                    if matches!(node.operation, Operation::Add(..) | Operation::Mul(..)) {
+                        overflowing.push(node.id);
+                    }
+                    Interval {
+                        min: if self.field.is_some() {
+                            0
+                        } else {
+                            min.min(limit) as u64
+                        },
+                        max: limit as u64,
+                    }
+                }
+            };
+            intervals.push(interval);
+        }
+        info!(overflowing = overflowing.len(), "analyzed bounds");
+        BoundsReport {
+            intervals,
+            overflowing,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_bounds_over_field() {
+        let mut builder = Builder::new_in_field(Field::new(257));
+        let x = builder.init();
+        let y = builder.init();
+        let sum = builder.add(x.clone(), y);
+        let product = builder.mul(x, sum.clone());
+
+        let report = builder.analyze_bounds(&[0..=10, 100..=200]);
+        assert_eq!(report.interval(&sum), Interval { min: 100, max: 210 });
+        assert_eq!(report.overflowing, vec![product.id]);
+        assert_eq!(report.interval(&product), Interval { min: 0, max: 256 });
+
+        assert!(builder.analyze_bounds(&[0..=1, 0..=100]).is_safe());
+    }
+}
@@ -0,0 +1,240 @@
+//! Constraint budgets.
+//!
+//! Proving time grows with the number of constraints, so real circuits are designed against a
+//! budget. `Builder::set_constraint_budget` sets one: as soon as the circuit grows past it, a
+//! warning names the scopes the constraints were spent in, so the gadget to blame is found while
+//! building rather than after. In strict mode `Builder::check_constraint_budget` additionally
+//! turns an exceeded budget into an error, for tests and build scripts that must fail on it.
+//!
+//! A constraint is attributed to the scope of the gate it defines; an equality assertion to the
+//! scope of the later of its two nodes.
+//!
+//! ```
+//! use arithmetic_circuit::budget::BudgetMode;
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! builder.set_constraint_budget(4);
+//! builder.set_budget_mode(BudgetMode::Strict);
+//! let x = builder.init();
+//! let square = builder.scope("square", |b| b.mul(x.clone(), x.clone()));
+//! let _ = builder.scope("powers", |b| {
+//!     (0..4).fold(square.clone(), |acc, _| b.mul(acc, x.clone()))
+//! });
+//!
+//! let error = builder.check_constraint_budget().unwrap_err();
+//! assert_eq!(error.used, 5);
+//! assert_eq!(error.scopes[0], ("powers".to_string(), 4));
+//! ```
+use crate::enums::Constraints;
+use crate::logging::LogLevel;
+use crate::Builder;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use tracing::warn;
+
+/// What happens when a circuit exceeds its constraint budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BudgetMode {
+    /// Log a warning once the budget is exceeded.
+    #[default]
+    Warn,
+    /// Also make `check_constraint_budget` fail.
+    Strict,
+}
+
+/// The budget set with `Builder::set_constraint_budget`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ConstraintBudget {
+    pub(crate) limit: usize,
+    pub(crate) mode: BudgetMode,
+}
+
+/// A circuit uses more constraints than its budget allows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    pub limit: usize,
+    pub used: usize,
+    /// The number of constraints per scope path, largest first.
+    pub scopes: Vec<(String, usize)>,
+}
+
+impl Display for BudgetExceeded {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let scopes: Vec<String> = self
+            .scopes
+            .iter()
+            .map(|(scope, count)| {
+                let scope = if scope.is_empty() { "<root>" } else { scope };
+                format!("{} {}", scope, count)
+            })
+            .collect();
+        write!(
+            f,
+            "{} constraints exceed the budget of {}: {}",
+            self.used,
+            self.limit,
+            scopes.join(", ")
+        )
+    }
+}
+
+impl Error for BudgetExceeded {}
+
+impl Builder {
+    /// Sets the number of constraints the circuit may use, see the `budget` module.
+    ///
+    /// If the circuit already exceeds the budget, the warning is logged right away.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit`: The largest number of constraints that does not trigger a warning.
+    pub fn set_constraint_budget(&mut self, limit: usize) {
+        let mode = self.budget.map(|budget| budget.mode).unwrap_or_default();
+        self.budget = Some(ConstraintBudget { limit, mode });
+        if self.constraints.len() > limit {
+            self.warn_budget_exceeded();
+        }
+    }
+
+    /// Chooses whether an exceeded budget is an error for `check_constraint_budget`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no budget was set.
+    pub fn set_budget_mode(&mut self, mode: BudgetMode) {
+        match &mut self.budget {
+            Some(budget) => budget.mode = mode,
+            None => panic!("no constraint budget set, see Builder::set_constraint_budget"),
+        }
+    }
+
+    /// Removes the constraint budget.
+    pub fn clear_constraint_budget(&mut self) {
+        self.budget = None;
+    }
+
+    /// Returns the number of constraints per scope path, largest first and ties by path.
+    pub fn constraints_by_scope(&self) -> Vec<(String, usize)> {
+        let mut counts = vec![0; self.scopes.len()];
+        for constraint in &self.constraints {
+            counts[self.constraint_scope(constraint)] += 1;
+        }
+        let mut scopes: Vec<(String, usize)> = self
+            .scopes
+            .iter()
+            .cloned()
+            .zip(counts)
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        scopes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scopes
+    }
+
+    /// Returns the index in `scopes` a constraint is attributed to, see the module documentation.
+    pub(crate) fn constraint_scope(&self, constraint: &Constraints) -> usize {
+        let node = match *constraint {
+            Constraints::Add(_, _, out) | Constraints::Mul(_, _, out) => out,
+            Constraints::Eq(a, b) => a.max(b),
+        };
+        // the gate a constraint defines is added to the graph right after the constraint
+        self.node_scopes
+            .get(node)
+            .copied()
+            .unwrap_or(self.current_scope)
+    }
+
+    /// Checks the circuit against its budget.
+    ///
+    /// # Errors
+    ///
+    /// Fails in strict mode if the circuit has more constraints than the budget allows. Without
+    /// a budget, or in warning mode, it always succeeds.
+    pub fn check_constraint_budget(&self) -> Result<(), BudgetExceeded> {
+        match self.budget {
+            Some(ConstraintBudget {
+                limit,
+                mode: BudgetMode::Strict,
+            }) if self.constraints.len() > limit => Err(BudgetExceeded {
+                limit,
+                used: self.constraints.len(),
+                scopes: self.constraints_by_scope(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Warns when the constraint just added is the first one over the budget.
+    pub(crate) fn track_budget(&mut self) {
+        if let Some(budget) = self.budget {
+            if self.constraints.len() == budget.limit + 1 {
+                self.warn_budget_exceeded();
+            }
+        }
+    }
+
+    fn warn_budget_exceeded(&self) {
+        let Some(budget) = self.budget else {
+            return;
+        };
+        let exceeded = BudgetExceeded {
+            limit: budget.limit,
+            used: self.constraints.len(),
+            scopes: self.constraints_by_scope(),
+        };
+        warn!(
+            limit = budget.limit,
+            scope = &self.scopes[self.current_scope],
+            "{}",
+            exceeded
+        );
+        self.log(LogLevel::Info, || exceeded.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::CircuitLogger;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Default)]
+    struct Collect(RefCell<Vec<String>>);
+
+    impl CircuitLogger for Collect {
+        fn log(&self, _level: LogLevel, message: &str) {
+            self.0.borrow_mut().push(message.to_string());
+        }
+    }
+
+    #[test]
+    fn test_warns_once_per_crossing() {
+        let logger = Rc::new(Collect::default());
+        let mut builder = Builder::new_with_logging(logger.clone());
+        builder.set_constraint_budget(1);
+        let x = builder.init();
+        let square = builder.mul(x.clone(), x.clone());
+        let checkpoint = builder.checkpoint();
+        let cube = builder.scope("cube", |b| b.mul(square.clone(), x.clone()));
+        builder.assert_equal(cube, x.clone());
+        let warnings = |logger: &Collect| {
+            logger
+                .0
+                .borrow()
+                .iter()
+                .filter(|message| message.contains("exceed the budget"))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            warnings(&logger),
+            vec!["2 constraints exceed the budget of 1: <root> 1, cube 1"]
+        );
+        assert!(builder.check_constraint_budget().is_ok());
+
+        builder.rollback(checkpoint);
+        let _ = builder.add(square, x);
+        assert_eq!(warnings(&logger).len(), 2);
+    }
+}
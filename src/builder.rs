@@ -1,8 +1,17 @@
-use crate::enums::{Constraints, CustomU64, ExprVal};
-use crate::Node;
-use env_logger;
-use log::{debug, info};
-use std::vec;
+use crate::budget::ConstraintBudget;
+use crate::enums::{ConstraintView, Constraints, CustomU64, Operation};
+use crate::evaluator::Evaluator;
+use crate::field::Field;
+use crate::hint::Hint;
+use crate::hooks::{EvaluationObserver, GateObserver};
+use crate::logging::{CircuitLogger, LogLevel};
+use crate::metrics::Metrics;
+use crate::replay::{LoggedOp, ReplayLog};
+use crate::{Node, NodeId};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use tracing::{debug, info, info_span, trace, trace_span};
 
 /// Builder is used for constructing and managing circuits.
 ///
@@ -10,11 +19,73 @@ use std::vec;
 /// managing constraints (gates created through addition or mulitplication and manually added ones),
 /// and maintaining a reptresentation of the full computaion graph.
 ///
+#[derive(Clone)]
 pub struct Builder {
-    inputs: Vec<Node>,
+    pub(crate) inputs: Vec<usize>,
+    pub(crate) private_inputs: Vec<usize>,
+    pub(crate) outputs: Vec<usize>,
     pub(crate) constraints: Vec<Constraints>,
-    full_graph: Vec<Node>,
+    /// The index in `groups` of the group each constraint was created in, indexed like
+    /// `constraints`.
+    pub(crate) constraint_groups: Vec<Option<usize>>,
+    /// Every constraint group used so far.
+    pub(crate) groups: Vec<String>,
+    /// The index in `groups` of the group new constraints are tagged with.
+    pub(crate) current_group: Option<usize>,
+    /// The names of the groups `check_constraints` skips.
+    pub(crate) disabled_groups: Vec<String>,
+    pub(crate) full_graph: Vec<Node>,
+    /// The hints of the circuit, referred to by `Operation::Hint`.
+    pub(crate) hints: Vec<Hint>,
+    /// The wires checked against a range table and its number of bits, see `RangeTable::finish`.
+    pub(crate) lookups: Vec<(NodeId, usize)>,
+    /// Every scope path used so far, the root scope `""` first.
+    pub(crate) scopes: Vec<String>,
+    /// The index in `scopes` of the scope each node was created in, indexed by node id.
+    pub(crate) node_scopes: Vec<usize>,
+    /// The field the circuit computes in, `None` for plain `u64` arithmetic.
+    pub(crate) field: Option<Field>,
+    /// Whether `assert_equal` is lowered to a subtraction and `assert_zero`.
+    pub(crate) lower_equalities: bool,
+    /// The index in `scopes` of the scope new nodes are created in.
+    pub(crate) current_scope: usize,
+    logger: Option<Rc<dyn CircuitLogger>>,
+    /// Callbacks fired whenever a node gets its value, see `on_evaluate`.
+    pub(crate) evaluation_observers: Vec<Rc<EvaluationObserver>>,
+    /// Callbacks fired whenever a node or an equality assertion is created, see `on_gate`.
+    pub(crate) gate_observers: Vec<Rc<GateObserver>>,
+    metrics: Option<Cell<Metrics>>,
+    /// The inputs of the last evaluation, cleared whenever the graph changes.
+    pub(crate) last_fill: Option<Vec<u64>>,
+    /// The number of constraints the circuit may use, see `set_constraint_budget`.
+    pub(crate) budget: Option<ConstraintBudget>,
+    /// The operations since `record` was called, `None` when not recording.
+    pub(crate) recording: Option<ReplayLog>,
+    /// The size of the circuit this builder was forked from, see `fork`.
+    pub(crate) fork_point: Option<Checkpoint>,
+    /// The names given with `label`, by node id.
+    pub(crate) labels: BTreeMap<NodeId, String>,
 }
+/// The largest number of input combinations `Builder::exhaustive_check` will evaluate.
+pub const EXHAUSTIVE_LIMIT: u64 = 1 << 20;
+
+/// The panic message of a gate whose value overflows a `u64` in a circuit without a field.
+pub(crate) const OVERFLOW: &str = "gate overflows a u64, see Builder::new_in_field";
+
+/// The size of a circuit at some point of its construction, see `Builder::checkpoint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub(crate) nodes: usize,
+    pub(crate) constraints: usize,
+    inputs: usize,
+    private_inputs: usize,
+    pub(crate) outputs: usize,
+    hints: usize,
+    pub(crate) lookups: usize,
+    scope: usize,
+    group: Option<usize>,
+}
+
 impl Default for Builder {
     fn default() -> Self {
         Self::new()
@@ -23,11 +94,160 @@ impl Default for Builder {
 
 impl Builder {
     pub fn new() -> Self {
-        env_logger::try_init().unwrap_or_default();
         Builder {
             inputs: vec![],
+            private_inputs: vec![],
+            outputs: vec![],
             constraints: vec![],
+            constraint_groups: vec![],
+            groups: vec![],
+            current_group: None,
+            disabled_groups: vec![],
             full_graph: vec![],
+            hints: vec![],
+            lookups: vec![],
+            scopes: vec![String::new()],
+            node_scopes: vec![],
+            field: None,
+            lower_equalities: false,
+            current_scope: 0,
+            logger: None,
+            evaluation_observers: vec![],
+            gate_observers: vec![],
+            metrics: None,
+            last_fill: None,
+            budget: None,
+            recording: None,
+            fork_point: None,
+            labels: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a builder that also reports its messages to `logger`.
+    ///
+    /// Events are still emitted through `tracing` as usual. See the `logging` module.
+    ///
+    /// # Arguments
+    ///
+    /// * `logger`: Receives a message for every gate created, every evaluation and every check.
+    pub fn new_with_logging(logger: Rc<dyn CircuitLogger>) -> Self {
+        Builder {
+            logger: Some(logger),
+            ..Self::new()
+        }
+    }
+
+    /// Creates a builder whose nodes live in the prime field `field`.
+    ///
+    /// Constants and inputs are reduced modulo the field's prime and gates compute modulo it,
+    /// so values never overflow. Without a field, the builder computes with plain `u64`s, and
+    /// evaluating a gate whose value does not fit into a `u64` panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// use arithmetic_circuit::field::Field;
+    /// let mut builder = Builder::new_in_field(Field::new(7));
+    /// let x = builder.init();
+    /// let five = builder.constant(5);
+    /// let sum = builder.add(x, five);
+    /// builder.fill_nodes(vec![4]);
+    /// assert_eq!(builder.value(&sum), Some(2));
+    /// assert!(builder.check_constraints());
+    /// ```
+    pub fn new_in_field(field: Field) -> Self {
+        Builder {
+            field: Some(field),
+            ..Self::new()
+        }
+    }
+
+    /// Returns the field the circuit computes in, `None` for plain `u64` arithmetic.
+    pub fn field(&self) -> Option<Field> {
+        self.field
+    }
+
+    /// Returns a builder without any nodes that shares the field, logger, scopes and metrics
+    /// setting of this one, as the target of passes that rebuild a circuit.
+    pub(crate) fn empty_like(&self) -> Builder {
+        Builder {
+            scopes: self.scopes.clone(),
+            field: self.field,
+            lower_equalities: self.lower_equalities,
+            logger: self.logger.clone(),
+            metrics: self.metrics.as_ref().map(|_| Cell::new(Metrics::default())),
+            ..Self::new()
+        }
+    }
+
+    /// Lowers every later `assert_equal(a, b)` to the gates of `a - b` followed by
+    /// `assert_zero`, which then asserts with the gate constraint `(a - b) * 1 = 0`, so that
+    /// every check is a gate constraint and no equality constraints are created.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit does not compute in a field, which subtraction needs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// use arithmetic_circuit::field::Field;
+    /// let mut builder = Builder::new_in_field(Field::new(97));
+    /// builder.lower_equalities(true);
+    /// let x = builder.init();
+    /// let five = builder.constant(5);
+    /// builder.assert_equal(x, five);
+    /// builder.fill_nodes(vec![5]);
+    /// assert!(builder.check_constraints());
+    /// builder.fill_nodes(vec![6]);
+    /// assert!(!builder.check_constraints());
+    /// ```
+    pub fn lower_equalities(&mut self, lower: bool) {
+        if lower && self.field.is_none() {
+            panic!("lowering equalities needs a circuit over a field, see Builder::new_in_field");
+        }
+        self.lower_equalities = lower;
+        self.record_op(|| LoggedOp::LowerEqualities(lower));
+    }
+
+    /// Starts counting the work done by the builder, see the `metrics` module.
+    ///
+    /// Enabling metrics again keeps the current counts.
+    pub fn enable_metrics(&mut self) {
+        if self.metrics.is_none() {
+            self.metrics = Some(Cell::new(Metrics::default()));
+        }
+    }
+
+    /// Returns the counters, or `None` if metrics were never enabled.
+    pub fn metrics(&self) -> Option<Metrics> {
+        self.metrics.as_ref().map(Cell::get)
+    }
+
+    /// Sets every counter back to zero.
+    pub fn reset_metrics(&mut self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.set(Metrics::default());
+        }
+    }
+
+    /// Updates the counters if metrics are enabled.
+    pub(crate) fn count(&self, update: impl FnOnce(&mut Metrics)) {
+        if let Some(metrics) = &self.metrics {
+            let mut current = metrics.get();
+            update(&mut current);
+            metrics.set(current);
+        }
+    }
+
+    /// Sends a message to the injected logger, if there is one.
+    ///
+    /// The message is only formatted when a logger is present.
+    pub(crate) fn log(&self, level: LogLevel, message: impl FnOnce() -> String) {
+        if let Some(logger) = &self.logger {
+            logger.log(level, &message());
         }
     }
 
@@ -43,13 +263,85 @@ impl Builder {
     /// let y = builder.init();
     /// ```
     pub fn init(&mut self) -> Node {
-        let node = Node::init();
-        self.inputs.push(node.clone());
-        self.full_graph.push(node.clone());
-        debug!("Initialized input node: {}", node);
+        self.push_input(false)
+    }
+
+    /// Adds an input node, registered as private if `private` is set.
+    fn push_input(&mut self, private: bool) -> Node {
+        let node = Node::init(self.full_graph.len());
+        self.inputs.push(node.id);
+        if private {
+            self.private_inputs.push(node.id);
+        }
+        self.push_node(node.clone());
+        debug!(gate = node.id, op = "input", "initialized input node");
+        self.log(LogLevel::Debug, || {
+            format!("initialized input {}", self.wire_name(node.id))
+        });
         node
     }
 
+    /// Initializes a private input in the graph.
+    ///
+    /// Private inputs behave exactly like inputs created with `init`, but they are only
+    /// known to the prover. They are not part of the public section of the witness.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut builder = Builder::new();
+    /// let secret = builder.init_private();
+    /// ```
+    pub fn init_private(&mut self) -> Node {
+        self.push_input(true)
+    }
+
+    /// Initializes `n` inputs, see `init`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut builder = Builder::new();
+    /// let row = builder.inputs(3);
+    /// let sum = builder.sum(&row);
+    /// builder.output(sum);
+    /// assert_eq!(builder.evaluate(vec![1, 2, 3]), vec![6]);
+    /// ```
+    pub fn inputs(&mut self, n: usize) -> Vec<Node> {
+        (0..n).map(|_| self.init()).collect()
+    }
+
+    /// Returns `true` if the node is an input created with `init_private`.
+    pub fn is_private(&self, node: &Node) -> bool {
+        self.private_inputs.contains(&node.id)
+    }
+
+    /// Marks a node as an output of the circuit.
+    ///
+    /// Outputs are the values a prover claims to have computed, see `protocol::Prover`.
+    ///
+    /// # Arguments
+    ///
+    /// * `node`: The node to expose as an output.
+    pub fn output(&mut self, node: Node) {
+        self.outputs.push(node.id);
+        self.record_op(|| LoggedOp::Output(node.id));
+        debug!(gate = node.id, "node marked as output");
+        self.log(LogLevel::Debug, || {
+            format!("{} marked as output", self.wire_name(node.id))
+        });
+    }
+
+    /// Returns the nodes marked as outputs, in the order they were marked.
+    pub fn outputs(&self) -> Vec<Node> {
+        self.outputs
+            .iter()
+            .map(|id| self.full_graph[*id].clone())
+            .collect()
+    }
+
     /// Initializes constants in the graph.
     ///
     /// given the function `F(x,y) = x^2 + y^2 + 5`,
@@ -67,12 +359,39 @@ impl Builder {
     /// let five = builder.constant(5);
     /// ```
     pub fn constant(&mut self, value: u64) -> Node {
-        let node = Node::new(value);
-        self.full_graph.push(node.clone());
-        debug!("Initialized node with constant value: {}", node);
+        let value = self.reduce(value);
+        let node = Node::new(self.full_graph.len(), value);
+        self.push_node(node.clone());
+        debug!(
+            gate = node.id,
+            op = "constant",
+            value,
+            "initialized constant node"
+        );
+        self.log(LogLevel::Debug, || {
+            format!(
+                "initialized constant {} = {}",
+                self.wire_name(node.id),
+                value
+            )
+        });
         node
     }
 
+    /// Initializes one constant per value, see `constant`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut builder = Builder::new();
+    /// let weights = builder.constants(&[2, 3, 5]);
+    /// assert_eq!(weights.len(), 3);
+    /// ```
+    pub fn constants(&mut self, values: &[u64]) -> Vec<Node> {
+        values.iter().map(|value| self.constant(*value)).collect()
+    }
+
     /// Adds two nodes in the graph, producing a new node as the result.
     /// There will also be a new constraint added to the graph.
     ///
@@ -88,10 +407,20 @@ impl Builder {
     /// # Returns
     ///
     /// A new node representing the sum of the two input nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if, without a field, both nodes are evaluated and their sum overflows a `u64`.
     pub fn add(&mut self, a: Node, b: Node) -> Node {
-        let node = Node::add(a.clone(), b.clone());
-        self.constraints.push(Constraints::Add(a, b, node.clone()));
-        self.full_graph.push(node.clone());
+        let _span = trace_span!("construct", op = "add").entered();
+        let node = Node::add(
+            self.full_graph.len(),
+            &self.full_graph[a.id],
+            &self.full_graph[b.id],
+            self.field,
+        );
+        self.push_constraint(Constraints::Add(a.id, b.id, node.id));
+        self.push_child(a.id, b.id, node.clone());
         node
     }
 
@@ -107,13 +436,276 @@ impl Builder {
     /// # Returns
     ///
     /// A new node representing the product of the two input nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `add` if the product overflows a `u64`.
     pub fn mul(&mut self, a: Node, b: Node) -> Node {
-        let node = Node::mul(a.clone(), b.clone());
-        self.constraints.push(Constraints::Mul(a, b, node.clone()));
-        self.full_graph.push(node.clone());
+        let _span = trace_span!("construct", op = "mul").entered();
+        let node = Node::mul(
+            self.full_graph.len(),
+            &self.full_graph[a.id],
+            &self.full_graph[b.id],
+            self.field,
+        );
+        self.push_constraint(Constraints::Mul(a.id, b.id, node.id));
+        self.push_child(a.id, b.id, node.clone());
         node
     }
 
+    /// Adds the literal `value` to a node, creating the constant node for it.
+    ///
+    /// The gate is an ordinary addition whose second operand is a constant, so it can be
+    /// recognised and folded into a linear combination later.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut builder = Builder::new();
+    /// let x = builder.init();
+    /// let tripled = builder.mul_const(x, 3);
+    /// let result = builder.add_const(tripled, 5);
+    /// builder.output(result);
+    /// assert_eq!(builder.evaluate(vec![4]), vec![17]);
+    /// ```
+    pub fn add_const(&mut self, a: Node, value: u64) -> Node {
+        let constant = self.constant(value);
+        self.add(a, constant)
+    }
+
+    /// Multiplies a node by the literal `value`, creating the constant node for it.
+    ///
+    /// See `add_const`.
+    pub fn mul_const(&mut self, a: Node, value: u64) -> Node {
+        let constant = self.constant(value);
+        self.mul(a, constant)
+    }
+
+    /// Adds a constraint in the current group.
+    fn push_constraint(&mut self, constraint: Constraints) {
+        self.constraints.push(constraint);
+        self.constraint_groups.push(self.current_group);
+        self.track_budget();
+    }
+
+    /// Records that `wire` is checked against a range table of `bits` bits.
+    pub(crate) fn push_lookup(&mut self, wire: NodeId, bits: usize) {
+        self.lookups.push((wire, bits));
+        self.record_op(|| LoggedOp::Lookup { wire, bits });
+    }
+
+    /// Registers `node` as a child of both of its operands and adds it to the graph.
+    fn push_child(&mut self, a: usize, b: usize, node: Node) {
+        let id = node.id;
+        self.full_graph[a].children.push(id);
+        if a != b {
+            self.full_graph[b].children.push(id);
+        }
+        self.push_node(node);
+        self.log(LogLevel::Debug, || format!("gate {}", self.gate_text(id)));
+        self.count(|metrics| metrics.gates_created += 1);
+    }
+
+    /// Adds a node to the graph in the current scope.
+    pub(crate) fn push_node(&mut self, node: Node) {
+        self.record_node(&node);
+        let (id, operation) = (node.id, node.operation);
+        self.full_graph.push(node);
+        self.node_scopes.push(self.current_scope);
+        self.last_fill = None;
+        self.notify_node_created(id, operation);
+    }
+
+    /// Enters a named scope. Every node created until the matching `pop_scope` belongs to it.
+    ///
+    /// Scopes nest: entering `round` inside `mimc` gives the scope `mimc/round`. They group the
+    /// nodes of a gadget for reports such as `profile_fill`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name of the scope, appended to the current scope path.
+    pub fn push_scope(&mut self, name: &str) {
+        self.record_op(|| LoggedOp::PushScope(name.to_string()));
+        let path = match self.current_scope {
+            0 => name.to_string(),
+            parent => format!("{}/{}", self.scopes[parent], name),
+        };
+        self.current_scope = match self.scopes.iter().position(|scope| *scope == path) {
+            Some(index) => index,
+            None => {
+                self.scopes.push(path);
+                self.scopes.len() - 1
+            }
+        };
+    }
+
+    /// Leaves the current scope.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no scope was entered.
+    pub fn pop_scope(&mut self) {
+        if self.current_scope == 0 {
+            panic!("pop_scope called without a matching push_scope");
+        }
+        self.record_op(|| LoggedOp::PopScope);
+        let parent = match self.scopes[self.current_scope].rsplit_once('/') {
+            Some((parent, _)) => parent.to_string(),
+            None => String::new(),
+        };
+        self.current_scope = self
+            .scopes
+            .iter()
+            .position(|scope| *scope == parent)
+            .unwrap();
+    }
+
+    /// Runs `build` inside a named scope, see `push_scope`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut builder = Builder::new();
+    /// let x = builder.init();
+    /// let x_squared = builder.scope("square", |b| b.mul(x.clone(), x.clone()));
+    /// assert_eq!(builder.scope_of(&x_squared), "square");
+    /// assert_eq!(builder.scope_of(&x), "");
+    /// ```
+    pub fn scope<T>(&mut self, name: &str, build: impl FnOnce(&mut Builder) -> T) -> T {
+        self.push_scope(name);
+        let result = build(self);
+        self.pop_scope();
+        result
+    }
+
+    /// Returns the path of the scope a node was created in, `""` for the root scope.
+    pub fn scope_of(&self, node: &Node) -> &str {
+        &self.scopes[self.node_scopes[node.id]]
+    }
+
+    /// Returns an independent copy of the builder to build an alternative continuation on.
+    ///
+    /// The copy starts with the circuit built so far and remembers its size, so what each of
+    /// several forks adds to the common prefix can be compared with `stats_since_fork` and
+    /// `report::compare`. Changes to a fork never affect the builder or other forks. The copy
+    /// shares hints, the logger and observers with the builder, keeps recording if the builder
+    /// records, and counts its metrics on its own. A `Builder` owns its graph, so the prefix is
+    /// copied once; `PersistentBuilder` shares it instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// use arithmetic_circuit::report;
+    ///
+    /// let mut base = Builder::new();
+    /// let x = base.init();
+    /// let y = base.init();
+    ///
+    /// // (x + y)^2 as a square or expanded
+    /// let mut product = base.fork();
+    /// let sum = product.add(x.clone(), y.clone());
+    /// let result = product.mul(sum.clone(), sum);
+    /// product.output(result);
+    /// let mut expanded = base.fork();
+    /// let xx = expanded.mul(x.clone(), x.clone());
+    /// let xy = expanded.mul(x.clone(), y.clone());
+    /// let xy2 = expanded.add(xy.clone(), xy);
+    /// let yy = expanded.mul(y.clone(), y);
+    /// let partial = expanded.add(xx, xy2);
+    /// let result = expanded.add(partial, yy);
+    /// expanded.output(result);
+    ///
+    /// assert_eq!(product.evaluate(vec![2, 3]), expanded.evaluate(vec![2, 3]));
+    /// let comparison = report::compare(&product.stats_since_fork(), &expanded.stats_since_fork());
+    /// assert_eq!(comparison.delta("constraints"), Some(4));
+    /// assert_eq!(base.stats().constraints, 0);
+    /// ```
+    pub fn fork(&self) -> Builder {
+        let mut fork = self.clone();
+        fork.fork_point = Some(self.checkpoint());
+        debug!(nodes = self.full_graph.len(), "forked builder");
+        fork
+    }
+
+    /// Returns the size of the circuit when this builder was forked, `None` if it was not.
+    pub fn fork_point(&self) -> Option<Checkpoint> {
+        self.fork_point
+    }
+
+    /// Saves the current size of the circuit so it can be restored with `rollback`.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            nodes: self.full_graph.len(),
+            constraints: self.constraints.len(),
+            inputs: self.inputs.len(),
+            private_inputs: self.private_inputs.len(),
+            outputs: self.outputs.len(),
+            hints: self.hints.len(),
+            lookups: self.lookups.len(),
+            scope: self.current_scope,
+            group: self.current_group,
+        }
+    }
+
+    /// Undoes everything built since `checkpoint` was taken.
+    ///
+    /// Nodes, constraints, inputs and outputs created after the checkpoint are removed and the
+    /// scope that was current at the checkpoint is entered again. Nodes returned by the builder
+    /// after the checkpoint must not be used anymore.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit is smaller than at the checkpoint, e.g. because of an earlier rollback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut builder = Builder::new();
+    /// let x = builder.init();
+    /// let checkpoint = builder.checkpoint();
+    /// let _ = builder.mul(x.clone(), x.clone());
+    /// builder.rollback(checkpoint);
+    /// let _ = builder.add(x.clone(), x);
+    /// builder.fill_nodes(vec![3]);
+    /// assert_eq!(builder.witness(), Some(vec![1, 3, 6]));
+    /// ```
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        if checkpoint.nodes > self.full_graph.len()
+            || checkpoint.constraints > self.constraints.len()
+            || checkpoint.inputs > self.inputs.len()
+            || checkpoint.private_inputs > self.private_inputs.len()
+            || checkpoint.outputs > self.outputs.len()
+            || checkpoint.hints > self.hints.len()
+            || checkpoint.lookups > self.lookups.len()
+        {
+            panic!(
+                "cannot roll back to a checkpoint the circuit has already been rolled back past"
+            );
+        }
+        self.full_graph.truncate(checkpoint.nodes);
+        self.node_scopes.truncate(checkpoint.nodes);
+        self.constraints.truncate(checkpoint.constraints);
+        self.constraint_groups.truncate(checkpoint.constraints);
+        self.inputs.truncate(checkpoint.inputs);
+        self.private_inputs.truncate(checkpoint.private_inputs);
+        self.outputs.truncate(checkpoint.outputs);
+        self.hints.truncate(checkpoint.hints);
+        self.lookups.truncate(checkpoint.lookups);
+        self.labels.retain(|id, _| *id < checkpoint.nodes);
+        self.full_graph
+            .iter_mut()
+            .for_each(|node| node.children.retain(|child| *child < checkpoint.nodes));
+        self.current_scope = checkpoint.scope;
+        self.current_group = checkpoint.group;
+        self.last_fill = None;
+        self.record_op(|| LoggedOp::Rollback(checkpoint));
+        debug!(nodes = checkpoint.nodes, "rolled back to checkpoint");
+    }
+
     /// Creates assertion that two nodes are equal in value.
     ///
     /// This creates a new constraint of equality between two nodes that will be checked when
@@ -125,99 +717,694 @@ impl Builder {
     /// * `b`: The second node.
     ///
     pub fn assert_equal(&mut self, a: Node, b: Node) {
-        self.constraints.push(Constraints::Eq(a.clone(), b.clone()));
-        debug!("equality constraint between {:?} and {:?} added", a, b);
+        if self.lower_equalities {
+            let difference = self.sub(a, b);
+            self.assert_zero(difference);
+        } else {
+            self.push_equality(a, b);
+        }
+    }
+
+    /// Creates assertion that a node is zero.
+    ///
+    /// With `lower_equalities`, the assertion is the gate constraint `a * 1 = 0` rather than an
+    /// equality.
+    ///
+    /// # Arguments
+    ///
+    /// * `a`: The node.
+    pub fn assert_zero(&mut self, a: Node) {
+        if self.lower_equalities {
+            let one = self.constant(1);
+            let zero = self.constant(0);
+            self.push_zero_assertion(a, one, zero);
+        } else {
+            let zero = self.constant(0);
+            self.push_equality(a, zero);
+        }
+    }
+
+    /// Subtracts `b` from `a`, computed as `a + (p - 1) b`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit does not compute in a field.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// use arithmetic_circuit::field::Field;
+    /// let mut builder = Builder::new_in_field(Field::new(97));
+    /// let (a, b) = (builder.init(), builder.init());
+    /// let difference = builder.sub(a, b);
+    /// builder.output(difference);
+    /// assert_eq!(builder.evaluate(vec![3, 5]), vec![95]);
+    /// ```
+    pub fn sub(&mut self, a: Node, b: Node) -> Node {
+        let Some(field) = self.field else {
+            panic!("subtraction needs a circuit over a field, see Builder::new_in_field");
+        };
+        let negated = self.mul_const(b, field.modulus() - 1);
+        self.add(a, negated)
+    }
+
+    pub(crate) fn push_equality(&mut self, a: Node, b: Node) {
+        self.push_constraint(Constraints::Eq(a.id, b.id));
+        self.record_op(|| LoggedOp::AssertEqual(a.id, b.id));
+        self.notify_equality_created(a.id, b.id);
+        debug!(a = a.id, b = b.id, op = "eq", "equality constraint added");
+        self.log(LogLevel::Debug, || {
+            format!(
+                "equality constraint {} == {} added",
+                self.term(a.id),
+                self.term(b.id)
+            )
+        });
+    }
+
+    /// Pushes the constraint `a * one = zero`, which asserts that `a` is zero when `one` and
+    /// `zero` are the constants `1` and `0`.
+    pub(crate) fn push_zero_assertion(&mut self, a: Node, one: Node, zero: Node) {
+        self.push_constraint(Constraints::Mul(a.id, one.id, zero.id));
+        self.record_op(|| LoggedOp::AssertZero(a.id, one.id, zero.id));
+        self.notify_equality_created(a.id, zero.id);
+        debug!(a = a.id, op = "zero", "zero assertion added");
+        self.log(LogLevel::Debug, || {
+            format!("zero assertion {} * 1 = 0 added", self.term(a.id))
+        });
+    }
+
+    /// Returns the node a constraint asserts to be zero, if it is a multiplication constraint
+    /// pushed by `assert_zero` rather than the constraint of a gate.
+    pub(crate) fn zero_assertion(&self, constraint: &Constraints) -> Option<NodeId> {
+        match *constraint {
+            Constraints::Mul(a, b, out)
+                if self.full_graph[out].operation != Operation::Mul(a, b) =>
+            {
+                Some(a)
+            }
+            _ => None,
+        }
+    }
+
+    /// Copies another circuit into this one, wiring its inputs to existing nodes.
+    ///
+    /// Every constant, addition, multiplication and equality assertion of `circuit` is recreated
+    /// in this builder, so the copy generates the same constraints as the original.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit`: The circuit to copy.
+    /// * `inputs`: Nodes of this builder to use as the inputs of `circuit`, in order.
+    ///
+    /// # Returns
+    ///
+    /// The nodes corresponding to the outputs of `circuit`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of nodes does not match the number of inputs of `circuit`, or if the
+    /// gates of `circuit` depend on each other in a cycle.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut square = Builder::new();
+    /// let x = square.init();
+    /// let x_squared = square.mul(x.clone(), x);
+    /// square.output(x_squared);
+    ///
+    /// let mut builder = Builder::new();
+    /// let y = builder.init();
+    /// let y_squared = builder.call(&square, vec![y]).remove(0);
+    /// let y_fourth = builder.call(&square, vec![y_squared]).remove(0);
+    /// builder.fill_nodes(vec![3]);
+    /// assert_eq!(builder.value(&y_fourth), Some(81));
+    /// ```
+    pub fn call(&mut self, circuit: &Builder, inputs: Vec<Node>) -> Vec<Node> {
+        let _span =
+            trace_span!("construct", op = "call", gates = circuit.full_graph.len()).entered();
+        if inputs.len() != circuit.inputs.len() {
+            panic!("number of nodes supplied does not match number of inputs of the circuit");
+        }
+        if let Err(error) = circuit.check_acyclic() {
+            panic!("{}", error);
+        }
+        let mut map = vec![0; circuit.full_graph.len()];
+        circuit
+            .inputs
+            .iter()
+            .zip(&inputs)
+            .for_each(|(id, node)| map[*id] = node.id);
+        for node in &circuit.full_graph {
+            map[node.id] = match node.operation {
+                Operation::Input => map[node.id],
+                Operation::Constant(value) => self.constant(value).id,
+                Operation::Add(a, b) => {
+                    let (a, b) = (self.node(map[a]), self.node(map[b]));
+                    self.add(a, b).id
+                }
+                Operation::Mul(a, b) => {
+                    let (a, b) = (self.node(map[a]), self.node(map[b]));
+                    self.mul(a, b).id
+                }
+                Operation::Hint(index) => {
+                    let hint = &circuit.hints[index];
+                    self.push_hint(Hint {
+                        inputs: hint.inputs.iter().map(|id| map[*id]).collect(),
+                        compute: hint.compute.clone(),
+                    })
+                    .id
+                }
+            };
+        }
+        for constraint in &circuit.constraints {
+            if let Constraints::Eq(a, b) = constraint {
+                self.assert_equal(self.node(map[*a]), self.node(map[*b]));
+            } else if let Some(a) = circuit.zero_assertion(constraint) {
+                self.assert_zero(self.node(map[a]));
+            }
+        }
+        circuit
+            .outputs
+            .iter()
+            .map(|id| self.node(map[*id]))
+            .collect()
+    }
+
+    /// Returns the current state of the node with the given id.
+    pub(crate) fn node(&self, id: usize) -> Node {
+        self.full_graph[id].clone()
     }
 
     /// Evaluates the nodes using the provided inputs.
     ///
     /// This assigns the provided inputs to the input nodes and then evaluates the
-    /// arithmetic expressions represented by child nodes.
+    /// arithmetic expressions represented by the rest of the graph.
     ///
     /// # Arguments
     ///
     /// * `input`: A slice of values meant to be assigned to input nodes. number of inputs supplied
-    /// must equal number of input nodes.
+    ///   must equal number of input nodes.
     ///
     /// # Behavior
     ///
     /// The method does the following:
     /// 1. Assigns values from the `input` slice to the input nodes.
-    /// 2. Iterates through the input nodes and their children and evaluates the children node's
-    /// values or partially evaluates them.
-    /// 3. Lastly, it calls `evaluate_children` on all partially evaluated nodes to ensure the
-    /// graph is evaluated completely.
+    /// 2. Walks the graph in order of node ids. Since a node's operands always come before it,
+    ///    each node can be evaluated directly from the values of its operands.
     ///
     /// This ensures that all nodes in the graph have definite values assigned after the
-    /// function completes.
+    /// function completes. The graph may be filled again with different inputs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of inputs does not match the circuit or, without a field, if a value
+    /// overflows a `u64`.
     pub fn fill_nodes(&mut self, input: Vec<u64>) {
+        self.checked_fill_nodes(input).expect(OVERFLOW);
+    }
+
+    /// Evaluates the nodes like `fill_nodes`, stopping at the first gate that overflows a `u64`.
+    ///
+    /// # Returns
+    ///
+    /// `None` if a gate overflows, leaving the graph partially filled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of inputs does not match the circuit.
+    pub(crate) fn checked_fill_nodes(&mut self, input: Vec<u64>) -> Option<()> {
+        let _span =
+            info_span!("fill", inputs = input.len(), nodes = self.full_graph.len()).entered();
         if input.len() != self.inputs.len() {
             panic!(
                 "number of input arguments supplied does not match number of inputs for function"
             );
         }
-        input.iter().enumerate().for_each(|(index, value)| {
-            self.inputs[index].value = CustomU64::Val(*value);
-            debug!(
-                "input node #{} now has value: {}",
-                index, self.inputs[index]
+        if self.last_fill.as_ref() == Some(&input) {
+            debug!("graph is already filled with these inputs");
+            self.count(|metrics| metrics.cache_hits += 1);
+            return Some(());
+        }
+        self.assign_inputs(&input);
+        for id in 0..self.full_graph.len() {
+            self.checked_evaluate_node(id)?;
+        }
+        self.log(LogLevel::Info, || {
+            format!(
+                "filled {} nodes from inputs {:?}",
+                self.full_graph.len(),
+                input
+            )
+        });
+        self.count(|metrics| metrics.evaluation_passes += 1);
+        self.last_fill = Some(input);
+        Some(())
+    }
+
+    /// Assigns values to the input nodes, the first step of an evaluation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values does not match the number of inputs.
+    pub(crate) fn assign_inputs(&mut self, input: &[u64]) {
+        if input.len() != self.inputs.len() {
+            panic!(
+                "number of input arguments supplied does not match number of inputs for function"
             );
+        }
+        input.iter().enumerate().for_each(|(index, value)| {
+            let value = self.reduce(*value);
+            let node = &mut self.full_graph[self.inputs[index]];
+            node.value = CustomU64::Val(value);
+            debug!(input = index, gate = node.id, value, "input node assigned");
+            let id = node.id;
+            self.notify_evaluated(id, value);
         });
+        self.last_fill = None;
+    }
+
+    /// Evaluates a single node from the values of its operands.
+    pub(crate) fn evaluate_node(&mut self, id: usize) {
+        self.checked_evaluate_node(id).expect(OVERFLOW);
+    }
 
-        let mut partial_evals = vec![];
-        self.inputs.iter().for_each(|node| {
-            if let Some(children_nodes) = node.children.clone() {
-                children_nodes.iter().for_each(|child_node| {
-                    match &child_node.borrow().value {
-                        CustomU64::Expr(ExprVal::Add(Some(c_value))) => {
-                            if let CustomU64::Val(node_value) = node.value {
-                                child_node.borrow_mut().value =
-                                    CustomU64::Val(*c_value + node_value);
-                            }
-                        }
-                        CustomU64::Expr(ExprVal::Add(None)) => {
-                            if let CustomU64::Val(node_value) = node.value {
-                                child_node.borrow_mut().value = CustomU64::Val(node_value);
-                                partial_evals.push(child_node.clone());
-                            }
-                        }
-                        CustomU64::Expr(ExprVal::Mul(Some(c_value))) => {
-                            if let CustomU64::Val(node_val) = node.value {
-                                child_node.borrow_mut().value =
-                                    CustomU64::Val(*c_value * node_val);
-                            }
-                        }
-                        CustomU64::Expr(ExprVal::Mul(None)) => {
-                            if let CustomU64::Val(node_value) = node.value {
-                                child_node.borrow_mut().value = CustomU64::Val(node_value);
-                                partial_evals.push(child_node.clone());
-                            }
-                        }
-                        _ => { panic!("The input variable should have already been populated with a value");}
-                    }
-                })
+    /// Evaluates a single node like `evaluate_node`, returning `None` if the gate overflows.
+    pub(crate) fn checked_evaluate_node(&mut self, id: usize) -> Option<()> {
+        let value = match self.full_graph[id].operation {
+            Operation::Input => return Some(()),
+            Operation::Constant(value) => value,
+            Operation::Add(a, b) => match self.field {
+                Some(field) => field.add(self.evaluated(a), self.evaluated(b)),
+                None => self.evaluated(a).checked_add(self.evaluated(b))?,
+            },
+            Operation::Mul(a, b) => match self.field {
+                Some(field) => field.mul(self.evaluated(a), self.evaluated(b)),
+                None => self.evaluated(a).checked_mul(self.evaluated(b))?,
+            },
+            Operation::Hint(index) => {
+                self.hints[index].evaluate(|id| self.evaluated(id), |value| self.reduce(value))
             }
+        };
+        self.full_graph[id].value = CustomU64::Val(value);
+        trace!(gate = id, value, "node evaluated");
+        self.notify_evaluated(id, value);
+        Some(())
+    }
+
+    /// Evaluates the nodes like `fill_nodes` and explains every step of the evaluation.
+    ///
+    /// # Arguments
+    ///
+    /// * `input`: Values for the input nodes, as for `fill_nodes`.
+    ///
+    /// # Returns
+    ///
+    /// One human-readable line per node, in evaluation order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut builder = Builder::new();
+    /// let x = builder.init();
+    /// let five = builder.constant(5);
+    /// let x_squared = builder.mul(x.clone(), x);
+    /// let _ = builder.add(x_squared, five);
+    /// let steps = builder.fill_nodes_explained(vec![3]);
+    /// assert_eq!(
+    ///     steps,
+    ///     vec![
+    ///         "input x0 = 3",
+    ///         "constant c0 = 5",
+    ///         "gate w2: mul(x0=3, x0=3) = 9",
+    ///         "gate w3: add(w2=9, c0=5) = 14",
+    ///     ]
+    /// );
+    /// ```
+    pub fn fill_nodes_explained(&mut self, input: Vec<u64>) -> Vec<String> {
+        self.fill_nodes(input);
+        let operand = |id: usize| format!("{}={}", self.wire_name(id), self.evaluated(id));
+        self.full_graph
+            .iter()
+            .map(|node| match node.operation {
+                Operation::Input => {
+                    format!("input {} = {}", self.wire_name(node.id), node)
+                }
+                Operation::Constant(value) => {
+                    format!("constant {} = {}", self.wire_name(node.id), value)
+                }
+                Operation::Add(a, b) => {
+                    format!(
+                        "gate {}: add({}, {}) = {}",
+                        self.wire_name(node.id),
+                        operand(a),
+                        operand(b),
+                        node
+                    )
+                }
+                Operation::Mul(a, b) => {
+                    format!(
+                        "gate {}: mul({}, {}) = {}",
+                        self.wire_name(node.id),
+                        operand(a),
+                        operand(b),
+                        node
+                    )
+                }
+                Operation::Hint(index) => {
+                    let operands: Vec<String> = self
+                        .hint_inputs(index)
+                        .iter()
+                        .map(|id| operand(*id))
+                        .collect();
+                    format!(
+                        "hint {}: hint({}) = {}",
+                        self.wire_name(node.id),
+                        operands.join(", "),
+                        node
+                    )
+                }
+            })
+            .collect()
+    }
+
+    /// Maps a value into the circuit's field, if it has one.
+    pub(crate) fn reduce(&self, value: u64) -> u64 {
+        match self.field {
+            Some(field) => field.reduce(value),
+            None => value,
+        }
+    }
+
+    /// Returns the value of an operand during `fill_nodes`.
+    fn evaluated(&self, id: usize) -> u64 {
+        self.full_graph[id]
+            .evaluated()
+            .expect("The input variable should have already been populated with a value")
+    }
+
+    /// Returns the current value of a node in the graph.
+    ///
+    /// # Returns
+    ///
+    /// `Some(value)` if the node has been evaluated, otherwise `None`.
+    pub fn value(&self, node: &Node) -> Option<u64> {
+        self.full_graph[node.id].evaluated()
+    }
+
+    /// Evaluates the circuit on `input` without modifying it.
+    ///
+    /// # Arguments
+    ///
+    /// * `input`: Values for the input nodes, as for `fill_nodes`.
+    ///
+    /// # Returns
+    ///
+    /// The values of the nodes marked with `output`, in order.
+    pub fn evaluate(&self, input: Vec<u64>) -> Vec<u64> {
+        Evaluator::new(self).evaluate(&input).outputs
+    }
+
+    /// Returns the ids of the nodes in the order their values appear in the witness.
+    ///
+    /// Public inputs come first in the order they were initialized, followed by every
+    /// other node of the graph (including private inputs) in order of id. The leading
+    /// constant `1` of the witness is not part of the layout.
+    pub fn witness_layout(&self) -> Vec<usize> {
+        let public = self.public_inputs();
+        let mut layout = public.clone();
+        layout.extend(
+            self.full_graph
+                .iter()
+                .map(|node| node.id)
+                .filter(|id| !public.contains(id)),
+        );
+        layout
+    }
+
+    /// Returns the ids of the public inputs in the order they were initialized.
+    pub(crate) fn public_inputs(&self) -> Vec<usize> {
+        self.inputs
+            .iter()
+            .filter(|id| !self.private_inputs.contains(id))
+            .copied()
+            .collect()
+    }
+
+    /// Returns the full assignment vector of the circuit after `fill_nodes` was called.
+    ///
+    /// The witness is laid out like an R1CS witness: `[1, public inputs…, internal wires…]`,
+    /// following the order given by `witness_layout`.
+    ///
+    /// # Returns
+    ///
+    /// `Some(witness)` if every node has been evaluated, otherwise `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut builder = Builder::new();
+    /// let x = builder.init();
+    /// let five = builder.constant(5);
+    /// let _ = builder.mul(x, five);
+    /// builder.fill_nodes(vec![3]);
+    /// assert_eq!(builder.witness(), Some(vec![1, 3, 5, 15]));
+    /// ```
+    pub fn witness(&self) -> Option<Vec<u64>> {
+        let mut witness = vec![1];
+        for id in self.witness_layout() {
+            witness.push(self.full_graph[id].evaluated()?);
+        }
+        Some(witness)
+    }
+
+    /// Encodes the shape of the circuit (field, operations, constraints, private inputs and
+    /// outputs) as a sequence of words, starting with the modulus or `0` without a field. Values
+    /// assigned by `fill_nodes` are not part of the encoding.
+    pub(crate) fn structure_words(&self) -> Vec<u64> {
+        let mut words = vec![
+            self.field.map_or(0, |field| field.modulus()),
+            self.full_graph.len() as u64,
+        ];
+        self.full_graph
+            .iter()
+            .for_each(|node| words.extend(node.operation.words()));
+        self.hints.iter().for_each(|hint| {
+            words.push(hint.inputs.len() as u64);
+            words.extend(hint.inputs.iter().map(|id| *id as u64));
         });
-        partial_evals.iter_mut().for_each(|node| {
-            node.borrow_mut().evaluate_children();
-        });
+        self.constraints
+            .iter()
+            .for_each(|constraint| words.extend(constraint.words()));
+        words.push(self.private_inputs.len() as u64);
+        words.extend(self.private_inputs.iter().map(|id| *id as u64));
+        words.push(self.outputs.len() as u64);
+        words.extend(self.outputs.iter().map(|id| *id as u64));
+        words
+    }
+
+    /// Returns an iterator over all constraints in the order they were generated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// use arithmetic_circuit::enums::ConstraintView;
+    /// let mut builder = Builder::new();
+    /// let x = builder.init();
+    /// let square = builder.mul(x.clone(), x.clone());
+    /// builder.assert_equal(square.clone(), x.clone());
+    ///
+    /// let constraints: Vec<ConstraintView> = builder.constraints().collect();
+    /// assert_eq!(
+    ///     constraints,
+    ///     vec![
+    ///         ConstraintView::Mul { a: x.id, b: x.id, out: square.id },
+    ///         ConstraintView::Eq { a: square.id, b: x.id },
+    ///     ]
+    /// );
+    /// ```
+    pub fn constraints(&self) -> impl Iterator<Item = ConstraintView> + '_ {
+        self.constraints.iter().map(ConstraintView::from)
     }
 
     /// Checks if all constraints in the circuit hold true.
     ///
     /// Constraints to be checked include those generated from node operations (addition, multiplication)
-    /// and any manually asserted using `assert_equal`.
+    /// and any manually asserted using `assert_equal`. Constraints of disabled groups are
+    /// skipped, and the skipped groups are reported, see `disable_group`.
     ///
     /// # Returns
     ///
     /// Returns `true` if all constraints hold, otherwise `false`.
-    pub fn check_constraints(&mut self) -> bool {
-        self.constraints
-            .iter()
-            .all(|constraint| constraint.is_valid());
+    pub fn check_constraints(&self) -> bool {
+        let _span = info_span!("check_constraints", constraints = self.constraints.len()).entered();
+        let skipped = self.skipped_groups();
+        if !skipped.is_empty() {
+            info!(?skipped, "skipping disabled constraint groups");
+            self.log(LogLevel::Info, || {
+                format!("skipping disabled constraint groups {:?}", skipped)
+            });
+        }
+        let disabled = self.disabled_group_indices();
+        for (index, constraint) in self.constraints.iter().enumerate() {
+            let group = self.constraint_groups.get(index).copied().flatten();
+            if group.is_some_and(|group| disabled[group]) {
+                continue;
+            }
+            self.count(|metrics| metrics.constraints_checked += 1);
+            if !constraint.is_valid(self.field, |id| self.full_graph[id].evaluated()) {
+                info!(constraint = index, ?constraint, "constraint does not hold");
+                self.log(LogLevel::Info, || {
+                    format!(
+                        "constraint {} does not hold: {}",
+                        index,
+                        self.constraint_text(constraint)
+                    )
+                });
+                return false;
+            }
+        }
         info!("all constraints hold true");
+        self.log(LogLevel::Info, || "all constraints hold true".to_string());
+        true
+    }
+
+    /// Checks a witness vector against the structure of the circuit.
+    ///
+    /// Unlike `check_constraints`, none of the values stored in the graph are used: every
+    /// value is taken from `witness`, which must follow the layout described in `witness`.
+    /// Besides the constraints, the leading `1` and the values of all constant nodes are checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `witness`: The assignment vector to check.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the witness satisfies the circuit, otherwise `false`.
+    pub fn check_witness(&self, witness: &[u64]) -> bool {
+        let _span = info_span!("check_witness", constraints = self.constraints.len()).entered();
+        let layout = self.witness_layout();
+        if witness.len() != layout.len() + 1 || witness[0] != 1 {
+            info!("witness does not match the layout of the circuit");
+            return false;
+        }
+        if witness.iter().any(|value| self.reduce(*value) != *value) {
+            info!("witness holds values outside of the field");
+            return false;
+        }
+        let mut values = vec![0; self.full_graph.len()];
+        layout
+            .iter()
+            .zip(&witness[1..])
+            .for_each(|(id, value)| values[*id] = *value);
+
+        for node in &self.full_graph {
+            if let Operation::Constant(constant) = node.operation {
+                if values[node.id] != constant {
+                    info!(gate = node.id, "constant node does not hold its value");
+                    return false;
+                }
+            }
+        }
+        for (index, constraint) in self.constraints.iter().enumerate() {
+            self.count(|metrics| metrics.constraints_checked += 1);
+            if !constraint.is_valid(self.field, |id| Some(values[id])) {
+                info!(
+                    constraint = index,
+                    ?constraint,
+                    "constraint does not hold for the witness"
+                );
+                return false;
+            }
+        }
         true
     }
+
+    /// Evaluates the circuit on every combination of input values and checks its constraints
+    /// each time, proving by brute force that they hold on the whole domain.
+    ///
+    /// Only possible for circuits over a small field, see `new_in_field`. The circuit itself is
+    /// not modified.
+    ///
+    /// # Returns
+    ///
+    /// `Ok` with the number of combinations checked, or `Err` with the first inputs, in order of
+    /// creation, for which a constraint does not hold.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the builder has no field or if there are more than `EXHAUSTIVE_LIMIT`
+    /// combinations of inputs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// use arithmetic_circuit::field::Field;
+    /// // (x + 1)^2 = x^2 + 2x + 1 holds for every x in F_13
+    /// let mut builder = Builder::new_in_field(Field::new(13));
+    /// let x = builder.init();
+    /// let one = builder.constant(1);
+    /// let two = builder.constant(2);
+    /// let x_plus_one = builder.add(x.clone(), one.clone());
+    /// let left = builder.mul(x_plus_one.clone(), x_plus_one);
+    /// let x_squared = builder.mul(x.clone(), x.clone());
+    /// let two_x = builder.mul(two, x);
+    /// let partial = builder.add(x_squared, two_x);
+    /// let right = builder.add(partial, one);
+    /// builder.assert_equal(left, right);
+    /// assert_eq!(builder.exhaustive_check(), Ok(13));
+    /// ```
+    pub fn exhaustive_check(&self) -> Result<u64, Vec<u64>> {
+        let field = self
+            .field
+            .expect("exhaustive checks need a builder over a field");
+        let combinations = (0..self.inputs.len()).try_fold(1u64, |count, _| {
+            count
+                .checked_mul(field.modulus())
+                .filter(|count| *count <= EXHAUSTIVE_LIMIT)
+        });
+        let Some(combinations) = combinations else {
+            panic!(
+                "{} inputs over F_{} exceed the limit of {} combinations",
+                self.inputs.len(),
+                field.modulus(),
+                EXHAUSTIVE_LIMIT
+            );
+        };
+        let _span = info_span!("exhaustive_check", combinations).entered();
+
+        let mut circuit = self.clone();
+        let mut input = vec![0; self.inputs.len()];
+        for _ in 0..combinations {
+            circuit.assign_inputs(&input);
+            (0..circuit.full_graph.len()).for_each(|id| circuit.evaluate_node(id));
+            let holds = circuit.constraints.iter().all(|constraint| {
+                constraint.is_valid(circuit.field, |id| circuit.full_graph[id].evaluated())
+            });
+            if !holds {
+                info!(?input, "constraints do not hold");
+                return Err(input);
+            }
+            // count through all combinations like an odometer in base p
+            for value in input.iter_mut() {
+                *value += 1;
+                if *value < field.modulus() {
+                    break;
+                }
+                *value = 0;
+            }
+        }
+        info!("constraints hold for every input");
+        Ok(combinations)
+    }
 }
 
 #[cfg(test)]
@@ -234,6 +1421,194 @@ mod tests {
         let x_squared_plus_5 = builder.add(x_squared, five);
         let _ = builder.add(x_squared_plus_5, x);
         builder.fill_nodes(vec![5]);
-        assert_eq!(builder.check_constraints(), true);
+        assert!(builder.check_constraints());
+    }
+
+    #[test]
+    fn test_metrics_are_opt_in_and_resettable() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let _ = builder.mul(x.clone(), x.clone());
+        assert_eq!(builder.metrics(), None);
+
+        builder.enable_metrics();
+        let _ = builder.add(x.clone(), x);
+        builder.fill_nodes(vec![1]);
+        builder.fill_nodes(vec![2]);
+        builder.fill_nodes(vec![2]);
+        let metrics = builder.metrics().unwrap();
+        assert_eq!(metrics.gates_created, 1);
+        assert_eq!(metrics.evaluation_passes, 2);
+        assert_eq!(metrics.cache_hits, 1);
+
+        builder.reset_metrics();
+        assert_eq!(builder.metrics(), Some(Metrics::default()));
+    }
+
+    #[test]
+    fn test_rollback_removes_speculative_gates() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let checkpoint = builder.checkpoint();
+        builder.push_scope("speculative");
+        let y = builder.init_private();
+        let product = builder.mul(x.clone(), y);
+        builder.assert_equal(product.clone(), x.clone());
+        builder.output(product);
+        builder.rollback(checkpoint);
+
+        assert_eq!(builder.full_graph.len(), 1);
+        assert!(builder.constraints.is_empty() && builder.outputs.is_empty());
+        assert!(builder.full_graph[0].children.is_empty());
+        let double = builder.add(x.clone(), x);
+        assert_eq!(builder.scope_of(&double), "");
+        builder.fill_nodes(vec![2]);
+        assert!(builder.check_constraints());
+    }
+
+    #[test]
+    fn test_field_mode_wraps_around() {
+        let mut builder = Builder::new_in_field(Field::new(11));
+        let x = builder.init();
+        let big = builder.constant(30);
+        let product = builder.mul(x, big.clone());
+        builder.output(product);
+        assert_eq!(builder.value(&big), Some(8));
+        builder.fill_nodes(vec![u64::MAX]);
+        assert_eq!(
+            builder.outputs()[0].evaluated(),
+            Some((u64::MAX % 11) * 8 % 11)
+        );
+
+        let mut witness = builder.witness().unwrap();
+        assert!(builder.check_witness(&witness));
+        witness[1] += 11;
+        assert!(!builder.check_witness(&witness));
+    }
+
+    #[test]
+    fn test_exhaustive_check_finds_counterexample() {
+        // x * y = x only holds if x = 0 or y = 1
+        let mut builder = Builder::new_in_field(Field::new(5));
+        let x = builder.init();
+        let y = builder.init();
+        let product = builder.mul(x.clone(), y);
+        builder.assert_equal(product, x);
+        assert_eq!(builder.exhaustive_check(), Err(vec![1, 0]));
+    }
+
+    #[test]
+    fn test_failing_equality() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let x_squared = builder.mul(x.clone(), x.clone());
+        let nine = builder.constant(9);
+        builder.assert_equal(x_squared, nine);
+        builder.fill_nodes(vec![3]);
+        assert!(builder.check_constraints());
+        builder.fill_nodes(vec![4]);
+        assert!(!builder.check_constraints());
+    }
+
+    #[test]
+    fn test_witness_layout() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let five = builder.constant(5);
+        let y = builder.init();
+        let sum = builder.add(x.clone(), five);
+        let _ = builder.mul(sum, y);
+        assert_eq!(builder.witness(), None);
+        builder.fill_nodes(vec![2, 3]);
+        assert_eq!(builder.witness_layout(), vec![0, 2, 1, 3, 4]);
+        assert_eq!(builder.witness(), Some(vec![1, 2, 3, 5, 7, 21]));
+    }
+
+    #[test]
+    fn test_private_inputs_in_witness() {
+        let mut builder = Builder::new();
+        let secret = builder.init_private();
+        let x = builder.init();
+        let _ = builder.mul(secret, x);
+        builder.fill_nodes(vec![6, 7]);
+        assert_eq!(builder.witness_layout(), vec![1, 0, 2]);
+        let witness = builder.witness().unwrap();
+        assert_eq!(witness, vec![1, 7, 6, 42]);
+        assert!(builder.check_witness(&witness));
+        assert!(!builder.check_witness(&[1, 7, 6, 43]));
+    }
+
+    #[test]
+    fn test_lowered_equalities_in_r1cs() {
+        let field = Field::new(97);
+        let mut builder = Builder::new_in_field(field);
+        builder.lower_equalities(true);
+        let x = builder.init();
+        let x_squared = builder.mul(x.clone(), x);
+        let nine = builder.constant(9);
+        builder.assert_equal(x_squared, nine);
+        for (input, holds) in [(3, true), (94, true), (4, false)] {
+            builder.fill_nodes(vec![input]);
+            assert_eq!(builder.check_constraints(), holds);
+            let r1cs = crate::r1cs::R1cs::from_builder(&builder);
+            assert_eq!(r1cs.is_satisfied(&builder.witness().unwrap(), field), holds);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "gate overflows a u64")]
+    fn test_overflow_without_field_panics() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let _ = builder.mul(x.clone(), x);
+        builder.fill_nodes(vec![1 << 32]);
+    }
+
+    #[test]
+    #[should_panic(expected = "gate overflows a u64")]
+    fn test_folding_constants_checks_sum_overflow() {
+        let mut builder = Builder::new();
+        let max = builder.constant(u64::MAX);
+        let one = builder.constant(1);
+        let _ = builder.add(max, one);
+    }
+
+    #[test]
+    #[should_panic(expected = "gate overflows a u64")]
+    fn test_folding_constants_checks_product_overflow() {
+        let mut builder = Builder::new();
+        let large = builder.constant(1 << 32);
+        let _ = builder.mul(large.clone(), large);
+    }
+
+    #[test]
+    fn test_lowered_equalities_leave_no_equality_constraints() {
+        let mut builder = Builder::new_in_field(Field::new(97));
+        builder.lower_equalities(true);
+        let x = builder.init();
+        let y = builder.init();
+        let x_squared = builder.mul(x.clone(), x);
+        builder.assert_equal(x_squared, y.clone());
+        builder.assert_zero(y);
+
+        let (optimized, _) = crate::passes::PassManager::default().run(&builder);
+        let circuits = [
+            builder.clone(),
+            optimized,
+            builder.dedup().0,
+            builder.canonicalize(),
+            builder.specialize(&[None, Some(0)]),
+        ];
+        for mut circuit in circuits {
+            assert!(!circuit
+                .constraints
+                .iter()
+                .any(|c| matches!(c, Constraints::Eq(..))));
+            let inputs = circuit.inputs.len();
+            circuit.fill_nodes(vec![0; inputs]);
+            assert!(circuit.check_constraints());
+            circuit.fill_nodes(vec![1; inputs]);
+            assert!(!circuit.check_constraints());
+        }
     }
 }
@@ -0,0 +1,192 @@
+//! Canonical form of circuits and structural isomorphism.
+//!
+//! Two circuits built by code that creates the same gates in a different order, or passes
+//! the operands of additions and multiplications the other way around, compute the same thing
+//! but differ node by node. `Builder::canonicalize` rebuilds a circuit in a normal form that
+//! only depends on its structure: inputs keep their order, every other node is created as soon
+//! as its operands exist, the structurally smallest first, commutative operands are sorted and
+//! equality assertions are sorted after all gates. `Builder::is_isomorphic_to` compares the
+//! canonical forms of two circuits.
+//!
+//! Nodes are ordered by the same structural keys as `Builder::fingerprint`. Identical gates
+//! have identical keys, so circuits should be deduplicated first, see `Builder::dedup`. Hints
+//! are compared by the nodes they read, not by their code.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut first = Builder::new();
+//! let (x, y) = (first.init(), first.init());
+//! let square = first.mul(x.clone(), x.clone());
+//! let sum = first.add(x, y);
+//! let product = first.mul(square, sum);
+//! first.output(product);
+//!
+//! let mut second = Builder::new();
+//! let (x, y) = (second.init(), second.init());
+//! let sum = second.add(y, x.clone());
+//! let square = second.mul(x.clone(), x);
+//! let product = second.mul(sum, square);
+//! second.output(product);
+//!
+//! assert!(first.is_isomorphic_to(&second));
+//! assert_eq!(first.canonicalize().dump_constraints(), second.canonicalize().dump_constraints());
+//! ```
+use crate::enums::{Constraints, Operation};
+use crate::hint::Hint;
+use crate::{Builder, Node};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+impl Builder {
+    /// Rebuilds the circuit in canonical form, see the module documentation.
+    ///
+    /// Nodes returned by this builder do not refer to the rebuilt circuit. The field and the
+    /// logger carry over, scopes and constraint groups do not.
+    pub fn canonicalize(&self) -> Builder {
+        let keys = self.node_keys(true);
+        let mut circuit = self.empty_like();
+        let mut nodes: Vec<Option<Node>> = vec![None; self.full_graph.len()];
+        let mut missing: Vec<usize> = vec![0; self.full_graph.len()];
+        let mut users: Vec<Vec<usize>> = vec![vec![]; self.full_graph.len()];
+        let mut ready = BinaryHeap::new();
+        for node in &self.full_graph {
+            match node.operation {
+                Operation::Input if self.private_inputs.contains(&node.id) => {
+                    nodes[node.id] = Some(circuit.init_private());
+                }
+                Operation::Input => nodes[node.id] = Some(circuit.init()),
+                operation => {
+                    let operands = self.operands(&operation);
+                    missing[node.id] = operands.len();
+                    for operand in operands {
+                        users[operand].push(node.id);
+                    }
+                }
+            }
+        }
+        for node in &self.full_graph {
+            if node.operation != Operation::Input && missing[node.id] == 0 {
+                ready.push(Reverse((keys[node.id], node.id)));
+            }
+        }
+        let mut release = |id: usize, ready: &mut BinaryHeap<_>| {
+            for user in &users[id] {
+                missing[*user] -= 1;
+                if missing[*user] == 0 {
+                    ready.push(Reverse((keys[*user], *user)));
+                }
+            }
+        };
+        for input in &self.inputs {
+            release(*input, &mut ready);
+        }
+
+        while let Some(Reverse((_, id))) = ready.pop() {
+            let operand = |id: usize| nodes[id].clone().unwrap();
+            let new = match self.full_graph[id].operation {
+                Operation::Constant(value) => circuit.constant(value),
+                Operation::Add(a, b) | Operation::Mul(a, b) => {
+                    let (a, b) = match keys[a] <= keys[b] {
+                        true => (operand(a), operand(b)),
+                        false => (operand(b), operand(a)),
+                    };
+                    match self.full_graph[id].operation {
+                        Operation::Add(..) => circuit.add(a, b),
+                        _ => circuit.mul(a, b),
+                    }
+                }
+                Operation::Hint(index) => {
+                    let hint = &self.hints[index];
+                    circuit.push_hint(Hint {
+                        inputs: hint.inputs.iter().map(|id| operand(*id).id).collect(),
+                        compute: hint.compute.clone(),
+                    })
+                }
+                Operation::Input => unreachable!("inputs are created first"),
+            };
+            nodes[id] = Some(new);
+            release(id, &mut ready);
+        }
+
+        let mut equalities: Vec<(u64, u64, usize, usize)> = self
+            .constraints
+            .iter()
+            .filter_map(|constraint| match *constraint {
+                Constraints::Eq(a, b) if keys[a] <= keys[b] => Some((keys[a], keys[b], a, b)),
+                Constraints::Eq(a, b) => Some((keys[b], keys[a], b, a)),
+                _ => None,
+            })
+            .collect();
+        equalities.sort_unstable();
+        for (_, _, a, b) in equalities {
+            let (a, b) = (nodes[a].clone().unwrap(), nodes[b].clone().unwrap());
+            circuit.assert_equal(a, b);
+        }
+        let mut zeros: Vec<(u64, usize)> = self
+            .constraints
+            .iter()
+            .filter_map(|constraint| self.zero_assertion(constraint))
+            .map(|a| (keys[a], a))
+            .collect();
+        zeros.sort_unstable();
+        for (_, a) in zeros {
+            circuit.assert_zero(nodes[a].clone().unwrap());
+        }
+        for output in &self.outputs {
+            circuit.output(nodes[*output].clone().unwrap());
+        }
+        circuit
+    }
+
+    /// Returns `true` if both circuits have the same canonical form: the same field, inputs,
+    /// gates, constants, hint inputs, equality assertions and outputs, up to the order of
+    /// creation and of commutative operands.
+    pub fn is_isomorphic_to(&self, other: &Builder) -> bool {
+        let (first, second) = (self.canonicalize(), other.canonicalize());
+        first.field == second.field
+            && first.private_inputs == second.private_inputs
+            && first.outputs == second.outputs
+            && first.constraints == second.constraints
+            && first.full_graph.len() == second.full_graph.len()
+            && first
+                .full_graph
+                .iter()
+                .zip(&second.full_graph)
+                .all(|(a, b)| {
+                    a.operation == b.operation
+                        && first.operands(&a.operation) == second.operands(&b.operation)
+                })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{random_circuit, CircuitConfig, TestRng};
+
+    #[test]
+    fn test_canonical_form_keeps_semantics() {
+        let mut rng = TestRng::new(5);
+        for _ in 0..20 {
+            let random = random_circuit(&mut rng, &CircuitConfig::default());
+            let canonical = random.circuit.canonicalize();
+            assert_eq!(canonical.evaluate(random.inputs.clone()), random.outputs);
+            assert!(canonical.is_isomorphic_to(&random.circuit));
+            assert_eq!(canonical.fingerprint(), random.circuit.fingerprint());
+        }
+    }
+
+    #[test]
+    fn test_different_constants_are_not_isomorphic() {
+        let mut first = Builder::new();
+        let x = first.init();
+        let two = first.constant(2);
+        let _ = first.mul(x, two);
+        let mut second = Builder::new();
+        let x = second.init();
+        let three = second.constant(3);
+        let _ = second.mul(x, three);
+        assert!(!first.is_isomorphic_to(&second));
+    }
+}
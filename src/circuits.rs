@@ -0,0 +1,129 @@
+//! Ready-made example circuits.
+//!
+//! Each constructor returns a complete circuit together with names for its inputs and outputs,
+//! a runnable starting point to inspect, export or modify.
+//!
+//! ```
+//! use arithmetic_circuit::circuits::fibonacci;
+//!
+//! let circuit = fibonacci(10);
+//! assert_eq!(circuit.input_names(), vec!["f0", "f1"]);
+//! assert_eq!(circuit.builder.evaluate(vec![0, 1]), vec![55]);
+//! ```
+use crate::{Builder, Node};
+
+/// A circuit with named inputs and outputs, in the order the builder takes and returns them.
+#[derive(Clone)]
+pub struct LabelledCircuit {
+    pub builder: Builder,
+    pub inputs: Vec<(String, Node)>,
+    pub outputs: Vec<(String, Node)>,
+}
+
+impl LabelledCircuit {
+    /// Returns the names of the inputs.
+    pub fn input_names(&self) -> Vec<&str> {
+        self.inputs.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Returns the names of the outputs.
+    pub fn output_names(&self) -> Vec<&str> {
+        self.outputs.iter().map(|(name, _)| name.as_str()).collect()
+    }
+}
+
+/// Computes the `n`-th Fibonacci number `f_n` from `f0` and `f1` with `n - 1` additions.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::circuits::fibonacci;
+///
+/// let circuit = fibonacci(6);
+/// assert_eq!(circuit.output_names(), vec!["f6"]);
+/// assert_eq!(circuit.builder.evaluate(vec![2, 1]), vec![18]);
+/// ```
+pub fn fibonacci(n: usize) -> LabelledCircuit {
+    let mut builder = Builder::new();
+    let start = builder.inputs(2);
+    let state = builder.repeat(n.saturating_sub(1), start.clone(), |b, state| {
+        let next = b.add(state[0].clone(), state[1].clone());
+        vec![state[1].clone(), next]
+    });
+    let output = state[n.min(1)].clone();
+    builder.output(output.clone());
+    LabelledCircuit {
+        builder,
+        inputs: vec![
+            ("f0".into(), start[0].clone()),
+            ("f1".into(), start[1].clone()),
+        ],
+        outputs: vec![(format!("f{}", n), output)],
+    }
+}
+
+/// Computes the `n`-th term of the linear recurrence `a_(k+d) = Σ c_i a_(k+i)` with constant
+/// coefficients `c_0, …, c_(d-1)`, from the initial terms `a0, …, a(d-1)`.
+///
+/// # Panics
+///
+/// Panics if no coefficients are given.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::circuits::linear_recurrence;
+///
+/// // a_(k+2) = 2 a_(k+1) + 3 a_k
+/// let circuit = linear_recurrence(&[3, 2], 4);
+/// assert_eq!(circuit.input_names(), vec!["a0", "a1"]);
+/// assert_eq!(circuit.builder.evaluate(vec![1, 1]), vec![41]);
+/// ```
+pub fn linear_recurrence(coefficients: &[u64], n: usize) -> LabelledCircuit {
+    if coefficients.is_empty() {
+        panic!("a linear recurrence needs at least one coefficient");
+    }
+    let order = coefficients.len();
+    let mut builder = Builder::new();
+    let start = builder.inputs(order);
+    let coefficients = builder.constants(coefficients);
+    let state = builder.repeat(n.saturating_sub(order - 1), start.clone(), |b, state| {
+        let mut next = b.constant(0);
+        for (c, term) in coefficients.iter().zip(state) {
+            let product = b.mul(c.clone(), term.clone());
+            next = b.add(next, product);
+        }
+        let mut next_state = state[1..].to_vec();
+        next_state.push(next);
+        next_state
+    });
+    let output = state[n.min(order - 1)].clone();
+    builder.output(output.clone());
+    LabelledCircuit {
+        builder,
+        inputs: start
+            .into_iter()
+            .enumerate()
+            .map(|(i, node)| (format!("a{}", i), node))
+            .collect(),
+        outputs: vec![(format!("a{}", n), output)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fibonacci_is_order_two_recurrence() {
+        for n in 0..8 {
+            let first = fibonacci(n);
+            let second = linear_recurrence(&[1, 1], n);
+            assert_eq!(first.output_names(), vec![format!("f{}", n)]);
+            assert_eq!(
+                first.builder.evaluate(vec![3, 4]),
+                second.builder.evaluate(vec![3, 4])
+            );
+        }
+    }
+}
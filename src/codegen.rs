@@ -0,0 +1,165 @@
+//! Generation of Rust source code from a circuit.
+//!
+//! `Builder::codegen_rust` prints the computation of the outputs as a standalone Rust function
+//! of straight-line code, one `let` per node, named like the wires in
+//! `Builder::fill_nodes_explained`. It shows how the graph flattens into an ordinary program and
+//! can be pasted into any crate, it has no dependencies.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let y = builder.init();
+//! let five = builder.constant(5);
+//! let x_y = builder.mul(x, y);
+//! let sum = builder.add(x_y, five);
+//! builder.output(sum);
+//!
+//! assert_eq!(
+//!     builder.codegen_rust("circuit").unwrap(),
+//!     "\
+//! pub fn circuit(inputs: [u64; 2]) -> [u64; 1] {
+//!     let x0 = inputs[0];
+//!     let x1 = inputs[1];
+//!     let c0: u64 = 5;
+//!     let w3 = x0 * x1;
+//!     let w4 = w3 + c0;
+//!     [w4]
+//! }
+//! "
+//! );
+//! ```
+use crate::enums::Operation;
+use crate::Builder;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Why `Builder::codegen_rust` could not generate a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodegenError {
+    /// An output depends on the hint with this name, whose Rust closure has no source code.
+    Hint { wire: String },
+}
+
+impl Display for CodegenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodegenError::Hint { wire } => {
+                write!(
+                    f,
+                    "an output depends on hint {}, which cannot be exported",
+                    wire
+                )
+            }
+        }
+    }
+}
+
+impl Error for CodegenError {}
+
+impl Builder {
+    /// Generates a Rust function computing the outputs of the circuit from its inputs.
+    ///
+    /// The function takes the inputs in order of creation and returns the outputs in the order
+    /// they were registered. Over a field, every value is reduced modulo the field's prime. Nodes
+    /// no output depends on, and constraints, are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `fn_name`: The name of the generated function.
+    ///
+    /// # Returns
+    ///
+    /// The source code of the function.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an output depends on a hint, since hints run closures whose code is not known.
+    pub fn codegen_rust(&self, fn_name: &str) -> Result<String, CodegenError> {
+        let needed = self.needed_nodes();
+        let mut code = format!(
+            "pub fn {}(inputs: [u64; {}]) -> [u64; {}] {{\n",
+            fn_name,
+            self.inputs.len(),
+            self.outputs.len()
+        );
+        let modulus = self.field.map(|field| field.modulus());
+        for node in self.full_graph.iter().filter(|node| needed[node.id]) {
+            let name = self.wire_name(node.id);
+            let value = match (node.operation, modulus) {
+                (Operation::Input, None) => format!("inputs[{}]", self.input_position(node.id)),
+                (Operation::Input, Some(p)) => {
+                    format!("inputs[{}] % {}", self.input_position(node.id), p)
+                }
+                (Operation::Constant(value), _) => {
+                    code.push_str(&format!("    let {}: u64 = {};\n", name, value));
+                    continue;
+                }
+                (Operation::Add(a, b), None) => {
+                    format!("{} + {}", self.wire_name(a), self.wire_name(b))
+                }
+                (Operation::Mul(a, b), None) => {
+                    format!("{} * {}", self.wire_name(a), self.wire_name(b))
+                }
+                (Operation::Add(a, b), Some(p)) => format!(
+                    "((({} as u128) + ({} as u128)) % {}) as u64",
+                    self.wire_name(a),
+                    self.wire_name(b),
+                    p
+                ),
+                (Operation::Mul(a, b), Some(p)) => format!(
+                    "((({} as u128) * ({} as u128)) % {}) as u64",
+                    self.wire_name(a),
+                    self.wire_name(b),
+                    p
+                ),
+                (Operation::Hint(_), _) => return Err(CodegenError::Hint { wire: name }),
+            };
+            code.push_str(&format!("    let {} = {};\n", name, value));
+        }
+        let outputs: Vec<String> = self.outputs.iter().map(|id| self.wire_name(*id)).collect();
+        code.push_str(&format!("    [{}]\n}}\n", outputs.join(", ")));
+        Ok(code)
+    }
+
+    /// Returns the position of an input node among the inputs, in order of creation.
+    fn input_position(&self, id: usize) -> usize {
+        self.inputs.iter().position(|input| *input == id).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_codegen_in_field() {
+        let mut builder = Builder::new_in_field(Field::new(97));
+        let x = builder.init();
+        let _ = builder.init();
+        let square = builder.mul(x.clone(), x);
+        builder.output(square);
+        assert_eq!(
+            builder.codegen_rust("square").unwrap(),
+            "pub fn square(inputs: [u64; 2]) -> [u64; 1] {\n    let x0 = inputs[0] % 97;\n    \
+             let w2 = (((x0 as u128) * (x0 as u128)) % 97) as u64;\n    [w2]\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_hints_are_an_error() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let half = builder.hint(&[&x], |values| values[0] / 2);
+        builder.label(&half, "half");
+        builder.output(half);
+        assert_eq!(
+            builder.codegen_rust("half"),
+            Err(CodegenError::Hint {
+                wire: "half".to_string()
+            })
+        );
+    }
+}
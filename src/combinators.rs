@@ -0,0 +1,225 @@
+//! Helpers for building repetitive structure.
+//!
+//! Circuits are built by Rust code running once, so a loop in the builder code unrolls into
+//! gates. These helpers capture common shapes of such loops: `Builder::fold` threads an
+//! accumulator through a list of wires, and `Builder::repeat` applies a transition function to
+//! a state of wires a fixed number of times.
+//!
+//! Summing or multiplying many wires with a fold builds a chain as deep as the list is long.
+//! `Builder::add_many` and `Builder::mul_many` combine them in a balanced tree instead, so the
+//! product of `n` wires has multiplicative depth `⌈log2 n⌉`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! // Horner's rule for 2x^2 + 3x + 4
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let coefficients = [2, 3, 4].map(|c| builder.constant(c));
+//! let zero = builder.constant(0);
+//! let result = builder.fold(&coefficients, zero, |b, acc, c| {
+//!     let scaled = b.mul(acc, x.clone());
+//!     b.add(scaled, c.clone())
+//! });
+//! builder.output(result);
+//! assert_eq!(builder.evaluate(vec![5]), vec![69]);
+//! ```
+use crate::{Builder, Node};
+
+impl Builder {
+    /// Threads an accumulator through `wires`, building the gates of every step with `step`.
+    ///
+    /// # Arguments
+    ///
+    /// * `wires`: The elements, visited in order.
+    /// * `init`: The initial accumulator, usually a node but any state works.
+    /// * `step`: Builds the next accumulator from the current one and an element.
+    ///
+    /// # Returns
+    ///
+    /// The final accumulator, `init` if `wires` is empty.
+    pub fn fold<T>(
+        &mut self,
+        wires: &[Node],
+        init: T,
+        mut step: impl FnMut(&mut Builder, T, &Node) -> T,
+    ) -> T {
+        let mut acc = init;
+        for wire in wires {
+            acc = step(self, acc, wire);
+        }
+        acc
+    }
+
+    /// Unrolls `n` applications of the transition function `step` to a state of wires.
+    ///
+    /// # Arguments
+    ///
+    /// * `n`: The number of steps.
+    /// * `state`: The initial state.
+    /// * `step`: Builds the next state from the current one.
+    ///
+    /// # Returns
+    ///
+    /// The state after `n` steps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` returns a state of a different length than it was given.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    ///
+    /// // the 10th and 11th Fibonacci numbers
+    /// let mut builder = Builder::new();
+    /// let state = vec![builder.init(), builder.init()];
+    /// let last = builder.repeat(10, state, |b, state| {
+    ///     let next = b.add(state[0].clone(), state[1].clone());
+    ///     vec![state[1].clone(), next]
+    /// });
+    /// last.into_iter().for_each(|node| builder.output(node));
+    /// assert_eq!(builder.evaluate(vec![0, 1]), vec![55, 89]);
+    /// ```
+    pub fn repeat(
+        &mut self,
+        n: usize,
+        state: Vec<Node>,
+        mut step: impl FnMut(&mut Builder, &[Node]) -> Vec<Node>,
+    ) -> Vec<Node> {
+        let mut state = state;
+        for i in 0..n {
+            let next = step(self, &state);
+            if next.len() != state.len() {
+                panic!(
+                    "step {} turned a state of {} wires into {}",
+                    i,
+                    state.len(),
+                    next.len()
+                );
+            }
+            state = next;
+        }
+        state
+    }
+
+    /// Adds the wires in a balanced tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no wires are given, see `sum` for a version returning `0`.
+    pub fn add_many(&mut self, wires: &[Node]) -> Node {
+        self.balanced(wires, Builder::add)
+            .expect("cannot add an empty list of wires")
+    }
+
+    /// Multiplies the wires in a balanced tree, minimizing the multiplicative depth.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no wires are given, see `product` for a version returning `1`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    ///
+    /// let mut builder = Builder::new();
+    /// let wires: Vec<_> = (0..8).map(|_| builder.init()).collect();
+    /// let product = builder.mul_many(&wires);
+    /// builder.output(product);
+    /// assert_eq!(builder.mul_depth(), vec![3]);
+    /// assert_eq!(builder.evaluate((1..=8).collect()), vec![40320]);
+    /// ```
+    pub fn mul_many(&mut self, wires: &[Node]) -> Node {
+        self.balanced(wires, Builder::mul)
+            .expect("cannot multiply an empty list of wires")
+    }
+
+    /// Adds the wires in a balanced tree, returning a constant `0` for no wires.
+    pub fn sum(&mut self, wires: &[Node]) -> Node {
+        match self.balanced(wires, Builder::add) {
+            Some(sum) => sum,
+            None => self.constant(0),
+        }
+    }
+
+    /// Multiplies the wires in a balanced tree, returning a constant `1` for no wires.
+    pub fn product(&mut self, wires: &[Node]) -> Node {
+        match self.balanced(wires, Builder::mul) {
+            Some(product) => product,
+            None => self.constant(1),
+        }
+    }
+
+    /// Combines neighbouring wires level by level until one is left, `None` for no wires.
+    fn balanced(
+        &mut self,
+        wires: &[Node],
+        combine: fn(&mut Builder, Node, Node) -> Node,
+    ) -> Option<Node> {
+        let mut level = wires.to_vec();
+        while level.len() > 1 {
+            let mut next = Vec::with_capacity(level.len().div_ceil(2));
+            let mut pairs = level.into_iter();
+            while let Some(first) = pairs.next() {
+                next.push(match pairs.next() {
+                    Some(second) => combine(self, first, second),
+                    None => first,
+                });
+            }
+            level = next;
+        }
+        level.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_sum_and_product() {
+        let mut builder = Builder::new();
+        let wires: Vec<Node> = (0..4).map(|_| builder.init()).collect();
+        let zero = builder.constant(0);
+        let one = builder.constant(1);
+        let sum = builder.fold(&wires, zero, |b, acc, x| b.add(acc, x.clone()));
+        let product = builder.fold(&wires, one.clone(), |b, acc, x| b.mul(acc, x.clone()));
+        let empty = builder.fold(&[], one, |b, acc, x| b.mul(acc, x.clone()));
+        builder.output(sum);
+        builder.output(product);
+        builder.output(empty);
+        assert_eq!(builder.evaluate(vec![2, 3, 4, 5]), vec![14, 120, 1]);
+    }
+
+    #[test]
+    fn test_balanced_trees() {
+        let mut builder = Builder::new();
+        let wires: Vec<Node> = (0..5).map(|_| builder.init()).collect();
+        let results = [
+            builder.add_many(&wires),
+            builder.mul_many(&wires),
+            builder.sum(&[]),
+            builder.product(&[]),
+            builder.product(&wires[..1]),
+        ];
+        results.into_iter().for_each(|node| builder.output(node));
+        assert_eq!(builder.mul_depth(), vec![0, 3, 0, 0, 0]);
+        assert_eq!(
+            builder.evaluate(vec![1, 2, 3, 4, 5]),
+            vec![15, 120, 0, 1, 1]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "step 0 turned a state of 2 wires into 1")]
+    fn test_repeat_keeps_state_size() {
+        let mut builder = Builder::new();
+        let state = vec![builder.init(), builder.init()];
+        builder.repeat(3, state, |b, state| {
+            vec![b.add(state[0].clone(), state[1].clone())]
+        });
+    }
+}
@@ -0,0 +1,164 @@
+//! Toy hash-based commitments to a witness.
+//!
+//! A commitment lets the prover fix its witness without revealing it. The witness is cut into
+//! chunks and every chunk is hashed together with a random salt. Later the prover can open
+//! single chunks, revealing their values and salts, and anyone holding the commitment can
+//! check that the revealed values are the ones committed to. Chunks that are never opened
+//! stay hidden.
+//!
+//! The hash is not cryptographic, so these commitments are neither hiding nor binding in any
+//! real sense. They only demonstrate the commit/open/verify API.
+//!
+//! ```
+//! use arithmetic_circuit::commitment::CommittedWitness;
+//!
+//! let committed = CommittedWitness::commit(vec![1, 3, 9, 27], 2, 42);
+//! let commitment = committed.commitment();
+//! let opening = committed.open(1);
+//! assert_eq!(opening.values, vec![9, 27]);
+//! assert!(commitment.verify(&opening));
+//! ```
+use crate::hash::{hash_words, mix};
+
+/// The public part of a commitment: one digest per chunk of the witness.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commitment {
+    pub chunk_size: usize,
+    pub digests: Vec<u64>,
+}
+
+/// Reveals the values of a single chunk together with the salt used to commit to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opening {
+    pub chunk: usize,
+    pub values: Vec<u64>,
+    pub salt: u64,
+}
+
+/// The prover's side of a commitment: the witness, the salts and the resulting commitment.
+#[derive(Debug, Clone)]
+pub struct CommittedWitness {
+    witness: Vec<u64>,
+    salts: Vec<u64>,
+    commitment: Commitment,
+}
+
+/// Hashes the values of a chunk together with its salt and position.
+fn digest(chunk: usize, values: &[u64], salt: u64) -> u64 {
+    let mut words = vec![chunk as u64, salt];
+    words.extend_from_slice(values);
+    hash_words(&words)
+}
+
+impl CommittedWitness {
+    /// Commits to a witness.
+    ///
+    /// # Arguments
+    ///
+    /// * `witness`: The values to commit to.
+    /// * `chunk_size`: Number of values per chunk, the last chunk may be shorter.
+    /// * `seed`: Source of the salts. Committing twice with the same seed gives the same commitment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn commit(witness: Vec<u64>, chunk_size: usize, seed: u64) -> Self {
+        if chunk_size == 0 {
+            panic!("chunk size of a commitment must be positive");
+        }
+        let salts: Vec<u64> = (0..witness.len().div_ceil(chunk_size))
+            .map(|chunk| mix(seed ^ mix(chunk as u64)))
+            .collect();
+        let digests = witness
+            .chunks(chunk_size)
+            .zip(&salts)
+            .enumerate()
+            .map(|(chunk, (values, salt))| digest(chunk, values, *salt))
+            .collect();
+        CommittedWitness {
+            witness,
+            salts,
+            commitment: Commitment {
+                chunk_size,
+                digests,
+            },
+        }
+    }
+
+    /// Returns the public commitment, which can be sent to the verifier.
+    pub fn commitment(&self) -> Commitment {
+        self.commitment.clone()
+    }
+
+    /// Opens a chunk of the witness.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk` is out of range.
+    pub fn open(&self, chunk: usize) -> Opening {
+        let size = self.commitment.chunk_size;
+        let end = ((chunk + 1) * size).min(self.witness.len());
+        Opening {
+            chunk,
+            values: self.witness[chunk * size..end].to_vec(),
+            salt: self.salts[chunk],
+        }
+    }
+
+    /// Opens the chunk containing the value at `index` of the witness.
+    pub fn open_index(&self, index: usize) -> Opening {
+        self.open(index / self.commitment.chunk_size)
+    }
+}
+
+impl Commitment {
+    /// Checks that an opening reveals the values that were committed to.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the opening matches the commitment, otherwise `false`.
+    pub fn verify(&self, opening: &Opening) -> bool {
+        match self.digests.get(opening.chunk) {
+            Some(expected) => *expected == digest(opening.chunk, &opening.values, opening.salt),
+            None => false,
+        }
+    }
+
+    /// Returns the value at `index` of the witness if `opening` is a valid opening containing it.
+    pub fn value_at(&self, opening: &Opening, index: usize) -> Option<u64> {
+        if !self.verify(opening) || index / self.chunk_size != opening.chunk {
+            return None;
+        }
+        opening.values.get(index % self.chunk_size).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_and_verify() {
+        let committed = CommittedWitness::commit(vec![1, 2, 3, 4, 5], 2, 7);
+        let commitment = committed.commitment();
+        assert_eq!(commitment.digests.len(), 3);
+        let last = committed.open(2);
+        assert_eq!(last.values, vec![5]);
+        assert!(commitment.verify(&last));
+        assert_eq!(commitment.value_at(&committed.open_index(3), 3), Some(4));
+        assert_eq!(commitment.value_at(&last, 3), None);
+    }
+
+    #[test]
+    fn test_tampered_openings_are_rejected() {
+        let committed = CommittedWitness::commit(vec![1, 2, 3, 4], 2, 7);
+        let commitment = committed.commitment();
+        let mut opening = committed.open(0);
+        opening.values[1] = 3;
+        assert!(!commitment.verify(&opening));
+
+        let mut moved = committed.open(0);
+        moved.chunk = 1;
+        assert!(!commitment.verify(&moved));
+    }
+}
@@ -0,0 +1,425 @@
+//! Compilation of a circuit into a flat program.
+//!
+//! `Builder::compile` turns the graph into a list of three-address instructions over a small
+//! register file. Only the nodes the outputs depend on are compiled, and a register is reused
+//! as soon as the value it holds is no longer needed. Running the program touches nothing but
+//! the instruction list and the registers, which makes repeated evaluation much cheaper than
+//! `fill_nodes`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::compile::Instruction;
+//!
+//! // x^2 + 5
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let five = builder.constant(5);
+//! let square = builder.mul(x.clone(), x);
+//! let sum = builder.add(square, five);
+//! builder.output(sum);
+//!
+//! let compiled = builder.compile();
+//! assert_eq!(
+//!     compiled.instructions(),
+//!     &[
+//!         Instruction::Load { dst: 0, input: 0 },
+//!         Instruction::Const { dst: 1, value: 5 },
+//!         Instruction::Mul { dst: 0, a: 0, b: 0 },
+//!         Instruction::Add { dst: 1, a: 0, b: 1 },
+//!     ]
+//! );
+//! assert_eq!(compiled.evaluate(&[3]), vec![14]);
+//! ```
+use crate::builder::OVERFLOW;
+use crate::enums::Operation;
+use crate::field::Field;
+use crate::hint::HintFn;
+use crate::Builder;
+use std::fmt::{Debug, Display, Formatter};
+use tracing::info;
+
+/// A single step of a compiled circuit. Operands and destinations are register indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    /// Loads the input at position `input`, in order of creation.
+    Load {
+        dst: usize,
+        input: usize,
+    },
+    Const {
+        dst: usize,
+        value: u64,
+    },
+    Add {
+        dst: usize,
+        a: usize,
+        b: usize,
+    },
+    Mul {
+        dst: usize,
+        a: usize,
+        b: usize,
+    },
+    /// Runs the function of the hint at position `hint`, in order of creation, on `args`.
+    Hint {
+        dst: usize,
+        hint: usize,
+        args: Vec<usize>,
+    },
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::Load { dst, input } => write!(f, "r{} = x{}", dst, input),
+            Instruction::Const { dst, value } => write!(f, "r{} = {}", dst, value),
+            Instruction::Add { dst, a, b } => write!(f, "r{} = r{} + r{}", dst, a, b),
+            Instruction::Mul { dst, a, b } => write!(f, "r{} = r{} * r{}", dst, a, b),
+            Instruction::Hint { dst, hint, args } => {
+                let args: Vec<String> = args.iter().map(|r| format!("r{}", r)).collect();
+                write!(f, "r{} = h{}({})", dst, hint, args.join(", "))
+            }
+        }
+    }
+}
+
+/// A compiled circuit as a closure from inputs to outputs, see `CompiledCircuit::to_fn`.
+pub type CircuitFn = Box<dyn Fn(&[u64]) -> Vec<u64>>;
+
+/// A circuit compiled into a straight-line program, see `Builder::compile`.
+#[derive(Clone)]
+pub struct CompiledCircuit {
+    pub(crate) instructions: Vec<Instruction>,
+    /// The function of every hint, indexed like the hints of the circuit.
+    pub(crate) hints: Vec<HintFn>,
+    pub(crate) registers: usize,
+    pub(crate) inputs: usize,
+    /// The register holding each output after the program ran.
+    pub(crate) outputs: Vec<usize>,
+    pub(crate) field: Option<Field>,
+}
+
+impl Debug for CompiledCircuit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompiledCircuit")
+            .field("instructions", &self.instructions)
+            .field("registers", &self.registers)
+            .field("inputs", &self.inputs)
+            .field("outputs", &self.outputs)
+            .field("field", &self.field)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CompiledCircuit {
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Returns the number of registers the program needs.
+    pub fn registers(&self) -> usize {
+        self.registers
+    }
+
+    /// Runs the program.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs`: Values for the inputs of the circuit, in order of creation.
+    ///
+    /// # Returns
+    ///
+    /// The values of the outputs, in the order they were registered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of inputs does not match the circuit or, without a field, if a value
+    /// overflows a `u64`.
+    pub fn evaluate(&self, inputs: &[u64]) -> Vec<u64> {
+        if inputs.len() != self.inputs {
+            panic!(
+                "number of input arguments supplied does not match number of inputs for function"
+            );
+        }
+        let mut registers = vec![0; self.registers];
+        for instruction in &self.instructions {
+            match *instruction {
+                Instruction::Load { dst, input } => {
+                    registers[dst] = self
+                        .field
+                        .map_or(inputs[input], |f| f.reduce(inputs[input]))
+                }
+                Instruction::Const { dst, value } => registers[dst] = value,
+                Instruction::Add { dst, a, b } => {
+                    registers[dst] = match self.field {
+                        Some(field) => field.add(registers[a], registers[b]),
+                        None => registers[a].checked_add(registers[b]).expect(OVERFLOW),
+                    }
+                }
+                Instruction::Mul { dst, a, b } => {
+                    registers[dst] = match self.field {
+                        Some(field) => field.mul(registers[a], registers[b]),
+                        None => registers[a].checked_mul(registers[b]).expect(OVERFLOW),
+                    }
+                }
+                Instruction::Hint {
+                    dst,
+                    hint,
+                    ref args,
+                } => {
+                    let values: Vec<u64> = args.iter().map(|r| registers[*r]).collect();
+                    let value = (self.hints[hint])(&values);
+                    registers[dst] = self.field.map_or(value, |f| f.reduce(value));
+                }
+            }
+        }
+        self.outputs.iter().map(|r| registers[*r]).collect()
+    }
+
+    /// Turns the program into a closure computing the outputs from the inputs.
+    ///
+    /// Every instruction becomes a small closure with its registers and the field resolved
+    /// up front, so evaluating does not dispatch on the instruction kind anymore.
+    ///
+    /// # Panics
+    ///
+    /// The returned closure panics like `evaluate`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut builder = Builder::new();
+    /// let x = builder.init();
+    /// let cube = builder.mul(x.clone(), x.clone());
+    /// let cube = builder.mul(cube, x);
+    /// builder.output(cube);
+    /// let cube = builder.compile().to_fn();
+    /// assert_eq!(cube(&[3]), vec![27]);
+    /// assert_eq!(cube(&[4]), vec![64]);
+    /// ```
+    pub fn to_fn(&self) -> CircuitFn {
+        type Step = Box<dyn Fn(&mut [u64], &[u64])>;
+        let steps: Vec<Step> = self
+            .instructions
+            .iter()
+            .map(|instruction| -> Step {
+                match (instruction.clone(), self.field) {
+                    (Instruction::Load { dst, input }, None) => {
+                        Box::new(move |r, inputs| r[dst] = inputs[input])
+                    }
+                    (Instruction::Load { dst, input }, Some(field)) => {
+                        Box::new(move |r, inputs| r[dst] = field.reduce(inputs[input]))
+                    }
+                    (Instruction::Const { dst, value }, _) => Box::new(move |r, _| r[dst] = value),
+                    (Instruction::Add { dst, a, b }, None) => {
+                        Box::new(move |r, _| r[dst] = r[a].checked_add(r[b]).expect(OVERFLOW))
+                    }
+                    (Instruction::Mul { dst, a, b }, None) => {
+                        Box::new(move |r, _| r[dst] = r[a].checked_mul(r[b]).expect(OVERFLOW))
+                    }
+                    (Instruction::Add { dst, a, b }, Some(field)) => {
+                        Box::new(move |r, _| r[dst] = field.add(r[a], r[b]))
+                    }
+                    (Instruction::Mul { dst, a, b }, Some(field)) => {
+                        Box::new(move |r, _| r[dst] = field.mul(r[a], r[b]))
+                    }
+                    (Instruction::Hint { dst, hint, args }, field) => {
+                        let compute = self.hints[hint].clone();
+                        Box::new(move |r, _| {
+                            let values: Vec<u64> = args.iter().map(|a| r[*a]).collect();
+                            let value = compute(&values);
+                            r[dst] = field.map_or(value, |field| field.reduce(value));
+                        })
+                    }
+                }
+            })
+            .collect();
+        let (registers, inputs, outputs) = (self.registers, self.inputs, self.outputs.clone());
+        Box::new(move |input: &[u64]| {
+            if input.len() != inputs {
+                panic!(
+                    "number of input arguments supplied does not match number of inputs for function"
+                );
+            }
+            let mut r = vec![0; registers];
+            steps.iter().for_each(|step| step(&mut r, input));
+            outputs.iter().map(|output| r[*output]).collect()
+        })
+    }
+}
+
+impl Display for CompiledCircuit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(f, "{}", instruction)?;
+        }
+        let outputs: Vec<String> = self.outputs.iter().map(|r| format!("r{}", r)).collect();
+        write!(f, "return [{}]", outputs.join(", "))
+    }
+}
+
+impl Builder {
+    /// Compiles the computation of the outputs into a flat program.
+    ///
+    /// Constraints are not part of the program, it only computes the outputs. Nodes that no
+    /// output depends on are left out. Hints become `Instruction::Hint`s calling the function of
+    /// the hint, which the compiled circuit keeps a handle to.
+    pub fn compile(&self) -> CompiledCircuit {
+        let count = self.full_graph.len();
+        let needed = self.needed_nodes();
+        // the last instruction reading each node, outputs are read at the very end
+        let mut last_use = vec![0; count];
+        for (id, node) in self.full_graph.iter().enumerate() {
+            if needed[id] {
+                for operand in self.operands(&node.operation) {
+                    last_use[operand] = id;
+                }
+            }
+        }
+        self.outputs
+            .iter()
+            .for_each(|id| last_use[*id] = usize::MAX);
+
+        let mut register = vec![usize::MAX; count];
+        let mut free: Vec<usize> = vec![];
+        let mut registers = 0;
+        let mut instructions = vec![];
+        for id in (0..count).filter(|id| needed[*id]) {
+            let operation = self.full_graph[id].operation;
+            // operands whose last use is this node give their registers back first, so the
+            // result can overwrite one of them
+            for operand in self.operands(&operation) {
+                if last_use[operand] == id && !free.contains(&register[operand]) {
+                    free.push(register[operand]);
+                }
+            }
+            let dst = free.pop().unwrap_or_else(|| {
+                registers += 1;
+                registers - 1
+            });
+            instructions.push(match operation {
+                Operation::Input => Instruction::Load {
+                    dst,
+                    input: self.inputs.iter().position(|input| *input == id).unwrap(),
+                },
+                Operation::Constant(value) => Instruction::Const { dst, value },
+                Operation::Add(a, b) => Instruction::Add {
+                    dst,
+                    a: register[a],
+                    b: register[b],
+                },
+                Operation::Mul(a, b) => Instruction::Mul {
+                    dst,
+                    a: register[a],
+                    b: register[b],
+                },
+                Operation::Hint(hint) => Instruction::Hint {
+                    dst,
+                    hint,
+                    args: self
+                        .hint_inputs(hint)
+                        .iter()
+                        .map(|input| register[*input])
+                        .collect(),
+                },
+            });
+            register[id] = dst;
+        }
+        info!(
+            instructions = instructions.len(),
+            registers, "compiled circuit"
+        );
+        CompiledCircuit {
+            instructions,
+            hints: self.hints.iter().map(|hint| hint.compute.clone()).collect(),
+            registers,
+            inputs: self.inputs.len(),
+            outputs: self.outputs.iter().map(|id| register[*id]).collect(),
+            field: self.field,
+        }
+    }
+
+    /// Marks the nodes the outputs depend on, indexed by id.
+    pub(crate) fn needed_nodes(&self) -> Vec<bool> {
+        let mut needed = vec![false; self.full_graph.len()];
+        self.outputs.iter().for_each(|id| needed[*id] = true);
+        // walk down from the outputs, operands always have smaller ids
+        for id in (0..self.full_graph.len()).rev() {
+            if !needed[id] {
+                continue;
+            }
+            match self.full_graph[id].operation {
+                Operation::Add(a, b) | Operation::Mul(a, b) => {
+                    needed[a] = true;
+                    needed[b] = true;
+                }
+                Operation::Hint(index) => self
+                    .hint_inputs(index)
+                    .iter()
+                    .for_each(|id| needed[*id] = true),
+                Operation::Input | Operation::Constant(_) => {}
+            }
+        }
+        needed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{random_circuit, CircuitConfig, TestRng};
+
+    #[test]
+    fn test_compiled_circuit_matches_evaluate() {
+        let mut rng = TestRng::new(8);
+        let config = CircuitConfig {
+            depth: 10,
+            width: 6,
+            max_fan_out: 4,
+            max_value: u64::MAX,
+            ..CircuitConfig::default()
+        };
+        for _ in 0..50 {
+            let random = random_circuit(&mut rng, &config);
+            let compiled = random.circuit.compile();
+            assert_eq!(compiled.evaluate(&random.inputs), random.outputs);
+            assert_eq!(compiled.to_fn()(&random.inputs), random.outputs);
+            assert!(compiled.registers() < random.circuit.full_graph.len());
+        }
+    }
+
+    #[test]
+    fn test_unused_nodes_are_not_compiled() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let y = builder.init();
+        let _ = builder.mul(y.clone(), y);
+        builder.output(x.clone());
+        builder.output(x);
+        let compiled = builder.compile();
+        assert_eq!(
+            compiled.instructions(),
+            &[Instruction::Load { dst: 0, input: 0 }]
+        );
+        assert_eq!(compiled.evaluate(&[4, 5]), vec![4, 4]);
+        assert_eq!(compiled.to_string(), "r0 = x0\nreturn [r0, r0]");
+    }
+
+    #[test]
+    fn test_hints_are_called() {
+        let mut builder = Builder::new_in_field(Field::new(97));
+        let x = builder.init();
+        let bits = crate::gadgets::to_bits(&mut builder, &x, 4);
+        let inverse = builder.hint(&[&x], |values| Field::new(97).inv(values[0]).unwrap_or(0));
+        builder.output(bits[1].clone());
+        builder.output(inverse);
+        let compiled = builder.compile();
+        assert!(compiled.to_string().contains("= h1(r0)"));
+        for x in [0, 6, 13] {
+            let expected = builder.evaluate(vec![x]);
+            assert_eq!(compiled.evaluate(&[x]), expected);
+            assert_eq!(compiled.to_fn()(&[x]), expected);
+        }
+    }
+}
@@ -0,0 +1,458 @@
+//! Constraint systems on their own.
+//!
+//! A `ConstraintSystem` holds the constraints of a circuit over the positions of a witness
+//! vector laid out like `Builder::witness`: a leading `1`, the public inputs, then every other
+//! wire. It knows nothing about how the witness was computed. It can be extracted from a
+//! builder, written down by hand, saved as text and parsed back, and checked against any witness
+//! vector, which keeps the circuit (what is checked) apart from the witness (what is checked
+//! against it).
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::constraint_system::ConstraintSystem;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let square = builder.mul(x.clone(), x);
+//! let nine = builder.constant(9);
+//! builder.assert_equal(square, nine);
+//!
+//! let system = builder.constraint_system();
+//! let text = system.to_string();
+//! assert_eq!(text, "variables 4\npublic 1\nmul 1 1 2\neq 2 3\nconst 3 9\n");
+//!
+//! // the witness is [1, x, x * x, 9]
+//! let parsed: ConstraintSystem = text.parse().unwrap();
+//! assert!(parsed.is_satisfied(&[1, 3, 9, 9]));
+//! assert!(parsed.verify(&[1, 2, 4, 9]).is_err());
+//! ```
+use crate::backend::Backend;
+use crate::field::{is_prime, Field};
+use crate::{Builder, NodeId};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A constraint over positions of the witness vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    /// `w[a] + w[b] = w[out]`
+    Add { a: usize, b: usize, out: usize },
+    /// `w[a] * w[b] = w[out]`
+    Mul { a: usize, b: usize, out: usize },
+    /// `w[a] = w[b]`
+    Eq { a: usize, b: usize },
+    /// `w[wire] = value`
+    Constant { wire: usize, value: u64 },
+    /// `w[wire] < 2^bits`
+    Range { wire: usize, bits: usize },
+}
+
+/// Why a witness does not satisfy a constraint system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The witness has the wrong number of entries.
+    Length { expected: usize, found: usize },
+    /// The first entry of the witness is not `1`.
+    One,
+    /// The constraint at this index does not hold.
+    Violated(usize),
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::Length { expected, found } => {
+                write!(
+                    f,
+                    "expected a witness of {} values, found {}",
+                    expected, found
+                )
+            }
+            VerifyError::One => write!(f, "the witness does not start with 1"),
+            VerifyError::Violated(index) => write!(f, "constraint {} does not hold", index),
+        }
+    }
+}
+
+impl Error for VerifyError {}
+
+/// Why a text could not be parsed as a constraint system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line the error is on, starting at 1.
+    pub line: usize,
+    pub reason: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Constraints over a witness vector, see the module documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintSystem {
+    /// Length of the witness, including the leading `1`.
+    num_variables: usize,
+    /// Number of public inputs, which follow the leading `1` in the witness.
+    num_public: usize,
+    /// The field the constraints are checked in, `None` for `u64` arithmetic without overflow.
+    field: Option<Field>,
+    constraints: Vec<Constraint>,
+}
+
+impl ConstraintSystem {
+    /// Creates a constraint system without any constraints.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the witness has no room for the leading `1` and the public inputs.
+    pub fn new(num_variables: usize, num_public: usize, field: Option<Field>) -> Self {
+        if num_public >= num_variables {
+            panic!(
+                "a witness of {} values cannot hold 1 and {} public inputs",
+                num_variables, num_public
+            );
+        }
+        ConstraintSystem {
+            num_variables,
+            num_public,
+            field,
+            constraints: vec![],
+        }
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.num_variables
+    }
+
+    pub fn num_public(&self) -> usize {
+        self.num_public
+    }
+
+    pub fn field(&self) -> Option<Field> {
+        self.field
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    /// Appends a constraint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the constraint refers to a position outside the witness.
+    pub fn push(&mut self, constraint: Constraint) {
+        let positions = match constraint {
+            Constraint::Add { a, b, out } | Constraint::Mul { a, b, out } => vec![a, b, out],
+            Constraint::Eq { a, b } => vec![a, b],
+            Constraint::Constant { wire, .. } | Constraint::Range { wire, .. } => vec![wire],
+        };
+        if let Some(position) = positions.iter().find(|p| **p >= self.num_variables) {
+            panic!(
+                "position {} is outside a witness of {} values",
+                position, self.num_variables
+            );
+        }
+        self.constraints.push(constraint);
+    }
+
+    /// Returns whether `witness` satisfies every constraint.
+    pub fn is_satisfied(&self, witness: &[u64]) -> bool {
+        self.verify(witness).is_ok()
+    }
+
+    /// Checks `witness` against every constraint, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first reason the witness is rejected.
+    pub fn verify(&self, witness: &[u64]) -> Result<(), VerifyError> {
+        if witness.len() != self.num_variables {
+            return Err(VerifyError::Length {
+                expected: self.num_variables,
+                found: witness.len(),
+            });
+        }
+        if witness[0] != 1 {
+            return Err(VerifyError::One);
+        }
+        match self.violations(witness).next() {
+            Some(index) => Err(VerifyError::Violated(index)),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the indices of the constraints `witness` violates, which must have the length of
+    /// the witness.
+    pub(crate) fn violations<'a>(&'a self, witness: &'a [u64]) -> impl Iterator<Item = usize> + 'a {
+        let reduce = move |value: u64| self.field.map_or(value, |field| field.reduce(value));
+        let holds = move |constraint: &Constraint| match *constraint {
+            Constraint::Add { a, b, out } => {
+                let sum = match self.field {
+                    Some(field) => Some(field.add(witness[a], witness[b])),
+                    None => witness[a].checked_add(witness[b]),
+                };
+                sum == Some(reduce(witness[out]))
+            }
+            Constraint::Mul { a, b, out } => {
+                let product = match self.field {
+                    Some(field) => Some(field.mul(witness[a], witness[b])),
+                    None => witness[a].checked_mul(witness[b]),
+                };
+                product == Some(reduce(witness[out]))
+            }
+            Constraint::Eq { a, b } => reduce(witness[a]) == reduce(witness[b]),
+            Constraint::Constant { wire, value } => reduce(witness[wire]) == reduce(value),
+            Constraint::Range { wire, bits } => {
+                u128::from(reduce(witness[wire])) < 1u128 << bits.min(64)
+            }
+        };
+        self.constraints
+            .iter()
+            .enumerate()
+            .filter(move |(_, constraint)| !holds(constraint))
+            .map(|(index, _)| index)
+    }
+}
+
+impl Builder {
+    /// Extracts the constraints of the circuit, over the witness layout of `Builder::witness`.
+    ///
+    /// The constraints are those of `Builder::emit`: the gates and equality assertions in order
+    /// of creation, then one constraint pinning every constant, then the range table lookups.
+    pub fn constraint_system(&self) -> ConstraintSystem {
+        let layout = self.witness_layout();
+        let mut index = vec![0; self.full_graph.len()];
+        layout
+            .iter()
+            .enumerate()
+            .for_each(|(position, id)| index[*id] = position + 1);
+        let mut extractor = Extractor {
+            index,
+            system: ConstraintSystem::new(layout.len() + 1, self.public_inputs().len(), self.field),
+        };
+        self.emit(&mut extractor);
+        extractor.system
+    }
+}
+
+/// The backend behind `Builder::constraint_system`, translating node ids to witness positions.
+struct Extractor {
+    index: Vec<usize>,
+    system: ConstraintSystem,
+}
+
+impl Backend for Extractor {
+    fn constant(&mut self, out: NodeId, value: u64) {
+        let wire = self.index[out];
+        self.system.push(Constraint::Constant { wire, value });
+    }
+
+    fn add_gate(&mut self, a: NodeId, b: NodeId, out: NodeId) {
+        let (a, b, out) = (self.index[a], self.index[b], self.index[out]);
+        self.system.push(Constraint::Add { a, b, out });
+    }
+
+    fn mul_gate(&mut self, a: NodeId, b: NodeId, out: NodeId) {
+        let (a, b, out) = (self.index[a], self.index[b], self.index[out]);
+        self.system.push(Constraint::Mul { a, b, out });
+    }
+
+    fn equality(&mut self, a: NodeId, b: NodeId) {
+        let (a, b) = (self.index[a], self.index[b]);
+        self.system.push(Constraint::Eq { a, b });
+    }
+
+    fn lookup(&mut self, wire: NodeId, bits: usize) {
+        let wire = self.index[wire];
+        self.system.push(Constraint::Range { wire, bits });
+    }
+}
+
+impl Display for ConstraintSystem {
+    /// Writes the system in the line-based text format read by `FromStr`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "variables {}", self.num_variables)?;
+        writeln!(f, "public {}", self.num_public)?;
+        if let Some(field) = self.field {
+            writeln!(f, "field {}", field.modulus())?;
+        }
+        for constraint in &self.constraints {
+            writeln!(f, "{}", constraint)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Constraint {
+    /// Writes the constraint as a line of the text format of `ConstraintSystem`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constraint::Add { a, b, out } => write!(f, "add {} {} {}", a, b, out),
+            Constraint::Mul { a, b, out } => write!(f, "mul {} {} {}", a, b, out),
+            Constraint::Eq { a, b } => write!(f, "eq {} {}", a, b),
+            Constraint::Constant { wire, value } => write!(f, "const {} {}", wire, value),
+            Constraint::Range { wire, bits } => write!(f, "range {} {}", wire, bits),
+        }
+    }
+}
+
+impl FromStr for ConstraintSystem {
+    type Err = ParseError;
+
+    /// Parses the text format written by `Display`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+        let mut header = |keyword: &str| -> Result<u64, ParseError> {
+            match lines.next() {
+                Some((line, text)) => match parse_line(line, text)? {
+                    (word, numbers) if word == keyword && numbers.len() == 1 => Ok(numbers[0]),
+                    _ => Err(ParseError {
+                        line,
+                        reason: format!("expected `{} <number>`", keyword),
+                    }),
+                },
+                None => Err(ParseError {
+                    line: 0,
+                    reason: format!("missing `{}` line", keyword),
+                }),
+            }
+        };
+        let num_variables = header("variables")? as usize;
+        let num_public = header("public")? as usize;
+        if num_public >= num_variables {
+            return Err(ParseError {
+                line: 2,
+                reason: "the public inputs do not fit in the witness".to_string(),
+            });
+        }
+        let mut system = ConstraintSystem::new(num_variables, num_public, None);
+        for (line, text) in lines {
+            let (word, numbers) = parse_line(line, text)?;
+            let error = |reason: &str| ParseError {
+                line,
+                reason: reason.to_string(),
+            };
+            if word == "field" {
+                match numbers[..] {
+                    [modulus] if system.field.is_none() && system.constraints.is_empty() => {
+                        if !is_prime(modulus) {
+                            return Err(error("the field modulus is not a prime"));
+                        }
+                        system.field = Some(Field::new(modulus));
+                    }
+                    _ => return Err(error("expected `field <prime>` before the constraints")),
+                }
+                continue;
+            }
+            let position = |number: u64| -> Result<usize, ParseError> {
+                match usize::try_from(number) {
+                    Ok(position) if position < num_variables => Ok(position),
+                    _ => Err(error("position outside the witness")),
+                }
+            };
+            let constraint = match (word, &numbers[..]) {
+                ("add", [a, b, out]) => Constraint::Add {
+                    a: position(*a)?,
+                    b: position(*b)?,
+                    out: position(*out)?,
+                },
+                ("mul", [a, b, out]) => Constraint::Mul {
+                    a: position(*a)?,
+                    b: position(*b)?,
+                    out: position(*out)?,
+                },
+                ("eq", [a, b]) => Constraint::Eq {
+                    a: position(*a)?,
+                    b: position(*b)?,
+                },
+                ("const", [wire, value]) => Constraint::Constant {
+                    wire: position(*wire)?,
+                    value: *value,
+                },
+                ("range", [wire, bits]) => Constraint::Range {
+                    wire: position(*wire)?,
+                    bits: *bits as usize,
+                },
+                _ => return Err(error(&format!("invalid constraint `{}`", text))),
+            };
+            system.push(constraint);
+        }
+        Ok(system)
+    }
+}
+
+/// Splits a line into its keyword and numbers.
+fn parse_line(line: usize, text: &str) -> Result<(&str, Vec<u64>), ParseError> {
+    let mut words = text.split_whitespace();
+    let keyword = words.next().unwrap_or_default();
+    let numbers = words
+        .map(|word| word.parse())
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(|_| ParseError {
+            line,
+            reason: format!("invalid number in `{}`", text),
+        })?;
+    Ok((keyword, numbers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_agrees_with_builder() {
+        let field = Field::new(1_000_003);
+        let mut builder = Builder::new_in_field(field);
+        let mut table = builder.range_table(2);
+        let x = builder.init();
+        let secret = builder.init_private();
+        let product = builder.mul(x.clone(), secret.clone());
+        let seven = builder.constant(7);
+        builder.assert_equal(product, seven);
+        table.lookup(&secret);
+        let challenge = builder.init();
+        table.finish(&mut builder, &challenge);
+
+        let system: ConstraintSystem = builder.constraint_system().to_string().parse().unwrap();
+        assert_eq!(system, builder.constraint_system());
+        let three = field.inv(3).unwrap();
+        for inputs in [
+            vec![7, 1, 5],
+            vec![field.mul(7, three), 3, 5],
+            vec![7, 2, 5],
+        ] {
+            builder.fill_nodes(inputs);
+            let witness = builder.witness().unwrap();
+            assert_eq!(system.is_satisfied(&witness), builder.check_constraints());
+        }
+        assert_eq!(
+            system.verify(&[1, 2]),
+            Err(VerifyError::Length {
+                expected: system.num_variables(),
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        let error = "variables 3\npublic 1\nadd 0 1 2\nmul 0 1 3\n"
+            .parse::<ConstraintSystem>()
+            .unwrap_err();
+        assert_eq!(error.line, 4);
+        assert!("variables 3\npublic 1\nfield 91\n"
+            .parse::<ConstraintSystem>()
+            .is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Estimating the proving cost of a circuit.
+//!
+//! Counting constraints treats every gate alike, but proof systems do not: a multiplication
+//! usually costs more than an addition, which some systems get almost for free, and a lookup has
+//! a cost of its own. A `CostModel` assigns a weight to every kind of gate and
+//! `Builder::estimate_cost_with` sums the weights per scope, so two implementations of the same
+//! gadget can be compared under the cost model of the target proof system.
+//!
+//! Constants, inputs and hints add no constraints and cost nothing. A lookup recorded by
+//! `RangeTable::finish` is charged on top of the gates of the argument emulating it, in the scope
+//! of the wire looked up.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::cost::CostModel;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! // x^4 by squaring twice, or by multiplying by x three times
+//! let _ = builder.scope("square", |b| {
+//!     let square = b.mul(x.clone(), x.clone());
+//!     b.mul(square.clone(), square)
+//! });
+//! let _ = builder.scope("naive", |b| {
+//!     (0..3).fold(x.clone(), |acc, _| b.mul(acc, x.clone()))
+//! });
+//!
+//! let report = builder.estimate_cost();
+//! assert_eq!(report.total, 15);
+//! assert_eq!(report.scopes[0].scope, "naive");
+//! assert_eq!(report.scopes[0].cost, 9);
+//!
+//! let cheap_mul = CostModel { mul: 1, ..CostModel::default() };
+//! assert_eq!(builder.estimate_cost_with(&cheap_mul).total, 5);
+//! assert_eq!(report.scopes[0].muls, 3);
+//! assert_eq!(report.scopes[1].scope, "square");
+//! assert_eq!((report.scopes[1].muls, report.scopes[1].cost), (2, 6));
+//! assert_eq!(
+//!     report.to_string(),
+//!     "scope                             add    mul     eq  lookup     cost\n\
+//!      naive                               0      3      0       0        9\n\
+//!      square                              0      2      0       0        6\n\
+//!      total                                                             15"
+//! );
+//! ```
+use crate::enums::Constraints;
+use crate::Builder;
+use std::fmt::{Display, Formatter};
+
+/// The cost of every kind of gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostModel {
+    pub add: u64,
+    pub mul: u64,
+    /// The cost of an equality assertion.
+    pub equality: u64,
+    /// The cost of looking a wire up in a range table.
+    pub lookup: u64,
+}
+
+impl Default for CostModel {
+    /// Additions cost 1, multiplications 3, equalities 1 and lookups 2.
+    fn default() -> Self {
+        CostModel {
+            add: 1,
+            mul: 3,
+            equality: 1,
+            lookup: 2,
+        }
+    }
+}
+
+/// The gates of a single scope and their cost.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ScopeCost {
+    /// The scope path, `""` for the root scope.
+    pub scope: String,
+    pub adds: usize,
+    pub muls: usize,
+    pub equalities: usize,
+    pub lookups: usize,
+    pub cost: u64,
+}
+
+/// The cost of a circuit per scope, most expensive scope first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostReport {
+    pub total: u64,
+    pub scopes: Vec<ScopeCost>,
+}
+
+impl Display for CostReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<30} {:>6} {:>6} {:>6} {:>7} {:>8}",
+            "scope", "add", "mul", "eq", "lookup", "cost"
+        )?;
+        for scope in &self.scopes {
+            let name = match scope.scope.as_str() {
+                "" => "<root>",
+                name => name,
+            };
+            writeln!(
+                f,
+                "{:<30} {:>6} {:>6} {:>6} {:>7} {:>8}",
+                name, scope.adds, scope.muls, scope.equalities, scope.lookups, scope.cost
+            )?;
+        }
+        write!(f, "{:<30} {:>37}", "total", self.total)
+    }
+}
+
+impl Builder {
+    /// Estimates the cost of the circuit under the default `CostModel`.
+    pub fn estimate_cost(&self) -> CostReport {
+        self.estimate_cost_with(&CostModel::default())
+    }
+
+    /// Estimates the cost of the circuit under `model`, see the `cost` module.
+    ///
+    /// # Returns
+    ///
+    /// The gates and cost of every scope that has any, most expensive first and ties by path.
+    pub fn estimate_cost_with(&self, model: &CostModel) -> CostReport {
+        let mut scopes: Vec<ScopeCost> = self
+            .scopes
+            .iter()
+            .map(|scope| ScopeCost {
+                scope: scope.clone(),
+                ..ScopeCost::default()
+            })
+            .collect();
+        for constraint in &self.constraints {
+            let scope = &mut scopes[self.constraint_scope(constraint)];
+            match constraint {
+                Constraints::Add(..) => scope.adds += 1,
+                Constraints::Mul(..) => scope.muls += 1,
+                Constraints::Eq(..) => scope.equalities += 1,
+            }
+        }
+        for (wire, _) in &self.lookups {
+            scopes[self.node_scopes[*wire]].lookups += 1;
+        }
+        let mut scopes: Vec<ScopeCost> = scopes
+            .into_iter()
+            .filter(|scope| scope.adds + scope.muls + scope.equalities + scope.lookups > 0)
+            .map(|scope| ScopeCost {
+                cost: scope.adds as u64 * model.add
+                    + scope.muls as u64 * model.mul
+                    + scope.equalities as u64 * model.equality
+                    + scope.lookups as u64 * model.lookup,
+                ..scope
+            })
+            .collect();
+        scopes.sort_by(|a, b| b.cost.cmp(&a.cost).then_with(|| a.scope.cmp(&b.scope)));
+        CostReport {
+            total: scopes.iter().map(|scope| scope.cost).sum(),
+            scopes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_lookups_are_charged_to_the_wire_scope() {
+        let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+        let mut table = builder.range_table(2);
+        let wire = builder.scope("wire", |b| b.init());
+        table.lookup(&wire);
+        let challenge = builder.init();
+        builder.scope("table", |b| table.finish(b, &challenge));
+
+        let free_gates = CostModel {
+            add: 0,
+            mul: 0,
+            equality: 0,
+            lookup: 5,
+        };
+        let report = builder.estimate_cost_with(&free_gates);
+        assert_eq!(report.total, 5);
+        assert_eq!(report.scopes[0].scope, "wire");
+        assert_eq!(report.scopes[0].lookups, 1);
+        assert_eq!(report.scopes[1].scope, "table");
+        assert!(report.scopes[1].muls > 0);
+    }
+}
@@ -0,0 +1,249 @@
+//! Removal of duplicate gates and constraints.
+//!
+//! Building the same expression twice creates two identical gates, each with its own
+//! constraint. `Builder::dedup` rebuilds the circuit with every gate computed only once: gates
+//! with the same operation on the same operands are merged into one wire, and so are equal
+//! constants. Additions and multiplications commute, so `a * b` and `b * a` are merged too.
+//! Equality assertions that became duplicates, or that compare a wire with itself, are dropped.
+//! The rebuilt circuit accepts exactly the same inputs and computes the same outputs.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let y = builder.init();
+//! let first = builder.mul(x.clone(), y.clone());
+//! let second = builder.mul(y, x);
+//! let sum = builder.add(first, second);
+//! builder.output(sum);
+//!
+//! let (deduplicated, report) = builder.dedup();
+//! // the second product and its constraint are gone
+//! assert_eq!((report.nodes_before, report.nodes_after), (5, 4));
+//! assert_eq!((report.constraints_before, report.constraints_after), (3, 2));
+//! assert_eq!(deduplicated.evaluate(vec![3, 4]), builder.evaluate(vec![3, 4]));
+//! ```
+use crate::enums::{Constraints, Operation};
+use crate::hint::Hint;
+use crate::{Builder, Node};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use tracing::info;
+
+/// How much `Builder::dedup` shrank a circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DedupReport {
+    pub nodes_before: usize,
+    pub nodes_after: usize,
+    pub constraints_before: usize,
+    pub constraints_after: usize,
+}
+
+impl Display for DedupReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "nodes: {} -> {}, constraints: {} -> {}",
+            self.nodes_before, self.nodes_after, self.constraints_before, self.constraints_after
+        )
+    }
+}
+
+impl Builder {
+    /// Rebuilds the circuit without duplicate gates, constants and equality assertions.
+    ///
+    /// Nodes returned by this builder do not refer to the rebuilt circuit. Scopes, constraint
+    /// groups, range table lookups, labels, the field and the logger carry over.
+    ///
+    /// # Returns
+    ///
+    /// The rebuilt circuit and how much smaller it is.
+    pub fn dedup(&self) -> (Builder, DedupReport) {
+        let mut circuit = self.empty_like();
+        circuit.groups = self.groups.clone();
+        circuit.disabled_groups = self.disabled_groups.clone();
+        let mut gate_groups = vec![None; self.full_graph.len()];
+        for (constraint, group) in self.constraints.iter().zip(&self.constraint_groups) {
+            if let Constraints::Add(_, _, out) | Constraints::Mul(_, _, out) = *constraint {
+                if self.zero_assertion(constraint).is_none() {
+                    gate_groups[out] = *group;
+                }
+            }
+        }
+        let mut nodes: Vec<Node> = Vec::with_capacity(self.full_graph.len());
+        let mut constants: HashMap<u64, Node> = HashMap::new();
+        let mut gates: HashMap<(bool, usize, usize), Node> = HashMap::new();
+        for node in &self.full_graph {
+            circuit.current_scope = self.node_scopes[node.id];
+            circuit.current_group = gate_groups[node.id];
+            let new = match node.operation {
+                Operation::Input if self.private_inputs.contains(&node.id) => {
+                    circuit.init_private()
+                }
+                Operation::Input => circuit.init(),
+                Operation::Constant(value) => constants
+                    .entry(value)
+                    .or_insert_with(|| circuit.constant(value))
+                    .clone(),
+                Operation::Add(a, b) | Operation::Mul(a, b) => {
+                    let is_mul = matches!(node.operation, Operation::Mul(..));
+                    let (a, b) = (nodes[a].clone(), nodes[b].clone());
+                    let key = (is_mul, a.id.min(b.id), a.id.max(b.id));
+                    gates
+                        .entry(key)
+                        .or_insert_with(|| match is_mul {
+                            true => circuit.mul(a, b),
+                            false => circuit.add(a, b),
+                        })
+                        .clone()
+                }
+                // hints may be impure, so they are never merged
+                Operation::Hint(index) => {
+                    let hint = &self.hints[index];
+                    circuit.push_hint(Hint {
+                        inputs: hint.inputs.iter().map(|id| nodes[*id].id).collect(),
+                        compute: hint.compute.clone(),
+                    })
+                }
+            };
+            nodes.push(new);
+        }
+        circuit.current_scope = 0;
+
+        let mut equalities = HashSet::new();
+        let mut zeros = HashSet::new();
+        for (constraint, group) in self.constraints.iter().zip(&self.constraint_groups) {
+            circuit.current_group = *group;
+            if let Constraints::Eq(a, b) = *constraint {
+                let (a, b) = (nodes[a].clone(), nodes[b].clone());
+                if a.id != b.id && equalities.insert((a.id.min(b.id), a.id.max(b.id))) {
+                    circuit.assert_equal(a, b);
+                }
+            } else if let Some(a) = self.zero_assertion(constraint) {
+                if zeros.insert(nodes[a].id) {
+                    // every lowered zero assertion shares one pair of constants
+                    let mut constant = |value| {
+                        constants
+                            .entry(value)
+                            .or_insert_with(|| circuit.constant(value))
+                            .clone()
+                    };
+                    let (one, zero) = (constant(1), constant(0));
+                    circuit.push_zero_assertion(nodes[a].clone(), one, zero);
+                }
+            }
+        }
+        circuit.current_group = None;
+        for (wire, bits) in &self.lookups {
+            circuit.push_lookup(nodes[*wire].id, *bits);
+        }
+        for (id, label) in &self.labels {
+            circuit.labels.insert(nodes[*id].id, label.clone());
+        }
+        for output in &self.outputs {
+            circuit.output(nodes[*output].clone());
+        }
+
+        let report = DedupReport {
+            nodes_before: self.full_graph.len(),
+            nodes_after: circuit.full_graph.len(),
+            constraints_before: self.constraints.len(),
+            constraints_after: circuit.constraints.len(),
+        };
+        info!(%report, "deduplicated circuit");
+        (circuit, report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+    use crate::testing::{random_circuit, CircuitConfig, TestRng};
+
+    #[test]
+    fn test_dedup_keeps_semantics() {
+        let mut rng = TestRng::new(21);
+        let config = CircuitConfig {
+            inputs: 1,
+            private_inputs: 1,
+            constants: 1,
+            max_value: 3,
+            assertions: 2,
+            ..CircuitConfig::default()
+        };
+        for _ in 0..30 {
+            let random = random_circuit(&mut rng, &config);
+            let (deduplicated, report) = random.circuit.dedup();
+            assert!(report.constraints_after <= report.constraints_before);
+            let sets = vec![random.inputs, vec![1, 2]];
+            let results = random.circuit.fill_many(&sets);
+            for (result, deduplicated) in results.iter().zip(deduplicated.fill_many(&sets)) {
+                assert_eq!(result.outputs, deduplicated.outputs);
+                assert_eq!(result.satisfied, deduplicated.satisfied);
+            }
+        }
+    }
+
+    #[test]
+    fn test_duplicate_assertions_are_dropped() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let five = builder.constant(5);
+        let other_five = builder.constant(5);
+        builder.assert_equal(x.clone(), five.clone());
+        builder.assert_equal(other_five, x.clone());
+        builder.assert_equal(five.clone(), five);
+
+        let (deduplicated, report) = builder.dedup();
+        assert_eq!(report.nodes_after, 2);
+        assert_eq!(deduplicated.constraints, vec![Constraints::Eq(0, 1)]);
+    }
+
+    #[test]
+    fn test_lowered_zero_assertions_share_constants() {
+        let mut builder = Builder::new_in_field(Field::new(101));
+        builder.lower_equalities(true);
+        let x = builder.init();
+        let y = builder.init();
+        builder.assert_zero(x.clone());
+        builder.assert_zero(y);
+        builder.assert_zero(x);
+
+        let (mut deduplicated, report) = builder.dedup();
+        // two inputs, one and zero
+        assert_eq!(report.nodes_after, 4);
+        assert_eq!(report.constraints_after, 2);
+        deduplicated.fill_nodes(vec![0, 0]);
+        assert!(deduplicated.check_constraints());
+        deduplicated.fill_nodes(vec![0, 1]);
+        assert!(!deduplicated.check_constraints());
+    }
+
+    #[test]
+    fn test_lookups_labels_and_groups_carry_over() {
+        let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+        let mut table = builder.range_table(4);
+        let x = builder.init();
+        let first = builder.add_const(x.clone(), 3);
+        let second = builder.add_const(x, 3);
+        table.lookup(&second);
+        builder.label(&first, "first");
+        let challenge = builder.init();
+        builder.label(&challenge, "challenge");
+        builder.group("table", |b| table.finish(b, &challenge));
+        builder.disable_group("table");
+
+        let (mut deduplicated, _) = builder.dedup();
+        assert_eq!(deduplicated.lookups.len(), 1);
+        let (wire, bits) = deduplicated.lookups[0];
+        assert_eq!(deduplicated.labels[&wire], "first");
+        assert_eq!(bits, 4);
+        assert_eq!(deduplicated.skipped_groups(), vec!["table"]);
+        deduplicated.fill_nodes(vec![13, 5]);
+        assert!(deduplicated.check_constraints());
+        deduplicated.enable_group("table");
+        assert!(!deduplicated.check_constraints());
+    }
+}
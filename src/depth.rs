@@ -0,0 +1,86 @@
+//! Multiplicative depth of circuits.
+//!
+//! The multiplicative depth of a node is the largest number of multiplications on a path from
+//! the inputs to the node. It bounds the noise growth in FHE schemes and the degree of the
+//! polynomials a proof system has to handle, so reducing it is a common optimization goal.
+//! Multiplications by a value that depends on no input are scalings and do not count.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! // x^4 computed naively and by repeated squaring
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let x2 = builder.mul(x.clone(), x.clone());
+//! let x3 = builder.mul(x2.clone(), x.clone());
+//! let naive = builder.mul(x3, x);
+//! let squared = builder.mul(x2.clone(), x2);
+//! builder.output(naive);
+//! builder.output(squared);
+//!
+//! assert_eq!(builder.mul_depth(), vec![3, 2]);
+//! ```
+use crate::enums::Operation;
+use crate::Builder;
+
+impl Builder {
+    /// Returns the multiplicative depth of every output, in the order they were registered.
+    pub fn mul_depth(&self) -> Vec<usize> {
+        let depths = self.node_mul_depths();
+        self.outputs.iter().map(|id| depths[*id]).collect()
+    }
+
+    /// Returns the multiplicative depth of every node, indexed by id.
+    ///
+    /// Nodes deep in the circuit that feed many others are good candidates for restructuring,
+    /// for example by balancing a chain of multiplications into a tree.
+    pub fn node_mul_depths(&self) -> Vec<usize> {
+        let mut depths: Vec<usize> = Vec::with_capacity(self.full_graph.len());
+        // whether a node depends on any input
+        let mut variable: Vec<bool> = Vec::with_capacity(self.full_graph.len());
+        for node in &self.full_graph {
+            let (depth, is_variable) = match node.operation {
+                Operation::Input => (0, true),
+                Operation::Constant(_) => (0, false),
+                // hints are computed outside of the circuit, like inputs
+                Operation::Hint(index) => {
+                    (0, self.hint_inputs(index).iter().any(|id| variable[*id]))
+                }
+                Operation::Add(a, b) => (depths[a].max(depths[b]), variable[a] || variable[b]),
+                Operation::Mul(a, b) => {
+                    let depth = depths[a].max(depths[b]);
+                    match (variable[a], variable[b]) {
+                        (true, true) => (depth + 1, true),
+                        (a, b) => (depth, a || b),
+                    }
+                }
+            };
+            depths.push(depth);
+            variable.push(is_variable);
+        }
+        depths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaling_is_free() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let three = builder.constant(3);
+        let nine = builder.mul(three.clone(), three);
+        let scaled = builder.mul(x.clone(), nine.clone());
+        let square = builder.mul(scaled.clone(), x);
+        let sum = builder.add(square.clone(), scaled.clone());
+        builder.output(sum.clone());
+
+        let depths = builder.node_mul_depths();
+        assert_eq!(depths[nine.id], 0);
+        assert_eq!(depths[scaled.id], 0);
+        assert_eq!(depths[square.id], 1);
+        assert_eq!(builder.mul_depth(), vec![1]);
+    }
+}
@@ -0,0 +1,207 @@
+//! Auditing hints for nondeterminism.
+//!
+//! A hint runs arbitrary Rust code, and nothing stops that code from reading a clock, a random
+//! number generator or a global counter. The constraints around a good hint usually still hold,
+//! so such a hint goes unnoticed, but the witness it produces changes from run to run: proofs of
+//! the same statement are no longer reproducible, a witness cannot be regenerated to debug a
+//! failing proof, and two parties computing the same witness disagree. Witness generation must
+//! therefore be a deterministic function of the inputs.
+//!
+//! `Builder::record_hints` evaluates the circuit and records what every hint read and returned.
+//! `HintRecording::replay` calls every hint again on the recorded values and reports the hints
+//! whose value changed; `Builder::audit_hints` does both at once. A recording can also be kept
+//! and replayed later, for instance against the same circuit built by another run.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let half = builder.hint(&[&x], |values| values[0] / 2);
+//! let calls = AtomicU64::new(0);
+//! let noisy = builder.hint(&[&x], move |values| values[0] + calls.fetch_add(1, Ordering::Relaxed));
+//! builder.output(half);
+//! builder.output(noisy.clone());
+//!
+//! let flagged = builder.audit_hints(&[10]);
+//! assert_eq!(flagged.len(), 1);
+//! assert_eq!(flagged[0].node, noisy.id);
+//! ```
+use crate::enums::Operation;
+use crate::evaluator::Evaluator;
+use crate::Builder;
+use std::fmt::{Display, Formatter};
+use tracing::{info, warn};
+
+/// What one hint read and returned during an evaluation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HintRecord {
+    /// The id of the hint node.
+    pub node: usize,
+    /// The values of the nodes the hint read, in order.
+    pub inputs: Vec<u64>,
+    /// The value of the hint.
+    pub output: u64,
+}
+
+/// The values of every hint of a circuit during one evaluation, see `Builder::record_hints`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HintRecording {
+    records: Vec<HintRecord>,
+}
+
+/// A hint that returned a different value when called again on the same values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NondeterministicHint {
+    /// The id of the hint node.
+    pub node: usize,
+    /// The values the hint read.
+    pub inputs: Vec<u64>,
+    /// The value in the recording.
+    pub recorded: u64,
+    /// The value when called again.
+    pub replayed: u64,
+}
+
+impl Display for NondeterministicHint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "hint {} returned {} and then {} for inputs {:?}",
+            self.node, self.recorded, self.replayed, self.inputs
+        )
+    }
+}
+
+impl HintRecording {
+    /// Returns the records, in order of node id.
+    pub fn records(&self) -> &[HintRecord] {
+        &self.records
+    }
+
+    /// Calls the hints of `circuit` again on the recorded values and compares their values.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit`: The circuit the recording was made from, or one with the same hints.
+    ///
+    /// # Returns
+    ///
+    /// The hints whose value differs from the recording, in order of node id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a recorded node is not a hint of `circuit`.
+    pub fn replay(&self, circuit: &Builder) -> Vec<NondeterministicHint> {
+        let flagged: Vec<NondeterministicHint> = self
+            .records
+            .iter()
+            .filter_map(|record| {
+                let index = match circuit.full_graph.get(record.node).map(|n| n.operation) {
+                    Some(Operation::Hint(index)) => index,
+                    _ => panic!("node {} is not a hint of the circuit", record.node),
+                };
+                let replayed = circuit.reduce((circuit.hints[index].compute)(&record.inputs));
+                (replayed != record.output).then(|| NondeterministicHint {
+                    node: record.node,
+                    inputs: record.inputs.clone(),
+                    recorded: record.output,
+                    replayed,
+                })
+            })
+            .collect();
+        for hint in &flagged {
+            warn!(node = hint.node, "{}", hint);
+        }
+        info!(
+            hints = self.records.len(),
+            nondeterministic = flagged.len(),
+            "replayed hints"
+        );
+        flagged
+    }
+}
+
+impl Builder {
+    /// Evaluates the circuit and records the values every hint read and returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `input`: Values for the input nodes, in order of creation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of inputs does not match the circuit.
+    pub fn record_hints(&self, input: &[u64]) -> HintRecording {
+        let witness = Evaluator::new(self).evaluate(input).witness;
+        let mut values = vec![0; self.full_graph.len()];
+        for (position, id) in self.witness_layout().into_iter().enumerate() {
+            values[id] = witness[position + 1];
+        }
+        let records = self
+            .full_graph
+            .iter()
+            .filter_map(|node| match node.operation {
+                Operation::Hint(index) => Some(HintRecord {
+                    node: node.id,
+                    inputs: self.hints[index]
+                        .inputs
+                        .iter()
+                        .map(|id| values[*id])
+                        .collect(),
+                    output: values[node.id],
+                }),
+                _ => None,
+            })
+            .collect();
+        HintRecording { records }
+    }
+
+    /// Runs every hint twice on the values of one evaluation and returns the hints whose values
+    /// differ, see the `determinism` module.
+    ///
+    /// An empty result does not prove the hints deterministic: a hint may only misbehave on other
+    /// inputs or in other runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of inputs does not match the circuit.
+    pub fn audit_hints(&self, input: &[u64]) -> Vec<NondeterministicHint> {
+        self.record_hints(input).replay(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_recording_replays_in_field() {
+        let field = Field::new(17);
+        let mut builder = Builder::new_in_field(field);
+        let x = builder.init();
+        let inverse = builder.hint(&[&x], move |values| field.inv(values[0]).unwrap_or(0));
+        let shifted = builder.hint(&[&x, &inverse], |values| values[0] + values[1] + 17);
+        builder.output(shifted);
+
+        let recording = builder.record_hints(&[3]);
+        assert_eq!(
+            recording.records(),
+            &[
+                HintRecord {
+                    node: inverse.id,
+                    inputs: vec![3],
+                    output: 6
+                },
+                HintRecord {
+                    node: inverse.id + 1,
+                    inputs: vec![3, 6],
+                    output: 9
+                }
+            ]
+        );
+        assert!(recording.replay(&builder).is_empty());
+    }
+}
@@ -0,0 +1,393 @@
+//! Structural comparison of two circuits.
+//!
+//! `Builder::diff` matches the nodes and constraints of two circuits by their structure rather
+//! than by their ids, so a refactor of gadget code that only reorders gates produces an empty
+//! diff. Constants are matched by their position among the constants of a circuit, which lets a
+//! changed constant show up as such instead of as a removed and an added gate.
+//!
+//! `Builder::fingerprint` condenses the same structure, constants included, into a single word
+//! that can be pinned in a test to catch accidental changes to a gadget.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut before = Builder::new();
+//! let x = before.init();
+//! let two = before.constant(2);
+//! let _ = before.mul(x, two);
+//!
+//! let mut after = Builder::new();
+//! let x = after.init();
+//! let three = after.constant(3);
+//! let product = after.mul(x.clone(), three);
+//! let _ = after.add(product, x);
+//!
+//! let diff = before.diff(&after);
+//! assert_eq!(diff.changed_constants, vec![("c0".to_string(), 2, 3)]);
+//! assert_eq!(diff.added_gates, vec!["w3 = add(w2, x0)"]);
+//! assert!(diff.removed_gates.is_empty());
+//! assert_eq!(diff.added_constraints, vec!["w2 + x0 = w3"]);
+//! assert!(diff.removed_constraints.is_empty());
+//! assert_eq!(
+//!     diff.to_string(),
+//!     "+ gate w3 = add(w2, x0)\n+ constraint w2 + x0 = w3\n~ constant c0: 2 -> 3\n"
+//! );
+//! ```
+use crate::enums::{Constraints, Operation};
+use crate::hash::hash_words;
+use crate::Builder;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// The structural differences between two circuits.
+///
+/// Gates and constraints are described with the wire names of the circuit they belong to, see
+/// `Builder::fill_nodes_explained`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CircuitDiff {
+    /// Nodes only present in the other circuit, including inputs and constants.
+    pub added_gates: Vec<String>,
+    /// Nodes only present in this circuit, including inputs and constants.
+    pub removed_gates: Vec<String>,
+    pub added_constraints: Vec<String>,
+    pub removed_constraints: Vec<String>,
+    /// Constants present in both circuits with different values, as `(name, old, new)`.
+    pub changed_constants: Vec<(String, u64, u64)>,
+}
+
+impl CircuitDiff {
+    /// Returns `true` if both circuits have the same structure and constants.
+    pub fn is_empty(&self) -> bool {
+        self.added_gates.is_empty()
+            && self.removed_gates.is_empty()
+            && self.added_constraints.is_empty()
+            && self.removed_constraints.is_empty()
+            && self.changed_constants.is_empty()
+    }
+}
+
+impl Display for CircuitDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for gate in &self.removed_gates {
+            writeln!(f, "- gate {}", gate)?;
+        }
+        for gate in &self.added_gates {
+            writeln!(f, "+ gate {}", gate)?;
+        }
+        for constraint in &self.removed_constraints {
+            writeln!(f, "- constraint {}", constraint)?;
+        }
+        for constraint in &self.added_constraints {
+            writeln!(f, "+ constraint {}", constraint)?;
+        }
+        for (name, old, new) in &self.changed_constants {
+            writeln!(f, "~ constant {}: {} -> {}", name, old, new)?;
+        }
+        Ok(())
+    }
+}
+
+impl Builder {
+    /// Compares the structure of this circuit with `other`.
+    ///
+    /// # Arguments
+    ///
+    /// * `other`: The circuit to compare against, usually a refactored version of this one.
+    ///
+    /// # Returns
+    ///
+    /// What has to change to turn this circuit into `other`.
+    pub fn diff(&self, other: &Builder) -> CircuitDiff {
+        let (keys, other_keys) = (self.node_keys(false), other.node_keys(false));
+        let constraint_keys = self.constraint_keys(&keys);
+        let other_constraint_keys = other.constraint_keys(&other_keys);
+
+        let constants = self.constant_values();
+        let changed_constants = other
+            .constant_values()
+            .iter()
+            .zip(constants)
+            .enumerate()
+            .filter(|(_, (new, old))| *new != old)
+            .map(|(index, (new, old))| (format!("c{}", index), old, *new))
+            .collect();
+
+        CircuitDiff {
+            added_gates: unmatched(&keys, &other_keys)
+                .map(|id| other.describe_gate(id))
+                .collect(),
+            removed_gates: unmatched(&other_keys, &keys)
+                .map(|id| self.describe_gate(id))
+                .collect(),
+            added_constraints: unmatched(&constraint_keys, &other_constraint_keys)
+                .map(|index| other.describe_constraint(&other.constraints[index]))
+                .collect(),
+            removed_constraints: unmatched(&other_constraint_keys, &constraint_keys)
+                .map(|index| self.describe_constraint(&self.constraints[index]))
+                .collect(),
+            changed_constants,
+        }
+    }
+
+    /// Computes a hash of the circuit's structure that does not depend on the order in which
+    /// gates and constraints were created.
+    ///
+    /// Two circuits have the same fingerprint if they compute in the same field, compute the same
+    /// gates from the same inputs and constants, assert the same constraints and have the same
+    /// outputs in the same order. The code of a hint cannot be compared, so hints count as the
+    /// same if they read the same nodes and were created at the same position among the hints.
+    /// Values assigned by `fill_nodes` are ignored. Like every hash in this crate it is not
+    /// cryptographic.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut builder = Builder::new();
+    /// let x = builder.init();
+    /// let five = builder.constant(5);
+    /// let sum = builder.add(x, five);
+    /// builder.output(sum);
+    /// assert_eq!(builder.fingerprint(), builder.clone().fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let keys = self.node_keys(true);
+        let mut nodes = keys.clone();
+        nodes.sort_unstable();
+        let mut constraints = self.constraint_keys(&keys);
+        constraints.sort_unstable();
+        let outputs = self.outputs.iter().map(|id| keys[*id]);
+
+        let modulus = self.field.map_or(0, |field| field.modulus());
+        let mut words = vec![modulus, nodes.len() as u64];
+        words.extend(nodes);
+        words.push(constraints.len() as u64);
+        words.extend(constraints);
+        words.push(self.outputs.len() as u64);
+        words.extend(outputs);
+        hash_words(&words)
+    }
+
+    /// Hashes every node together with the nodes it is built from.
+    ///
+    /// Inputs are keyed by their position and visibility, constants by their value if
+    /// `constant_values` is set and by their position among the constants otherwise. Operands of
+    /// additions and multiplications are sorted, since both commute. Hints are keyed by their
+    /// position among the hints and their inputs, in order.
+    pub(crate) fn node_keys(&self, constant_values: bool) -> Vec<u64> {
+        let mut keys: Vec<u64> = Vec::with_capacity(self.full_graph.len());
+        let (mut inputs, mut constants) = (0, 0);
+        for node in &self.full_graph {
+            let words = match node.operation {
+                Operation::Input => {
+                    inputs += 1;
+                    vec![0, inputs - 1, self.private_inputs.contains(&node.id) as u64]
+                }
+                Operation::Constant(value) if constant_values => vec![1, value],
+                Operation::Constant(_) => {
+                    constants += 1;
+                    vec![1, constants - 1]
+                }
+                Operation::Add(a, b) => vec![2, keys[a].min(keys[b]), keys[a].max(keys[b])],
+                Operation::Mul(a, b) => vec![3, keys[a].min(keys[b]), keys[a].max(keys[b])],
+                Operation::Hint(index) => {
+                    let mut words = vec![7, index as u64];
+                    words.extend(self.hint_inputs(index).iter().map(|id| keys[*id]));
+                    words
+                }
+            };
+            keys.push(hash_words(&words));
+        }
+        keys
+    }
+
+    fn constraint_keys(&self, keys: &[u64]) -> Vec<u64> {
+        self.constraints
+            .iter()
+            .map(|constraint| {
+                let mut ids: Vec<u64> = constraint.ids().iter().map(|id| keys[*id]).collect();
+                match constraint {
+                    Constraints::Add(..) | Constraints::Mul(..) => ids[..2].sort_unstable(),
+                    Constraints::Eq(..) => ids.sort_unstable(),
+                }
+                let kind = match constraint {
+                    Constraints::Add(..) => 0,
+                    Constraints::Mul(..) => 1,
+                    Constraints::Eq(..) => 2,
+                };
+                hash_words(&[&[kind][..], &ids].concat())
+            })
+            .collect()
+    }
+
+    fn constant_values(&self) -> Vec<u64> {
+        self.full_graph
+            .iter()
+            .filter_map(|node| match node.operation {
+                Operation::Constant(value) => Some(value),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub(crate) fn describe_gate(&self, id: usize) -> String {
+        let name = self.wire_name(id);
+        match self.full_graph[id].operation {
+            Operation::Input if self.private_inputs.contains(&id) => {
+                format!("private input {}", name)
+            }
+            Operation::Input => format!("input {}", name),
+            Operation::Constant(value) => format!("constant {} = {}", name, value),
+            Operation::Add(a, b) => {
+                format!(
+                    "{} = add({}, {})",
+                    name,
+                    self.wire_name(a),
+                    self.wire_name(b)
+                )
+            }
+            Operation::Mul(a, b) => {
+                format!(
+                    "{} = mul({}, {})",
+                    name,
+                    self.wire_name(a),
+                    self.wire_name(b)
+                )
+            }
+            Operation::Hint(index) => {
+                let inputs: Vec<String> = self
+                    .hint_inputs(index)
+                    .iter()
+                    .map(|id| self.wire_name(*id))
+                    .collect();
+                format!("{} = hint({})", name, inputs.join(", "))
+            }
+        }
+    }
+
+    fn describe_constraint(&self, constraint: &Constraints) -> String {
+        match *constraint {
+            Constraints::Add(a, b, c) => format!(
+                "{} + {} = {}",
+                self.wire_name(a),
+                self.wire_name(b),
+                self.wire_name(c)
+            ),
+            Constraints::Mul(a, b, c) => format!(
+                "{} * {} = {}",
+                self.wire_name(a),
+                self.wire_name(b),
+                self.wire_name(c)
+            ),
+            Constraints::Eq(a, b) => format!("{} = {}", self.wire_name(a), self.wire_name(b)),
+        }
+    }
+}
+
+/// Returns the positions of the keys in `theirs` that have no counterpart in `ours`, matching
+/// repeated keys one to one.
+fn unmatched<'a>(ours: &[u64], theirs: &'a [u64]) -> impl Iterator<Item = usize> + 'a {
+    let mut available: HashMap<u64, usize> = HashMap::new();
+    ours.iter()
+        .for_each(|key| *available.entry(*key).or_default() += 1);
+    theirs
+        .iter()
+        .enumerate()
+        .filter(move |(_, key)| match available.get_mut(key) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .map(|(position, _)| position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reordered_circuits_have_no_diff() {
+        let mut before = Builder::new();
+        let x = before.init();
+        let y = before.init();
+        let square = before.mul(x.clone(), x.clone());
+        let sum = before.add(x, y.clone());
+        before.assert_equal(square, sum);
+
+        let mut after = Builder::new();
+        let x = after.init();
+        let y = after.init();
+        let sum = after.add(y, x.clone());
+        let square = after.mul(x.clone(), x);
+        after.assert_equal(sum, square);
+
+        assert!(before.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let build = |constant: u64, swap: bool| {
+            let mut builder = Builder::new();
+            let x = builder.init();
+            let c = builder.constant(constant);
+            let (a, b) = match swap {
+                true => (c.clone(), x.clone()),
+                false => (x.clone(), c.clone()),
+            };
+            let product = builder.mul(a, b);
+            let square = builder.mul(x.clone(), x);
+            let sum = builder.add(product, square);
+            builder.output(sum);
+            builder
+        };
+        assert_eq!(build(3, false).fingerprint(), build(3, true).fingerprint());
+        assert_ne!(build(3, false).fingerprint(), build(4, false).fingerprint());
+
+        let mut filled = build(3, false);
+        filled.fill_nodes(vec![2]);
+        assert_eq!(filled.fingerprint(), build(3, false).fingerprint());
+
+        let mut more_outputs = build(3, false);
+        more_outputs.output(more_outputs.outputs()[0].clone());
+        assert_ne!(more_outputs.fingerprint(), build(3, false).fingerprint());
+    }
+
+    #[test]
+    fn test_hints_with_the_same_inputs_differ() {
+        // outputs the bits of x in either order
+        let build = |swap: bool| {
+            let mut builder = Builder::new();
+            let x = builder.init();
+            let low = builder.hint(&[&x], |values| values[0] & 1);
+            let high = builder.hint(&[&x], |values| values[0] >> 1 & 1);
+            let (first, second) = match swap {
+                true => (high, low),
+                false => (low, high),
+            };
+            builder.output(first);
+            builder.output(second);
+            builder
+        };
+        assert_eq!(build(false).fingerprint(), build(false).fingerprint());
+        assert_ne!(build(false).fingerprint(), build(true).fingerprint());
+    }
+
+    #[test]
+    fn test_removed_constraint() {
+        let mut before = Builder::new();
+        let x = before.init();
+        let y = before.init();
+        before.assert_equal(x, y);
+
+        let mut after = Builder::new();
+        let _ = after.init();
+        let _ = after.init();
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.removed_constraints, vec!["x0 = x1"]);
+        assert!(diff.added_gates.is_empty() && diff.removed_gates.is_empty());
+        assert_eq!(after.diff(&before).added_constraints, vec!["x0 = x1"]);
+    }
+}
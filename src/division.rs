@@ -0,0 +1,101 @@
+//! Integer division with remainder.
+//!
+//! Gates can only add and multiply, so `Builder::div_rem` hints the quotient and remainder and
+//! proves them correct: `a = q * b + r` together with `r < b` pins both down uniquely, as long
+//! as nothing wraps around. The operands, quotient and remainder are all range checked to the
+//! bit width, which rules out wrapping, and `r < b` is a comparison, so a division costs about
+//! `9w` constraints for `w`-bit values.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let a = builder.init();
+//! let b = builder.init();
+//! let (quotient, remainder) = builder.div_rem(a, b, 16);
+//! builder.output(quotient);
+//! builder.output(remainder);
+//! assert_eq!(builder.evaluate(vec![1000, 7]), vec![142, 6]);
+//! ```
+use crate::gadgets::{check_field_bits, less_than, to_bits};
+use crate::{Builder, Node};
+
+impl Builder {
+    /// Divides `a` by `b`, rounding down.
+    ///
+    /// Division by zero, or an operand of more than `bit_width` bits, leaves the constraints
+    /// unsatisfiable.
+    ///
+    /// # Arguments
+    ///
+    /// * `a`: The dividend, range checked to be below `2^bit_width`.
+    /// * `b`: The divisor, range checked to be below `2^bit_width`.
+    /// * `bit_width`: The number of bits of the values.
+    ///
+    /// # Returns
+    ///
+    /// The quotient and the remainder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `2 * bit_width + 1` is 63 or more, or the circuit computes in a field with at
+    /// most `2^(2 * bit_width + 1)` elements, since `q * b + r` could wrap around.
+    pub fn div_rem(&mut self, a: Node, b: Node, bit_width: usize) -> (Node, Node) {
+        let product_bits = 2 * bit_width + 1;
+        if product_bits >= 63 {
+            panic!(
+                "dividing {}-bit values needs room for {} bits",
+                bit_width, product_bits
+            );
+        }
+        check_field_bits(
+            self,
+            product_bits,
+            &format!("dividing {}-bit values", bit_width),
+        );
+        let quotient = self.hint(&[&a, &b], |values| {
+            values[0].checked_div(values[1]).unwrap_or(0)
+        });
+        let remainder = self.hint(&[&a, &b], |values| {
+            values[0].checked_rem(values[1]).unwrap_or(values[0])
+        });
+        to_bits(self, &a, bit_width);
+        to_bits(self, &b, bit_width);
+        to_bits(self, &quotient, bit_width);
+        to_bits(self, &remainder, bit_width);
+
+        let product = self.mul(quotient.clone(), b.clone());
+        let sum = self.add(product, remainder.clone());
+        self.assert_equal(sum, a);
+        let less = less_than(self, &remainder, &b, bit_width);
+        let one = self.constant(1);
+        self.assert_equal(less, one);
+        (quotient, remainder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_div_rem_in_field() {
+        let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+        let a = builder.init();
+        let b = builder.init();
+        let (quotient, remainder) = builder.div_rem(a, b, 12);
+        builder.output(quotient);
+        builder.output(remainder);
+        for (a, b) in [(0, 1), (4095, 1), (4095, 4095), (100, 7), (6, 100)] {
+            builder.fill_nodes(vec![a, b]);
+            assert!(builder.check_constraints());
+            assert_eq!(builder.evaluate(vec![a, b]), vec![a / b, a % b]);
+        }
+        builder.fill_nodes(vec![5, 0]);
+        assert!(!builder.check_constraints());
+        // a divisor beyond the bit width would make any remainder below it acceptable
+        builder.fill_nodes(vec![5, 1 << 12]);
+        assert!(!builder.check_constraints());
+    }
+}
@@ -0,0 +1,137 @@
+//! Evaluation domains: multiplicative subgroups of a prime field and their cosets.
+//!
+//! The nonzero elements of `F_p` form a cyclic group of order `p - 1`, so for every `n` dividing
+//! `p - 1` there is exactly one subgroup of size `n`, generated by a primitive `n`-th root of
+//! unity `ω`: `{1, ω, ω², …, ω^(n-1)}`. Polynomials evaluated on such a domain are what FFTs
+//! interpolate quickly and what FRI folds, since squaring every element of a subgroup of even
+//! size gives the subgroup of half the size. A coset `s·<ω>` shifts the subgroup by a nonzero
+//! `s`, which keeps the structure but avoids the subgroup's own elements.
+//!
+//! Domains here always have a power of two size, which is what radix-2 FFTs and FRI need.
+//!
+//! ```
+//! use arithmetic_circuit::domain::Domain;
+//! use arithmetic_circuit::field::Field;
+//!
+//! let field = Field::new(17);
+//! let domain = Domain::subgroup(field, 4);
+//! assert_eq!(domain.generator, 13);
+//! assert_eq!(domain.elements().collect::<Vec<_>>(), vec![1, 13, 16, 4]);
+//! assert_eq!(domain.square().elements().collect::<Vec<_>>(), vec![1, 16]);
+//! ```
+use crate::field::Field;
+use crate::polynomial::Polynomial;
+
+/// A coset `offset·<generator>` of a multiplicative subgroup whose size is a power of two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Domain {
+    pub field: Field,
+    pub size: usize,
+    pub generator: u64,
+    pub offset: u64,
+}
+
+impl Domain {
+    /// Creates the coset of size `size` shifted by `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is not a power of two dividing `p - 1`, or `offset` is zero.
+    pub fn new(field: Field, size: usize, offset: u64) -> Self {
+        if !size.is_power_of_two() || field.reduce(offset) == 0 {
+            panic!("a domain needs a power of two size and a non-zero offset");
+        }
+        let generator = field
+            .root_of_unity(size as u64)
+            .expect("the field has no subgroup of the requested size");
+        Domain {
+            field,
+            size,
+            generator,
+            offset: field.reduce(offset),
+        }
+    }
+
+    /// Creates the subgroup of size `size`, the coset with offset 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is not a power of two dividing `p - 1`.
+    pub fn subgroup(field: Field, size: usize) -> Self {
+        Domain::new(field, size, 1)
+    }
+
+    /// Returns the `index`-th element `offset·generator^index`.
+    pub fn element(&self, index: usize) -> u64 {
+        self.field
+            .mul(self.offset, self.field.pow(self.generator, index as u64))
+    }
+
+    /// Returns an iterator over the elements in order of their index.
+    pub fn elements(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.size).scan(self.field.reduce(self.offset), |element, _| {
+            let current = *element;
+            *element = self.field.mul(current, self.generator);
+            Some(current)
+        })
+    }
+
+    /// Returns `true` if `x` is an element of the domain.
+    ///
+    /// Every element `x` of the coset satisfies `x^size = offset^size`, and no other value does.
+    pub fn contains(&self, x: u64) -> bool {
+        let size = self.size as u64;
+        self.field.reduce(x) != 0 && self.field.pow(x, size) == self.field.pow(self.offset, size)
+    }
+
+    /// Evaluates a polynomial on every element of the domain.
+    pub fn evaluate(&self, polynomial: &Polynomial) -> Vec<u64> {
+        self.elements()
+            .map(|x| polynomial.evaluate(x, self.field))
+            .collect()
+    }
+
+    /// Returns the vanishing polynomial `x^size - offset^size`, which is zero exactly on the
+    /// elements of the domain.
+    pub fn vanishing(&self) -> Polynomial {
+        let mut coefficients = vec![0; self.size + 1];
+        coefficients[0] = self
+            .field
+            .neg(self.field.pow(self.offset, self.size as u64));
+        coefficients[self.size] = 1;
+        Polynomial::new(coefficients)
+    }
+
+    /// Returns the domain of the squares of this domain's elements, which has half the size.
+    pub fn square(&self) -> Self {
+        Domain {
+            field: self.field,
+            size: self.size / 2,
+            generator: self.field.mul(self.generator, self.generator),
+            offset: self.field.mul(self.offset, self.offset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coset_elements() {
+        let field = Field::new(97);
+        let domain = Domain::new(field, 8, 5);
+        let elements: Vec<u64> = domain.elements().collect();
+        assert_eq!(elements.len(), 8);
+        assert_eq!(field.pow(domain.generator, 8), 1);
+        assert_ne!(field.pow(domain.generator, 4), 1);
+        for (index, element) in elements.iter().enumerate() {
+            assert_eq!(*element, domain.element(index));
+            assert!(domain.contains(*element));
+        }
+        let members = (1..97).filter(|x| domain.contains(*x)).count();
+        assert_eq!(members, 8);
+        assert!(!domain.contains(0));
+        assert_eq!(domain.vanishing(), Polynomial::vanishing(&elements, field));
+    }
+}
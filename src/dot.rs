@@ -0,0 +1,465 @@
+//! Graphviz DOT export and import.
+//!
+//! `Builder::to_dot` draws the circuit as a directed graph: every node points to the gates
+//! using it, and equality assertions are dashed lines between the two nodes. The attributes of
+//! the graph describe the circuit completely, so `Builder::from_dot` can rebuild it from the
+//! output, or from a graph drawn by hand or edited with another tool, as long as it sticks to
+//! this dialect:
+//!
+//! - `field=<p>` as a graph attribute makes the circuit compute in `F_p`.
+//! - Every node has an `op` attribute: `input`, `private`, `const` together with `value=<n>`,
+//!   `add` or `mul`. The names of the nodes are free.
+//! - An `add` or `mul` node has exactly two incoming edges, its operands, in the order they
+//!   are listed. Both edges come from the same node for a square.
+//! - An edge with `eq=true` asserts that its two nodes are equal.
+//! - A node with `output=<i>` is the `i`-th output.
+//!
+//! Inputs are created in the order they are listed, followed by the other nodes, each after
+//! its operands. Hints are exported for viewing but cannot be imported, since their
+//! computation is Rust code.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let dot = r#"
+//! digraph circuit {
+//!     x [op=input];
+//!     five [op=const, value=5];
+//!     square [op=mul];
+//!     result [op=add, output=0];
+//!     x -> square;
+//!     x -> square;
+//!     square -> result;
+//!     five -> result;
+//! }
+//! "#;
+//! let builder = Builder::from_dot(dot).unwrap();
+//! assert_eq!(builder.evaluate(vec![3]), vec![14]);
+//! assert_eq!(Builder::from_dot(&builder.to_dot()).unwrap().evaluate(vec![3]), vec![14]);
+//! ```
+use crate::enums::{Constraints, Operation};
+use crate::field::{is_prime, Field};
+use crate::{Builder, Node};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Why `Builder::from_dot` could not rebuild a circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DotError {
+    /// The text is not a DOT digraph of the supported subset.
+    Syntax(String),
+    /// A node or the graph has missing or invalid attributes.
+    Invalid { node: String, reason: String },
+    /// The node depends on itself.
+    Cycle { node: String },
+}
+
+impl Display for DotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DotError::Syntax(message) => write!(f, "invalid DOT: {}", message),
+            DotError::Invalid { node, reason } => write!(f, "node {}: {}", node, reason),
+            DotError::Cycle { node } => write!(f, "node {} depends on itself", node),
+        }
+    }
+}
+
+impl Error for DotError {}
+
+impl Builder {
+    /// Returns the circuit as a Graphviz digraph in the dialect read by `from_dot`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = "digraph circuit {\n".to_string();
+        if let Some(field) = self.field {
+            dot.push_str(&format!("    field={};\n", field.modulus()));
+        }
+        for node in &self.full_graph {
+            let mut attributes = match node.operation {
+                Operation::Input if self.private_inputs.contains(&node.id) => {
+                    format!("op=private, label=\"{}\"", self.wire_name(node.id))
+                }
+                Operation::Input => format!("op=input, label=\"{}\"", self.wire_name(node.id)),
+                Operation::Constant(value) => {
+                    format!(
+                        "op=const, value={}, label=\"{}\", shape=box",
+                        value,
+                        self.term(node.id)
+                    )
+                }
+                Operation::Add(..) => format!(
+                    "op=add, label=\"{} (+)\", shape=circle",
+                    self.wire_name(node.id)
+                ),
+                Operation::Mul(..) => format!(
+                    "op=mul, label=\"{} (*)\", shape=circle",
+                    self.wire_name(node.id)
+                ),
+                Operation::Hint(_) => format!(
+                    "op=hint, label=\"{} (hint)\", shape=diamond",
+                    self.wire_name(node.id)
+                ),
+            };
+            if let Some(position) = self.outputs.iter().position(|id| *id == node.id) {
+                attributes.push_str(&format!(", output={}, peripheries=2", position));
+            }
+            dot.push_str(&format!("    n{} [{}];\n", node.id, attributes));
+        }
+        for node in &self.full_graph {
+            for operand in self.operands(&node.operation) {
+                dot.push_str(&format!("    n{} -> n{};\n", operand, node.id));
+            }
+        }
+        for constraint in &self.constraints {
+            if let Constraints::Eq(a, b) = *constraint {
+                dot.push_str(&format!(
+                    "    n{} -> n{} [eq=true, style=dashed, dir=none];\n",
+                    a, b
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Rebuilds a circuit from a digraph in the dialect written by `to_dot`, see the module
+    /// documentation.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the text cannot be parsed, a node is not a valid input, constant or gate,
+    /// including hints, or the gates depend on each other in a cycle.
+    pub fn from_dot(dot: &str) -> Result<Builder, DotError> {
+        let graph = parse(&tokenize(dot)?)?;
+        let mut builder = match graph.attribute("field") {
+            Some(value) => {
+                let modulus = value
+                    .parse()
+                    .ok()
+                    .filter(|modulus| is_prime(*modulus))
+                    .ok_or_else(|| DotError::Invalid {
+                        node: "graph".to_string(),
+                        reason: format!("field {} is not a prime", value),
+                    })?;
+                Builder::new_in_field(Field::new(modulus))
+            }
+            None => Builder::new(),
+        };
+
+        let mut operands: Vec<Vec<usize>> = vec![vec![]; graph.nodes.len()];
+        let mut equalities = vec![];
+        for (from, to, attributes) in &graph.edges {
+            let (from, to) = (graph.index(from), graph.index(to));
+            if attributes.iter().any(|(k, v)| k == "eq" && v == "true") {
+                equalities.push((from, to));
+            } else {
+                operands[to].push(from);
+            }
+        }
+
+        let mut built: Vec<Option<Node>> = vec![None; graph.nodes.len()];
+        for (index, node) in graph.nodes.iter().enumerate() {
+            match node.attribute("op") {
+                Some("input") => built[index] = Some(builder.init()),
+                Some("private") => built[index] = Some(builder.init_private()),
+                _ => {}
+            }
+        }
+        // built nodes return early, so a node still marked as visiting is on the current path
+        let mut visiting = vec![false; graph.nodes.len()];
+        for index in 0..graph.nodes.len() {
+            build(
+                &mut builder,
+                &graph,
+                &operands,
+                index,
+                &mut built,
+                &mut visiting,
+            )?;
+        }
+
+        for (a, b) in equalities {
+            let (a, b) = (built[a].clone().unwrap(), built[b].clone().unwrap());
+            builder.assert_equal(a, b);
+        }
+        let mut outputs = vec![];
+        for (index, node) in graph.nodes.iter().enumerate() {
+            if let Some(position) = node.attribute("output") {
+                let position: usize = position.parse().map_err(|_| node.invalid("bad output"))?;
+                outputs.push((position, built[index].clone().unwrap()));
+            }
+        }
+        outputs.sort_by_key(|(position, _)| *position);
+        for (_, node) in outputs {
+            builder.output(node);
+        }
+        Ok(builder)
+    }
+}
+
+/// Builds the node at `index` after its operands, unless it is built already.
+fn build(
+    builder: &mut Builder,
+    graph: &Graph,
+    operands: &[Vec<usize>],
+    index: usize,
+    built: &mut [Option<Node>],
+    visiting: &mut [bool],
+) -> Result<Node, DotError> {
+    if let Some(node) = &built[index] {
+        return Ok(node.clone());
+    }
+    let node = &graph.nodes[index];
+    if visiting[index] {
+        return Err(DotError::Cycle {
+            node: node.name.clone(),
+        });
+    }
+    visiting[index] = true;
+    let result = match node.attribute("op") {
+        Some("const") => {
+            let value = node
+                .attribute("value")
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| node.invalid("a constant needs a numeric value"))?;
+            if !operands[index].is_empty() {
+                return Err(node.invalid("a constant has no operands"));
+            }
+            builder.constant(value)
+        }
+        Some(op @ ("add" | "mul")) => {
+            let [a, b] = operands[index][..] else {
+                return Err(node.invalid("a gate needs exactly two operands"));
+            };
+            let a = build(builder, graph, operands, a, built, visiting)?;
+            let b = build(builder, graph, operands, b, built, visiting)?;
+            match op {
+                "add" => builder.add(a, b),
+                _ => builder.mul(a, b),
+            }
+        }
+        Some("hint") => return Err(node.invalid("hints cannot be imported")),
+        Some(op) => return Err(node.invalid(&format!("unknown op {}", op))),
+        None => return Err(node.invalid("missing op attribute")),
+    };
+    built[index] = Some(result.clone());
+    Ok(result)
+}
+
+/// The `key=value` pairs of a statement, in order.
+type Attributes = Vec<(String, String)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Id(String),
+    Arrow,
+    Open,
+    Close,
+    OpenBracket,
+    CloseBracket,
+    Equals,
+    Separator,
+}
+
+/// A node of the parsed graph with its attributes, merged over all its statements.
+struct DotNode {
+    name: String,
+    attributes: Attributes,
+}
+
+impl DotNode {
+    fn attribute(&self, key: &str) -> Option<&str> {
+        find(&self.attributes, key)
+    }
+
+    fn invalid(&self, reason: &str) -> DotError {
+        DotError::Invalid {
+            node: self.name.clone(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+struct Graph {
+    attributes: Attributes,
+    /// The nodes in the order they first appear.
+    nodes: Vec<DotNode>,
+    edges: Vec<(String, String, Attributes)>,
+    indices: HashMap<String, usize>,
+}
+
+impl Graph {
+    fn attribute(&self, key: &str) -> Option<&str> {
+        find(&self.attributes, key)
+    }
+
+    fn index(&self, name: &str) -> usize {
+        self.indices[name]
+    }
+
+    /// Adds a node, or the attributes to an existing one.
+    fn declare(&mut self, name: &str, attributes: Attributes) {
+        let index = *self.indices.entry(name.to_string()).or_insert_with(|| {
+            self.nodes.push(DotNode {
+                name: name.to_string(),
+                attributes: vec![],
+            });
+            self.nodes.len() - 1
+        });
+        self.nodes[index].attributes.extend(attributes);
+    }
+}
+
+/// Returns the last value of `key`, as later attributes override earlier ones in DOT.
+fn find<'a>(attributes: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .rev()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+fn tokenize(dot: &str) -> Result<Vec<Token>, DotError> {
+    let mut tokens = vec![];
+    let mut chars = dot.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().take_while(|c| *c != '\n').for_each(drop);
+            }
+            '#' => chars.by_ref().take_while(|c| *c != '\n').for_each(drop),
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '[' => tokens.push(Token::OpenBracket),
+            ']' => tokens.push(Token::CloseBracket),
+            '=' => tokens.push(Token::Equals),
+            ';' | ',' => tokens.push(Token::Separator),
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                tokens.push(Token::Arrow);
+            }
+            '"' => {
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => id.extend(chars.next()),
+                        Some(c) => id.push(c),
+                        None => return Err(DotError::Syntax("unterminated string".to_string())),
+                    }
+                }
+                tokens.push(Token::Id(id));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' => {
+                let mut id = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '.')
+                {
+                    id.push(c);
+                }
+                tokens.push(Token::Id(id));
+            }
+            c => return Err(DotError::Syntax(format!("unexpected character {:?}", c))),
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse(tokens: &[Token]) -> Result<Graph, DotError> {
+    let mut graph = Graph {
+        attributes: vec![],
+        nodes: vec![],
+        edges: vec![],
+        indices: HashMap::new(),
+    };
+    let mut rest = match tokens {
+        [Token::Id(digraph), Token::Open, rest @ ..] if digraph == "digraph" => rest,
+        [Token::Id(digraph), Token::Id(_), Token::Open, rest @ ..] if digraph == "digraph" => rest,
+        _ => return Err(DotError::Syntax("expected `digraph {`".to_string())),
+    };
+    loop {
+        rest = match rest {
+            [Token::Close] => return Ok(graph),
+            [Token::Separator, rest @ ..] => rest,
+            [Token::Id(key), Token::Equals, Token::Id(value), rest @ ..] => {
+                graph.attributes.push((key.clone(), value.clone()));
+                rest
+            }
+            [Token::Id(from), Token::Arrow, Token::Id(to), rest @ ..] => {
+                let (attributes, rest) = parse_attributes(rest)?;
+                graph.declare(from, vec![]);
+                graph.declare(to, vec![]);
+                graph.edges.push((from.clone(), to.clone(), attributes));
+                rest
+            }
+            [Token::Id(name), rest @ ..] => {
+                let (attributes, rest) = parse_attributes(rest)?;
+                // default attributes for all nodes, edges or the graph only affect drawing
+                if !["node", "edge", "graph"].contains(&name.as_str()) {
+                    graph.declare(name, attributes);
+                }
+                rest
+            }
+            _ => return Err(DotError::Syntax("unexpected statement".to_string())),
+        };
+    }
+}
+
+/// Parses an optional `[key=value, ...]` list.
+fn parse_attributes(tokens: &[Token]) -> Result<(Attributes, &[Token]), DotError> {
+    let [Token::OpenBracket, rest @ ..] = tokens else {
+        return Ok((vec![], tokens));
+    };
+    let mut rest = rest;
+    let mut attributes = vec![];
+    loop {
+        rest = match rest {
+            [Token::CloseBracket, rest @ ..] => return Ok((attributes, rest)),
+            [Token::Separator, rest @ ..] => rest,
+            [Token::Id(key), Token::Equals, Token::Id(value), rest @ ..] => {
+                attributes.push((key.clone(), value.clone()));
+                rest
+            }
+            _ => return Err(DotError::Syntax("invalid attribute list".to_string())),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_keeps_constraints() {
+        let mut builder = Builder::new_in_field(Field::new(97));
+        let secret = builder.init_private();
+        let x = builder.init();
+        let product = builder.mul(secret, x.clone());
+        let seven = builder.constant(7);
+        builder.assert_equal(product.clone(), seven);
+        builder.output(x);
+        builder.output(product);
+
+        let rebuilt = Builder::from_dot(&builder.to_dot()).unwrap();
+        assert_eq!(rebuilt.to_dot(), builder.to_dot());
+        assert_eq!(rebuilt.dump_constraints(), builder.dump_constraints());
+    }
+
+    #[test]
+    fn test_invalid_graphs() {
+        let cycle = "digraph { a [op=add]; b [op=add]; a -> b; a -> b; b -> a; b -> a; }";
+        assert!(matches!(
+            Builder::from_dot(cycle),
+            Err(DotError::Cycle { .. })
+        ));
+        let unary = "digraph { x [op=input]; y [op=mul]; x -> y; }";
+        assert!(matches!(
+            Builder::from_dot(unary),
+            Err(DotError::Invalid { .. })
+        ));
+        assert!(matches!(
+            Builder::from_dot("graph {}"),
+            Err(DotError::Syntax(_))
+        ));
+    }
+}
@@ -0,0 +1,92 @@
+//! Human-readable listing of the constraint system.
+//!
+//! `Builder::dump_constraints` writes every constraint as an equation over named wires, the way
+//! textbooks present constraint systems. Wires carry the names of the `names` module, except
+//! that constants without a label appear as their value.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let y = builder.init();
+//! let five = builder.constant(5);
+//! let square = builder.mul(x.clone(), x);
+//! let shifted = builder.add(square, five);
+//! builder.assert_equal(shifted, y);
+//!
+//! assert_eq!(builder.dump_constraints(), "w3 = x0 * x0\nw4 = w3 + 5\nw4 == x1\n");
+//! ```
+use crate::enums::{Constraints, Operation};
+use crate::Builder;
+
+impl Builder {
+    /// Returns every constraint as an equation on its own line, in the order they were
+    /// generated.
+    pub fn dump_constraints(&self) -> String {
+        self.constraints
+            .iter()
+            .map(|constraint| self.constraint_text(constraint) + "\n")
+            .collect()
+    }
+
+    /// Returns a constraint as an equation, as listed by `dump_constraints`.
+    pub(crate) fn constraint_text(&self, constraint: &Constraints) -> String {
+        match *constraint {
+            Constraints::Add(a, b, c) => {
+                format!("{} = {} + {}", self.term(c), self.term(a), self.term(b))
+            }
+            Constraints::Mul(a, b, c) => {
+                format!("{} = {} * {}", self.term(c), self.term(a), self.term(b))
+            }
+            Constraints::Eq(a, b) => format!("{} == {}", self.term(a), self.term(b)),
+        }
+    }
+
+    /// Returns what a gate computes as an equation, such as `w3 = x0 * x1`.
+    pub(crate) fn gate_text(&self, id: usize) -> String {
+        let operation = match self.full_graph[id].operation {
+            Operation::Add(a, b) => format!("{} + {}", self.term(a), self.term(b)),
+            Operation::Mul(a, b) => format!("{} * {}", self.term(a), self.term(b)),
+            Operation::Hint(index) => {
+                let operands: Vec<String> = self
+                    .hint_inputs(index)
+                    .iter()
+                    .map(|id| self.term(*id))
+                    .collect();
+                format!("hint({})", operands.join(", "))
+            }
+            Operation::Input => "input".to_string(),
+            Operation::Constant(value) => value.to_string(),
+        };
+        format!("{} = {}", self.wire_name(id), operation)
+    }
+
+    /// Returns the value of a constant without a label and the name of any other node.
+    pub(crate) fn term(&self, id: usize) -> String {
+        match self.full_graph[id].operation {
+            Operation::Constant(value) if !self.labels.contains_key(&id) => value.to_string(),
+            _ => self.wire_name(id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump_of_called_circuit() {
+        let mut square = Builder::new();
+        let x = square.init();
+        let x_squared = square.mul(x.clone(), x);
+        square.output(x_squared);
+
+        let mut builder = Builder::new();
+        let y = builder.init();
+        let two = builder.constant(2);
+        let double = builder.mul(two, y);
+        builder.call(&square, vec![double]);
+        assert_eq!(builder.dump_constraints(), "w2 = 2 * x0\nw3 = w2 * w2\n");
+    }
+}
@@ -1,4 +1,5 @@
-use crate::Node;
+use crate::field::Field;
+use crate::NodeId;
 
 /// Represents a u64 or an expression.
 ///
@@ -26,47 +27,101 @@ pub enum ExprVal {
     Input,
 }
 
+/// Represents the operation that produced a node.
+///
+/// Arithmetic operations hold the ids of their operands, which always
+/// come earlier in the graph than the node itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Operation {
+    #[default]
+    Input,
+    Constant(u64),
+    Add(usize, usize),
+    Mul(usize, usize),
+    /// A value computed by the hint with this index, see `Builder::hint`.
+    Hint(usize),
+}
+impl Operation {
+    /// Encodes the operation as a tag followed by its data, for hashing.
+    pub(crate) fn words(&self) -> Vec<u64> {
+        match self {
+            Operation::Input => vec![0],
+            Operation::Constant(value) => vec![1, *value],
+            Operation::Add(a, b) => vec![2, *a as u64, *b as u64],
+            Operation::Mul(a, b) => vec![3, *a as u64, *b as u64],
+            Operation::Hint(index) => vec![7, *index as u64],
+        }
+    }
+}
+
 /// Represents various constraints between nodes in the circuit.
 ///
 /// Constraints are used to ensure the validity of the operations
 /// performed on the nodes. These can also be thought of as gates.
 /// These constraints are created when nodes undergo arithmetic operations or when
-/// equality between nodes is requested in builder::assert_equal(..)
+/// equality between nodes is requested in builder::assert_equal(..).
+/// Nodes are referred to by their id in the graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum Constraints {
-    Add(Node, Node, Node),
-    Mul(Node, Node, Node),
-    Eq(Node, Node),
+    Add(usize, usize, usize),
+    Mul(usize, usize, usize),
+    Eq(usize, usize),
 }
 impl Constraints {
+    /// Encodes the constraint as a tag followed by the ids it refers to, for hashing.
+    pub(crate) fn words(&self) -> Vec<u64> {
+        match self {
+            Constraints::Add(a, b, c) => vec![4, *a as u64, *b as u64, *c as u64],
+            Constraints::Mul(a, b, c) => vec![5, *a as u64, *b as u64, *c as u64],
+            Constraints::Eq(a, b) => vec![6, *a as u64, *b as u64],
+        }
+    }
+
+    /// Returns the ids of the nodes the constraint refers to.
+    pub(crate) fn ids(&self) -> Vec<usize> {
+        match self {
+            Constraints::Add(a, b, c) | Constraints::Mul(a, b, c) => vec![*a, *b, *c],
+            Constraints::Eq(a, b) => vec![*a, *b],
+        }
+    }
+
     /// Checks if the constraint holds true or not.
     ///
+    /// # Arguments
+    ///
+    /// * `field`: The field the circuit computes in, `None` for plain `u64` arithmetic without
+    ///   overflow.
+    /// * `value`: Looks up the value of a node by id, `None` if it is unevaluated.
+    ///
     /// # Returns
     ///
     /// * `true` if the constraint is valid.
     /// * `false` otherwise.
-    pub fn is_valid(&self) -> bool {
+    pub fn is_valid(&self, field: Option<Field>, value: impl Fn(usize) -> Option<u64>) -> bool {
         match self {
             Constraints::Add(a, b, c) => {
-                if let (CustomU64::Val(a), CustomU64::Val(b), CustomU64::Val(c)) =
-                    (&a.value, &b.value, &c.value)
-                {
-                    *a + *b == *c
+                if let (Some(a), Some(b), Some(c)) = (value(*a), value(*b), value(*c)) {
+                    match field {
+                        Some(field) => field.add(a, b) == c,
+                        None => a.checked_add(b) == Some(c),
+                    }
                 } else {
                     false
                 }
             }
             Constraints::Mul(a, b, c) => {
-                if let (CustomU64::Val(a), CustomU64::Val(b), CustomU64::Val(c)) =
-                    (&a.value, &b.value, &c.value)
-                {
-                    *a * *b == *c
+                if let (Some(a), Some(b), Some(c)) = (value(*a), value(*b), value(*c)) {
+                    match field {
+                        Some(field) => field.mul(a, b) == c,
+                        None => a.checked_mul(b) == Some(c),
+                    }
                 } else {
                     false
                 }
             }
             Constraints::Eq(a, b) => {
-                if let (CustomU64::Val(a), CustomU64::Val(b)) = (&a.value, &b.value) {
-                    *a == *b
+                if let (Some(a), Some(b)) = (value(*a), value(*b)) {
+                    a == b
                 } else {
                     false
                 }
@@ -74,3 +129,26 @@ impl Constraints {
         }
     }
 }
+
+/// A read-only view of a constraint, as returned by `Builder::constraints`.
+///
+/// Nodes are referred to by their id in the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstraintView {
+    /// `a + b = out`
+    Add { a: NodeId, b: NodeId, out: NodeId },
+    /// `a * b = out`
+    Mul { a: NodeId, b: NodeId, out: NodeId },
+    /// `a = b`, added by `assert_equal`.
+    Eq { a: NodeId, b: NodeId },
+}
+
+impl From<&Constraints> for ConstraintView {
+    fn from(constraint: &Constraints) -> Self {
+        match *constraint {
+            Constraints::Add(a, b, out) => ConstraintView::Add { a, b, out },
+            Constraints::Mul(a, b, out) => ConstraintView::Mul { a, b, out },
+            Constraints::Eq(a, b) => ConstraintView::Eq { a, b },
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Evaluation without a builder.
+//!
+//! An `Evaluator` copies the shape of a circuit once and then computes witnesses from inputs
+//! without ever touching the `Builder`: evaluation takes `&self` and keeps its values on the
+//! stack of the call. One evaluator can therefore be shared by many threads at once, each
+//! evaluating the same circuit on its own inputs, while the builder stays free to be extended.
+//! `Builder::evaluate`, `fill_many` and `fill_lanes` all run on it; `fill_nodes` remains for
+//! storing the values in the graph.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::evaluator::Evaluator;
+//! use std::thread;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let y = builder.init();
+//! let product = builder.mul(x, y);
+//! builder.output(product);
+//!
+//! let evaluator = Evaluator::new(&builder);
+//! let outputs: Vec<Vec<u64>> = thread::scope(|scope| {
+//!     let handles: Vec<_> = (1..4)
+//!         .map(|x| {
+//!             let evaluator = &evaluator;
+//!             scope.spawn(move || evaluator.evaluate(&[x, 10]).outputs)
+//!         })
+//!         .collect();
+//!     handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+//! });
+//! assert_eq!(outputs, vec![vec![10], vec![20], vec![30]]);
+//!
+//! // the witness is [1, x, y, x * y]
+//! assert_eq!(evaluator.evaluate(&[6, 7]).witness, vec![1, 6, 7, 42]);
+//! ```
+use crate::batch::{LaneResult, WitnessResult};
+use crate::builder::OVERFLOW;
+use crate::enums::{Constraints, Operation};
+use crate::field::Field;
+use crate::hint::Hint;
+use crate::Builder;
+
+/// An immutable copy of everything needed to evaluate a circuit, see the module documentation.
+#[derive(Clone)]
+pub struct Evaluator {
+    operations: Vec<Operation>,
+    hints: Vec<Hint>,
+    /// The position of every node among the inputs, `None` for other nodes.
+    input_positions: Vec<Option<usize>>,
+    inputs: usize,
+    field: Option<Field>,
+    constraints: Vec<Constraints>,
+    layout: Vec<usize>,
+    outputs: Vec<usize>,
+}
+
+impl Evaluator {
+    /// Copies the shape of `circuit`. Values stored in the graph are ignored.
+    pub fn new(circuit: &Builder) -> Self {
+        let mut input_positions = vec![None; circuit.full_graph.len()];
+        circuit
+            .inputs
+            .iter()
+            .enumerate()
+            .for_each(|(position, id)| input_positions[*id] = Some(position));
+        Evaluator {
+            operations: circuit
+                .full_graph
+                .iter()
+                .map(|node| node.operation)
+                .collect(),
+            hints: circuit.hints.clone(),
+            input_positions,
+            inputs: circuit.inputs.len(),
+            field: circuit.field,
+            constraints: circuit.constraints.clone(),
+            layout: circuit.witness_layout(),
+            outputs: circuit.outputs.clone(),
+        }
+    }
+
+    /// Returns the number of inputs the circuit takes.
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    /// Evaluates the circuit on one set of inputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `input`: Values for the input nodes, in order of creation.
+    ///
+    /// # Returns
+    ///
+    /// The witness, the outputs and whether every constraint holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of inputs does not match the circuit or, without a field, if a value
+    /// overflows a `u64`.
+    pub fn evaluate(&self, input: &[u64]) -> WitnessResult {
+        if input.len() != self.inputs {
+            panic!(
+                "number of input arguments supplied does not match number of inputs for function"
+            );
+        }
+        let reduce = |value: u64| self.field.map_or(value, |field| field.reduce(value));
+        let mut values: Vec<u64> = Vec::with_capacity(self.operations.len());
+        for (id, operation) in self.operations.iter().enumerate() {
+            let value = match *operation {
+                Operation::Input => reduce(input[self.input_positions[id].unwrap()]),
+                Operation::Constant(value) => value,
+                Operation::Add(a, b) => match self.field {
+                    Some(field) => field.add(values[a], values[b]),
+                    None => values[a].checked_add(values[b]).expect(OVERFLOW),
+                },
+                Operation::Mul(a, b) => match self.field {
+                    Some(field) => field.mul(values[a], values[b]),
+                    None => values[a].checked_mul(values[b]).expect(OVERFLOW),
+                },
+                Operation::Hint(index) => self.hints[index].evaluate(|id| values[id], reduce),
+            };
+            values.push(value);
+        }
+        let mut witness = vec![1];
+        witness.extend(self.layout.iter().map(|id| values[*id]));
+        WitnessResult {
+            witness,
+            outputs: self.outputs.iter().map(|id| values[*id]).collect(),
+            satisfied: self
+                .constraints
+                .iter()
+                .all(|constraint| constraint.is_valid(self.field, |id| Some(values[id]))),
+        }
+    }
+
+    /// Evaluates the circuit on `N` sets of inputs in a single pass, see `Builder::fill_lanes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of inputs does not match the circuit or, without a field, if a value
+    /// overflows a `u64`.
+    pub fn evaluate_lanes<const N: usize>(&self, input: &[[u64; N]]) -> LaneResult<N> {
+        if input.len() != self.inputs {
+            panic!(
+                "number of input arguments supplied does not match number of inputs for function"
+            );
+        }
+        let mut values: Vec<[u64; N]> = Vec::with_capacity(self.operations.len());
+        for (id, operation) in self.operations.iter().enumerate() {
+            let value = match (*operation, self.field) {
+                (Operation::Input, field) => {
+                    let lanes = input[self.input_positions[id].unwrap()];
+                    field.map_or(lanes, |field| lanes.map(|value| field.reduce(value)))
+                }
+                (Operation::Constant(value), _) => [value; N],
+                (Operation::Add(a, b), None) => lanes(|lane| {
+                    values[a][lane]
+                        .checked_add(values[b][lane])
+                        .expect(OVERFLOW)
+                }),
+                (Operation::Mul(a, b), None) => lanes(|lane| {
+                    values[a][lane]
+                        .checked_mul(values[b][lane])
+                        .expect(OVERFLOW)
+                }),
+                (Operation::Add(a, b), Some(field)) => {
+                    lanes(|lane| field.add(values[a][lane], values[b][lane]))
+                }
+                (Operation::Mul(a, b), Some(field)) => {
+                    lanes(|lane| field.mul(values[a][lane], values[b][lane]))
+                }
+                (Operation::Hint(index), field) => lanes(|lane| {
+                    self.hints[index].evaluate(
+                        |id| values[id][lane],
+                        |value| field.map_or(value, |field| field.reduce(value)),
+                    )
+                }),
+            };
+            values.push(value);
+        }
+        let mut witness = vec![[1; N]];
+        witness.extend(self.layout.iter().map(|id| values[*id]));
+        LaneResult {
+            witness,
+            outputs: self.outputs.iter().map(|id| values[*id]).collect(),
+            satisfied: lanes(|lane| {
+                self.constraints
+                    .iter()
+                    .all(|constraint| constraint.is_valid(self.field, |id| Some(values[id][lane])))
+            }),
+        }
+    }
+}
+
+/// Computes every lane of a wire.
+fn lanes<T, const N: usize>(value: impl FnMut(usize) -> T) -> [T; N] {
+    std::array::from_fn(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{random_circuit, CircuitConfig, TestRng};
+
+    #[test]
+    fn test_evaluator_matches_fill_nodes() {
+        let mut rng = TestRng::new(428);
+        for _ in 0..20 {
+            let mut random = random_circuit(&mut rng, &CircuitConfig::default());
+            let evaluator = Evaluator::new(&random.circuit);
+            let result = evaluator.evaluate(&random.inputs);
+            random.circuit.fill_nodes(random.inputs.clone());
+            assert_eq!(Some(result.witness), random.circuit.witness());
+            assert_eq!(result.satisfied, random.circuit.check_constraints());
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Radix-2 number theoretic transforms.
+//!
+//! The NTT is the FFT over a prime field: it evaluates a polynomial of degree below `n` on a
+//! domain of size `n` (see the `domain` module) in `O(n log n)` field operations instead of
+//! `O(n²)`. It splits the polynomial into even and odd coefficients, `f(x) = f_e(x²) + x f_o(x²)`,
+//! evaluates both halves on the squared domain of half the size and combines the results using
+//! `ω^(i + n/2) = -ω^i`. The inverse NTT interpolates in the same way, using `ω⁻¹` and dividing
+//! by `n`.
+//!
+//! Cosets `s·<ω>` are handled by scaling the `i`-th coefficient by `s^i` before the transform,
+//! since `f(s·x) = Σ (c_i s^i) x^i`.
+//!
+//! ```
+//! use arithmetic_circuit::domain::Domain;
+//! use arithmetic_circuit::fft;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::polynomial::Polynomial;
+//!
+//! let field = Field::new(97);
+//! let domain = Domain::new(field, 8, 5);
+//! let polynomial = Polynomial::new(vec![3, 1, 4, 1, 5]);
+//!
+//! let evaluations = fft::ntt(polynomial.coefficients(), &domain);
+//! assert_eq!(evaluations, domain.evaluate(&polynomial));
+//! assert_eq!(fft::interpolate(&evaluations, &domain), polynomial);
+//! ```
+use crate::domain::Domain;
+use crate::polynomial::Polynomial;
+
+/// Evaluates the polynomial with the given coefficients, lowest degree first, on every element
+/// of the domain.
+///
+/// # Panics
+///
+/// Panics if there are more coefficients than elements in the domain.
+pub fn ntt(coefficients: &[u64], domain: &Domain) -> Vec<u64> {
+    if coefficients.len() > domain.size {
+        panic!("the domain is too small for a polynomial with this many coefficients");
+    }
+    let field = domain.field;
+    let mut values = vec![0; domain.size];
+    let mut power = 1;
+    for (value, coefficient) in values.iter_mut().zip(coefficients) {
+        *value = field.mul(*coefficient, power);
+        power = field.mul(power, domain.offset);
+    }
+    transform(&mut values, domain, domain.generator);
+    values
+}
+
+/// Returns the coefficients, lowest degree first, of the polynomial of degree below the domain's
+/// size taking the given values on the elements of the domain.
+///
+/// # Panics
+///
+/// Panics if the number of values does not match the size of the domain.
+pub fn intt(evaluations: &[u64], domain: &Domain) -> Vec<u64> {
+    if evaluations.len() != domain.size {
+        panic!("expected one value per element of the domain");
+    }
+    let field = domain.field;
+    let mut values = evaluations.to_vec();
+    transform(&mut values, domain, field.inv(domain.generator).unwrap());
+    let size_inverse = field.inv(domain.size as u64).unwrap();
+    let offset_inverse = field.inv(domain.offset).unwrap();
+    let mut factor = size_inverse;
+    for value in values.iter_mut() {
+        *value = field.mul(*value, factor);
+        factor = field.mul(factor, offset_inverse);
+    }
+    values
+}
+
+/// Interpolates the values on the elements of the domain, see `intt`.
+pub fn interpolate(evaluations: &[u64], domain: &Domain) -> Polynomial {
+    Polynomial::new(intt(evaluations, domain))
+}
+
+/// Evaluates the polynomial with coefficients `values` on the powers of `root`, in place, using
+/// the iterative Cooley-Tukey algorithm.
+fn transform(values: &mut [u64], domain: &Domain, root: u64) {
+    let field = domain.field;
+    let size = values.len();
+    let bits = size.trailing_zeros();
+    if bits == 0 {
+        return;
+    }
+    for i in 0..size {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+    let mut length = 2;
+    while length <= size {
+        let step = field.pow(root, (size / length) as u64);
+        for start in (0..size).step_by(length) {
+            let mut twiddle = 1;
+            for i in start..start + length / 2 {
+                let even = values[i];
+                let odd = field.mul(values[i + length / 2], twiddle);
+                values[i] = field.add(even, odd);
+                values[i + length / 2] = field.sub(even, odd);
+                twiddle = field.mul(twiddle, step);
+            }
+        }
+        length *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_round_trip() {
+        let field = Field::new(18_446_744_069_414_584_321);
+        for size in [1, 2, 16, 64] {
+            let domain = Domain::subgroup(field, size);
+            let coefficients: Vec<u64> = (0..size as u64).map(|i| i * i + 7).collect();
+            let evaluations = ntt(&coefficients, &domain);
+            let polynomial = Polynomial::new(coefficients.clone());
+            assert_eq!(evaluations, domain.evaluate(&polynomial));
+            assert_eq!(intt(&evaluations, &domain), coefficients);
+        }
+    }
+}
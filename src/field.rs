@@ -0,0 +1,198 @@
+//! Arithmetic in a prime field.
+//!
+//! The teaching modules that need division (QAP interpolation, FFTs, commitments to
+//! polynomials, ...) work over a prime field `F_p`. Elements are plain `u64`s in `0..p`.
+//!
+//! ```
+//! use arithmetic_circuit::field::Field;
+//!
+//! let field = Field::new(17);
+//! assert_eq!(field.mul(5, 7), 1);
+//! assert_eq!(field.inv(5), Some(7));
+//! assert_eq!(field.sub(3, 5), 15);
+//! ```
+
+/// A prime field `F_p` given by its modulus `p`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Field {
+    modulus: u64,
+}
+
+impl Field {
+    /// Creates the field of integers modulo `modulus`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `modulus` is not prime.
+    pub fn new(modulus: u64) -> Self {
+        if !is_prime(modulus) {
+            panic!("field modulus {} is not prime", modulus);
+        }
+        Field { modulus }
+    }
+
+    /// Returns the modulus `p` of the field.
+    pub fn modulus(&self) -> u64 {
+        self.modulus
+    }
+
+    /// Maps any integer into the field.
+    pub fn reduce(&self, value: u64) -> u64 {
+        value % self.modulus
+    }
+
+    pub fn add(&self, a: u64, b: u64) -> u64 {
+        ((a as u128 + b as u128) % self.modulus as u128) as u64
+    }
+
+    pub fn sub(&self, a: u64, b: u64) -> u64 {
+        self.add(a, self.neg(b))
+    }
+
+    pub fn neg(&self, a: u64) -> u64 {
+        match self.reduce(a) {
+            0 => 0,
+            a => self.modulus - a,
+        }
+    }
+
+    pub fn mul(&self, a: u64, b: u64) -> u64 {
+        ((a as u128 * b as u128) % self.modulus as u128) as u64
+    }
+
+    /// Raises `base` to the power `exponent` by square-and-multiply.
+    pub fn pow(&self, base: u64, mut exponent: u64) -> u64 {
+        let mut base = self.reduce(base);
+        let mut result = self.reduce(1);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// Returns the multiplicative inverse of `a`, or `None` if `a` is zero.
+    ///
+    /// Uses Fermat's little theorem: `a^(p-2) * a = a^(p-1) = 1`.
+    pub fn inv(&self, a: u64) -> Option<u64> {
+        match self.reduce(a) {
+            0 => None,
+            a => Some(self.pow(a, self.modulus - 2)),
+        }
+    }
+
+    /// Divides `a` by `b`, or returns `None` if `b` is zero.
+    pub fn div(&self, a: u64, b: u64) -> Option<u64> {
+        self.inv(b).map(|b_inv| self.mul(a, b_inv))
+    }
+
+    /// Returns an element of multiplicative order exactly `order`, a primitive `order`-th root of unity.
+    ///
+    /// # Returns
+    ///
+    /// `None` if `order` is zero or does not divide `p - 1`, in which case no such element exists.
+    pub fn root_of_unity(&self, order: u64) -> Option<u64> {
+        if order == 0 || !(self.modulus - 1).is_multiple_of(order) {
+            return None;
+        }
+        let factors = prime_factors(order);
+        (1..self.modulus)
+            .map(|candidate| self.pow(candidate, (self.modulus - 1) / order))
+            .find(|root| factors.iter().all(|q| self.pow(*root, order / q) != 1))
+    }
+}
+
+/// Returns the distinct prime factors of `n` by trial division.
+fn prime_factors(mut n: u64) -> Vec<u64> {
+    let mut factors = vec![];
+    let mut divisor = 2;
+    while divisor * divisor <= n {
+        if n.is_multiple_of(divisor) {
+            factors.push(divisor);
+            while n.is_multiple_of(divisor) {
+                n /= divisor;
+            }
+        }
+        divisor += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+/// Deterministic Miller–Rabin primality test, exact for every `u64`.
+pub(crate) fn is_prime(n: u64) -> bool {
+    const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    if let Some(p) = WITNESSES.iter().find(|p| n.is_multiple_of(**p)) {
+        return n == *p;
+    }
+    let mul = |a: u64, b: u64| ((a as u128 * b as u128) % n as u128) as u64;
+    let pow = |mut base: u64, mut exponent: u64| {
+        let mut result = 1;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = mul(result, base);
+            }
+            base = mul(base, base);
+            exponent >>= 1;
+        }
+        result
+    };
+    let shift = (n - 1).trailing_zeros();
+    let odd = (n - 1) >> shift;
+    WITNESSES.iter().all(|a| {
+        let mut x = pow(*a, odd);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..shift {
+            x = mul(x, x);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primality() {
+        let primes: Vec<u64> = (0..30).filter(|n| is_prime(*n)).collect();
+        assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert!(is_prime(18_446_744_069_414_584_321));
+        assert!(!is_prime(18_446_744_069_414_584_323));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let field = Field::new(18_446_744_069_414_584_321);
+        let a = field.modulus() - 1;
+        assert_eq!(field.add(a, 2), 1);
+        assert_eq!(field.mul(a, a), 1);
+        for value in [1, 2, 12345, a] {
+            assert_eq!(field.mul(value, field.inv(value).unwrap()), 1);
+        }
+        assert_eq!(field.inv(0), None);
+    }
+
+    #[test]
+    fn test_root_of_unity() {
+        let field = Field::new(97);
+        let root = field.root_of_unity(32).unwrap();
+        assert_eq!(field.pow(root, 32), 1);
+        assert_ne!(field.pow(root, 16), 1);
+        assert!(field.root_of_unity(5).is_none());
+        assert_eq!(field.root_of_unity(1), Some(1));
+    }
+}
@@ -0,0 +1,179 @@
+//! Non-negative fixed-point numbers.
+//!
+//! A number in the `Qm.n` format is stored as the integer `x * 2^n` with `m + n` bits, so adding
+//! two numbers is a single addition gate. The product of two numbers carries `2n` fractional
+//! bits and is rescaled by dividing by `2^n` with `Builder::div_rem`, rounding down. That is
+//! enough to evaluate small models, such as a neuron with private weights, inside a circuit.
+//!
+//! Inputs and products are range checked to `m + n` bits, so a product that does not fit into
+//! the format leaves the constraints unsatisfiable. Sums are not checked until they are
+//! multiplied. Negative numbers are not supported.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::fixed::{Fixed, QFormat};
+//!
+//! // a neuron 0.5 * x + 0.25 * y + 1 in Q4.8
+//! let format = QFormat::new(4, 8);
+//! let mut builder = Builder::new();
+//! let x = Fixed::input(&mut builder, format);
+//! let y = Fixed::input(&mut builder, format);
+//! let (w0, w1, bias) = (
+//!     Fixed::constant(&mut builder, 0.5, format),
+//!     Fixed::constant(&mut builder, 0.25, format),
+//!     Fixed::constant(&mut builder, 1.0, format),
+//! );
+//! let first = x.mul(&mut builder, &w0);
+//! let second = y.mul(&mut builder, &w1);
+//! let sum = first.add(&mut builder, &second);
+//! let output = sum.add(&mut builder, &bias);
+//! builder.output(output.node);
+//!
+//! let inputs = vec![format.encode(3.0), format.encode(2.5)];
+//! let result = builder.evaluate(inputs)[0];
+//! assert_eq!(format.decode(result), 3.125);
+//! ```
+use crate::gadgets::to_bits;
+use crate::{Builder, Node};
+
+/// The `Qm.n` format: `m` integer bits and `n` fractional bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct QFormat {
+    pub integer_bits: usize,
+    pub fraction_bits: usize,
+}
+
+impl QFormat {
+    /// Creates the `Qm.n` format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the format has more than 15 bits, which would let the rescaling after a
+    /// multiplication overflow.
+    pub fn new(integer_bits: usize, fraction_bits: usize) -> Self {
+        if integer_bits + fraction_bits > 15 {
+            panic!("fixed-point formats can have at most 15 bits");
+        }
+        QFormat {
+            integer_bits,
+            fraction_bits,
+        }
+    }
+
+    /// Returns the number of bits of a number, `m + n`.
+    pub fn bits(&self) -> usize {
+        self.integer_bits + self.fraction_bits
+    }
+
+    /// Encodes a number, rounding to the nearest representable value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number is negative or too large for the format.
+    pub fn encode(&self, value: f64) -> u64 {
+        let scaled = (value * (1u64 << self.fraction_bits) as f64).round();
+        if !(0.0..(1u64 << self.bits()) as f64).contains(&scaled) {
+            panic!("{} cannot be represented in {:?}", value, self);
+        }
+        scaled as u64
+    }
+
+    /// Decodes the integer representation of a number.
+    pub fn decode(&self, raw: u64) -> f64 {
+        raw as f64 / (1u64 << self.fraction_bits) as f64
+    }
+}
+
+/// A fixed-point number in a circuit.
+#[derive(Debug, Clone)]
+pub struct Fixed {
+    /// The node holding the integer representation `x * 2^n`.
+    pub node: Node,
+    pub format: QFormat,
+}
+
+impl Fixed {
+    /// Adds an input, given in its integer representation, and range checks it to the format.
+    pub fn input(builder: &mut Builder, format: QFormat) -> Self {
+        let node = builder.init();
+        to_bits(builder, &node, format.bits());
+        Fixed { node, format }
+    }
+
+    /// Adds a constant, rounded to the format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value cannot be represented, see `QFormat::encode`.
+    pub fn constant(builder: &mut Builder, value: f64, format: QFormat) -> Self {
+        Fixed {
+            node: builder.constant(format.encode(value)),
+            format,
+        }
+    }
+
+    /// Adds two numbers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the formats differ.
+    pub fn add(&self, builder: &mut Builder, other: &Fixed) -> Fixed {
+        self.check_format(other);
+        Fixed {
+            node: builder.add(self.node.clone(), other.node.clone()),
+            format: self.format,
+        }
+    }
+
+    /// Multiplies two numbers, rounding the result down, and range checks the result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the formats differ, or the circuit computes in a field too small for
+    /// `Builder::div_rem` on products of `2(m + n)` bits.
+    pub fn mul(&self, builder: &mut Builder, other: &Fixed) -> Fixed {
+        self.check_format(other);
+        let product = builder.mul(self.node.clone(), other.node.clone());
+        let scale = builder.constant(1 << self.format.fraction_bits);
+        let (quotient, _) = builder.div_rem(product, scale, 2 * self.format.bits());
+        to_bits(builder, &quotient, self.format.bits());
+        Fixed {
+            node: quotient,
+            format: self.format,
+        }
+    }
+
+    fn check_format(&self, other: &Fixed) {
+        if self.format != other.format {
+            panic!(
+                "cannot combine numbers in {:?} and {:?}",
+                self.format, other.format
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_rounds_down() {
+        let format = QFormat::new(6, 4);
+        let mut builder = Builder::new();
+        let x = Fixed::input(&mut builder, format);
+        let y = Fixed::input(&mut builder, format);
+        let product = x.mul(&mut builder, &y);
+        builder.output(product.node);
+
+        // 1.5625 * 2.0625 = 3.22265625, rounded down to 3.1875
+        let inputs = vec![format.encode(1.5625), format.encode(2.0625)];
+        builder.fill_nodes(inputs.clone());
+        assert!(builder.check_constraints());
+        assert_eq!(format.decode(builder.evaluate(inputs)[0]), 3.1875);
+
+        // 63 * 2 does not fit into 6 integer bits
+        builder.fill_nodes(vec![format.encode(63.0), format.encode(2.0)]);
+        assert!(!builder.check_constraints());
+    }
+}
@@ -0,0 +1,200 @@
+//! Folding R1CS instances, as in Nova.
+//!
+//! Incrementally verifiable computation proves a long chain of steps without verifying a proof
+//! per step. Nova gets there by folding: two instances of the same R1CS are combined into one
+//! whose satisfaction implies, except with small probability, that both were satisfied.
+//!
+//! Plain R1CS cannot be folded, because `A z · B z = C z` is not linear in `z`. A relaxed R1CS
+//! adds a scalar `u` and an error vector `E`:
+//!
+//! `(A z) ∘ (B z) = u · (C z) + E`
+//!
+//! where `z` holds `u` in place of the leading `1` of the witness. Every satisfied plain
+//! instance is a relaxed instance with `u = 1` and `E = 0`. Folding instances 1 and 2 with a
+//! random challenge `r` gives
+//!
+//! `z = z_1 + r z_2`, `u = u_1 + r u_2`, `E = E_1 + r T + r² E_2`
+//!
+//! with the cross term `T = A z_1 ∘ B z_2 + A z_2 ∘ B z_1 - u_1 C z_2 - u_2 C z_1`, which the
+//! prover sends before `r` is chosen. Nova also commits to `z` and `E` so the verifier folds
+//! commitments instead of the vectors themselves; this toy folds the vectors in the open.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::folding::Folding;
+//! use arithmetic_circuit::transcript::Transcript;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let square = builder.mul(x.clone(), x);
+//! builder.output(square);
+//!
+//! let folding = Folding::new(&builder, Field::new(101));
+//! let mut transcript = Transcript::new("folding");
+//! builder.fill_nodes(vec![3]);
+//! let first = folding.relax(&builder.witness().unwrap());
+//! builder.fill_nodes(vec![7]);
+//! let second = folding.relax(&builder.witness().unwrap());
+//!
+//! let (folded, _) = folding.fold(&first, &second, &mut transcript);
+//! assert!(folding.is_satisfied(&folded));
+//! ```
+use crate::field::Field;
+use crate::sparse::ConstraintMatrices;
+use crate::transcript::Transcript;
+use crate::Builder;
+
+/// An instance of a relaxed R1CS together with its witness.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelaxedInstance {
+    /// The witness, with `u` in place of the leading `1`.
+    pub z: Vec<u64>,
+    /// The scalar `u`, `1` for a plain instance.
+    pub u: u64,
+    /// The error vector `E`, one entry per row, zero for a plain instance.
+    pub error: Vec<u64>,
+}
+
+/// The relaxed R1CS of a circuit and the folding scheme over it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Folding {
+    pub field: Field,
+    matrices: ConstraintMatrices,
+}
+
+impl Folding {
+    pub fn new(circuit: &Builder, field: Field) -> Self {
+        Folding {
+            field,
+            matrices: circuit.constraint_matrices(),
+        }
+    }
+
+    /// Turns a plain witness (see `Builder::witness`) into a relaxed instance with `u = 1` and
+    /// `E = 0`.
+    pub fn relax(&self, witness: &[u64]) -> RelaxedInstance {
+        RelaxedInstance {
+            z: witness
+                .iter()
+                .map(|value| self.field.reduce(*value))
+                .collect(),
+            u: 1,
+            error: vec![0; self.matrices.a.num_rows()],
+        }
+    }
+
+    /// Checks `(A z) ∘ (B z) = u · (C z) + E` row by row.
+    pub fn is_satisfied(&self, instance: &RelaxedInstance) -> bool {
+        let field = self.field;
+        if instance.z.len() != self.matrices.a.num_cols
+            || instance.error.len() != self.matrices.a.num_rows()
+            || instance.z[0] != instance.u
+        {
+            return false;
+        }
+        let (a, b, c) = self.products(&instance.z);
+        (0..a.len()).all(|i| {
+            field.mul(a[i], b[i]) == field.add(field.mul(instance.u, c[i]), instance.error[i])
+        })
+    }
+
+    /// Computes the cross term `T` of two instances.
+    pub fn cross_term(&self, first: &RelaxedInstance, second: &RelaxedInstance) -> Vec<u64> {
+        let field = self.field;
+        let (a_1, b_1, c_1) = self.products(&first.z);
+        let (a_2, b_2, c_2) = self.products(&second.z);
+        (0..a_1.len())
+            .map(|i| {
+                let products = field.add(field.mul(a_1[i], b_2[i]), field.mul(a_2[i], b_1[i]));
+                let scaled = field.add(field.mul(first.u, c_2[i]), field.mul(second.u, c_1[i]));
+                field.sub(products, scaled)
+            })
+            .collect()
+    }
+
+    /// Folds two instances into one, absorbing the cross term into the transcript before
+    /// squeezing the challenge `r` from it.
+    ///
+    /// # Returns
+    ///
+    /// The folded instance and the challenge.
+    pub fn fold(
+        &self,
+        first: &RelaxedInstance,
+        second: &RelaxedInstance,
+        transcript: &mut Transcript,
+    ) -> (RelaxedInstance, u64) {
+        let field = self.field;
+        let cross_term = self.cross_term(first, second);
+        transcript.absorb("folding cross term", &cross_term);
+        let r = transcript.squeeze_below("folding challenge", field.modulus());
+        let r_squared = field.mul(r, r);
+        let folded = RelaxedInstance {
+            z: first
+                .z
+                .iter()
+                .zip(&second.z)
+                .map(|(z_1, z_2)| field.add(*z_1, field.mul(r, *z_2)))
+                .collect(),
+            u: field.add(first.u, field.mul(r, second.u)),
+            error: (0..cross_term.len())
+                .map(|i| {
+                    let linear = field.add(first.error[i], field.mul(r, cross_term[i]));
+                    field.add(linear, field.mul(r_squared, second.error[i]))
+                })
+                .collect(),
+        };
+        (folded, r)
+    }
+
+    /// Returns `A z`, `B z` and `C z`.
+    fn products(&self, z: &[u64]) -> (Vec<u64>, Vec<u64>, Vec<u64>) {
+        let field = self.field;
+        (
+            self.matrices.a.mul_vec(z, field),
+            self.matrices.b.mul_vec(z, field),
+            self.matrices.c.mul_vec(z, field),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folding_chains_and_catches_bad_instances() {
+        // x * y + 4 == x
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let y = builder.init();
+        let product = builder.mul(x.clone(), y);
+        let four = builder.constant(4);
+        let sum = builder.add(product, four);
+        builder.assert_equal(sum, x);
+        let field = Field::new(2_147_483_647);
+        let folding = Folding::new(&builder, field);
+        let mut transcript = Transcript::new("folding");
+
+        // fold three valid instances one after another
+        builder.fill_nodes(vec![4, 0]);
+        let mut accumulator = folding.relax(&builder.witness().unwrap());
+        for input in [2, 3] {
+            // x * y + 4 == x has the solution x = 4 / (1 - y) in the field
+            let x = field.div(4, field.sub(1, input)).unwrap();
+            builder.fill_nodes(vec![x, input]);
+            let instance = folding.relax(&builder.witness().unwrap());
+            assert!(folding.is_satisfied(&instance));
+            accumulator = folding.fold(&accumulator, &instance, &mut transcript).0;
+            assert!(folding.is_satisfied(&accumulator));
+        }
+
+        // an unsatisfied instance spoils the accumulator
+        builder.fill_nodes(vec![5, 5]);
+        let bad = folding.relax(&builder.witness().unwrap());
+        assert!(!folding.is_satisfied(&bad));
+        let (folded, _) = folding.fold(&accumulator, &bad, &mut transcript);
+        assert!(!folding.is_satisfied(&folded));
+    }
+}
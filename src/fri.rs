@@ -0,0 +1,356 @@
+//! A simplified FRI low-degree test.
+//!
+//! FRI ("Fast Reed–Solomon IOP of proximity") convinces a verifier that a vector of evaluations on
+//! a domain comes from a polynomial of low degree. Each round splits `f(x)` into its even and odd
+//! parts, `f(x) = f_e(x²) + x f_o(x²)`, and folds them with a random challenge `β` into
+//! `f'(y) = f_e(y) + β f_o(y)`. The new polynomial has half the degree and lives on a domain of half
+//! the size, since `x` and `-x` share the same square. After `log2(d)` rounds a polynomial of
+//! degree below `d` has become a constant. The verifier spot-checks a few random positions:
+//! `f'(x²)` must follow from `f(x)` and `f(-x)` in every round.
+//!
+//! The evaluation domain is a coset `s·<ω>` of a multiplicative subgroup, with `ω` a root of unity
+//! whose order is a power of two. To keep the recursion easy to trace, every layer is kept in the
+//! proof in full and committed to with a single hash. A real FRI proof commits to layers with
+//! Merkle trees and only reveals the queried positions.
+//!
+//! ```
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::fri::{self, Domain};
+//! use arithmetic_circuit::polynomial::Polynomial;
+//! use arithmetic_circuit::transcript::Transcript;
+//!
+//! let field = Field::new(257);
+//! let domain = Domain::new(field, 32, 3);
+//! // degree 7 < 8, evaluated on a domain 4 times larger than needed
+//! let polynomial = Polynomial::new(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+//! let evaluations = domain.evaluate(&polynomial);
+//!
+//! let proof = fri::prove(&domain, evaluations, 8, &mut Transcript::new("fri"));
+//! assert_eq!(proof.rounds.len(), 3);
+//! assert!(fri::verify(&domain, &proof, 8, 4, &mut Transcript::new("fri")));
+//! ```
+pub use crate::domain::Domain;
+use crate::field::Field;
+use crate::hash::hash_words;
+use crate::qap::Qap;
+use crate::r1cs::R1cs;
+use crate::transcript::Transcript;
+use crate::Builder;
+use tracing::{debug, info};
+
+/// A single folding round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FriRound {
+    /// Evaluations of the current polynomial on the current domain.
+    pub evaluations: Vec<u64>,
+    /// The hash the prover committed to before receiving `challenge`.
+    pub commitment: u64,
+    /// The verifier's challenge `β` used to fold this layer into the next one.
+    pub challenge: u64,
+}
+
+/// The folding rounds and the final layer, which should be constant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FriProof {
+    pub rounds: Vec<FriRound>,
+    pub final_layer: Vec<u64>,
+}
+
+/// Folds the evaluations at `x` and `-x` into the evaluation of the next layer at `x²`.
+fn fold(field: Field, x: u64, f_x: u64, f_minus_x: u64, challenge: u64) -> u64 {
+    let two = field.reduce(2);
+    let even = field.div(field.add(f_x, f_minus_x), two).unwrap();
+    let odd = field
+        .div(field.sub(f_x, f_minus_x), field.mul(two, x))
+        .unwrap();
+    field.add(even, field.mul(challenge, odd))
+}
+
+/// Runs the commit phase of FRI on `evaluations` over `domain`.
+///
+/// # Arguments
+///
+/// * `domain`: The domain the evaluations live on.
+/// * `evaluations`: One value per element of the domain.
+/// * `degree_bound`: The claimed bound `d` on the degree (degree < d), a power of two.
+/// * `transcript`: Source of the challenges. Commitments are absorbed into it.
+///
+/// # Panics
+///
+/// Panics if the number of evaluations does not match the domain, or `degree_bound` is not a
+/// power of two smaller than the domain.
+pub fn prove(
+    domain: &Domain,
+    evaluations: Vec<u64>,
+    degree_bound: usize,
+    transcript: &mut Transcript,
+) -> FriProof {
+    if evaluations.len() != domain.size {
+        panic!("FRI needs exactly one evaluation per domain element");
+    }
+    if !degree_bound.is_power_of_two() || degree_bound >= domain.size {
+        panic!("FRI degree bound must be a power of two smaller than the domain");
+    }
+    let field = domain.field;
+    let mut domain = *domain;
+    let mut layer = evaluations;
+    let mut rounds = vec![];
+    for round in 0..degree_bound.trailing_zeros() {
+        let commitment = hash_words(&layer);
+        transcript.absorb("fri layer", &[commitment]);
+        let challenge = transcript.squeeze_below("fri challenge", field.modulus());
+        let half = domain.size / 2;
+        let next = (0..half)
+            .map(|index| {
+                fold(
+                    field,
+                    domain.element(index),
+                    layer[index],
+                    layer[index + half],
+                    challenge,
+                )
+            })
+            .collect();
+        debug!(
+            "FRI round {} folded {} values with challenge {}",
+            round, domain.size, challenge
+        );
+        rounds.push(FriRound {
+            evaluations: layer,
+            commitment,
+            challenge,
+        });
+        layer = next;
+        domain = domain.square();
+    }
+    transcript.absorb("fri final layer", &layer);
+    FriProof {
+        rounds,
+        final_layer: layer,
+    }
+}
+
+/// Runs the query phase of FRI against a proof.
+///
+/// # Arguments
+///
+/// * `domain`: The domain of the first layer.
+/// * `proof`: The prover's layers.
+/// * `degree_bound`: The claimed bound on the degree, as given to `prove`.
+/// * `num_queries`: How many random positions to check. More queries catch cheating more often.
+/// * `transcript`: Must be in the same state the prover's transcript was in.
+///
+/// # Returns
+///
+/// Returns `true` if the final layer is constant on the domain folded once per round and every
+/// queried fold is consistent.
+pub fn verify(
+    domain: &Domain,
+    proof: &FriProof,
+    degree_bound: usize,
+    num_queries: usize,
+    transcript: &mut Transcript,
+) -> bool {
+    let field = domain.field;
+    if !degree_bound.is_power_of_two()
+        || proof.rounds.len() != degree_bound.trailing_zeros() as usize
+        || proof
+            .rounds
+            .first()
+            .map_or(domain.size, |round| round.evaluations.len())
+            != domain.size
+        // the final layer lives on the domain folded once per round, also without any rounds
+        || proof.final_layer.is_empty()
+        || proof.final_layer.len() != domain.size >> proof.rounds.len()
+    {
+        info!("FRI proof does not match the degree bound or domain");
+        return false;
+    }
+    for round in &proof.rounds {
+        transcript.absorb("fri layer", &[round.commitment]);
+        let challenge = transcript.squeeze_below("fri challenge", field.modulus());
+        if challenge != round.challenge || hash_words(&round.evaluations) != round.commitment {
+            info!("FRI round does not match its commitment or challenge");
+            return false;
+        }
+    }
+    transcript.absorb("fri final layer", &proof.final_layer);
+    if proof.final_layer.windows(2).any(|pair| pair[0] != pair[1]) {
+        info!("FRI final layer is not constant");
+        return false;
+    }
+
+    for query in 0..num_queries {
+        let mut index = transcript.squeeze_below("fri query", domain.size as u64) as usize;
+        let mut layer_domain = *domain;
+        for (round, layer) in proof.rounds.iter().enumerate() {
+            let half = layer_domain.size / 2;
+            index %= half;
+            let next = proof
+                .rounds
+                .get(round + 1)
+                .map_or(&proof.final_layer, |next| &next.evaluations);
+            if next.len() != half || layer.evaluations.len() != layer_domain.size {
+                return false;
+            }
+            let folded = fold(
+                field,
+                layer_domain.element(index),
+                layer.evaluations[index],
+                layer.evaluations[index + half],
+                layer.challenge,
+            );
+            if folded != next[index] {
+                info!("FRI query {} failed in round {}", query, round);
+                return false;
+            }
+            layer_domain = layer_domain.square();
+        }
+    }
+    info!("FRI accepted degree bound {}", degree_bound);
+    true
+}
+
+/// Evaluates the circuit's quotient `(A(x) * B(x) - C(x)) / Z(x)` pointwise on a domain.
+///
+/// The R1CS of the filled circuit is turned into a `Qap`. If the witness satisfies it, the
+/// quotient is the polynomial `H(x)` and has low degree. Otherwise the division leaves a
+/// remainder, the values do not come from any low-degree polynomial and FRI rejects them.
+///
+/// # Arguments
+///
+/// * `circuit`: The circuit, after `fill_nodes` was called.
+/// * `field`: The field to work in.
+/// * `blowup`: Ratio between the domain size and the degree bound, a power of two.
+///
+/// # Returns
+///
+/// The domain, the evaluations and the degree bound to hand to `prove`.
+///
+/// # Panics
+///
+/// Panics if the circuit was not filled or the field has no subgroup large enough.
+pub fn circuit_quotient(
+    circuit: &Builder,
+    field: Field,
+    blowup: usize,
+) -> (Domain, Vec<u64>, usize) {
+    let witness: Vec<u64> = circuit
+        .witness()
+        .expect("the circuit must be filled before extracting its polynomial")
+        .iter()
+        .map(|value| field.reduce(*value))
+        .collect();
+    let qap = Qap::from_r1cs(&R1cs::from_builder(circuit), field);
+    let (a, b, c) = qap.combine(&witness);
+    let numerator = a.mul(&b, field).sub(&c, field);
+
+    // H has degree at most m - 2 for m constraints
+    let degree_bound = qap
+        .points
+        .len()
+        .saturating_sub(1)
+        .max(1)
+        .next_power_of_two();
+    let size = degree_bound * blowup.max(2).next_power_of_two();
+    let domain = (2..field.modulus())
+        .map(|offset| Domain::new(field, size, offset))
+        .find(|domain| (0..size).all(|i| qap.target.evaluate(domain.element(i), field) != 0))
+        .expect("no coset of the domain avoids the constraint points");
+    let evaluations = (0..size)
+        .map(|i| {
+            let x = domain.element(i);
+            field
+                .div(numerator.evaluate(x, field), qap.target.evaluate(x, field))
+                .unwrap()
+        })
+        .collect();
+    (domain, evaluations, degree_bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polynomial::Polynomial;
+
+    fn circuit(x: u64, claimed: u64) -> Builder {
+        // x^3 + x == claimed
+        let mut builder = Builder::new();
+        let out = builder.init();
+        let x_node = builder.init();
+        let x_squared = builder.mul(x_node.clone(), x_node.clone());
+        let x_cubed = builder.mul(x_squared, x_node.clone());
+        let sum = builder.add(x_cubed, x_node);
+        builder.assert_equal(sum, out);
+        builder.fill_nodes(vec![claimed, x]);
+        builder
+    }
+
+    #[test]
+    fn test_high_degree_is_rejected() {
+        let field = Field::new(257);
+        let domain = Domain::new(field, 32, 3);
+        let polynomial = Polynomial::new((1..=12).collect());
+        let proof = prove(
+            &domain,
+            domain.evaluate(&polynomial),
+            8,
+            &mut Transcript::new("t"),
+        );
+        assert!(!verify(&domain, &proof, 8, 8, &mut Transcript::new("t")));
+        let proof = prove(
+            &domain,
+            domain.evaluate(&polynomial),
+            16,
+            &mut Transcript::new("t"),
+        );
+        assert!(verify(&domain, &proof, 16, 8, &mut Transcript::new("t")));
+    }
+
+    #[test]
+    fn test_final_layer_must_cover_the_domain() {
+        let field = Field::new(257);
+        let domain = Domain::new(field, 8, 3);
+        let constant = domain.evaluate(&Polynomial::new(vec![5]));
+        let mut proof = prove(&domain, constant, 1, &mut Transcript::new("t"));
+        assert!(verify(&domain, &proof, 1, 4, &mut Transcript::new("t")));
+        for length in [0, 1, 16] {
+            proof.final_layer = vec![5; length];
+            assert!(!verify(&domain, &proof, 1, 4, &mut Transcript::new("t")));
+        }
+    }
+
+    #[test]
+    fn test_circuit_quotient() {
+        let field = Field::new(257);
+        let (domain, evaluations, degree_bound) = circuit_quotient(&circuit(3, 30), field, 4);
+        let proof = prove(
+            &domain,
+            evaluations,
+            degree_bound,
+            &mut Transcript::new("t"),
+        );
+        assert!(verify(
+            &domain,
+            &proof,
+            degree_bound,
+            4,
+            &mut Transcript::new("t")
+        ));
+
+        let (domain, evaluations, degree_bound) = circuit_quotient(&circuit(3, 31), field, 4);
+        let proof = prove(
+            &domain,
+            evaluations,
+            degree_bound,
+            &mut Transcript::new("t"),
+        );
+        assert!(!verify(
+            &domain,
+            &proof,
+            degree_bound,
+            4,
+            &mut Transcript::new("t")
+        ));
+    }
+}
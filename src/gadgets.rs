@@ -0,0 +1,286 @@
+//! Reusable building blocks for circuits.
+//!
+//! Gadgets are functions that add the gates of a common computation to a `Builder` and return
+//! the nodes holding the result, much like `Builder::call` does for a whole circuit. Every
+//! gadget with a cryptographic or numeric meaning comes with a native reference implementation
+//! computing the same values outside of the circuit, so the two can be cross-checked.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::gadgets;
+//!
+//! let mut builder = Builder::new_in_field(Field::new(101));
+//! let x = builder.init();
+//! let x_to_the_10 = gadgets::pow(&mut builder, &x, 10);
+//! builder.output(x_to_the_10);
+//! assert_eq!(builder.evaluate(vec![2]), vec![1024 % 101]);
+//! ```
+use crate::{Builder, Node};
+
+mod compare;
+mod ec;
+mod merkle;
+mod mimc;
+mod poseidon;
+pub mod puzzles;
+mod schnorr;
+mod sha256;
+mod shift;
+mod sized;
+mod sort;
+mod uint32;
+
+pub use compare::{abs_diff, less_than, max, min};
+pub use ec::{assert_on_curve, ec_add, ec_double, Curve, EcPoint};
+pub use merkle::{merkle_path_native, merkle_root, merkle_root_native, merkle_verify};
+pub use mimc::{mimc, mimc_constants, mimc_native, mimc_permutation};
+pub use poseidon::{
+    poseidon, poseidon_native, poseidon_permutation, poseidon_permutation_native, PoseidonParams,
+};
+pub use schnorr::{schnorr_verify, Schnorr, Signature};
+pub use sha256::{sha256_compress, sha256_compress_native, SHA256_IV};
+pub use shift::{rotate_left, rotate_right, shift_left, shift_right};
+pub use sized::BoolWire;
+pub use sort::{assert_sorted_permutation, sort};
+pub use uint32::U32;
+
+/// Raises `base` to a constant power by square-and-multiply, using about `2 log2(exponent)`
+/// multiplication gates.
+///
+/// # Arguments
+///
+/// * `builder`: The circuit to add the gates to.
+/// * `base`: The node to exponentiate.
+/// * `exponent`: The power, known when the circuit is built.
+pub fn pow(builder: &mut Builder, base: &Node, exponent: u64) -> Node {
+    if exponent == 0 {
+        return builder.constant(1);
+    }
+    let mut result: Option<Node> = None;
+    for bit in (0..u64::BITS - exponent.leading_zeros()).rev() {
+        if let Some(current) = result.take() {
+            result = Some(builder.mul(current.clone(), current));
+        }
+        if exponent >> bit & 1 == 1 {
+            result = Some(match result.take() {
+                Some(current) => builder.mul(current, base.clone()),
+                None => base.clone(),
+            });
+        }
+    }
+    result.unwrap()
+}
+
+/// Constrains `bit` to be `0` or `1` with `bit * bit = bit`.
+pub fn assert_bit(builder: &mut Builder, bit: &Node) {
+    let square = builder.mul(bit.clone(), bit.clone());
+    builder.assert_equal(square, bit.clone());
+}
+
+/// Panics if the circuit computes in a field with at most `2^bits` elements, where values
+/// below `2^bits` would wrap around.
+///
+/// # Arguments
+///
+/// * `builder`: The circuit.
+/// * `bits`: The number of bits the field must hold.
+/// * `what`: The operation that needs them, the subject of the panic message.
+pub(crate) fn check_field_bits(builder: &Builder, bits: usize, what: &str) {
+    if let Some(field) = builder.field() {
+        if bits >= 64 || field.modulus() >> bits == 0 {
+            panic!("{} needs a field with more than 2^{} elements", what, bits);
+        }
+    }
+}
+
+/// Decomposes `value` into `count` bits, least significant first.
+///
+/// The bits are hinted, each constrained to be a bit, and their weighted sum is constrained to
+/// equal `value`, which proves that `value` is below `2^count`. Over a field this needs
+/// `2^count` to be below the modulus.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::gadgets::to_bits;
+///
+/// let mut builder = Builder::new();
+/// let x = builder.init();
+/// let bits = to_bits(&mut builder, &x, 4);
+/// bits.into_iter().for_each(|bit| builder.output(bit));
+/// assert_eq!(builder.evaluate(vec![6]), vec![0, 1, 1, 0]);
+///
+/// builder.fill_nodes(vec![16]);
+/// assert!(!builder.check_constraints());
+/// ```
+pub fn to_bits(builder: &mut Builder, value: &Node, count: usize) -> Vec<Node> {
+    let bits = hint_bits(builder, value, count);
+    let recomposed = from_bits(builder, &bits);
+    builder.assert_equal(recomposed, value.clone());
+    bits
+}
+
+/// Constrains the number with the given bits, least significant first, to be below `bound`.
+///
+/// Walking from the most significant bit, a product tracks whether the bits so far equal those
+/// of `bound`. While they do, a bit where `bound` has a `0` must be `0` too, and at the end the
+/// bits must differ somewhere. This costs at most two gates per bit and, unlike `less_than`,
+/// works for any number of bits, so it makes decompositions with `2^count` above the modulus
+/// canonical by bounding them by the modulus.
+///
+/// # Panics
+///
+/// Panics if `bound` is zero, which no number is below.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::gadgets::{assert_bits_below, to_bits};
+///
+/// let mut builder = Builder::new();
+/// let x = builder.init();
+/// let bits = to_bits(&mut builder, &x, 4);
+/// assert_bits_below(&mut builder, &bits, 10);
+/// builder.fill_nodes(vec![9]);
+/// assert!(builder.check_constraints());
+/// builder.fill_nodes(vec![10]);
+/// assert!(!builder.check_constraints());
+/// ```
+pub fn assert_bits_below(builder: &mut Builder, bits: &[Node], bound: u64) {
+    if bound == 0 {
+        panic!("no number is below 0");
+    }
+    if bits.len() < 64 && bound >> bits.len() != 0 {
+        return;
+    }
+    // `None` while no bit of the bound was set, when the prefixes trivially agree
+    let mut equal: Option<Node> = None;
+    for (i, bit) in bits.iter().enumerate().rev() {
+        let prefix = match &equal {
+            Some(equal) => builder.mul(equal.clone(), bit.clone()),
+            None => bit.clone(),
+        };
+        match i < 64 && bound >> i & 1 == 1 {
+            true => equal = Some(prefix),
+            false => builder.assert_zero(prefix),
+        }
+    }
+    builder.assert_zero(equal.expect("a nonzero bound has a set bit"));
+}
+
+/// Computes `Σ 2^i bits[i]` by doubling, most significant bit first.
+///
+/// # Panics
+///
+/// Panics if no bits are given.
+pub fn from_bits(builder: &mut Builder, bits: &[Node]) -> Node {
+    bits.iter()
+        .rev()
+        .cloned()
+        .reduce(|value, bit| {
+            let double = builder.add(value.clone(), value);
+            builder.add(double, bit)
+        })
+        .expect("cannot recompose an empty list of bits")
+}
+
+/// Hints the lowest `count` bits of `value`, least significant first, and constrains each to
+/// be `0` or `1`.
+pub(crate) fn hint_bits(builder: &mut Builder, value: &Node, count: usize) -> Vec<Node> {
+    (0..count)
+        .map(|i| {
+            let bit = builder.hint(&[value], move |values| values[0] >> i & 1);
+            assert_bit(builder, &bit);
+            bit
+        })
+        .collect()
+}
+
+/// Divides `a` by `b` in the circuit's field.
+///
+/// The quotient is hinted and checked with a single constraint `quotient * b = a`. If `b` is
+/// zero the constraint fails unless `a` is zero too, in which case any quotient satisfies it.
+///
+/// # Panics
+///
+/// Panics if the circuit does not compute in a field.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::field::Field;
+/// use arithmetic_circuit::gadgets::div;
+///
+/// let mut builder = Builder::new_in_field(Field::new(17));
+/// let (a, b) = (builder.init(), builder.init());
+/// let quotient = div(&mut builder, &a, &b);
+/// builder.output(quotient);
+/// assert_eq!(builder.evaluate(vec![1, 3]), vec![6]);
+/// ```
+pub fn div(builder: &mut Builder, a: &Node, b: &Node) -> Node {
+    let Some(field) = builder.field() else {
+        panic!("division needs a circuit over a field, see Builder::new_in_field");
+    };
+    let quotient = builder.hint(&[a, b], move |values| {
+        field.div(values[0], values[1]).unwrap_or(0)
+    });
+    let product = builder.mul(quotient.clone(), b.clone());
+    builder.assert_equal(product, a.clone());
+    quotient
+}
+
+/// Returns `if_set` if `bit` is `1` and `if_unset` if it is `0`.
+///
+/// The result is hinted and checked with `s + bit * if_unset = if_unset + bit * if_set`, which
+/// needs no subtraction and so works without a field too. `bit` is not constrained to be a bit,
+/// see `assert_bit`.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::gadgets::select;
+///
+/// let mut builder = Builder::new();
+/// let bit = builder.init();
+/// let (a, b) = (builder.init(), builder.init());
+/// let selected = select(&mut builder, &bit, &a, &b);
+/// builder.output(selected);
+/// assert_eq!(builder.evaluate(vec![1, 3, 4]), vec![3]);
+/// assert_eq!(builder.evaluate(vec![0, 3, 4]), vec![4]);
+/// ```
+pub fn select(builder: &mut Builder, bit: &Node, if_set: &Node, if_unset: &Node) -> Node {
+    let selected = builder.hint(&[bit, if_set, if_unset], |values| match values[0] {
+        0 => values[2],
+        _ => values[1],
+    });
+    let unset = builder.mul(bit.clone(), if_unset.clone());
+    let left = builder.add(selected.clone(), unset);
+    let set = builder.mul(bit.clone(), if_set.clone());
+    let right = builder.add(if_unset.clone(), set);
+    builder.assert_equal(left, right);
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pow_gate_count() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let powers: Vec<Node> = [0, 1, 5, 8]
+            .iter()
+            .map(|e| pow(&mut builder, &x, *e))
+            .collect();
+        powers.into_iter().for_each(|power| builder.output(power));
+        assert_eq!(builder.evaluate(vec![3]), vec![1, 3, 243, 6561]);
+        // 5 = 101b takes two squarings and a multiplication, 8 = 1000b three squarings
+        assert_eq!(builder.constraints().count(), 6);
+    }
+}
@@ -0,0 +1,160 @@
+//! Comparison of integers of a known bit width.
+//!
+//! Circuits cannot branch on values, so comparing `a < b` produces a bit instead. For values
+//! below `2^w`, the difference `t = a + 2^w - b` lies in `[1, 2^(w+1))` and its bit `w` is set
+//! exactly when `a >= b`. The difference is hinted, constrained by `a + 2^w = b + t` and
+//! decomposed into `w + 1` bits, so the comparison costs about `3w` constraints and needs no
+//! subtraction in the circuit.
+//!
+//! `min`, `max` and `abs_diff` select between the values with the result of one comparison.
+use crate::gadgets::{check_field_bits, select, to_bits};
+use crate::{Builder, Node};
+
+/// Returns a bit that is `1` if `a < b` and `0` otherwise.
+///
+/// Both values must be below `2^bit_width`, e.g. range checked with `to_bits`; otherwise the
+/// constraints fail or the result is meaningless.
+///
+/// # Panics
+///
+/// Panics if `bit_width` is 63 or more, or the circuit computes in a field with at most
+/// `2^(bit_width + 1)` elements.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::gadgets::less_than;
+///
+/// let mut builder = Builder::new();
+/// let (a, b) = (builder.init(), builder.init());
+/// let lt = less_than(&mut builder, &a, &b, 8);
+/// builder.output(lt);
+/// assert_eq!(builder.evaluate(vec![3, 200]), vec![1]);
+/// assert_eq!(builder.evaluate(vec![200, 200]), vec![0]);
+/// ```
+pub fn less_than(builder: &mut Builder, a: &Node, b: &Node, bit_width: usize) -> Node {
+    check_width(builder, bit_width);
+    let shift = 1u64 << bit_width;
+    let difference = builder.hint(&[a, b], move |values| {
+        values[0].wrapping_add(shift).wrapping_sub(values[1])
+    });
+    let bits = to_bits(builder, &difference, bit_width + 1);
+    let offset = builder.constant(shift);
+    let left = builder.add(a.clone(), offset);
+    let right = builder.add(b.clone(), difference);
+    builder.assert_equal(left, right);
+
+    // the top bit says a >= b, its complement is the result
+    let at_least = bits[bit_width].clone();
+    let less = builder.hint(&[&at_least], |values| (values[0] == 0) as u64);
+    let sum = builder.add(less.clone(), at_least);
+    let one = builder.constant(1);
+    builder.assert_equal(sum, one);
+    less
+}
+
+/// Returns the smaller of two values below `2^bit_width`.
+///
+/// # Panics
+///
+/// Panics if the values cannot be compared, see `less_than`.
+pub fn min(builder: &mut Builder, a: &Node, b: &Node, bit_width: usize) -> Node {
+    let less = less_than(builder, a, b, bit_width);
+    select(builder, &less, a, b)
+}
+
+/// Returns the larger of two values below `2^bit_width`.
+///
+/// # Panics
+///
+/// Panics if the values cannot be compared, see `less_than`.
+pub fn max(builder: &mut Builder, a: &Node, b: &Node, bit_width: usize) -> Node {
+    let less = less_than(builder, a, b, bit_width);
+    select(builder, &less, b, a)
+}
+
+/// Returns `|a - b|` for two values below `2^bit_width`.
+///
+/// The difference is hinted and checked with `min + difference = max`, so it needs no
+/// subtraction in the circuit.
+///
+/// # Panics
+///
+/// Panics if the values cannot be compared, see `less_than`.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::gadgets::abs_diff;
+///
+/// let mut builder = Builder::new();
+/// let (a, b) = (builder.init(), builder.init());
+/// let difference = abs_diff(&mut builder, &a, &b, 16);
+/// builder.output(difference);
+/// assert_eq!(builder.evaluate(vec![3, 1000]), vec![997]);
+/// assert_eq!(builder.evaluate(vec![1000, 3]), vec![997]);
+/// ```
+pub fn abs_diff(builder: &mut Builder, a: &Node, b: &Node, bit_width: usize) -> Node {
+    let less = less_than(builder, a, b, bit_width);
+    let low = select(builder, &less, a, b);
+    let high = select(builder, &less, b, a);
+    let difference = builder.hint(&[&low, &high], |values| values[1].wrapping_sub(values[0]));
+    let sum = builder.add(low, difference.clone());
+    builder.assert_equal(sum, high);
+    difference
+}
+
+/// Panics if values of `bit_width` bits cannot be compared in the circuit.
+fn check_width(builder: &Builder, bit_width: usize) {
+    if bit_width >= 63 {
+        panic!("values to compare must have fewer than 63 bits");
+    }
+    let what = format!("comparing {}-bit values", bit_width);
+    check_field_bits(builder, bit_width + 1, &what);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_less_than_in_field() {
+        let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+        let (a, b) = (builder.init(), builder.init());
+        let lt = less_than(&mut builder, &a, &b, 10);
+        builder.output(lt);
+        for a in [0, 1, 512, 1023] {
+            for b in [0, 1, 512, 1023] {
+                builder.fill_nodes(vec![a, b]);
+                assert!(builder.check_constraints());
+                assert_eq!(builder.evaluate(vec![a, b]), vec![(a < b) as u64]);
+            }
+        }
+        // values out of range cannot be compared
+        builder.fill_nodes(vec![1024, 0]);
+        assert!(!builder.check_constraints());
+    }
+
+    #[test]
+    fn test_min_max_abs_diff() {
+        let mut builder = Builder::new();
+        let (a, b) = (builder.init(), builder.init());
+        let results = [
+            min(&mut builder, &a, &b, 8),
+            max(&mut builder, &a, &b, 8),
+            abs_diff(&mut builder, &a, &b, 8),
+        ];
+        results.into_iter().for_each(|node| builder.output(node));
+        for (a, b) in [(0, 0), (5, 17), (255, 3)] {
+            builder.fill_nodes(vec![a, b]);
+            assert!(builder.check_constraints());
+            assert_eq!(
+                builder.evaluate(vec![a, b]),
+                vec![a.min(b), a.max(b), a.abs_diff(b)]
+            );
+        }
+    }
+}
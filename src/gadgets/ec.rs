@@ -0,0 +1,233 @@
+//! Affine point arithmetic on a toy short Weierstrass curve.
+//!
+//! The curve `y^2 = x^3 + ax + b` over `F_p` is far too small to be secure; it only shows how
+//! elliptic curve operations turn into constraints. Adding two points takes the slope
+//! `λ = (y2 - y1) / (x2 - x1)`, doubling `λ = (3x^2 + a) / (2y)`, and both finish with
+//! `x3 = λ^2 - x1 - x2` and `y3 = λ(x1 - x3) - y1`. The division hints the inverse of the
+//! denominator and checks it with one multiplication (see `div`), which makes affine
+//! coordinates cheap in circuits even though they are slow natively.
+//!
+//! The gadgets do not handle the point at infinity: adding needs `x1 != x2` and doubling
+//! `y != 0`, otherwise the denominator has no inverse and the constraints fail.
+use crate::field::Field;
+use crate::gadgets::div;
+use crate::{Builder, Node};
+
+/// A short Weierstrass curve `y^2 = x^3 + ax + b` over a prime field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Curve {
+    pub field: Field,
+    pub a: u64,
+    pub b: u64,
+}
+
+impl Curve {
+    /// A generator of the toy curve returned by `Curve::toy`.
+    pub const TOY_GENERATOR: (u64, u64) = (3, 3421);
+    /// The number of points of the toy curve, a prime, so every other point generates it.
+    pub const TOY_ORDER: u64 = 65129;
+
+    /// Creates the curve `y^2 = x^3 + ax + b`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the curve is singular, that is `4a^3 + 27b^2 = 0`.
+    pub fn new(field: Field, a: u64, b: u64) -> Self {
+        let (a, b) = (field.reduce(a), field.reduce(b));
+        let discriminant = field.add(
+            field.mul(4, field.pow(a, 3)),
+            field.mul(27, field.mul(b, b)),
+        );
+        if discriminant == 0 {
+            panic!("the curve is singular");
+        }
+        Curve { field, a, b }
+    }
+
+    /// The curve `y^2 = x^3 + 2x + 9` over `F_65381`, which has a prime number of points and
+    /// a field in which cubing is a permutation, so it pairs with MiMC.
+    pub fn toy() -> Self {
+        Curve::new(Field::new(65381), 2, 9)
+    }
+
+    /// Returns `true` if the point lies on the curve.
+    pub fn contains(&self, (x, y): (u64, u64)) -> bool {
+        let field = self.field;
+        let right = field.add(field.pow(x, 3), field.add(field.mul(self.a, x), self.b));
+        field.mul(y, y) == right
+    }
+
+    /// Adds two points outside of the circuit, `None` being the point at infinity.
+    pub fn add_native(&self, p: Option<(u64, u64)>, q: Option<(u64, u64)>) -> Option<(u64, u64)> {
+        let field = self.field;
+        let ((x1, y1), (x2, y2)) = match (p, q) {
+            (None, other) | (other, None) => return other,
+            (Some(p), Some(q)) => (p, q),
+        };
+        let slope = match x1 == x2 {
+            false => field.div(field.sub(y2, y1), field.sub(x2, x1)).unwrap(),
+            true if y1 != y2 || y1 == 0 => return None,
+            true => {
+                let numerator = field.add(field.mul(3, field.mul(x1, x1)), self.a);
+                field.div(numerator, field.add(y1, y1)).unwrap()
+            }
+        };
+        let x3 = field.sub(field.sub(field.mul(slope, slope), x1), x2);
+        let y3 = field.sub(field.mul(slope, field.sub(x1, x3)), y1);
+        Some((x3, y3))
+    }
+
+    /// Multiplies a point by a scalar outside of the circuit, by double-and-add.
+    pub fn mul_native(&self, scalar: u64, point: Option<(u64, u64)>) -> Option<(u64, u64)> {
+        let mut result = None;
+        for bit in (0..u64::BITS - scalar.leading_zeros()).rev() {
+            result = self.add_native(result, result);
+            if scalar >> bit & 1 == 1 {
+                result = self.add_native(result, point);
+            }
+        }
+        result
+    }
+}
+
+/// A point of a curve in affine coordinates, as nodes of a circuit.
+#[derive(Debug, Clone)]
+pub struct EcPoint {
+    pub x: Node,
+    pub y: Node,
+}
+
+/// Constrains the point to lie on the curve.
+///
+/// # Panics
+///
+/// Panics if the circuit does not compute in the curve's field.
+pub fn assert_on_curve(builder: &mut Builder, curve: &Curve, point: &EcPoint) {
+    check_field(builder, curve);
+    let square = builder.mul(point.x.clone(), point.x.clone());
+    let cube = builder.mul(square, point.x.clone());
+    let a = builder.constant(curve.a);
+    let ax = builder.mul(a, point.x.clone());
+    let b = builder.constant(curve.b);
+    let right = builder.add(cube, ax);
+    let right = builder.add(right, b);
+    let left = builder.mul(point.y.clone(), point.y.clone());
+    builder.assert_equal(left, right);
+}
+
+/// Adds two points with different x coordinates.
+///
+/// # Panics
+///
+/// Panics if the circuit does not compute in the curve's field.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::gadgets::{ec_add, ec_double, Curve, EcPoint};
+///
+/// let curve = Curve::toy();
+/// let mut builder = Builder::new_in_field(curve.field);
+/// let point = EcPoint { x: builder.init(), y: builder.init() };
+/// let doubled = ec_double(&mut builder, &curve, &point);
+/// let tripled = ec_add(&mut builder, &curve, &doubled, &point);
+/// builder.output(tripled.x);
+/// builder.output(tripled.y);
+///
+/// let (x, y) = Curve::TOY_GENERATOR;
+/// let (x3, y3) = curve.mul_native(3, Some((x, y))).unwrap();
+/// assert_eq!(builder.evaluate(vec![x, y]), vec![x3, y3]);
+/// ```
+pub fn ec_add(builder: &mut Builder, curve: &Curve, p: &EcPoint, q: &EcPoint) -> EcPoint {
+    check_field(builder, curve);
+    let numerator = builder.sub(q.y.clone(), p.y.clone());
+    let denominator = builder.sub(q.x.clone(), p.x.clone());
+    let slope = slope(builder, &numerator, &denominator);
+    finish(builder, &slope, p, &q.x)
+}
+
+/// Doubles a point with a non-zero y coordinate.
+///
+/// # Panics
+///
+/// Panics if the circuit does not compute in the curve's field.
+pub fn ec_double(builder: &mut Builder, curve: &Curve, p: &EcPoint) -> EcPoint {
+    check_field(builder, curve);
+    let square = builder.mul(p.x.clone(), p.x.clone());
+    let three = builder.constant(3);
+    let numerator = builder.mul(three, square);
+    let a = builder.constant(curve.a);
+    let numerator = builder.add(numerator, a);
+    let denominator = builder.add(p.y.clone(), p.y.clone());
+    let slope = slope(builder, &numerator, &denominator);
+    finish(builder, &slope, p, &p.x)
+}
+
+/// Computes `numerator / denominator`, constraining the denominator to be non-zero.
+fn slope(builder: &mut Builder, numerator: &Node, denominator: &Node) -> Node {
+    let one = builder.constant(1);
+    let inverse = div(builder, &one, denominator);
+    builder.mul(numerator.clone(), inverse)
+}
+
+/// Computes `x3 = λ^2 - x1 - x2` and `y3 = λ(x1 - x3) - y1`.
+fn finish(builder: &mut Builder, slope: &Node, p: &EcPoint, x2: &Node) -> EcPoint {
+    let square = builder.mul(slope.clone(), slope.clone());
+    let x = builder.sub(square, p.x.clone());
+    let x = builder.sub(x, x2.clone());
+    let difference = builder.sub(p.x.clone(), x.clone());
+    let y = builder.mul(slope.clone(), difference);
+    let y = builder.sub(y, p.y.clone());
+    EcPoint { x, y }
+}
+
+fn check_field(builder: &Builder, curve: &Curve) {
+    if builder.field() != Some(curve.field) {
+        panic!("curve arithmetic needs a circuit over the field of the curve");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gadgets_match_native() {
+        let curve = Curve::toy();
+        let generator = Some(Curve::TOY_GENERATOR);
+        assert!(curve.contains(Curve::TOY_GENERATOR));
+        assert_eq!(curve.mul_native(Curve::TOY_ORDER, generator), None);
+
+        let mut builder = Builder::new_in_field(curve.field);
+        let p = EcPoint {
+            x: builder.init(),
+            y: builder.init(),
+        };
+        let q = EcPoint {
+            x: builder.init(),
+            y: builder.init(),
+        };
+        assert_on_curve(&mut builder, &curve, &p);
+        assert_on_curve(&mut builder, &curve, &q);
+        let sum = ec_add(&mut builder, &curve, &p, &q);
+        let doubled = ec_double(&mut builder, &curve, &sum);
+        for node in [sum.x, sum.y, doubled.x, doubled.y] {
+            builder.output(node);
+        }
+
+        let (x1, y1) = curve.mul_native(5, generator).unwrap();
+        let (x2, y2) = curve.mul_native(1234, generator).unwrap();
+        let (sx, sy) = curve.mul_native(1239, generator).unwrap();
+        let (dx, dy) = curve.mul_native(2478, generator).unwrap();
+        builder.fill_nodes(vec![x1, y1, x2, y2]);
+        assert!(builder.check_constraints());
+        assert_eq!(builder.evaluate(vec![x1, y1, x2, y2]), vec![sx, sy, dx, dy]);
+
+        // a point off the curve, and adding a point to itself, are rejected
+        builder.fill_nodes(vec![x1, y1 + 1, x2, y2]);
+        assert!(!builder.check_constraints());
+        builder.fill_nodes(vec![x1, y1, x1, y1]);
+        assert!(!builder.check_constraints());
+    }
+}
@@ -0,0 +1,263 @@
+//! Merkle trees over a pluggable hash.
+//!
+//! A tree of arity `k` hashes every group of `k` consecutive nodes of a level into one node of
+//! the level above, until a single root is left. The hash is any gadget taking a slice of nodes,
+//! so the same tree works with MiMC, Poseidon or a test hash, and the native reference takes the
+//! matching native function.
+//!
+//! `merkle_verify` checks a membership proof in a binary tree: the path of siblings from a leaf
+//! to the root, with the bits of the leaf's index telling on which side each sibling goes.
+use crate::gadgets::{assert_bit, select};
+use crate::{Builder, Node};
+
+/// Adds a Merkle tree over `leaves` to the circuit and returns its root.
+///
+/// The leaves are padded with zeros to the next power of `arity`; a single leaf is its own root.
+///
+/// # Arguments
+///
+/// * `builder`: The circuit to add the gates to.
+/// * `leaves`: The leaves of the tree, left to right.
+/// * `arity`: The number of children of every inner node.
+/// * `hash`: Adds the hash of the children of a node to the circuit, e.g. a closure calling
+///   `mimc` or `poseidon`.
+///
+/// # Panics
+///
+/// Panics if there are no leaves or the arity is below 2.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::gadgets::{merkle_root, merkle_root_native};
+///
+/// // a toy hash: the sum of the children
+/// let mut builder = Builder::new();
+/// let leaves: Vec<_> = (0..9).map(|_| builder.init()).collect();
+/// let root = merkle_root(&mut builder, &leaves, 3, |builder, children| {
+///     children
+///         .iter()
+///         .cloned()
+///         .reduce(|sum, child| builder.add(sum, child))
+///         .unwrap()
+/// });
+/// builder.output(root);
+///
+/// let values: Vec<u64> = (1..=9).collect();
+/// let expected = merkle_root_native(&values, 3, |children| children.iter().sum());
+/// assert_eq!(builder.evaluate(values), vec![expected]);
+/// ```
+pub fn merkle_root(
+    builder: &mut Builder,
+    leaves: &[Node],
+    arity: usize,
+    mut hash: impl FnMut(&mut Builder, &[Node]) -> Node,
+) -> Node {
+    check_shape(leaves.len(), arity);
+    let mut level = leaves.to_vec();
+    if level.len() > 1 {
+        let zero = builder.constant(0);
+        level.resize(padded_len(level.len(), arity), zero);
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(arity)
+            .map(|children| hash(builder, children))
+            .collect();
+    }
+    level.pop().unwrap()
+}
+
+/// Computes the root of a Merkle tree outside of the circuit, see `merkle_root`.
+///
+/// # Panics
+///
+/// Panics if there are no leaves or the arity is below 2.
+pub fn merkle_root_native(leaves: &[u64], arity: usize, hash: impl Fn(&[u64]) -> u64) -> u64 {
+    check_shape(leaves.len(), arity);
+    let mut level = leaves.to_vec();
+    if level.len() > 1 {
+        level.resize(padded_len(level.len(), arity), 0);
+    }
+    while level.len() > 1 {
+        level = level.chunks(arity).map(&hash).collect();
+    }
+    level[0]
+}
+
+/// Constrains `leaf` to be a leaf of the binary Merkle tree with root `root`.
+///
+/// At every level, the current node and its sibling are ordered with `select` by the index bit
+/// of that level and hashed into their parent; the last parent must equal the root.
+///
+/// # Arguments
+///
+/// * `builder`: The circuit to add the gates to.
+/// * `leaf`: The leaf whose membership is proven.
+/// * `path`: The siblings from the leaf's level up to the children of the root.
+/// * `root`: The root of the tree.
+/// * `index_bits`: The position of the leaf, least significant bit first, one bit per level. A
+///   bit of `1` means the current node is the right child. Every bit is constrained to be a bit.
+/// * `hash`: Adds the hash of two children to the circuit, as for `merkle_root`.
+///
+/// # Panics
+///
+/// Panics if the number of index bits does not match the length of the path.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::gadgets::{merkle_path_native, merkle_root_native, merkle_verify};
+///
+/// let hash = |children: &[u64]| 3 * children[0] + children[1];
+/// let leaves = [5, 6, 7, 8];
+/// let root = merkle_root_native(&leaves, 2, hash);
+/// let path = merkle_path_native(&leaves, 2, hash);
+///
+/// let mut builder = Builder::new();
+/// let leaf = builder.init();
+/// let siblings: Vec<_> = (0..2).map(|_| builder.init()).collect();
+/// let bits: Vec<_> = (0..2).map(|_| builder.init()).collect();
+/// let expected_root = builder.init();
+/// merkle_verify(&mut builder, &leaf, &siblings, &expected_root, &bits, |builder, children| {
+///     let three = builder.constant(3);
+///     let scaled = builder.mul(three, children[0].clone());
+///     builder.add(scaled, children[1].clone())
+/// });
+///
+/// // leaf 7 sits at index 2 = 0b10
+/// builder.fill_nodes(vec![7, path[0], path[1], 0, 1, root]);
+/// assert!(builder.check_constraints());
+/// builder.fill_nodes(vec![7, path[0], path[1], 1, 0, root]);
+/// assert!(!builder.check_constraints());
+/// ```
+pub fn merkle_verify(
+    builder: &mut Builder,
+    leaf: &Node,
+    path: &[Node],
+    root: &Node,
+    index_bits: &[Node],
+    mut hash: impl FnMut(&mut Builder, &[Node]) -> Node,
+) {
+    if index_bits.len() != path.len() {
+        panic!("the number of index bits does not match the length of the path");
+    }
+    let mut current = leaf.clone();
+    for (sibling, bit) in path.iter().zip(index_bits) {
+        assert_bit(builder, bit);
+        let left = select(builder, bit, sibling, &current);
+        let right = select(builder, bit, &current, sibling);
+        current = hash(builder, &[left, right]);
+    }
+    builder.assert_equal(current, root.clone());
+}
+
+/// Computes the siblings on the path from the leaf at `index` to the root of a binary Merkle
+/// tree, as expected by `merkle_verify`.
+///
+/// # Panics
+///
+/// Panics if there are no leaves or `index` is not the position of a leaf.
+pub fn merkle_path_native(leaves: &[u64], index: usize, hash: impl Fn(&[u64]) -> u64) -> Vec<u64> {
+    check_shape(leaves.len(), 2);
+    if index >= leaves.len() {
+        panic!("the index is not the position of a leaf");
+    }
+    let mut level = leaves.to_vec();
+    level.resize(padded_len(level.len(), 2), 0);
+    let mut index = index;
+    let mut path = vec![];
+    while level.len() > 1 {
+        path.push(level[index ^ 1]);
+        level = level.chunks(2).map(&hash).collect();
+        index /= 2;
+    }
+    path
+}
+
+/// Returns the smallest power of `arity` that is at least `len`.
+fn padded_len(len: usize, arity: usize) -> usize {
+    let mut padded = 1;
+    while padded < len {
+        padded *= arity;
+    }
+    padded
+}
+
+fn check_shape(leaves: usize, arity: usize) {
+    if leaves == 0 {
+        panic!("a Merkle tree needs at least one leaf");
+    }
+    if arity < 2 {
+        panic!("the arity of a Merkle tree must be at least 2");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+    use crate::gadgets::{mimc, mimc_constants, mimc_native};
+
+    #[test]
+    fn test_root_matches_native_with_mimc() {
+        let field = Field::new(1_000_000_007);
+        let constants = mimc_constants(field, 8);
+        for (count, arity) in [(1, 2), (5, 2), (4, 4), (10, 3)] {
+            let mut builder = Builder::new_in_field(field);
+            let leaves: Vec<Node> = (0..count).map(|_| builder.init()).collect();
+            let root = merkle_root(&mut builder, &leaves, arity, |builder, children| {
+                mimc(builder, children, 8, &constants)
+            });
+            builder.output(root);
+
+            let values: Vec<u64> = (0..count as u64).map(|i| i * 7 + 1).collect();
+            let expected = merkle_root_native(&values, arity, |children| {
+                mimc_native(field, children, 8, &constants)
+            });
+            assert_eq!(builder.evaluate(values), vec![expected]);
+        }
+    }
+
+    #[test]
+    fn test_verify_every_leaf() {
+        let field = Field::new(1_000_000_007);
+        let constants = mimc_constants(field, 8);
+        let hash = |children: &[u64]| mimc_native(field, children, 8, &constants);
+        let leaves = [11, 12, 13, 14, 15];
+        let root = merkle_root_native(&leaves, 2, hash);
+
+        let mut builder = Builder::new_in_field(field);
+        let leaf = builder.init();
+        let path: Vec<Node> = (0..3).map(|_| builder.init()).collect();
+        let bits: Vec<Node> = (0..3).map(|_| builder.init()).collect();
+        let expected_root = builder.init();
+        merkle_verify(
+            &mut builder,
+            &leaf,
+            &path,
+            &expected_root,
+            &bits,
+            |builder, children| mimc(builder, children, 8, &constants),
+        );
+        for (index, value) in leaves.iter().enumerate() {
+            let mut inputs = vec![*value];
+            inputs.extend(merkle_path_native(&leaves, index, hash));
+            inputs.extend((0..3).map(|level| (index >> level & 1) as u64));
+            inputs.push(root);
+            builder.fill_nodes(inputs.clone());
+            assert!(builder.check_constraints());
+
+            // the wrong leaf, or a non-bit index, is rejected
+            inputs[0] += 1;
+            builder.fill_nodes(inputs.clone());
+            assert!(!builder.check_constraints());
+            inputs[0] -= 1;
+            inputs[4] = 2;
+            builder.fill_nodes(inputs);
+            assert!(!builder.check_constraints());
+        }
+    }
+}
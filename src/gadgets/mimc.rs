@@ -0,0 +1,139 @@
+//! The MiMC hash, the classic example of a circuit-friendly hash.
+//!
+//! The MiMC permutation with key `k` applies `r` rounds of `x ← (x + k + c_i)^3` with round
+//! constants `c_i` and adds the key once more at the end. Every round costs two multiplication
+//! gates, far fewer than a bit-oriented hash needs. Cubing is a permutation of `F_p` when
+//! `gcd(3, p - 1) = 1`, and about `log_3(p)` rounds are needed for security; the toy fields of
+//! this crate need only a few. Messages are hashed in the Miyaguchi–Preneel mode: starting from
+//! `h = 0`, every input `x` updates `h ← E_h(x) + h + x`.
+use crate::field::Field;
+use crate::gadgets::pow;
+use crate::hash::mix;
+use crate::{Builder, Node};
+
+/// Derives `rounds` round constants, the first one zero as in the MiMC paper.
+pub fn mimc_constants(field: Field, rounds: usize) -> Vec<u64> {
+    (0..rounds as u64)
+        .map(|round| match round {
+            0 => 0,
+            _ => field.reduce(mix(round ^ 0x4d69_4d43)),
+        })
+        .collect()
+}
+
+/// Adds the MiMC permutation of `x` under `key` to the circuit.
+///
+/// # Panics
+///
+/// Panics if the circuit does not compute in a field.
+pub fn mimc_permutation(builder: &mut Builder, x: &Node, key: &Node, constants: &[u64]) -> Node {
+    if builder.field().is_none() {
+        panic!("MiMC needs a circuit over a field, see Builder::new_in_field");
+    }
+    let mut x = x.clone();
+    for constant in constants {
+        let constant = builder.constant(*constant);
+        let shifted = builder.add(x, key.clone());
+        let shifted = builder.add(shifted, constant);
+        x = pow(builder, &shifted, 3);
+    }
+    builder.add(x, key.clone())
+}
+
+/// Adds the MiMC hash of `inputs` to the circuit and returns the digest.
+///
+/// # Arguments
+///
+/// * `builder`: The circuit to add the gates to. It must compute in a field.
+/// * `inputs`: The message, one field element per node.
+/// * `rounds`: The number of rounds of the permutation.
+/// * `constants`: One round constant per round, e.g. from `mimc_constants`.
+///
+/// # Panics
+///
+/// Panics if the number of constants does not match the number of rounds, or the circuit does
+/// not compute in a field.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::field::Field;
+/// use arithmetic_circuit::gadgets;
+///
+/// let field = Field::new(1_000_000_007);
+/// let constants = gadgets::mimc_constants(field, 20);
+/// let mut builder = Builder::new_in_field(field);
+/// let message = vec![builder.init(), builder.init()];
+/// let digest = gadgets::mimc(&mut builder, &message, 20, &constants);
+/// builder.output(digest);
+///
+/// let expected = gadgets::mimc_native(field, &[1, 2], 20, &constants);
+/// assert_eq!(builder.evaluate(vec![1, 2]), vec![expected]);
+/// ```
+pub fn mimc(builder: &mut Builder, inputs: &[Node], rounds: usize, constants: &[u64]) -> Node {
+    if constants.len() != rounds {
+        panic!("MiMC needs one round constant per round");
+    }
+    let mut state = builder.constant(0);
+    for input in inputs {
+        let encrypted = mimc_permutation(builder, input, &state, constants);
+        let mixed = builder.add(encrypted, state);
+        state = builder.add(mixed, input.clone());
+    }
+    state
+}
+
+/// Computes the MiMC hash of `inputs` outside of a circuit.
+///
+/// # Panics
+///
+/// Panics if the number of constants does not match the number of rounds.
+pub fn mimc_native(field: Field, inputs: &[u64], rounds: usize, constants: &[u64]) -> u64 {
+    if constants.len() != rounds {
+        panic!("MiMC needs one round constant per round");
+    }
+    let permutation = |x: u64, key: u64| {
+        let x = constants.iter().fold(field.reduce(x), |x, constant| {
+            field.pow(field.add(field.add(x, key), *constant), 3)
+        });
+        field.add(x, key)
+    };
+    inputs.iter().fold(0, |state, input| {
+        let input = field.reduce(*input);
+        field.add(field.add(permutation(input, state), state), input)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRng;
+
+    #[test]
+    fn test_matches_native() {
+        let field = Field::new(1_000_000_007);
+        let constants = mimc_constants(field, 10);
+        let mut builder = Builder::new_in_field(field);
+        let message: Vec<Node> = (0..3).map(|_| builder.init()).collect();
+        let digest = mimc(&mut builder, &message, 10, &constants);
+        builder.output(digest);
+        // two multiplications per round and input
+        assert_eq!(
+            builder
+                .constraints()
+                .filter(|c| matches!(c, crate::enums::ConstraintView::Mul { .. }))
+                .count(),
+            60
+        );
+
+        let mut rng = TestRng::new(3);
+        for _ in 0..5 {
+            let inputs: Vec<u64> = (0..3).map(|_| rng.below(field.modulus())).collect();
+            let expected = mimc_native(field, &inputs, 10, &constants);
+            builder.fill_nodes(inputs);
+            assert!(builder.check_constraints());
+            assert_eq!(builder.outputs()[0].evaluated(), Some(expected));
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! A simplified Poseidon sponge.
+//!
+//! Poseidon keeps a state of `t` field elements. Every round adds round constants to the
+//! state, applies the S-box `x ← x^5` and multiplies the state with an MDS matrix, so every
+//! output element depends on every input element. Full rounds apply the S-box to the whole
+//! state, partial rounds only to the first element, which saves most of the multiplications
+//! while the full rounds at the start and end keep the permutation secure. The S-box is a
+//! permutation of `F_p` when `gcd(5, p - 1) = 1`.
+//!
+//! The hash is a sponge: the first element of the state is the capacity, the others the rate.
+//! Inputs are padded with a `1` and zeros to a multiple of the rate, added into the rate part
+//! chunk by chunk with a permutation after every chunk, and the digest is the first rate
+//! element. The MDS matrix is the Cauchy matrix `1 / (i + t + j)` and the round constants are
+//! derived from a fixed seed; the real Poseidon instances fix both with a dedicated procedure.
+use crate::field::Field;
+use crate::gadgets::pow;
+use crate::hash::mix;
+use crate::{Builder, Node};
+
+const ALPHA: u64 = 5;
+
+/// The parameters of a Poseidon permutation over a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoseidonParams {
+    pub field: Field,
+    /// The size `t` of the state, one capacity element plus the rate.
+    pub width: usize,
+    /// The number of full rounds, half of them before and half after the partial rounds.
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    /// One constant per round and state element.
+    pub round_constants: Vec<Vec<u64>>,
+    /// The `t` by `t` MDS matrix.
+    pub mds: Vec<Vec<u64>>,
+}
+
+impl PoseidonParams {
+    /// Derives round constants and the MDS matrix for the given shape.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is below 2, `full_rounds` is odd, or the field is too small for the
+    /// MDS matrix.
+    pub fn new(field: Field, width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        if width < 2 || !full_rounds.is_multiple_of(2) {
+            panic!("Poseidon needs a width of at least 2 and an even number of full rounds");
+        }
+        if (2 * width as u64) >= field.modulus() {
+            panic!("the field is too small for a Cauchy MDS matrix of this width");
+        }
+        let round_constants = (0..full_rounds + partial_rounds)
+            .map(|round| {
+                (0..width)
+                    .map(|i| field.reduce(mix(((round * width + i) as u64) ^ 0x506f_7365)))
+                    .collect()
+            })
+            .collect();
+        let mds = (0..width)
+            .map(|i| {
+                (0..width)
+                    .map(|j| field.inv((i + width + j) as u64).unwrap())
+                    .collect()
+            })
+            .collect();
+        PoseidonParams {
+            field,
+            width,
+            full_rounds,
+            partial_rounds,
+            round_constants,
+            mds,
+        }
+    }
+
+    /// Returns `true` if round `round` applies the S-box to the whole state.
+    fn is_full(&self, round: usize) -> bool {
+        round < self.full_rounds / 2 || round >= self.full_rounds / 2 + self.partial_rounds
+    }
+
+    /// Pads the message with a `1` and zeros to a multiple of the rate.
+    fn padded<T: Clone>(&self, inputs: &[T], one: T, zero: T) -> Vec<T> {
+        let rate = self.width - 1;
+        let mut padded = inputs.to_vec();
+        padded.push(one);
+        while !padded.len().is_multiple_of(rate) {
+            padded.push(zero.clone());
+        }
+        padded
+    }
+}
+
+/// Adds the Poseidon permutation of `state` to the circuit.
+///
+/// # Panics
+///
+/// Panics if the state does not match the width, or the circuit does not compute in the
+/// parameters' field.
+pub fn poseidon_permutation(
+    builder: &mut Builder,
+    state: &[Node],
+    params: &PoseidonParams,
+) -> Vec<Node> {
+    if builder.field() != Some(params.field) {
+        panic!("Poseidon needs a circuit over the field of its parameters");
+    }
+    if state.len() != params.width {
+        panic!("the state does not match the width of the permutation");
+    }
+    let mut state = state.to_vec();
+    for (round, constants) in params.round_constants.iter().enumerate() {
+        for (element, constant) in state.iter_mut().zip(constants) {
+            let constant = builder.constant(*constant);
+            *element = builder.add(element.clone(), constant);
+        }
+        let boxed = match params.is_full(round) {
+            true => state.len(),
+            false => 1,
+        };
+        for element in state.iter_mut().take(boxed) {
+            *element = pow(builder, element, ALPHA);
+        }
+        state = params
+            .mds
+            .iter()
+            .map(|row| {
+                let terms: Vec<Node> = row
+                    .iter()
+                    .zip(&state)
+                    .map(|(coefficient, element)| {
+                        let coefficient = builder.constant(*coefficient);
+                        builder.mul(coefficient, element.clone())
+                    })
+                    .collect();
+                terms
+                    .into_iter()
+                    .reduce(|sum, term| builder.add(sum, term))
+                    .unwrap()
+            })
+            .collect();
+    }
+    state
+}
+
+/// Adds the Poseidon sponge hash of `inputs` to the circuit and returns the digest.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::field::Field;
+/// use arithmetic_circuit::gadgets::{self, PoseidonParams};
+///
+/// let field = Field::new(1_000_000_007);
+/// let params = PoseidonParams::new(field, 3, 8, 10);
+/// let mut builder = Builder::new_in_field(field);
+/// let message = vec![builder.init(), builder.init()];
+/// let digest = gadgets::poseidon(&mut builder, &message, &params);
+/// builder.output(digest);
+///
+/// let expected = gadgets::poseidon_native(&[1, 2], &params);
+/// assert_eq!(builder.evaluate(vec![1, 2]), vec![expected]);
+/// ```
+pub fn poseidon(builder: &mut Builder, inputs: &[Node], params: &PoseidonParams) -> Node {
+    let zero = builder.constant(0);
+    let one = builder.constant(1);
+    let mut state = vec![zero.clone(); params.width];
+    for chunk in params.padded(inputs, one, zero).chunks(params.width - 1) {
+        for (element, input) in state[1..].iter_mut().zip(chunk) {
+            *element = builder.add(element.clone(), input.clone());
+        }
+        state = poseidon_permutation(builder, &state, params);
+    }
+    state.swap_remove(1)
+}
+
+/// Computes the Poseidon permutation outside of a circuit.
+///
+/// # Panics
+///
+/// Panics if the state does not match the width.
+pub fn poseidon_permutation_native(state: &[u64], params: &PoseidonParams) -> Vec<u64> {
+    if state.len() != params.width {
+        panic!("the state does not match the width of the permutation");
+    }
+    let field = params.field;
+    let mut state: Vec<u64> = state.iter().map(|value| field.reduce(*value)).collect();
+    for (round, constants) in params.round_constants.iter().enumerate() {
+        for (element, constant) in state.iter_mut().zip(constants) {
+            *element = field.add(*element, *constant);
+        }
+        let boxed = match params.is_full(round) {
+            true => state.len(),
+            false => 1,
+        };
+        for element in state.iter_mut().take(boxed) {
+            *element = field.pow(*element, ALPHA);
+        }
+        state = params
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&state)
+                    .fold(0, |sum, (coefficient, element)| {
+                        field.add(sum, field.mul(*coefficient, *element))
+                    })
+            })
+            .collect();
+    }
+    state
+}
+
+/// Computes the Poseidon sponge hash of `inputs` outside of a circuit.
+pub fn poseidon_native(inputs: &[u64], params: &PoseidonParams) -> u64 {
+    let field = params.field;
+    let mut state = vec![0; params.width];
+    for chunk in params.padded(inputs, 1, 0).chunks(params.width - 1) {
+        for (element, input) in state[1..].iter_mut().zip(chunk) {
+            *element = field.add(*element, field.reduce(*input));
+        }
+        state = poseidon_permutation_native(&state, params);
+    }
+    state[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::ConstraintView;
+    use crate::gadgets::{mimc, mimc_constants};
+
+    #[test]
+    fn test_matches_native_and_is_cheaper_than_full_rounds() {
+        let field = Field::new(1_000_000_007);
+        let multiplications = |builder: &Builder| {
+            builder
+                .constraints()
+                .filter(|c| matches!(c, ConstraintView::Mul { .. }))
+                .count()
+        };
+        let mut counts = vec![];
+        for inputs in [vec![5], vec![1, 2, 3, 4]] {
+            let params = PoseidonParams::new(field, 3, 8, 20);
+            let mut builder = Builder::new_in_field(field);
+            let message: Vec<Node> = inputs.iter().map(|_| builder.init()).collect();
+            let digest = poseidon(&mut builder, &message, &params);
+            builder.output(digest);
+            builder.fill_nodes(inputs.clone());
+            assert!(builder.check_constraints());
+            assert_eq!(
+                builder.outputs()[0].evaluated(),
+                Some(poseidon_native(&inputs, &params))
+            );
+            counts.push(multiplications(&builder));
+        }
+        // per permutation: 3 multiplications per S-box, 9 for the MDS matrix per round
+        let per_permutation = 3 * (8 * 3 + 20) + 9 * 28;
+        assert_eq!(counts, vec![per_permutation, 3 * per_permutation]);
+
+        // MiMC with a comparable security margin for the same four elements
+        let mut builder = Builder::new_in_field(field);
+        let message: Vec<Node> = (0..4).map(|_| builder.init()).collect();
+        let constants = mimc_constants(field, 20);
+        mimc(&mut builder, &message, 20, &constants);
+        assert_eq!(multiplications(&builder), 4 * 20 * 2);
+    }
+}
@@ -0,0 +1,198 @@
+//! Building blocks for puzzle verifiers, and a complete Sudoku verifier.
+//!
+//! Proving knowledge of a puzzle solution without revealing it is the classic example of an NP
+//! statement. The blocks constrain cells to a range of values, sets of cells to be pairwise
+//! distinct, and one list of cells to be a permutation of another. All of them subtract, so
+//! they need a circuit over a field larger than every value involved.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::gadgets::puzzles::Sudoku;
+//!
+//! // a 4x4 Sudoku with 2x2 boxes, 0 marks an empty cell
+//! let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+//! let sudoku = Sudoku::new(&mut builder, 2);
+//! let puzzle = [1, 0, 0, 4, 0, 4, 1, 0, 2, 0, 0, 3, 0, 3, 2, 0];
+//! let solution = [1, 2, 3, 4, 3, 4, 1, 2, 2, 1, 4, 3, 4, 3, 2, 1];
+//! builder.fill_nodes(sudoku.inputs(&puzzle, &solution));
+//! assert!(builder.check_constraints());
+//! ```
+use crate::gadgets::div;
+use crate::{Builder, Node};
+use std::ops::RangeInclusive;
+
+/// Constrains `cell` to lie in `range` with `Π (cell - v) = 0` over every value `v` of the
+/// range, one multiplication per value.
+///
+/// # Panics
+///
+/// Panics if the range is empty or the circuit does not compute in a field.
+pub fn assert_in_range(builder: &mut Builder, cell: &Node, range: RangeInclusive<u64>) {
+    if range.is_empty() {
+        panic!("a cell cannot lie in an empty range");
+    }
+    let mut product = builder.constant(1);
+    for value in range {
+        let value = builder.constant(value);
+        let factor = builder.sub(cell.clone(), value);
+        product = builder.mul(product, factor);
+    }
+    let zero = builder.constant(0);
+    builder.assert_equal(product, zero);
+}
+
+/// Constrains the cells to be pairwise distinct.
+///
+/// The product of all pairwise differences must be non-zero, which is proven by exhibiting its
+/// inverse. The cost grows quadratically with the number of cells.
+///
+/// # Panics
+///
+/// Panics if the circuit does not compute in a field.
+pub fn assert_all_distinct(builder: &mut Builder, cells: &[Node]) {
+    let mut product = builder.constant(1);
+    for (i, first) in cells.iter().enumerate() {
+        for second in &cells[i + 1..] {
+            let difference = builder.sub(first.clone(), second.clone());
+            product = builder.mul(product, difference);
+        }
+    }
+    let one = builder.constant(1);
+    div(builder, &one, &product);
+}
+
+/// Constrains `second` to be a permutation of `first`.
+///
+/// Both lists are permutations of each other exactly when `Π (r - first_i) = Π (r - second_i)`
+/// as polynomials in `r`. Evaluating at a random `challenge` chosen after the cells are fixed
+/// catches a mismatch except with probability `n / p` for `n` cells.
+///
+/// # Panics
+///
+/// Panics if the lists differ in length or the circuit does not compute in a field.
+pub fn assert_permutation(
+    builder: &mut Builder,
+    first: &[Node],
+    second: &[Node],
+    challenge: &Node,
+) {
+    if first.len() != second.len() {
+        panic!("lists of different lengths cannot be permutations of each other");
+    }
+    let [first, second] = [first, second].map(|cells| {
+        let mut product = builder.constant(1);
+        for cell in cells {
+            let factor = builder.sub(challenge.clone(), cell.clone());
+            product = builder.mul(product, factor);
+        }
+        product
+    });
+    builder.assert_equal(first, second);
+}
+
+/// A Sudoku verifier: the puzzle is public, the solution private.
+///
+/// The grid has `n = box_size^2` rows and columns. Every solution cell must be in `1..=n`, every
+/// row, column and box must be pairwise distinct, and every given cell of the puzzle must match
+/// the solution. A puzzle cell of `0` is empty.
+#[derive(Debug, Clone)]
+pub struct Sudoku {
+    pub box_size: usize,
+    /// The cells of the puzzle, row by row.
+    pub puzzle: Vec<Node>,
+    /// The cells of the solution, row by row.
+    pub solution: Vec<Node>,
+}
+
+impl Sudoku {
+    /// Adds the inputs and constraints of the verifier to the circuit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit does not compute in a field.
+    pub fn new(builder: &mut Builder, box_size: usize) -> Self {
+        let n = box_size * box_size;
+        let puzzle: Vec<Node> = (0..n * n).map(|_| builder.init()).collect();
+        let solution: Vec<Node> = (0..n * n).map(|_| builder.init_private()).collect();
+
+        for (given, cell) in puzzle.iter().zip(&solution) {
+            assert_in_range(builder, cell, 1..=n as u64);
+            // given * (given - cell) = 0: the cell is empty or matches
+            let difference = builder.sub(given.clone(), cell.clone());
+            let product = builder.mul(given.clone(), difference);
+            let zero = builder.constant(0);
+            builder.assert_equal(product, zero);
+        }
+        for i in 0..n {
+            let row: Vec<Node> = (0..n).map(|j| solution[i * n + j].clone()).collect();
+            let column: Vec<Node> = (0..n).map(|j| solution[j * n + i].clone()).collect();
+            let (top, left) = (i / box_size * box_size, i % box_size * box_size);
+            let square: Vec<Node> = (0..n)
+                .map(|j| solution[(top + j / box_size) * n + left + j % box_size].clone())
+                .collect();
+            for group in [row, column, square] {
+                assert_all_distinct(builder, &group);
+            }
+        }
+        Sudoku {
+            box_size,
+            puzzle,
+            solution,
+        }
+    }
+
+    /// Returns the inputs of the circuit for a puzzle and its solution, both row by row.
+    pub fn inputs(&self, puzzle: &[u64], solution: &[u64]) -> Vec<u64> {
+        [puzzle, solution].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_sudoku() {
+        let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+        let sudoku = Sudoku::new(&mut builder, 3);
+        let solution: Vec<u64> = (0..81)
+            .map(|cell| {
+                let (row, column) = (cell / 9, cell % 9);
+                ((row * 3 + row / 3 + column) % 9 + 1) as u64
+            })
+            .collect();
+        let puzzle: Vec<u64> = solution
+            .iter()
+            .enumerate()
+            .map(|(cell, value)| if cell % 4 == 0 { *value } else { 0 })
+            .collect();
+        builder.fill_nodes(sudoku.inputs(&puzzle, &solution));
+        assert!(builder.check_constraints());
+
+        // swapping two cells of a row breaks the columns, contradicting a given breaks the puzzle
+        let mut swapped = solution.clone();
+        swapped.swap(1, 2);
+        builder.fill_nodes(sudoku.inputs(&puzzle, &swapped));
+        assert!(!builder.check_constraints());
+        let mut contradicted = puzzle.clone();
+        contradicted[0] = solution[0] % 9 + 1;
+        builder.fill_nodes(sudoku.inputs(&contradicted, &solution));
+        assert!(!builder.check_constraints());
+    }
+
+    #[test]
+    fn test_permutation() {
+        let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+        let first: Vec<Node> = (0..4).map(|_| builder.init()).collect();
+        let second: Vec<Node> = (0..4).map(|_| builder.init()).collect();
+        let challenge = builder.init();
+        assert_permutation(&mut builder, &first, &second, &challenge);
+
+        builder.fill_nodes(vec![1, 2, 3, 3, 3, 1, 3, 2, 987_654]);
+        assert!(builder.check_constraints());
+        builder.fill_nodes(vec![1, 2, 3, 3, 3, 1, 2, 2, 987_654]);
+        assert!(!builder.check_constraints());
+    }
+}
@@ -0,0 +1,324 @@
+//! Verification of Schnorr signatures on the toy curve, a complete example of checking a
+//! signature inside a circuit.
+//!
+//! A key pair is a secret scalar `x` and the point `P = xG`. To sign a message `m`, the signer
+//! picks a nonce `k`, computes `R = kG`, the challenge `e = H(R, P, m)` with MiMC and
+//! `s = k + ex mod n`, where `n` is the order of `G`. The signature `(R, s)` is valid if
+//! `sG = R + eP`.
+//!
+//! The circuit checks exactly that equation: it hashes the challenge with the MiMC gadget,
+//! decomposes `s` and `e` into bits, bounded by the modulus so that each value has only one
+//! decomposition, and computes both sides by double-and-add, choosing between
+//! the sum and the accumulator with `select`. Since the affine gadgets cannot represent the
+//! point at infinity, both sides start from the same fixed offset point. With a toy curve this
+//! small, an intermediate sum can still hit an exceptional case and make a valid signature fail;
+//! the chance is about one in a thousand.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::gadgets::{schnorr_verify, EcPoint, Schnorr};
+//!
+//! let schnorr = Schnorr::toy();
+//! let mut builder = Builder::new_in_field(schnorr.curve.field);
+//! let public_key = EcPoint { x: builder.init(), y: builder.init() };
+//! let message = builder.init();
+//! let r = EcPoint { x: builder.init(), y: builder.init() };
+//! let s = builder.init();
+//! schnorr_verify(&mut builder, &schnorr, &public_key, &message, &r, &s);
+//!
+//! let (key_x, key_y) = schnorr.public_key(1234);
+//! let signature = schnorr.sign(1234, 777, 42);
+//! let (r_x, r_y) = signature.r;
+//! builder.fill_nodes(vec![key_x, key_y, 42, r_x, r_y, signature.s]);
+//! assert!(builder.check_constraints());
+//! builder.fill_nodes(vec![key_x, key_y, 43, r_x, r_y, signature.s]);
+//! assert!(!builder.check_constraints());
+//! ```
+use crate::gadgets::ec::{assert_on_curve, ec_add, ec_double, Curve, EcPoint};
+use crate::gadgets::{assert_bits_below, mimc, mimc_constants, mimc_native, select, to_bits};
+use crate::{Builder, Node};
+
+/// The number of MiMC rounds of the challenge hash.
+const ROUNDS: usize = 12;
+
+/// The parameters of the signature scheme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schnorr {
+    pub curve: Curve,
+    pub generator: (u64, u64),
+    /// The order of the generator, a prime.
+    pub order: u64,
+    /// The point both sides of the verification equation start from in the circuit.
+    pub offset: (u64, u64),
+    /// The round constants of the MiMC challenge hash.
+    pub constants: Vec<u64>,
+}
+
+/// A signature `(R, s)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub r: (u64, u64),
+    pub s: u64,
+}
+
+impl Schnorr {
+    /// Signatures over `Curve::toy`.
+    pub fn toy() -> Self {
+        let curve = Curve::toy();
+        let generator = Curve::TOY_GENERATOR;
+        Schnorr {
+            curve,
+            generator,
+            order: Curve::TOY_ORDER,
+            offset: curve.mul_native(0x5eed, Some(generator)).unwrap(),
+            constants: mimc_constants(curve.field, ROUNDS),
+        }
+    }
+
+    /// Returns the public key `xG` of the secret key `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secret key is a multiple of the order.
+    pub fn public_key(&self, secret: u64) -> (u64, u64) {
+        self.curve
+            .mul_native(secret, Some(self.generator))
+            .expect("the secret key must not be a multiple of the order")
+    }
+
+    /// Computes the challenge `e = H(R, P, m)`.
+    pub fn challenge(&self, r: (u64, u64), public_key: (u64, u64), message: u64) -> u64 {
+        let inputs = [r.0, r.1, public_key.0, public_key.1, message];
+        mimc_native(self.curve.field, &inputs, ROUNDS, &self.constants)
+    }
+
+    /// Signs a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret`: The secret key.
+    /// * `nonce`: A fresh secret scalar. Reusing it for two messages reveals the secret key.
+    /// * `message`: The message, a field element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secret key or the nonce is a multiple of the order.
+    pub fn sign(&self, secret: u64, nonce: u64, message: u64) -> Signature {
+        let r = self
+            .curve
+            .mul_native(nonce, Some(self.generator))
+            .expect("the nonce must not be a multiple of the order");
+        let e = self.challenge(r, self.public_key(secret), message);
+        let n = self.order as u128;
+        let s = (nonce as u128 % n + e as u128 * (secret as u128 % n)) % n;
+        Signature { r, s: s as u64 }
+    }
+
+    /// Checks `sG = R + eP` outside of the circuit.
+    pub fn verify_native(
+        &self,
+        public_key: (u64, u64),
+        message: u64,
+        signature: &Signature,
+    ) -> bool {
+        let e = self.challenge(signature.r, public_key, message);
+        let left = self.curve.mul_native(signature.s, Some(self.generator));
+        let right = self.curve.add_native(
+            Some(signature.r),
+            self.curve.mul_native(e, Some(public_key)),
+        );
+        left == right
+    }
+
+    /// Returns the number of bits of a scalar, enough for both `s` and the challenge.
+    fn scalar_bits(&self) -> usize {
+        let largest = self.order.max(self.curve.field.modulus());
+        (u64::BITS - largest.leading_zeros()) as usize
+    }
+}
+
+/// Constrains `(r, s)` to be a valid signature of `message` under `public_key`.
+///
+/// # Arguments
+///
+/// * `builder`: The circuit to add the gates to. It must compute in the curve's field.
+/// * `schnorr`: The parameters of the scheme.
+/// * `public_key`: The signer's public key, constrained to lie on the curve.
+/// * `message`: The signed message.
+/// * `r`: The point of the signature, constrained to lie on the curve.
+/// * `s`: The scalar of the signature.
+///
+/// # Panics
+///
+/// Panics if the circuit does not compute in the curve's field.
+pub fn schnorr_verify(
+    builder: &mut Builder,
+    schnorr: &Schnorr,
+    public_key: &EcPoint,
+    message: &Node,
+    r: &EcPoint,
+    s: &Node,
+) {
+    let curve = &schnorr.curve;
+    assert_on_curve(builder, curve, public_key);
+    assert_on_curve(builder, curve, r);
+    let inputs = [
+        r.x.clone(),
+        r.y.clone(),
+        public_key.x.clone(),
+        public_key.y.clone(),
+        message.clone(),
+    ];
+    let e = mimc(builder, &inputs, ROUNDS, &schnorr.constants);
+    let bits = schnorr.scalar_bits();
+    // 2^bits exceeds the modulus, so without the bound both v and v + p would decompose
+    let modulus = curve.field.modulus();
+    let e_bits = to_bits(builder, &e, bits);
+    assert_bits_below(builder, &e_bits, modulus);
+    let s_bits = to_bits(builder, s, bits);
+    assert_bits_below(builder, &s_bits, modulus);
+
+    // the left side, s·G, adds precomputed multiples 2^i G
+    let offset = constant_point(builder, schnorr.offset);
+    let mut left = offset.clone();
+    let mut multiple = Some(schnorr.generator);
+    for bit in &s_bits {
+        let term = constant_point(builder, multiple.unwrap());
+        left = add_if(builder, curve, bit, &left, &term);
+        multiple = curve.add_native(multiple, multiple);
+    }
+
+    // the right side, R + e·P, doubles the public key in the circuit
+    let mut right = ec_add(builder, curve, &offset, r);
+    let mut multiple = public_key.clone();
+    for (i, bit) in e_bits.iter().enumerate() {
+        if i > 0 {
+            multiple = ec_double(builder, curve, &multiple);
+        }
+        right = add_if(builder, curve, bit, &right, &multiple);
+    }
+    builder.assert_equal(left.x, right.x);
+    builder.assert_equal(left.y, right.y);
+}
+
+/// Returns `accumulator + term` if `bit` is set and `accumulator` otherwise.
+fn add_if(
+    builder: &mut Builder,
+    curve: &Curve,
+    bit: &Node,
+    accumulator: &EcPoint,
+    term: &EcPoint,
+) -> EcPoint {
+    let sum = ec_add(builder, curve, accumulator, term);
+    EcPoint {
+        x: select(builder, bit, &sum.x, &accumulator.x),
+        y: select(builder, bit, &sum.y, &accumulator.y),
+    }
+}
+
+fn constant_point(builder: &mut Builder, (x, y): (u64, u64)) -> EcPoint {
+    EcPoint {
+        x: builder.constant(x),
+        y: builder.constant(y),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_signatures_verify_in_and_out_of_circuit() {
+        let schnorr = Schnorr::toy();
+        let mut builder = Builder::new_in_field(schnorr.curve.field);
+        let public_key = EcPoint {
+            x: builder.init(),
+            y: builder.init(),
+        };
+        let message = builder.init();
+        let r = EcPoint {
+            x: builder.init(),
+            y: builder.init(),
+        };
+        let s = builder.init();
+        schnorr_verify(&mut builder, &schnorr, &public_key, &message, &r, &s);
+
+        for (secret, nonce, message) in [(5, 9, 100), (40000, 31337, 7), (123, 65000, 65380)] {
+            let key = schnorr.public_key(secret);
+            let signature = schnorr.sign(secret, nonce, message);
+            assert!(schnorr.verify_native(key, message, &signature));
+            let inputs = vec![
+                key.0,
+                key.1,
+                message,
+                signature.r.0,
+                signature.r.1,
+                signature.s,
+            ];
+            builder.fill_nodes(inputs.clone());
+            assert!(builder.check_constraints());
+
+            // a forged s, or a signature under another key, is rejected
+            let forged = Signature {
+                s: (signature.s + 1) % schnorr.order,
+                ..signature
+            };
+            assert!(!schnorr.verify_native(key, message, &forged));
+            let mut inputs_forged = inputs.clone();
+            inputs_forged[5] = forged.s;
+            builder.fill_nodes(inputs_forged);
+            assert!(!builder.check_constraints());
+            let other = schnorr.public_key(secret + 1);
+            builder.fill_nodes(vec![
+                other.0, other.1, message, inputs[3], inputs[4], inputs[5],
+            ]);
+            assert!(!builder.check_constraints());
+        }
+    }
+
+    #[test]
+    fn test_non_canonical_scalar_bits_are_rejected() {
+        let schnorr = Schnorr::toy();
+        let modulus = schnorr.curve.field.modulus();
+        let mut builder = Builder::new_in_field(schnorr.curve.field);
+        let public_key = EcPoint {
+            x: builder.init(),
+            y: builder.init(),
+        };
+        let message = builder.init();
+        let r = EcPoint {
+            x: builder.init(),
+            y: builder.init(),
+        };
+        let s = builder.init();
+        schnorr_verify(&mut builder, &schnorr, &public_key, &message, &r, &s);
+
+        // the bits of v + p are a second decomposition of v, and (v + p)G = sG
+        let key = schnorr.public_key(5);
+        let (signature, v) = (9..)
+            .map(|nonce| {
+                let signature = schnorr.sign(5, nonce, 100);
+                let v = (signature.s + schnorr.order - modulus % schnorr.order) % schnorr.order;
+                (signature, v)
+            })
+            .find(|(_, v)| v + modulus < 1 << schnorr.scalar_bits())
+            .unwrap();
+        let forged = Signature { s: v, ..signature };
+        assert!(!schnorr.verify_native(key, 100, &forged));
+        let mut position = 0;
+        for hint in builder
+            .hints
+            .iter_mut()
+            .filter(|hint| hint.inputs == [s.id])
+        {
+            let i = position;
+            hint.compute = Arc::new(move |values| (values[0] + modulus) >> i & 1);
+            position += 1;
+        }
+        assert_eq!(position, schnorr.scalar_bits());
+        let (r_x, r_y) = signature.r;
+        builder.fill_nodes(vec![key.0, key.1, 100, r_x, r_y, v]);
+        assert!(!builder.check_constraints());
+    }
+}
@@ -0,0 +1,159 @@
+//! The SHA-256 compression function, the classic example of a hash that is expensive in
+//! arithmetic circuits.
+//!
+//! SHA-256 mixes additions modulo `2^32` with bitwise operations and rotations. A field has
+//! neither, so every word lives as 32 constrained bits (see `U32`): bitwise operations cost a
+//! few gates per bit, and every addition packs words into values and decomposes the sum again.
+//! One compression of a 512-bit block takes about 190 000 constraints, compared to a few
+//! hundred for a circuit-friendly hash such as MiMC or Poseidon over a whole field element.
+use crate::gadgets::uint32::U32;
+use crate::Builder;
+
+/// The initial hash value of SHA-256.
+pub const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// The round constants of SHA-256.
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Adds the SHA-256 compression of one message block to the circuit.
+///
+/// # Arguments
+///
+/// * `builder`: The circuit to add the gates to, without a field or over a field with more
+///   than `2^35` elements.
+/// * `state`: The chaining value, `SHA256_IV` for the first block.
+/// * `block`: The 16 big-endian words of the block.
+///
+/// # Returns
+///
+/// The new chaining value, the digest after the last block.
+pub fn sha256_compress(builder: &mut Builder, state: &[U32; 8], block: &[U32; 16]) -> [U32; 8] {
+    let mut schedule: Vec<U32> = block.to_vec();
+    for t in 16..64 {
+        let small_sigma0 = small_sigma(builder, &schedule[t - 15], 7, 18, 3);
+        let small_sigma1 = small_sigma(builder, &schedule[t - 2], 17, 19, 10);
+        let words = [
+            small_sigma1,
+            schedule[t - 7].clone(),
+            small_sigma0,
+            schedule[t - 16].clone(),
+        ];
+        schedule.push(U32::wrapping_add(builder, &words));
+    }
+
+    let mut working = state.clone();
+    for (t, word) in schedule.iter().enumerate() {
+        let [a, b, c, d, e, f, g, h] = working;
+        let big_sigma1 = big_sigma(builder, &e, 6, 11, 25);
+        let choice = e.choose(builder, &f, &g);
+        let constant = U32::constant(builder, K[t]);
+        let t1 = U32::wrapping_add(builder, &[h, big_sigma1, choice, constant, word.clone()]);
+        let big_sigma0 = big_sigma(builder, &a, 2, 13, 22);
+        let majority = a.majority(builder, &b, &c);
+        let t2 = U32::wrapping_add(builder, &[big_sigma0, majority]);
+        let new_e = U32::wrapping_add(builder, &[d, t1.clone()]);
+        let new_a = U32::wrapping_add(builder, &[t1, t2]);
+        working = [new_a, a, b, c, new_e, e, f, g];
+    }
+    std::array::from_fn(|i| U32::wrapping_add(builder, &[state[i].clone(), working[i].clone()]))
+}
+
+/// `Σ(x) = ROTR^r1(x) ⊕ ROTR^r2(x) ⊕ ROTR^r3(x)`.
+fn big_sigma(builder: &mut Builder, x: &U32, r1: usize, r2: usize, r3: usize) -> U32 {
+    x.rotate_right(r1)
+        .xor3(builder, &x.rotate_right(r2), &x.rotate_right(r3))
+}
+
+/// `σ(x) = ROTR^r1(x) ⊕ ROTR^r2(x) ⊕ SHR^s(x)`.
+fn small_sigma(builder: &mut Builder, x: &U32, r1: usize, r2: usize, s: usize) -> U32 {
+    let shifted = x.shift_right(builder, s);
+    x.rotate_right(r1)
+        .xor3(builder, &x.rotate_right(r2), &shifted)
+}
+
+/// Computes the SHA-256 compression of one block outside of the circuit, see
+/// `sha256_compress`.
+pub fn sha256_compress_native(state: &[u32; 8], block: &[u32; 16]) -> [u32; 8] {
+    let mut schedule = block.to_vec();
+    for t in 16..64 {
+        let x = schedule[t - 15];
+        let small_sigma0 = x.rotate_right(7) ^ x.rotate_right(18) ^ x >> 3;
+        let x = schedule[t - 2];
+        let small_sigma1 = x.rotate_right(17) ^ x.rotate_right(19) ^ x >> 10;
+        schedule.push(
+            small_sigma1
+                .wrapping_add(schedule[t - 7])
+                .wrapping_add(small_sigma0)
+                .wrapping_add(schedule[t - 16]),
+        );
+    }
+
+    let mut working = *state;
+    for (t, word) in schedule.iter().enumerate() {
+        let [a, b, c, d, e, f, g, h] = working;
+        let big_sigma1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let choice = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(big_sigma1)
+            .wrapping_add(choice)
+            .wrapping_add(K[t])
+            .wrapping_add(*word);
+        let big_sigma0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let majority = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = big_sigma0.wrapping_add(majority);
+        working = [t1.wrapping_add(t2), a, b, c, d.wrapping_add(t1), e, f, g];
+    }
+    std::array::from_fn(|i| state[i].wrapping_add(working[i]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+
+    /// The padded block of the message "abc".
+    const ABC: [u32; 16] = [0x61626380, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x18];
+
+    /// SHA-256("abc").
+    const ABC_DIGEST: [u32; 8] = [
+        0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61,
+        0xf20015ad,
+    ];
+
+    #[test]
+    fn test_native_compression_of_abc() {
+        assert_eq!(sha256_compress_native(&SHA256_IV, &ABC), ABC_DIGEST);
+    }
+
+    #[test]
+    fn test_compression_matches_native() {
+        let mut builder = Builder::new();
+        let inputs: Vec<Node> = (0..16).map(|_| builder.init()).collect();
+        let block: [U32; 16] = std::array::from_fn(|i| U32::from_node(&mut builder, &inputs[i]));
+        let state = SHA256_IV.map(|word| U32::constant(&mut builder, word));
+        let digest = sha256_compress(&mut builder, &state, &block);
+        for word in &digest {
+            let packed = word.pack(&mut builder);
+            builder.output(packed);
+        }
+
+        let inputs: Vec<u64> = ABC.iter().map(|word| *word as u64).collect();
+        assert_eq!(
+            builder.evaluate(inputs.clone()),
+            ABC_DIGEST.map(|word| word as u64).to_vec()
+        );
+        builder.fill_nodes(inputs);
+        assert!(builder.check_constraints());
+    }
+}
@@ -0,0 +1,119 @@
+//! Shifts and rotations of integers of a fixed bit width.
+//!
+//! A shift or rotation of a value only moves its bits around, so each gadget decomposes the
+//! value with `to_bits`, which also range checks it, reorders the bits and packs them again
+//! with `from_bits`. Values already held as bits, like the words of `U32`, are shifted for free.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::gadgets::{rotate_left, shift_right};
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let rotated = rotate_left(&mut builder, &x, 3, 8);
+//! let shifted = shift_right(&mut builder, &x, 3, 8);
+//! builder.output(rotated);
+//! builder.output(shifted);
+//! assert_eq!(builder.evaluate(vec![0b1011_0001]), vec![0b1000_1101, 0b0001_0110]);
+//! ```
+use crate::gadgets::{check_field_bits, from_bits, to_bits};
+use crate::{Builder, Node};
+
+/// Shifts `x` left by `amount` bits, dropping the bits shifted past `bit_width`.
+///
+/// # Panics
+///
+/// Panics if `bit_width` is zero, or the circuit computes in a field with at most
+/// `2^bit_width` elements.
+pub fn shift_left(builder: &mut Builder, x: &Node, amount: usize, bit_width: usize) -> Node {
+    permute_bits(builder, x, bit_width, |i| i.checked_sub(amount))
+}
+
+/// Shifts `x` right by `amount` bits, filling in zeros.
+///
+/// # Panics
+///
+/// Panics as `shift_left` does.
+pub fn shift_right(builder: &mut Builder, x: &Node, amount: usize, bit_width: usize) -> Node {
+    permute_bits(builder, x, bit_width, |i| {
+        Some(i + amount).filter(|source| *source < bit_width)
+    })
+}
+
+/// Rotates `x` left by `amount` bits within `bit_width` bits.
+///
+/// # Panics
+///
+/// Panics as `shift_left` does.
+pub fn rotate_left(builder: &mut Builder, x: &Node, amount: usize, bit_width: usize) -> Node {
+    let amount = amount % bit_width.max(1);
+    permute_bits(builder, x, bit_width, |i| {
+        Some((i + bit_width - amount) % bit_width)
+    })
+}
+
+/// Rotates `x` right by `amount` bits within `bit_width` bits.
+///
+/// # Panics
+///
+/// Panics as `shift_left` does.
+pub fn rotate_right(builder: &mut Builder, x: &Node, amount: usize, bit_width: usize) -> Node {
+    permute_bits(builder, x, bit_width, |i| Some((i + amount) % bit_width))
+}
+
+/// Decomposes `x` and packs a new value whose bit `i` is bit `source(i)` of `x`, or zero for
+/// `None`.
+fn permute_bits(
+    builder: &mut Builder,
+    x: &Node,
+    bit_width: usize,
+    source: impl Fn(usize) -> Option<usize>,
+) -> Node {
+    if bit_width == 0 {
+        panic!("cannot shift a value of zero bits");
+    }
+    let what = format!("shifting {}-bit values", bit_width);
+    check_field_bits(builder, bit_width, &what);
+    let bits = to_bits(builder, x, bit_width);
+    let zero = builder.constant(0);
+    let moved: Vec<Node> = (0..bit_width)
+        .map(|i| source(i).map_or(zero.clone(), |source| bits[source].clone()))
+        .collect();
+    from_bits(builder, &moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_native() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        for amount in [0, 1, 5, 16] {
+            let results = [
+                shift_left(&mut builder, &x, amount, 16),
+                shift_right(&mut builder, &x, amount, 16),
+                rotate_left(&mut builder, &x, amount, 16),
+                rotate_right(&mut builder, &x, amount, 16),
+            ];
+            results.into_iter().for_each(|node| builder.output(node));
+        }
+        let value = 0xbeef_u16;
+        let expected: Vec<u64> = [0, 1, 5, 16]
+            .into_iter()
+            .flat_map(|amount: u32| {
+                [
+                    value.checked_shl(amount).unwrap_or(0),
+                    value.checked_shr(amount).unwrap_or(0),
+                    value.rotate_left(amount),
+                    value.rotate_right(amount),
+                ]
+            })
+            .map(u64::from)
+            .collect();
+        assert_eq!(builder.evaluate(vec![value as u64]), expected);
+        builder.fill_nodes(vec![1 << 16]);
+        assert!(!builder.check_constraints());
+    }
+}
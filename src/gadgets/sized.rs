@@ -0,0 +1,151 @@
+//! Gadgets over fixed-size arrays.
+//!
+//! The gadgets working on slices check lengths when the circuit is built, if at all. These
+//! variants take and return arrays whose length is a const generic, so passing the bits of a
+//! 16-bit value where 8 bits are expected, or adding vectors of different lengths, fails to
+//! compile. A `BoolWire` is a node constrained to be a bit, so gadgets taking one need not
+//! constrain it again.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let bits = builder.to_bits::<4>(&x);
+//! let [low, _, _, high] = &bits;
+//! let both = low.and(&mut builder, high);
+//! let same = builder.from_bits(&bits);
+//! builder.output(both.into_node());
+//! builder.output(same);
+//! assert_eq!(builder.evaluate(vec![9]), vec![1, 9]);
+//!
+//! let weights = builder.constants(&[1, 2, 3]).try_into().unwrap();
+//! let values: [_; 3] = std::array::from_fn(|_| builder.init());
+//! let weighted = builder.inner_product(&weights, &values);
+//! builder.output(weighted);
+//! ```
+use crate::gadgets::{assert_bit, from_bits, select, to_bits};
+use crate::{Builder, Node};
+
+/// A node constrained to be `0` or `1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoolWire {
+    node: Node,
+}
+
+impl BoolWire {
+    /// Constrains `node` to be a bit.
+    pub fn new(builder: &mut Builder, node: Node) -> Self {
+        assert_bit(builder, &node);
+        BoolWire { node }
+    }
+
+    /// Creates the constant bit `value`, which needs no constraint.
+    pub fn constant(builder: &mut Builder, value: bool) -> Self {
+        BoolWire {
+            node: builder.constant(value as u64),
+        }
+    }
+
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    pub fn into_node(self) -> Node {
+        self.node
+    }
+
+    /// Returns the conjunction of two bits, a single multiplication.
+    pub fn and(&self, builder: &mut Builder, other: &BoolWire) -> BoolWire {
+        BoolWire {
+            node: builder.mul(self.node.clone(), other.node.clone()),
+        }
+    }
+
+    /// Returns `if_set` where the bit is one and `if_unset` where it is zero, see
+    /// `gadgets::select`.
+    pub fn select(&self, builder: &mut Builder, if_set: &Node, if_unset: &Node) -> Node {
+        select(builder, &self.node, if_set, if_unset)
+    }
+}
+
+impl Builder {
+    /// Decomposes `value` into `N` bits, least significant first, see `gadgets::to_bits`.
+    pub fn to_bits<const N: usize>(&mut self, value: &Node) -> [BoolWire; N] {
+        const { assert!(N > 0 && N < 64, "a value has between 1 and 63 bits") };
+        let bits = to_bits(self, value, N)
+            .into_iter()
+            .map(|node| BoolWire { node });
+        into_array(bits.collect())
+    }
+
+    /// Computes `Σ 2^i bits[i]`, see `gadgets::from_bits`.
+    pub fn from_bits<const N: usize>(&mut self, bits: &[BoolWire; N]) -> Node {
+        const { assert!(N > 0, "cannot recompose an empty list of bits") };
+        let nodes: Vec<Node> = bits.iter().map(|bit| bit.node.clone()).collect();
+        from_bits(self, &nodes)
+    }
+
+    /// Adds two vectors element by element.
+    pub fn add_arrays<const N: usize>(&mut self, a: &[Node; N], b: &[Node; N]) -> [Node; N] {
+        std::array::from_fn(|i| self.add(a[i].clone(), b[i].clone()))
+    }
+
+    /// Multiplies two vectors element by element.
+    pub fn mul_arrays<const N: usize>(&mut self, a: &[Node; N], b: &[Node; N]) -> [Node; N] {
+        std::array::from_fn(|i| self.mul(a[i].clone(), b[i].clone()))
+    }
+
+    /// Multiplies every element of a vector by `factor`.
+    pub fn scale_array<const N: usize>(&mut self, factor: &Node, v: &[Node; N]) -> [Node; N] {
+        std::array::from_fn(|i| self.mul(factor.clone(), v[i].clone()))
+    }
+
+    /// Returns `Σ a[i] * b[i]`, with the products summed in a balanced tree.
+    pub fn inner_product<const N: usize>(&mut self, a: &[Node; N], b: &[Node; N]) -> Node {
+        const { assert!(N > 0, "the inner product needs at least one element") };
+        let products = self.mul_arrays(a, b);
+        self.add_many(&products)
+    }
+
+    /// Selects `if_set` or `if_unset` as a whole, element by element with the same bit.
+    pub fn select_array<const N: usize>(
+        &mut self,
+        bit: &BoolWire,
+        if_set: &[Node; N],
+        if_unset: &[Node; N],
+    ) -> [Node; N] {
+        std::array::from_fn(|i| bit.select(self, &if_set[i], &if_unset[i]))
+    }
+}
+
+/// Converts a vector a gadget returned with `N` elements into an array.
+fn into_array<T, const N: usize>(elements: Vec<T>) -> [T; N] {
+    match elements.try_into() {
+        Ok(array) => array,
+        Err(_) => unreachable!("the gadget returned the wrong number of elements"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_select_array_in_field() {
+        let mut builder = Builder::new_in_field(Field::new(101));
+        let bit = builder.init();
+        let bit = BoolWire::new(&mut builder, bit);
+        let a: [Node; 2] = std::array::from_fn(|_| builder.init());
+        let b = builder.constants(&[7, 8]).try_into().unwrap();
+        let selected = builder.select_array(&bit, &a, &b);
+        let doubled = builder.add_arrays(&selected, &selected);
+        doubled.into_iter().for_each(|node| builder.output(node));
+        assert_eq!(builder.evaluate(vec![1, 3, 4]), vec![6, 8]);
+        assert_eq!(builder.evaluate(vec![0, 3, 4]), vec![14, 16]);
+
+        builder.fill_nodes(vec![2, 3, 4]);
+        assert!(!builder.check_constraints());
+    }
+}
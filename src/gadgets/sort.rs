@@ -0,0 +1,140 @@
+//! Sorting inside a circuit.
+//!
+//! A circuit cannot sort by branching, but a sorting network performs a fixed sequence of
+//! compare-exchange steps that does not depend on the values: every step compares two wires
+//! with `less_than` and routes the smaller one first with two `select`s. `sort` builds Batcher's
+//! odd-even merge sort, which needs `O(n log^2 n)` steps for `n` values.
+//!
+//! When the sorted list only has to be checked rather than computed, hinting it is much
+//! cheaper: `assert_sorted_permutation` compares neighbours, `n - 1` comparisons, and checks
+//! that the hinted list is a permutation of the input with `assert_permutation`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::gadgets::sort;
+//!
+//! let mut builder = Builder::new();
+//! let values = builder.inputs(5);
+//! let sorted = sort(&mut builder, &values, 8);
+//! sorted.into_iter().for_each(|node| builder.output(node));
+//! assert_eq!(builder.evaluate(vec![42, 7, 255, 7, 0]), vec![0, 7, 7, 42, 255]);
+//! ```
+use crate::gadgets::puzzles::assert_permutation;
+use crate::gadgets::{less_than, select, to_bits};
+use crate::{Builder, Node};
+
+/// Sorts values of `bit_width` bits in ascending order with an odd-even merge sorting network.
+///
+/// Every value is range checked to `bit_width` bits first, as the comparisons require.
+///
+/// # Panics
+///
+/// Panics if the values cannot be compared, see `less_than`.
+pub fn sort(builder: &mut Builder, values: &[Node], bit_width: usize) -> Vec<Node> {
+    let mut wires = values.to_vec();
+    for value in values {
+        to_bits(builder, value, bit_width);
+    }
+    for (i, j) in sorting_network(values.len()) {
+        let less = less_than(builder, &wires[j], &wires[i], bit_width);
+        let low = select(builder, &less, &wires[j], &wires[i]);
+        let high = select(builder, &less, &wires[i], &wires[j]);
+        wires[i] = low;
+        wires[j] = high;
+    }
+    wires
+}
+
+/// Constrains `sorted` to be `values` in ascending order.
+///
+/// Each neighbour of `sorted` is compared with the next one, and the lists are checked to be
+/// permutations of each other at `challenge`, which should be chosen at random once both are
+/// fixed. The values of `sorted` are range checked to `bit_width` bits.
+///
+/// # Panics
+///
+/// Panics if the lists differ in length, the values cannot be compared (see `less_than`), or
+/// the circuit does not compute in a field.
+pub fn assert_sorted_permutation(
+    builder: &mut Builder,
+    values: &[Node],
+    sorted: &[Node],
+    bit_width: usize,
+    challenge: &Node,
+) {
+    assert_permutation(builder, values, sorted, challenge);
+    for value in sorted {
+        to_bits(builder, value, bit_width);
+    }
+    let zero = builder.constant(0);
+    for pair in sorted.windows(2) {
+        let descending = less_than(builder, &pair[1], &pair[0], bit_width);
+        builder.assert_equal(descending, zero.clone());
+    }
+}
+
+/// Returns the compare-exchange steps of Batcher's odd-even merge sort for `n` wires.
+///
+/// Each step `(i, j)` with `i < j` puts the smaller value on wire `i`. Lengths other than
+/// powers of two are handled by leaving out the steps touching missing wires.
+fn sorting_network(n: usize) -> Vec<(usize, usize)> {
+    let mut steps = vec![];
+    let mut p = 1;
+    while p < n {
+        let mut k = p;
+        while k >= 1 {
+            let mut j = k % p;
+            while j + k < n {
+                for i in 0..k.min(n - j - k) {
+                    if (i + j) / (2 * p) == (i + j + k) / (2 * p) {
+                        steps.push((i + j, i + j + k));
+                    }
+                }
+                j += 2 * k;
+            }
+            k /= 2;
+        }
+        p *= 2;
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_network_sorts_every_bit_vector() {
+        // by the 0-1 principle, a network sorting all bit vectors sorts everything
+        for n in 0..=10 {
+            let steps = sorting_network(n);
+            for pattern in 0..1u32 << n {
+                let mut bits: Vec<u32> = (0..n).map(|i| pattern >> i & 1).collect();
+                for (i, j) in &steps {
+                    if bits[*i] > bits[*j] {
+                        bits.swap(*i, *j);
+                    }
+                }
+                assert!(bits.windows(2).all(|pair| pair[0] <= pair[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_hinted_sort_is_checked() {
+        let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+        let values = builder.inputs(4);
+        let sorted: Vec<Node> = (0..4).map(|_| builder.init_private()).collect();
+        let challenge = builder.init();
+        assert_sorted_permutation(&mut builder, &values, &sorted, 8, &challenge);
+
+        builder.fill_nodes(vec![9, 3, 200, 3, 3, 3, 9, 200, 123_456]);
+        assert!(builder.check_constraints());
+        // sorted but not a permutation, and a permutation but not sorted
+        builder.fill_nodes(vec![9, 3, 200, 3, 3, 9, 9, 200, 123_456]);
+        assert!(!builder.check_constraints());
+        builder.fill_nodes(vec![9, 3, 200, 3, 3, 9, 3, 200, 123_456]);
+        assert!(!builder.check_constraints());
+    }
+}
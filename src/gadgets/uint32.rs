@@ -0,0 +1,317 @@
+//! Arithmetic on 32-bit words represented by their bits.
+//!
+//! A `U32` holds one node per bit, least significant first, every one constrained to `0` or `1`.
+//! Rotations and shifts only relabel bits and cost nothing. Bitwise operations cost a few gates
+//! per bit, and each is checked with a polynomial identity that only the right bit satisfies,
+//! e.g. `x = a ⊕ b` with `x + 2ab = a + b`. Addition modulo `2^32` packs the words into single
+//! values, adds them and decomposes the sum again, hinting the bits of the sum and of the carry.
+//!
+//! Without a field every identity holds over the integers. Over a field the modulus has to
+//! exceed every sum of words, so that decompositions are unique.
+use crate::gadgets::{check_field_bits, from_bits, hint_bits, to_bits};
+use crate::hint::Hint;
+use crate::{Builder, Node};
+use std::sync::Arc;
+
+/// A 32-bit word in a circuit.
+#[derive(Debug, Clone)]
+pub struct U32 {
+    /// The bits, least significant first.
+    bits: Vec<Node>,
+    /// The node holding the value of the word, if the circuit has one already.
+    packed: Option<Node>,
+}
+
+impl U32 {
+    /// Adds a constant word to the circuit.
+    pub fn constant(builder: &mut Builder, value: u32) -> Self {
+        let zero = builder.constant(0);
+        let one = builder.constant(1);
+        U32 {
+            bits: (0..32)
+                .map(|i| match value >> i & 1 {
+                    0 => zero.clone(),
+                    _ => one.clone(),
+                })
+                .collect(),
+            packed: Some(builder.constant(value as u64)),
+        }
+    }
+
+    /// Decomposes a node into a word, constraining it to be below `2^32`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit computes in a field with at most `2^32` elements.
+    pub fn from_node(builder: &mut Builder, node: &Node) -> Self {
+        check_field_bits(builder, 32, "a 32-bit word");
+        U32 {
+            bits: to_bits(builder, node, 32),
+            packed: Some(node.clone()),
+        }
+    }
+
+    /// Returns the bits of the word, least significant first.
+    pub fn bits(&self) -> &[Node] {
+        &self.bits
+    }
+
+    /// Returns a node holding the value of the word, packing the bits if necessary.
+    pub fn pack(&self, builder: &mut Builder) -> Node {
+        match &self.packed {
+            Some(node) => node.clone(),
+            None => from_bits(builder, &self.bits),
+        }
+    }
+
+    /// Rotates the word right by `n` bits. Costs no gates.
+    pub fn rotate_right(&self, n: usize) -> Self {
+        U32 {
+            bits: (0..32).map(|i| self.bits[(i + n) % 32].clone()).collect(),
+            packed: None,
+        }
+    }
+
+    /// Shifts the word right by `n` bits, filling in zeros.
+    pub fn shift_right(&self, builder: &mut Builder, n: usize) -> Self {
+        let zero = builder.constant(0);
+        U32 {
+            bits: (0..32)
+                .map(|i| self.bits.get(i + n).unwrap_or(&zero).clone())
+                .collect(),
+            packed: None,
+        }
+    }
+
+    /// Computes the bitwise exclusive or of two words.
+    pub fn xor(&self, builder: &mut Builder, other: &U32) -> Self {
+        // x + 2ab = a + b
+        self.bitwise(
+            builder,
+            &[other],
+            |a| a[0] ^ a[1],
+            |builder, x, a| {
+                let ab = builder.mul(a[0].clone(), a[1].clone());
+                let left = builder.add(ab.clone(), ab);
+                let left = builder.add(x.clone(), left);
+                let right = builder.add(a[0].clone(), a[1].clone());
+                builder.assert_equal(left, right);
+            },
+        )
+    }
+
+    /// Computes the bitwise exclusive or of three words.
+    pub fn xor3(&self, builder: &mut Builder, second: &U32, third: &U32) -> Self {
+        // x + 2(ab + ac + bc) = a + b + c + 4abc
+        self.bitwise(
+            builder,
+            &[second, third],
+            |a| a[0] ^ a[1] ^ a[2],
+            |builder, x, a| {
+                let ab = builder.mul(a[0].clone(), a[1].clone());
+                let pairs = pair_products(builder, a, ab.clone());
+                let twice = builder.add(pairs.clone(), pairs);
+                let left = builder.add(x.clone(), twice);
+                let abc = builder.mul(ab, a[2].clone());
+                let four = builder.constant(4);
+                let abc = builder.mul(four, abc);
+                let right = builder.add(a[0].clone(), a[1].clone());
+                let right = builder.add(right, a[2].clone());
+                let right = builder.add(right, abc);
+                builder.assert_equal(left, right);
+            },
+        )
+    }
+
+    /// Computes the bitwise and of two words. Costs one gate per bit.
+    pub fn and(&self, builder: &mut Builder, other: &U32) -> Self {
+        U32 {
+            bits: self
+                .bits
+                .iter()
+                .zip(&other.bits)
+                .map(|(a, b)| builder.mul(a.clone(), b.clone()))
+                .collect(),
+            packed: None,
+        }
+    }
+
+    /// Flips every bit of the word.
+    pub fn not(&self, builder: &mut Builder) -> Self {
+        let one = builder.constant(1);
+        // x + a = 1
+        self.bitwise(
+            builder,
+            &[],
+            |a| 1 - a[0],
+            |builder, x, a| {
+                let sum = builder.add(x.clone(), a[0].clone());
+                builder.assert_equal(sum, one.clone());
+            },
+        )
+    }
+
+    /// Takes the bits of `if_set` where this word has a one and of `if_unset` elsewhere.
+    pub fn choose(&self, builder: &mut Builder, if_set: &U32, if_unset: &U32) -> Self {
+        // x + e·g = e·f + g
+        self.bitwise(
+            builder,
+            &[if_set, if_unset],
+            |a| match a[0] {
+                1 => a[1],
+                _ => a[2],
+            },
+            |builder, x, a| {
+                let eg = builder.mul(a[0].clone(), a[2].clone());
+                let left = builder.add(x.clone(), eg);
+                let ef = builder.mul(a[0].clone(), a[1].clone());
+                let right = builder.add(ef, a[2].clone());
+                builder.assert_equal(left, right);
+            },
+        )
+    }
+
+    /// Computes the bitwise majority of three words.
+    pub fn majority(&self, builder: &mut Builder, second: &U32, third: &U32) -> Self {
+        // x + 2abc = ab + ac + bc
+        self.bitwise(
+            builder,
+            &[second, third],
+            |a| (a[0] + a[1] + a[2] >= 2) as u64,
+            |builder, x, a| {
+                let ab = builder.mul(a[0].clone(), a[1].clone());
+                let abc = builder.mul(ab.clone(), a[2].clone());
+                let twice = builder.add(abc.clone(), abc);
+                let left = builder.add(x.clone(), twice);
+                let right = pair_products(builder, a, ab);
+                builder.assert_equal(left, right);
+            },
+        )
+    }
+
+    /// Adds words modulo `2^32`.
+    ///
+    /// The sum is packed into one value and decomposed into 32 bits and as many carry bits as
+    /// the number of words requires, all hinted and constrained.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no words are given, or the circuit computes in a field too small to hold the
+    /// sum.
+    pub fn wrapping_add(builder: &mut Builder, words: &[U32]) -> Self {
+        if words.is_empty() {
+            panic!("cannot add an empty list of words");
+        }
+        let carry_bits = (u64::BITS - (words.len() as u64 - 1).leading_zeros()) as usize;
+        check_field_bits(builder, 32 + carry_bits, "adding 32-bit words");
+        let mut sum = words[0].pack(builder);
+        for word in &words[1..] {
+            let word = word.pack(builder);
+            sum = builder.add(sum, word);
+        }
+        let mut bits = hint_bits(builder, &sum, 32 + carry_bits);
+        let carry = bits.split_off(32);
+        let low = from_bits(builder, &bits);
+        let total = match carry.is_empty() {
+            true => low.clone(),
+            false => {
+                let carry = from_bits(builder, &carry);
+                let shift = builder.constant(1 << 32);
+                let carry = builder.mul(carry, shift);
+                builder.add(low.clone(), carry)
+            }
+        };
+        builder.assert_equal(total, sum);
+        U32 {
+            bits,
+            packed: Some(low),
+        }
+    }
+
+    /// Applies a bitwise operation: every result bit is hinted with `compute` from the bits of
+    /// this word and `others` at the same position, and constrained by `constrain`.
+    fn bitwise(
+        &self,
+        builder: &mut Builder,
+        others: &[&U32],
+        compute: impl Fn(&[u64]) -> u64 + Send + Sync + 'static,
+        constrain: impl Fn(&mut Builder, &Node, &[Node]),
+    ) -> Self {
+        let compute = Arc::new(compute);
+        let bits = (0..32)
+            .map(|i| {
+                let operands: Vec<Node> = std::iter::once(self)
+                    .chain(others.iter().copied())
+                    .map(|word| word.bits[i].clone())
+                    .collect();
+                let x = builder.push_hint(Hint {
+                    inputs: operands.iter().map(|node| node.id).collect(),
+                    compute: compute.clone(),
+                });
+                constrain(builder, &x, &operands);
+                x
+            })
+            .collect();
+        U32 { bits, packed: None }
+    }
+}
+
+/// Returns `ab + ac + bc` for the bits `a`, given `ab`.
+fn pair_products(builder: &mut Builder, a: &[Node], ab: Node) -> Node {
+    let ac = builder.mul(a[0].clone(), a[2].clone());
+    let bc = builder.mul(a[1].clone(), a[2].clone());
+    let sum = builder.add(ab, ac);
+    builder.add(sum, bc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_operations() {
+        let mut builder = Builder::new();
+        let (x, y) = (builder.init(), builder.init());
+        let (a, b) = (
+            U32::from_node(&mut builder, &x),
+            U32::from_node(&mut builder, &y),
+        );
+        let c = U32::constant(&mut builder, 0x0f0f_0f0f);
+        let results = [
+            U32::wrapping_add(&mut builder, &[a.clone(), b.clone(), c.clone()]),
+            a.xor(&mut builder, &b),
+            a.xor3(&mut builder, &b, &c),
+            a.and(&mut builder, &b),
+            a.not(&mut builder),
+            a.choose(&mut builder, &b, &c),
+            a.majority(&mut builder, &b, &c),
+            a.rotate_right(7),
+            a.shift_right(&mut builder, 7),
+        ];
+        for result in &results {
+            let packed = result.pack(&mut builder);
+            builder.output(packed);
+        }
+
+        let (a, b, c) = (0xdead_beef_u32, 0xffff_0001_u32, 0x0f0f_0f0f_u32);
+        let expected = [
+            a.wrapping_add(b).wrapping_add(c),
+            a ^ b,
+            a ^ b ^ c,
+            a & b,
+            !a,
+            (a & b) | (!a & c),
+            (a & b) | (a & c) | (b & c),
+            a.rotate_right(7),
+            a >> 7,
+        ];
+        let outputs = builder.evaluate(vec![a as u64, b as u64]);
+        assert_eq!(outputs, expected.map(|value| value as u64).to_vec());
+        builder.fill_nodes(vec![a as u64, b as u64]);
+        assert!(builder.check_constraints());
+
+        // a value that does not fit into 32 bits cannot be decomposed
+        builder.fill_nodes(vec![1 << 32, 0]);
+        assert!(!builder.check_constraints());
+    }
+}
@@ -0,0 +1,290 @@
+//! An end-to-end walk through Groth16 with plain numbers.
+//!
+//! Groth16 proves that a witness satisfies an R1CS with three group elements. This sketch follows
+//! every algebraic step of the real protocol, but the "group elements" are plain field elements
+//! and a pairing `e(P, Q)` is simply the product `P * Q`. Nothing is hidden, so the result is not
+//! a proof of anything; it lets each intermediate value be printed and checked by hand.
+//!
+//! The steps are:
+//!
+//! 1. The circuit is converted into an `R1cs` and evaluated into a witness.
+//! 2. The R1CS is interpolated into a `Qap` and the witness is combined into `A(x)`, `B(x)`, `C(x)`.
+//!    The quotient `H(x) = (A(x) * B(x) - C(x)) / Z(x)` must leave no remainder.
+//! 3. The `setup` ceremony produces `τ`; the toxic waste `α, β, γ, δ` is derived next to it.
+//! 4. The prover picks blinding factors `r, s` and computes the proof
+//!    - `A = α + A(τ) + r δ`
+//!    - `B = β + B(τ) + s δ`
+//!    - `C = (Σ_private w_i K_i + H(τ) Z(τ)) / δ + s A + r B - r s δ`
+//!
+//!    where `K_i = β A_i(τ) + α B_i(τ) + C_i(τ)`.
+//! 5. The verifier checks `A * B = α β + (Σ_public w_i K_i / γ) γ + C δ`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::groth16_sketch::Walkthrough;
+//!
+//! // prove knowledge of a private `s` with s^3 + s + 5 = 35 (s = 3)
+//! let mut builder = Builder::new();
+//! let out = builder.init();
+//! let s = builder.init_private();
+//! let s_squared = builder.mul(s.clone(), s.clone());
+//! let s_cubed = builder.mul(s_squared, s.clone());
+//! let sum = builder.add(s_cubed, s);
+//! let five = builder.constant(5);
+//! let result = builder.add(sum, five);
+//! builder.assert_equal(result, out);
+//! builder.fill_nodes(vec![35, 3]);
+//!
+//! let walkthrough = Walkthrough::run(&builder, Field::new(2_147_483_647), 42);
+//! assert!(walkthrough.check_r1cs());
+//! assert!(walkthrough.check_divisibility());
+//! assert!(walkthrough.check_proof());
+//! ```
+use crate::field::Field;
+use crate::hash::mix;
+use crate::polynomial::Polynomial;
+use crate::qap::Qap;
+use crate::r1cs::R1cs;
+use crate::setup::{setup, ProvingKey};
+use crate::Builder;
+use tracing::info;
+
+/// The secrets of the setup. Anyone knowing them can forge proofs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToxicWaste {
+    pub tau: u64,
+    pub alpha: u64,
+    pub beta: u64,
+    pub gamma: u64,
+    pub delta: u64,
+}
+
+/// The three "group elements" of a Groth16 proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub a: u64,
+    pub b: u64,
+    pub c: u64,
+}
+
+/// What the verifier knows: the public inputs and the verifying part of the setup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifierData {
+    pub public_witness: Vec<u64>,
+    pub alpha_beta: u64,
+    pub gamma: u64,
+    pub delta: u64,
+    /// `K_i / γ` for the leading `1` and every public input.
+    pub public_terms: Vec<u64>,
+}
+
+/// Every intermediate value of a Groth16 run, so each step can be checked separately.
+#[derive(Debug, Clone)]
+pub struct Walkthrough {
+    pub field: Field,
+    pub r1cs: R1cs,
+    pub witness: Vec<u64>,
+    pub qap: Qap,
+    pub a: Polynomial,
+    pub b: Polynomial,
+    pub c: Polynomial,
+    pub h: Polynomial,
+    pub remainder: Polynomial,
+    pub toxic_waste: ToxicWaste,
+    pub proving_key: ProvingKey,
+    pub proof: Proof,
+    pub verifier: VerifierData,
+}
+
+/// Derives a non-zero field element from `seed`.
+fn sample(field: Field, seed: u64) -> u64 {
+    (1..)
+        .map(|attempt| field.reduce(mix(seed ^ mix(attempt))))
+        .find(|value| *value != 0)
+        .unwrap()
+}
+
+impl Walkthrough {
+    /// Runs every step of the protocol on a circuit that was already filled.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit`: The circuit, after `fill_nodes` was called.
+    /// * `field`: The field to work in. It must have more elements than the R1CS has rows.
+    /// * `seed`: Source of the setup secrets and the prover's blinding factors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit was not filled.
+    pub fn run(circuit: &Builder, field: Field, seed: u64) -> Self {
+        // step 1: R1CS and witness
+        let r1cs = R1cs::from_builder(circuit);
+        let witness: Vec<u64> = circuit
+            .witness()
+            .expect("the circuit must be filled before running Groth16")
+            .iter()
+            .map(|value| field.reduce(*value))
+            .collect();
+
+        // step 2: QAP and quotient
+        let qap = Qap::from_r1cs(&r1cs, field);
+        let (a, b, c) = qap.combine(&witness);
+        let (h, remainder) = qap.quotient(&witness);
+        info!("quotient H(x) has degree {}", h.degree());
+
+        // step 3: setup
+        let (proving_key, verifying_key) = setup(circuit, field, seed);
+        let toxic_waste = ToxicWaste {
+            tau: verifying_key.tau(),
+            alpha: sample(field, seed ^ 1),
+            beta: sample(field, seed ^ 2),
+            gamma: sample(field, seed ^ 3),
+            delta: sample(field, seed ^ 4),
+        };
+        let ToxicWaste {
+            tau,
+            alpha,
+            beta,
+            gamma,
+            delta,
+        } = toxic_waste.clone();
+        let k: Vec<u64> = (0..r1cs.num_variables)
+            .map(|i| {
+                let a_i = qap.a[i].evaluate(tau, field);
+                let b_i = qap.b[i].evaluate(tau, field);
+                let c_i = qap.c[i].evaluate(tau, field);
+                field.add(field.add(field.mul(beta, a_i), field.mul(alpha, b_i)), c_i)
+            })
+            .collect();
+        let num_public = r1cs.num_public + 1;
+
+        // step 4: the prover only evaluates at τ through the proving key
+        let r = sample(field, seed ^ 5);
+        let s = sample(field, seed ^ 6);
+        let a_tau = proving_key.evaluate_at_tau(a.coefficients());
+        let b_tau = proving_key.evaluate_at_tau(b.coefficients());
+        let hz_tau = field.mul(
+            proving_key.evaluate_at_tau(h.coefficients()),
+            proving_key.evaluate_at_tau(qap.target.coefficients()),
+        );
+        let proof_a = field.add(field.add(alpha, a_tau), field.mul(r, delta));
+        let proof_b = field.add(field.add(beta, b_tau), field.mul(s, delta));
+        let private_sum = (num_public..r1cs.num_variables)
+            .fold(0, |sum, i| field.add(sum, field.mul(witness[i], k[i])));
+        let proof_c = field.add(
+            field.div(field.add(private_sum, hz_tau), delta).unwrap(),
+            field.sub(
+                field.add(field.mul(s, proof_a), field.mul(r, proof_b)),
+                field.mul(field.mul(r, s), delta),
+            ),
+        );
+
+        // step 5: the verifier's data
+        let verifier = VerifierData {
+            public_witness: witness[..num_public].to_vec(),
+            alpha_beta: field.mul(alpha, beta),
+            gamma,
+            delta,
+            public_terms: k[..num_public]
+                .iter()
+                .map(|k_i| field.div(*k_i, gamma).unwrap())
+                .collect(),
+        };
+
+        Walkthrough {
+            field,
+            r1cs,
+            witness,
+            qap,
+            a,
+            b,
+            c,
+            h,
+            remainder,
+            toxic_waste,
+            proving_key,
+            proof: Proof {
+                a: proof_a,
+                b: proof_b,
+                c: proof_c,
+            },
+            verifier,
+        }
+    }
+
+    /// Step 1: the witness satisfies every row of the R1CS.
+    pub fn check_r1cs(&self) -> bool {
+        self.r1cs.is_satisfied(&self.witness, self.field)
+    }
+
+    /// Step 2: `A(x) * B(x) - C(x) = H(x) * Z(x)` with no remainder.
+    pub fn check_divisibility(&self) -> bool {
+        let field = self.field;
+        let lhs = self.a.mul(&self.b, field).sub(&self.c, field);
+        self.remainder.is_zero() && lhs == self.h.mul(&self.qap.target, field)
+    }
+
+    /// Step 5: the verification equation holds for the proof.
+    pub fn check_proof(&self) -> bool {
+        verify(&self.verifier, &self.proof, self.field)
+    }
+}
+
+/// Checks `A * B = α β + (Σ_public w_i K_i / γ) γ + C δ`, the "pairing" equation of Groth16.
+pub fn verify(verifier: &VerifierData, proof: &Proof, field: Field) -> bool {
+    let public_sum = verifier
+        .public_witness
+        .iter()
+        .zip(&verifier.public_terms)
+        .fold(0, |sum, (value, term)| {
+            field.add(sum, field.mul(*value, *term))
+        });
+    let rhs = field.add(
+        field.add(verifier.alpha_beta, field.mul(public_sum, verifier.gamma)),
+        field.mul(proof.c, verifier.delta),
+    );
+    field.mul(proof.a, proof.b) == rhs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circuit(input: u64, claimed: u64) -> Builder {
+        // x^2 + 3 == claimed
+        let mut builder = Builder::new();
+        let out = builder.init();
+        let x = builder.init_private();
+        let x_squared = builder.mul(x.clone(), x);
+        let three = builder.constant(3);
+        let sum = builder.add(x_squared, three);
+        builder.assert_equal(sum, out);
+        builder.fill_nodes(vec![claimed, input]);
+        builder
+    }
+
+    #[test]
+    fn test_valid_witness_verifies() {
+        let walkthrough = Walkthrough::run(&circuit(4, 19), Field::new(1_000_003), 7);
+        assert!(walkthrough.check_r1cs());
+        assert!(walkthrough.check_divisibility());
+        assert!(walkthrough.check_proof());
+    }
+
+    #[test]
+    fn test_invalid_witness_fails_every_step() {
+        let walkthrough = Walkthrough::run(&circuit(4, 20), Field::new(1_000_003), 7);
+        assert!(!walkthrough.check_r1cs());
+        assert!(!walkthrough.check_divisibility());
+        assert!(!walkthrough.check_proof());
+    }
+
+    #[test]
+    fn test_proof_is_bound_to_public_inputs() {
+        let walkthrough = Walkthrough::run(&circuit(4, 19), Field::new(1_000_003), 7);
+        let mut verifier = walkthrough.verifier.clone();
+        verifier.public_witness[1] = 20;
+        assert!(!verify(&verifier, &walkthrough.proof, walkthrough.field));
+    }
+}
@@ -0,0 +1,122 @@
+//! Named groups of constraints that can be switched off while debugging.
+//!
+//! Every constraint created inside `Builder::group` is tagged with the group's name. A disabled
+//! group is skipped by `check_constraints`, which reports the groups it skipped, so a failing
+//! circuit can be narrowed down by disabling, say, all range checks at once. Disabling a group
+//! never changes the circuit itself: exports, witnesses and `check_witness` still see every
+//! constraint.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! builder.group("range_checks", |b| {
+//!     let square = b.mul(x.clone(), x.clone());
+//!     b.assert_equal(square, x.clone());
+//! });
+//! let nine = builder.constant(9);
+//! builder.assert_equal(x, nine);
+//!
+//! builder.fill_nodes(vec![9]);
+//! assert!(!builder.check_constraints());
+//! builder.disable_group("range_checks");
+//! assert!(builder.check_constraints());
+//! assert_eq!(builder.skipped_groups(), vec!["range_checks"]);
+//! ```
+use crate::replay::LoggedOp;
+use crate::Builder;
+
+impl Builder {
+    /// Runs `build` with every constraint it creates tagged with the group `name`.
+    ///
+    /// Groups do not nest: inside a nested call, constraints belong to the innermost group.
+    pub fn group<T>(&mut self, name: &str, build: impl FnOnce(&mut Builder) -> T) -> T {
+        let outer = self.current_group.map(|index| self.groups[index].clone());
+        self.set_group(Some(name));
+        let result = build(self);
+        self.set_group(outer.as_deref());
+        result
+    }
+
+    /// Tags the constraints created from now on with the group `name`, or with none.
+    pub(crate) fn set_group(&mut self, name: Option<&str>) {
+        self.current_group =
+            name.map(
+                |name| match self.groups.iter().position(|group| group == name) {
+                    Some(index) => index,
+                    None => {
+                        self.groups.push(name.to_string());
+                        self.groups.len() - 1
+                    }
+                },
+            );
+        self.record_op(|| LoggedOp::Group(name.map(str::to_string)));
+    }
+
+    /// Makes `check_constraints` skip the constraints of the group `name`.
+    ///
+    /// The group does not need to exist yet.
+    pub fn disable_group(&mut self, name: &str) {
+        if !self.disabled_groups.iter().any(|group| group == name) {
+            self.disabled_groups.push(name.to_string());
+        }
+        self.record_op(|| LoggedOp::DisableGroup(name.to_string()));
+    }
+
+    /// Makes `check_constraints` check the constraints of the group `name` again.
+    pub fn enable_group(&mut self, name: &str) {
+        self.disabled_groups.retain(|group| group != name);
+        self.record_op(|| LoggedOp::EnableGroup(name.to_string()));
+    }
+
+    /// Returns the disabled groups that contain constraints, in the order they were created.
+    pub fn skipped_groups(&self) -> Vec<&str> {
+        let disabled = self.disabled_group_indices();
+        self.groups
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| disabled[*index] && self.constraint_groups.contains(&Some(*index)))
+            .map(|(_, name)| name.as_str())
+            .collect()
+    }
+
+    /// Returns whether each group, indexed like `groups`, is disabled.
+    pub(crate) fn disabled_group_indices(&self) -> Vec<bool> {
+        self.groups
+            .iter()
+            .map(|group| self.disabled_groups.contains(group))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enable_again_and_rollback() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let one = builder.constant(1);
+        builder.group("outer", |b| {
+            b.group("inner", |b| b.assert_equal(x.clone(), one.clone()));
+            b.assert_equal(x.clone(), one.clone());
+        });
+        builder.fill_nodes(vec![2]);
+        builder.disable_group("inner");
+        assert!(!builder.check_constraints());
+        builder.disable_group("outer");
+        assert!(builder.check_constraints());
+        builder.enable_group("inner");
+        assert!(!builder.check_constraints());
+        assert_eq!(builder.skipped_groups(), vec!["outer"]);
+
+        let checkpoint = builder.checkpoint();
+        builder.group("later", |b| b.assert_equal(x.clone(), one.clone()));
+        builder.disable_group("later");
+        assert_eq!(builder.skipped_groups(), vec!["outer", "later"]);
+        builder.rollback(checkpoint);
+        assert_eq!(builder.skipped_groups(), vec!["outer"]);
+    }
+}
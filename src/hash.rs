@@ -0,0 +1,33 @@
+//! A small, deterministic, non-cryptographic hash used by the teaching modules.
+//!
+//! Anything built on top of it (transcripts, commitments, fingerprints) only demonstrates the
+//! shape of a protocol. It offers no security at all.
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hashes a sequence of words into a single word.
+///
+/// This is FNV-1a over the little-endian bytes of every word, finished with the
+/// splitmix64 mixing function so that small changes in the input spread over all bits.
+pub(crate) fn hash_words(words: &[u64]) -> u64 {
+    let mut state = FNV_OFFSET;
+    for byte in words.iter().flat_map(|word| word.to_le_bytes()) {
+        state ^= byte as u64;
+        state = state.wrapping_mul(FNV_PRIME);
+    }
+    mix(state)
+}
+
+/// Hashes a string into a single word, used for domain separation labels.
+pub(crate) fn hash_str(label: &str) -> u64 {
+    let words: Vec<u64> = label.bytes().map(|byte| byte as u64).collect();
+    hash_words(&words)
+}
+
+/// The splitmix64 finalizer.
+pub(crate) fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,127 @@
+//! Hints: values computed outside of the constraints.
+//!
+//! Some computations are much cheaper to check than to express with additions and
+//! multiplications. Decomposing a value into bits, dividing, or comparing cannot be computed by
+//! the gates of a circuit, but the result is easy to verify: bits `b_i` of `x` satisfy
+//! `b_i * b_i = b_i` and `Σ 2^i b_i = x`. A hint computes such a value from other nodes with
+//! arbitrary Rust code while the circuit is filled. It adds no constraints, so like a private
+//! input its value is only trusted once the gadget using it constrains it.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! // prove that x is even by exhibiting its half
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let half = builder.hint(&[&x], |values| values[0] / 2);
+//! let double = builder.add(half.clone(), half);
+//! builder.assert_equal(double, x);
+//!
+//! builder.fill_nodes(vec![10]);
+//! assert!(builder.check_constraints());
+//! builder.fill_nodes(vec![11]);
+//! assert!(!builder.check_constraints());
+//! ```
+use crate::enums::Operation;
+use crate::logging::LogLevel;
+use crate::{Builder, Node};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use tracing::debug;
+
+/// The function of a hint, from the values of its inputs to its value.
+pub type HintFn = Arc<dyn Fn(&[u64]) -> u64 + Send + Sync>;
+
+/// A registered hint: the nodes it reads and how it computes its value.
+#[derive(Clone)]
+pub(crate) struct Hint {
+    pub(crate) inputs: Vec<usize>,
+    pub(crate) compute: HintFn,
+}
+
+impl Debug for Hint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hint")
+            .field("inputs", &self.inputs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Hint {
+    /// Computes the value of the hint from the values of its inputs, reduced into the field.
+    pub(crate) fn evaluate(
+        &self,
+        value: impl Fn(usize) -> u64,
+        reduce: impl Fn(u64) -> u64,
+    ) -> u64 {
+        let values: Vec<u64> = self.inputs.iter().map(|id| value(*id)).collect();
+        reduce((self.compute)(&values))
+    }
+}
+
+impl Builder {
+    /// Adds a node whose value is computed by `compute` from the values of `inputs` whenever the
+    /// circuit is filled.
+    ///
+    /// The hint is not constrained: the caller is responsible for adding constraints that only
+    /// hold for the right value. Over a field the value is reduced into the field.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs`: The nodes the hint reads, passed to `compute` in this order.
+    /// * `compute`: Computes the value of the hint. It should be a deterministic function of
+    ///   its arguments.
+    pub fn hint(
+        &mut self,
+        inputs: &[&Node],
+        compute: impl Fn(&[u64]) -> u64 + Send + Sync + 'static,
+    ) -> Node {
+        let inputs: Vec<usize> = inputs.iter().map(|node| node.id).collect();
+        self.push_hint(Hint {
+            inputs,
+            compute: Arc::new(compute),
+        })
+    }
+
+    /// Registers a hint and adds its node to the graph.
+    pub(crate) fn push_hint(&mut self, hint: Hint) -> Node {
+        let mut node = Node::init(self.full_graph.len());
+        node.operation = Operation::Hint(self.hints.len());
+        for input in &hint.inputs {
+            if !self.full_graph[*input].children.contains(&node.id) {
+                self.full_graph[*input].children.push(node.id);
+            }
+        }
+        debug!(gate = node.id, op = "hint", inputs = ?hint.inputs, "hint created");
+        self.hints.push(hint);
+        self.push_node(node.clone());
+        self.log(LogLevel::Debug, || {
+            format!("hint {}", self.gate_text(node.id))
+        });
+        node
+    }
+
+    /// Returns the ids of the nodes a hint node reads.
+    pub(crate) fn hint_inputs(&self, index: usize) -> &[usize] {
+        &self.hints[index].inputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hint_of_hint_in_field() {
+        let field = crate::field::Field::new(17);
+        let mut builder = Builder::new_in_field(field);
+        let x = builder.init();
+        let inverse = builder.hint(&[&x], move |values| field.inv(values[0]).unwrap_or(0));
+        let twice = builder.hint(&[&inverse], |values| values[0] * 2 + 17);
+        let one = builder.mul(x, inverse.clone());
+        builder.output(one);
+        builder.output(twice);
+        assert_eq!(builder.evaluate(vec![3]), vec![1, 12]);
+        assert!(builder.validate().is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+//! Callbacks into the builder.
+//!
+//! Observers registered with `Builder::on_gate` are called with a `GateEvent` for every node
+//! and every equality assertion as it is created, whether directly or by a gadget, so an
+//! external tool such as a live editor can mirror the circuit while it is built.
+//!
+//! Observers registered with `Builder::on_evaluate` are called with the id and value of every
+//! node as it gets its value, inputs first and then every other node in order of ids. They
+//! drive progress bars, live visualizations or external loggers without changing the
+//! evaluator. Observers are shared by clones of the builder, so they also fire for the copies
+//! evaluated by `evaluate` or `exhaustive_check`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let square = builder.mul(x.clone(), x);
+//! let _ = builder.add(square.clone(), square);
+//!
+//! let seen = Rc::new(RefCell::new(vec![]));
+//! let log = seen.clone();
+//! builder.on_evaluate(move |id, value| log.borrow_mut().push((id, value)));
+//! builder.fill_nodes(vec![3]);
+//! assert_eq!(*seen.borrow(), vec![(0, 3), (1, 9), (2, 18)]);
+//! ```
+use crate::enums::Operation;
+use crate::{Builder, NodeId};
+use std::rc::Rc;
+
+/// A callback receiving the id and value of an evaluated node.
+pub type EvaluationObserver = dyn Fn(NodeId, u64);
+
+/// A callback receiving every created node and equality assertion.
+pub type GateObserver = dyn Fn(&GateEvent);
+
+/// What was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateKind {
+    Input,
+    PrivateInput,
+    Constant(u64),
+    Add,
+    Mul,
+    Hint,
+    AssertEqual,
+}
+
+/// A node or equality assertion created in a builder, see `Builder::on_gate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GateEvent {
+    /// The id of the new node, `None` for an equality assertion.
+    pub id: Option<NodeId>,
+    pub kind: GateKind,
+    /// The nodes it is built from: the operands of a gate, the inputs of a hint or the two
+    /// nodes asserted to be equal.
+    pub operands: Vec<NodeId>,
+    /// The scope path it was created in, `""` for the root scope.
+    pub scope: String,
+}
+
+impl Builder {
+    /// Registers a callback fired whenever a node or an equality assertion is created.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer`: Called with a description of what was created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// use arithmetic_circuit::hooks::GateKind;
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// let kinds = Rc::new(RefCell::new(vec![]));
+    /// let log = kinds.clone();
+    /// let mut builder = Builder::new();
+    /// builder.on_gate(move |event| log.borrow_mut().push(event.kind));
+    /// let x = builder.init();
+    /// let y = builder.scope("square", |b| b.mul(x.clone(), x.clone()));
+    /// builder.assert_equal(y, x);
+    /// assert_eq!(*kinds.borrow(), vec![GateKind::Input, GateKind::Mul, GateKind::AssertEqual]);
+    /// ```
+    pub fn on_gate(&mut self, observer: impl Fn(&GateEvent) + 'static) {
+        self.gate_observers.push(Rc::new(observer));
+    }
+
+    /// Removes every callback registered with `on_gate`.
+    pub fn clear_gate_observers(&mut self) {
+        self.gate_observers.clear();
+    }
+
+    /// Fires the gate observers for a node that was just added to the graph.
+    pub(crate) fn notify_node_created(&self, id: NodeId, operation: Operation) {
+        if self.gate_observers.is_empty() {
+            return;
+        }
+        let kind = match operation {
+            Operation::Input if self.private_inputs.last() == Some(&id) => GateKind::PrivateInput,
+            Operation::Input => GateKind::Input,
+            Operation::Constant(value) => GateKind::Constant(value),
+            Operation::Add(..) => GateKind::Add,
+            Operation::Mul(..) => GateKind::Mul,
+            Operation::Hint(_) => GateKind::Hint,
+        };
+        self.notify_gate(Some(id), kind, self.operands(&operation));
+    }
+
+    /// Fires the gate observers for an equality assertion.
+    pub(crate) fn notify_equality_created(&self, a: NodeId, b: NodeId) {
+        if !self.gate_observers.is_empty() {
+            self.notify_gate(None, GateKind::AssertEqual, vec![a, b]);
+        }
+    }
+
+    fn notify_gate(&self, id: Option<NodeId>, kind: GateKind, operands: Vec<NodeId>) {
+        let event = GateEvent {
+            id,
+            kind,
+            operands,
+            scope: self.scopes[self.current_scope].clone(),
+        };
+        for observer in &self.gate_observers {
+            observer(&event);
+        }
+    }
+
+    /// Registers a callback fired whenever a node gets its value during an evaluation.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer`: Called with the id and the value of the node.
+    pub fn on_evaluate(&mut self, observer: impl Fn(NodeId, u64) + 'static) {
+        self.evaluation_observers.push(Rc::new(observer));
+    }
+
+    /// Removes every callback registered with `on_evaluate`.
+    pub fn clear_evaluation_observers(&mut self) {
+        self.evaluation_observers.clear();
+    }
+
+    /// Fires the evaluation observers for a node.
+    pub(crate) fn notify_evaluated(&self, id: NodeId, value: u64) {
+        for observer in &self.evaluation_observers {
+            observer(id, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_cached_fill_fires_nothing() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let _ = builder.mul(x.clone(), x);
+        let count = Rc::new(Cell::new(0));
+        let counter = count.clone();
+        builder.on_evaluate(move |_, _| counter.set(counter.get() + 1));
+        builder.fill_nodes(vec![2]);
+        builder.fill_nodes(vec![2]);
+        assert_eq!(count.get(), 2);
+        builder.clear_evaluation_observers();
+        builder.fill_nodes(vec![3]);
+        assert_eq!(count.get(), 2);
+    }
+
+    #[test]
+    fn test_gate_events_mirror_the_circuit() {
+        let events = Rc::new(std::cell::RefCell::new(vec![]));
+        let log = events.clone();
+        let mut builder = Builder::new();
+        builder.on_gate(move |event| log.borrow_mut().push(event.clone()));
+        let secret = builder.init_private();
+        let two = builder.constant(2);
+        let _ = builder.scope("double", |b| b.mul(secret, two));
+
+        let events = events.borrow();
+        assert_eq!(events[0].kind, GateKind::PrivateInput);
+        assert_eq!(events[1].kind, GateKind::Constant(2));
+        assert_eq!(
+            events[2],
+            GateEvent {
+                id: Some(2),
+                kind: GateKind::Mul,
+                operands: vec![0, 1],
+                scope: "double".to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,402 @@
+//! A high-level intermediate representation.
+//!
+//! A `Program` is a circuit written with richer operations than the builder's additions and
+//! multiplications: subtraction, division, constant powers, selection and linear combinations
+//! are single instructions. Optimizations see these operations whole, before they are spread
+//! over many gates, and exporters can print the program as it was written with its `Display`
+//! implementation. `Program::lower` then translates it into a `Builder` with the gadgets of the
+//! `gadgets` module, where the low-level tools apply.
+//!
+//! ```
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::ir::Program;
+//!
+//! // (x - y) / y + 3x + 4y
+//! let mut program = Program::new_in_field(Field::new(97));
+//! let x = program.input();
+//! let y = program.input();
+//! let difference = program.sub(x, y);
+//! let quotient = program.div(difference, y);
+//! let combination = program.linear_combination(&[(3, x), (4, y)]);
+//! let result = program.add(quotient, combination);
+//! program.output(result);
+//! assert_eq!(
+//!     program.to_string(),
+//!     "\
+//! v0 = input
+//! v1 = input
+//! v2 = v0 - v1
+//! v3 = v2 / v1
+//! v4 = 3 v0 + 4 v1
+//! v5 = v3 + v4
+//! output v5
+//! "
+//! );
+//!
+//! let builder = program.lower();
+//! assert_eq!(builder.evaluate(vec![9, 3]), vec![2 + 27 + 12]);
+//! ```
+use crate::field::Field;
+use crate::{gadgets, Builder, Node};
+use std::fmt::{Display, Formatter};
+use tracing::info;
+
+/// The index of a value in a `Program`.
+pub type ValueId = usize;
+
+/// An instruction of a `Program`, computing one value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    Input {
+        private: bool,
+    },
+    Constant(u64),
+    Add(ValueId, ValueId),
+    Mul(ValueId, ValueId),
+    Sub(ValueId, ValueId),
+    Div(ValueId, ValueId),
+    /// Raises a value to a power known when the circuit is built.
+    Pow(ValueId, u64),
+    /// `if_set` if `bit` is `1` and `if_unset` if it is `0`.
+    Select {
+        bit: ValueId,
+        if_set: ValueId,
+        if_unset: ValueId,
+    },
+    /// `Σ c_i v_i` with constant coefficients `c_i`.
+    LinearCombination(Vec<(u64, ValueId)>),
+}
+
+impl Op {
+    /// Returns the values the instruction reads.
+    pub fn operands(&self) -> Vec<ValueId> {
+        match self {
+            Op::Input { .. } | Op::Constant(_) => vec![],
+            Op::Add(a, b) | Op::Mul(a, b) | Op::Sub(a, b) | Op::Div(a, b) => vec![*a, *b],
+            Op::Pow(base, _) => vec![*base],
+            Op::Select {
+                bit,
+                if_set,
+                if_unset,
+            } => vec![*bit, *if_set, *if_unset],
+            Op::LinearCombination(terms) => terms.iter().map(|(_, value)| *value).collect(),
+        }
+    }
+}
+
+/// A circuit in the high-level representation, see the module documentation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    field: Option<Field>,
+    ops: Vec<Op>,
+    assertions: Vec<(ValueId, ValueId)>,
+    outputs: Vec<ValueId>,
+}
+
+impl Program {
+    /// Creates an empty program over the integers. Subtraction and division need a field and
+    /// cannot be lowered without one.
+    pub fn new() -> Self {
+        Program::default()
+    }
+
+    /// Creates an empty program computing in `field`.
+    pub fn new_in_field(field: Field) -> Self {
+        Program {
+            field: Some(field),
+            ..Program::default()
+        }
+    }
+
+    /// Returns the instructions in order, indexed by `ValueId`.
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// Returns the asserted equalities.
+    pub fn assertions(&self) -> &[(ValueId, ValueId)] {
+        &self.assertions
+    }
+
+    /// Returns the outputs.
+    pub fn outputs(&self) -> &[ValueId] {
+        &self.outputs
+    }
+
+    /// Appends an instruction. Constants are reduced into the field, if there is one.
+    ///
+    /// # Returns
+    ///
+    /// The value it computes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the instruction reads a value that does not exist yet.
+    pub fn push(&mut self, op: Op) -> ValueId {
+        self.check(&op.operands());
+        let op = match (self.field, op) {
+            (Some(field), Op::Constant(value)) => Op::Constant(field.reduce(value)),
+            (_, op) => op,
+        };
+        self.ops.push(op);
+        self.ops.len() - 1
+    }
+
+    /// Adds a public input.
+    pub fn input(&mut self) -> ValueId {
+        self.push(Op::Input { private: false })
+    }
+
+    /// Adds a private input.
+    pub fn private_input(&mut self) -> ValueId {
+        self.push(Op::Input { private: true })
+    }
+
+    pub fn constant(&mut self, value: u64) -> ValueId {
+        self.push(Op::Constant(value))
+    }
+
+    pub fn add(&mut self, a: ValueId, b: ValueId) -> ValueId {
+        self.push(Op::Add(a, b))
+    }
+
+    pub fn mul(&mut self, a: ValueId, b: ValueId) -> ValueId {
+        self.push(Op::Mul(a, b))
+    }
+
+    pub fn sub(&mut self, a: ValueId, b: ValueId) -> ValueId {
+        self.push(Op::Sub(a, b))
+    }
+
+    pub fn div(&mut self, a: ValueId, b: ValueId) -> ValueId {
+        self.push(Op::Div(a, b))
+    }
+
+    pub fn pow(&mut self, base: ValueId, exponent: u64) -> ValueId {
+        self.push(Op::Pow(base, exponent))
+    }
+
+    pub fn select(&mut self, bit: ValueId, if_set: ValueId, if_unset: ValueId) -> ValueId {
+        self.push(Op::Select {
+            bit,
+            if_set,
+            if_unset,
+        })
+    }
+
+    pub fn linear_combination(&mut self, terms: &[(u64, ValueId)]) -> ValueId {
+        self.push(Op::LinearCombination(terms.to_vec()))
+    }
+
+    /// Asserts that two values are equal.
+    pub fn assert_equal(&mut self, a: ValueId, b: ValueId) {
+        self.check(&[a, b]);
+        self.assertions.push((a, b));
+    }
+
+    /// Marks a value as an output.
+    pub fn output(&mut self, value: ValueId) {
+        self.check(&[value]);
+        self.outputs.push(value);
+    }
+
+    fn check(&self, values: &[ValueId]) {
+        if let Some(value) = values.iter().find(|value| **value >= self.ops.len()) {
+            panic!("value {} does not exist", value);
+        }
+    }
+
+    /// Replaces every instruction whose operands are all constants by its result.
+    ///
+    /// Divisions by zero, selections on anything but `0` or `1` and, without a field,
+    /// computations overflowing a `u64` or needing a field are left in place, so that the
+    /// lowered circuit still fails where it should.
+    ///
+    /// # Returns
+    ///
+    /// The number of instructions folded.
+    pub fn fold_constants(&mut self) -> usize {
+        let mut folded = 0;
+        for id in 0..self.ops.len() {
+            let constants: Option<Vec<u64>> = self.ops[id]
+                .operands()
+                .iter()
+                .map(|value| match self.ops[*value] {
+                    Op::Constant(value) => Some(value),
+                    _ => None,
+                })
+                .collect();
+            let value = match (&self.ops[id], constants) {
+                (Op::Input { .. } | Op::Constant(_), _) | (_, None) => None,
+                (op, Some(values)) => self.compute(op, &values),
+            };
+            if let Some(value) = value {
+                self.ops[id] = Op::Constant(value);
+                folded += 1;
+            }
+        }
+        info!(folded, "folded constants of program");
+        folded
+    }
+
+    /// Computes an instruction on known operand values, `None` if it cannot be folded.
+    fn compute(&self, op: &Op, values: &[u64]) -> Option<u64> {
+        match (self.field, op) {
+            (_, Op::Select { .. }) => match values[0] {
+                0 => Some(values[2]),
+                1 => Some(values[1]),
+                _ => None,
+            },
+            (Some(field), Op::Add(..)) => Some(field.add(values[0], values[1])),
+            (Some(field), Op::Mul(..)) => Some(field.mul(values[0], values[1])),
+            (Some(field), Op::Sub(..)) => Some(field.sub(values[0], values[1])),
+            (Some(field), Op::Div(..)) => field.div(values[0], values[1]),
+            (Some(field), Op::Pow(_, exponent)) => Some(field.pow(values[0], *exponent)),
+            (Some(field), Op::LinearCombination(terms)) => Some(
+                terms
+                    .iter()
+                    .zip(values)
+                    .fold(0, |sum, ((c, _), v)| field.add(sum, field.mul(*c, *v))),
+            ),
+            (None, Op::Add(..)) => values[0].checked_add(values[1]),
+            (None, Op::Mul(..)) => values[0].checked_mul(values[1]),
+            (None, Op::Pow(_, exponent)) => values[0].checked_pow(u32::try_from(*exponent).ok()?),
+            (None, Op::LinearCombination(terms)) => terms
+                .iter()
+                .zip(values)
+                .try_fold(0u64, |sum, ((c, _), v)| sum.checked_add(c.checked_mul(*v)?)),
+            _ => None,
+        }
+    }
+
+    /// Translates the program into add and mul gates.
+    ///
+    /// Subtraction uses `Builder::sub`, division `gadgets::div`, powers `gadgets::pow`,
+    /// selection `gadgets::select`, and a linear combination one constant multiplication per
+    /// term with a coefficient other than one followed by additions.
+    ///
+    /// # Returns
+    ///
+    /// A builder with the same inputs, in order, assertions and outputs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the program subtracts or divides without a field.
+    pub fn lower(&self) -> Builder {
+        let mut builder = match self.field {
+            Some(field) => Builder::new_in_field(field),
+            None => Builder::new(),
+        };
+        let mut values: Vec<Node> = Vec::with_capacity(self.ops.len());
+        for op in &self.ops {
+            let value = |id: &ValueId| values[*id].clone();
+            let node = match op {
+                Op::Input { private: false } => builder.init(),
+                Op::Input { private: true } => builder.init_private(),
+                Op::Constant(value) => builder.constant(*value),
+                Op::Add(a, b) => builder.add(value(a), value(b)),
+                Op::Mul(a, b) => builder.mul(value(a), value(b)),
+                Op::Sub(a, b) => builder.sub(value(a), value(b)),
+                Op::Div(a, b) => gadgets::div(&mut builder, &value(a), &value(b)),
+                Op::Pow(base, exponent) => gadgets::pow(&mut builder, &value(base), *exponent),
+                Op::Select {
+                    bit,
+                    if_set,
+                    if_unset,
+                } => gadgets::select(&mut builder, &value(bit), &value(if_set), &value(if_unset)),
+                Op::LinearCombination(terms) => {
+                    let products: Vec<Node> = terms
+                        .iter()
+                        .map(|(c, id)| match c {
+                            1 => value(id),
+                            _ => builder.mul_const(value(id), *c),
+                        })
+                        .collect();
+                    builder.sum(&products)
+                }
+            };
+            values.push(node);
+        }
+        for (a, b) in &self.assertions {
+            builder.assert_equal(values[*a].clone(), values[*b].clone());
+        }
+        for output in &self.outputs {
+            builder.output(values[*output].clone());
+        }
+        info!(
+            instructions = self.ops.len(),
+            nodes = builder.full_graph.len(),
+            "lowered program"
+        );
+        builder
+    }
+}
+
+impl Display for Program {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (id, op) in self.ops.iter().enumerate() {
+            write!(f, "v{} = ", id)?;
+            match op {
+                Op::Input { private: false } => writeln!(f, "input")?,
+                Op::Input { private: true } => writeln!(f, "private input")?,
+                Op::Constant(value) => writeln!(f, "{}", value)?,
+                Op::Add(a, b) => writeln!(f, "v{} + v{}", a, b)?,
+                Op::Mul(a, b) => writeln!(f, "v{} * v{}", a, b)?,
+                Op::Sub(a, b) => writeln!(f, "v{} - v{}", a, b)?,
+                Op::Div(a, b) => writeln!(f, "v{} / v{}", a, b)?,
+                Op::Pow(base, exponent) => writeln!(f, "v{} ^ {}", base, exponent)?,
+                Op::Select {
+                    bit,
+                    if_set,
+                    if_unset,
+                } => writeln!(f, "select(v{}, v{}, v{})", bit, if_set, if_unset)?,
+                Op::LinearCombination(terms) => {
+                    let terms: Vec<String> = terms
+                        .iter()
+                        .map(|(c, value)| format!("{} v{}", c, value))
+                        .collect();
+                    writeln!(f, "{}", terms.join(" + "))?
+                }
+            }
+        }
+        for (a, b) in &self.assertions {
+            writeln!(f, "assert v{} == v{}", a, b)?;
+        }
+        for output in &self.outputs {
+            writeln!(f, "output v{}", output)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folding_before_lowering_keeps_semantics() {
+        let mut program = Program::new_in_field(Field::new(101));
+        let x = program.input();
+        let bit = program.private_input();
+        let two = program.constant(2);
+        let three = program.constant(3);
+        let six = program.linear_combination(&[(1, two), (2, two)]);
+        let eight = program.pow(two, 3);
+        let third = program.div(two, three);
+        let chosen = program.select(bit, six, eight);
+        let scaled = program.mul(chosen, third);
+        let result = program.sub(scaled, x);
+        program.assert_equal(bit, bit);
+        program.output(result);
+
+        let mut folded = program.clone();
+        assert_eq!(folded.fold_constants(), 3);
+        assert!(folded.lower().full_graph.len() < program.lower().full_graph.len());
+        for inputs in [vec![5, 0], vec![7, 1]] {
+            assert_eq!(
+                folded.lower().evaluate(inputs.clone()),
+                program.lower().evaluate(inputs)
+            );
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Mock keys for the toy prover and verifier.
+//!
+//! Proof systems derive a proving key and a verifying key from a circuit in a setup phase, and
+//! each key only works for the circuit it was derived from. `CircuitKey::setup` models this key
+//! management without any cryptography: the keys are opaque tokens bound to the circuit's
+//! fingerprint, and `protocol::Prover` and `protocol::Verifier` refuse keys of another circuit.
+//! They are unrelated to the powers of tau in `setup::ProvingKey` and `setup::VerifyingKey`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::keys::CircuitKey;
+//!
+//! let mut square = Builder::new();
+//! let x = square.init();
+//! let _ = square.mul(x.clone(), x);
+//!
+//! let keys = CircuitKey::setup(&square);
+//! assert!(keys.prover_key.is_for(&square));
+//! assert!(keys.verifier_key.is_for(&square));
+//!
+//! let mut double = Builder::new();
+//! let x = double.init();
+//! let _ = double.add(x.clone(), x);
+//! assert!(!keys.verifier_key.is_for(&double));
+//! ```
+use crate::Builder;
+use tracing::info;
+
+/// The key a prover needs, see `CircuitKey::setup`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitProverKey {
+    fingerprint: u64,
+}
+
+/// The key a verifier needs, see `CircuitKey::setup`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitVerifierKey {
+    fingerprint: u64,
+}
+
+/// The pair of keys produced by the setup of a circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitKey {
+    pub prover_key: CircuitProverKey,
+    pub verifier_key: CircuitVerifierKey,
+}
+
+impl CircuitKey {
+    /// Derives the prover and verifier keys of a circuit.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit`: The circuit the keys are bound to. Values stored in it are ignored.
+    pub fn setup(circuit: &Builder) -> Self {
+        let fingerprint = circuit.fingerprint();
+        info!(fingerprint, "generated circuit keys");
+        CircuitKey {
+            prover_key: CircuitProverKey { fingerprint },
+            verifier_key: CircuitVerifierKey { fingerprint },
+        }
+    }
+}
+
+impl CircuitProverKey {
+    /// Returns `true` if the key was generated for `circuit`.
+    pub fn is_for(&self, circuit: &Builder) -> bool {
+        self.fingerprint == circuit.fingerprint()
+    }
+}
+
+impl CircuitVerifierKey {
+    /// Returns `true` if the key was generated for `circuit`.
+    pub fn is_for(&self, circuit: &Builder) -> bool {
+        self.fingerprint == circuit.fingerprint()
+    }
+}
@@ -7,17 +7,17 @@
 //! found in zk circuit implementations such as fields, witnesses, prover-verifier model, etc.
 //!
 //! ## TODO's
-//! - [ ] Add prime field for nodes to live in. Make graph generic in a prime field
+//! - [x] Add prime field for nodes to live in. Make graph generic in a prime field
 //! - [ ] Add support for parallelization for filling the graph
 //! - [ ] Add Asynchronous hints
-//! - [ ] Add more efficient evaluation of the graph
+//! - [x] Add more efficient evaluation of the graph
 //! - [ ] Add support for better graph visualization. More specifically after entire graph is filled with inputs and constants to log final output
 //!
 //!
 //! ## Core Concepts
 //!
 //! - **Builder**: center of the library. Builder provides methods to define polynomial functions by creating a "graph" of nodes,
-//!    handles arithmetic operations in circuit, and asserts + verifies constraints.
+//!   handles arithmetic operations in circuit, and asserts + verifies constraints.
 //!
 //! - **Node**: Represents a fundamental unit or variable in the circuit. Nodes can have actual values or unevaluated expressions
 //!   to be resolved at a later time once inputs are given.
@@ -40,14 +40,31 @@
 //!
 //! ## Logging
 //!
-//! This library uses env_logger crate to provide basic logging information.
-//! You may enable this by setting the RUST_LOG level before running your program.
-//! The following logging levels are used:
+//! This library is instrumented with the tracing crate and never installs a subscriber itself.
+//! To see its output, install one in your program, for example with tracing-subscriber and the
+//! RUST_LOG environment variable. The following levels are used:
 //!
-//! - **Info**: Provides general information about the graph's state.
-//! <br> RUST_LOG=info
+//! - **Info**: Provides general information about the graph's state. Evaluation runs in a `fill`
+//!   span and constraint checks in a `check_constraints` span.
+//!   <br> RUST_LOG=info
 //! - **Debug**: Provides more information regarding graph's state that may be useful for debugging.
-//! <br> RUST_LOG=debug
+//!   Every created gate is logged with structured `gate`, `op` and `value` fields.
+//!   <br> RUST_LOG=debug
+//! - **Trace**: Every gate creation runs in a `construct` span and every evaluated node is logged.
+//!   <br> RUST_LOG=trace
+//!
+//! Applications that do not use tracing can pass their own logger to `Builder::new_with_logging`,
+//! see the `logging` module.
+//!
+//!
+//! ## Testing
+//!
+//! The `testing` module generates random circuits together with satisfying inputs. With the
+//! `proptest` feature enabled, `testing::strategies` wraps them, and inputs for your own gadgets,
+//! as proptest strategies.
+//!
+//! `Builder::to_smtlib` exports the constraints for SMT solvers. With the `z3` feature enabled,
+//! `Builder::prove_assertion` runs Z3 on them directly to search for counterexamples.
 //!
 //!
 //! ## Note
@@ -57,8 +74,82 @@
 //!
 //!
 //!
+pub mod air;
+pub mod backend;
+pub mod batch;
+pub mod bounds;
+pub mod budget;
 pub mod builder;
+pub mod canonical;
+pub mod circuits;
+pub mod codegen;
+pub mod combinators;
+pub mod commitment;
+pub mod compile;
+pub mod constraint_system;
+pub mod cost;
+pub mod dedup;
+pub mod depth;
+pub mod determinism;
+pub mod diff;
+pub mod division;
+pub mod domain;
+pub mod dot;
+pub mod dump;
 pub mod enums;
+pub mod evaluator;
+pub mod fft;
+pub mod field;
+pub mod fixed;
+pub mod folding;
+pub mod fri;
+pub mod gadgets;
+pub mod groth16_sketch;
+pub mod groups;
+mod hash;
+pub mod hint;
+pub mod hooks;
+pub mod ir;
+pub mod keys;
+pub mod logging;
+pub mod lookup;
+pub mod metrics;
+pub mod mle;
+pub mod names;
 pub mod node;
+pub mod passes;
+pub mod pcs;
+pub mod persistent;
+pub mod poly;
+pub mod polynomial;
+pub mod profile;
+pub mod protocol;
+pub mod qap;
+pub mod r1cs;
+pub mod recursion;
+pub mod reference;
+pub mod replay;
+pub mod report;
+pub mod rlc;
+pub mod sage;
+pub mod session;
+pub mod setup;
+pub mod smtlib;
+pub mod solve;
+pub mod sparse;
+pub mod specialize;
+pub mod sumcheck;
+pub mod synthesis;
+pub mod template;
+pub mod testing;
+pub mod transcript;
+pub mod truth_table;
+pub mod validate;
+pub mod vanishing;
+pub mod verifier;
+pub mod visit;
+pub mod wasm;
+#[cfg(feature = "z3")]
+pub mod z3;
 pub use builder::Builder;
-pub use node::Node;
+pub use node::{Node, NodeId};
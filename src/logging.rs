@@ -0,0 +1,53 @@
+//! An injection point for application-provided loggers.
+//!
+//! The builder always emits `tracing` events, which cost nothing unless the host application
+//! installs a subscriber. Applications that do not use `tracing`, or want the messages of a single
+//! builder only, can hand their own `CircuitLogger` to `Builder::new_with_logging`. Nothing in this
+//! library installs a global logger.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::logging::{CircuitLogger, LogLevel};
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//!
+//! #[derive(Default)]
+//! struct Collect(RefCell<Vec<String>>);
+//! impl CircuitLogger for Collect {
+//!     fn log(&self, _level: LogLevel, message: &str) {
+//!         self.0.borrow_mut().push(message.to_string());
+//!     }
+//! }
+//!
+//! let logger = Rc::new(Collect::default());
+//! let mut builder = Builder::new_with_logging(logger.clone());
+//! let x = builder.init();
+//! let _ = builder.mul(x.clone(), x);
+//! assert_eq!(logger.0.borrow().len(), 2);
+//! ```
+
+/// The severity of a message, matching the levels used for `tracing` events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Info,
+    Debug,
+}
+
+/// Receives the messages of a builder.
+pub trait CircuitLogger {
+    fn log(&self, level: LogLevel, message: &str);
+}
+
+/// Prints messages up to a maximum level to standard error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StderrLogger {
+    pub max_level: LogLevel,
+}
+
+impl CircuitLogger for StderrLogger {
+    fn log(&self, level: LogLevel, message: &str) {
+        if level <= self.max_level {
+            eprintln!("[{:?}] {}", level, message);
+        }
+    }
+}
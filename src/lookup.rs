@@ -0,0 +1,209 @@
+//! Range checks against a shared lookup table.
+//!
+//! Range checking a wire with `to_bits` costs about `4w` constraints for `w` bits, for every
+//! wire. A range table instead holds the values `0..2^w` once and checks membership with a
+//! log-derivative argument: the wires `v_i` all lie in the table exactly when
+//!
+//! `Σ 1 / (r - v_i) = Σ m_j / (r - j)`
+//!
+//! for the hinted multiplicities `m_j` and a random `challenge` `r`. Every lookup then costs a
+//! handful of constraints, while the table costs a few per entry, once. Many lookups against a
+//! small table are much cheaper than decomposing every wire, and `RangeTable::finish` reports
+//! both costs.
+//!
+//! Like `assert_permutation`, the argument needs a field, and the challenge must be chosen at
+//! random once the wires are fixed; a mismatch slips through with probability about
+//! `(n + 2^w) / p` for `n` lookups.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//!
+//! let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+//! let mut table = builder.range_table(4);
+//! let wires: Vec<_> = (0..3).map(|_| builder.init()).collect();
+//! for wire in &wires {
+//!     table.lookup(wire);
+//! }
+//! let challenge = builder.init();
+//! let stats = table.finish(&mut builder, &challenge);
+//! assert_eq!(stats.lookups, 3);
+//! assert_eq!(
+//!     stats.to_string(),
+//!     "3 lookups into a 4-bit table: 80 constraints, 45 with bit decomposition"
+//! );
+//!
+//! builder.fill_nodes(vec![0, 15, 7, 123_456_789]);
+//! assert!(builder.check_constraints());
+//! builder.fill_nodes(vec![0, 16, 7, 123_456_789]);
+//! assert!(!builder.check_constraints());
+//! ```
+use crate::gadgets::{check_field_bits, div, to_bits};
+use crate::{Builder, Node};
+use std::fmt::{Display, Formatter};
+
+/// The largest number of bits of a range table, which has `2^bits` entries.
+pub const MAX_TABLE_BITS: usize = 20;
+
+/// A table of the values `0..2^bits` that wires are looked up in, see `Builder::range_table`.
+#[derive(Debug, Clone)]
+pub struct RangeTable {
+    bits: usize,
+    /// The negated table values `-j`, so that `r - j` is a single addition.
+    negated: Vec<Node>,
+    lookups: Vec<Node>,
+}
+
+/// The cost of the range checks of a table, compared with decomposing every wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeTableStats {
+    pub bits: usize,
+    pub lookups: usize,
+    /// Constraints added by the table and all lookups.
+    pub lookup_constraints: usize,
+    /// Constraints `to_bits` would have added to range check the same wires.
+    pub decomposition_constraints: usize,
+}
+
+impl Display for RangeTableStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} lookups into a {}-bit table: {} constraints, {} with bit decomposition",
+            self.lookups, self.bits, self.lookup_constraints, self.decomposition_constraints
+        )
+    }
+}
+
+impl Builder {
+    /// Registers a table of the values `0..2^bits` to range check wires against.
+    ///
+    /// The table itself is only a list of constants until `RangeTable::finish` adds the
+    /// constraints of all lookups at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is larger than `MAX_TABLE_BITS`, or the circuit does not compute in a
+    /// field with more than `2^bits` elements.
+    pub fn range_table(&mut self, bits: usize) -> RangeTable {
+        if bits > MAX_TABLE_BITS {
+            panic!("range tables can have at most {} bits", MAX_TABLE_BITS);
+        }
+        let Some(field) = self.field() else {
+            panic!("range tables need a circuit over a field, see Builder::new_in_field");
+        };
+        check_field_bits(self, bits, &format!("a {}-bit table", bits));
+        let negated = (0..1u64 << bits)
+            .map(|value| self.constant(field.sub(0, value)))
+            .collect();
+        RangeTable {
+            bits,
+            negated,
+            lookups: vec![],
+        }
+    }
+}
+
+impl RangeTable {
+    /// Returns the number of bits of the values in the table.
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Records that `wire` must be below `2^bits`. It is checked by `finish`.
+    pub fn lookup(&mut self, wire: &Node) {
+        self.lookups.push(wire.clone());
+    }
+
+    /// Adds the constraints checking every recorded lookup against the table, and registers
+    /// the lookups with the builder so that `Builder::emit` passes them to backends.
+    ///
+    /// # Arguments
+    ///
+    /// * `challenge`: The random point `r`, chosen once the looked up wires are fixed.
+    ///
+    /// # Returns
+    ///
+    /// The cost of the lookups, and what range checking the same wires with `to_bits` would have
+    /// cost.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are at least as many lookups as elements in the field, since the
+    /// multiplicities could wrap around.
+    pub fn finish(self, builder: &mut Builder, challenge: &Node) -> RangeTableStats {
+        let field = builder.field().expect("range tables need a field");
+        if self.lookups.len() as u64 >= field.modulus() {
+            panic!("too many lookups for the field");
+        }
+        let start = builder.constraints.len();
+        let one = builder.constant(1);
+        let mut left = builder.constant(0);
+        for wire in &self.lookups {
+            let difference = builder.sub(challenge.clone(), wire.clone());
+            let inverse = div(builder, &one, &difference);
+            left = builder.add(left, inverse);
+        }
+
+        let wires: Vec<&Node> = self.lookups.iter().collect();
+        let mut right = builder.constant(0);
+        for (value, negated) in self.negated.iter().enumerate() {
+            let value = value as u64;
+            let multiplicity = builder.hint(&wires, move |values| {
+                values.iter().filter(|wire| **wire == value).count() as u64
+            });
+            let difference = builder.add(challenge.clone(), negated.clone());
+            let term = div(builder, &multiplicity, &difference);
+            right = builder.add(right, term);
+        }
+        builder.assert_equal(left, right);
+        let bits = self.bits;
+        for wire in &self.lookups {
+            builder.push_lookup(wire.id, bits);
+        }
+
+        RangeTableStats {
+            bits: self.bits,
+            lookups: self.lookups.len(),
+            lookup_constraints: builder.constraints.len() - start,
+            decomposition_constraints: self.lookups.len() * decomposition_cost(self.bits),
+        }
+    }
+}
+
+/// Returns the number of constraints `to_bits` adds to range check one wire.
+fn decomposition_cost(bits: usize) -> usize {
+    if bits == 0 {
+        return 0;
+    }
+    let mut builder = Builder::new();
+    let wire = builder.init();
+    to_bits(&mut builder, &wire, bits);
+    builder.constraints.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_lookups_beat_decomposition() {
+        let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+        let mut table = builder.range_table(8);
+        let wires: Vec<Node> = (0..100).map(|_| builder.init()).collect();
+        wires.iter().for_each(|wire| table.lookup(wire));
+        let challenge = builder.init();
+        let stats = table.finish(&mut builder, &challenge);
+        assert!(stats.lookup_constraints < stats.decomposition_constraints);
+
+        // repeated values need multiplicities above one
+        let mut inputs: Vec<u64> = (0..100).map(|i| i % 7 * 36).collect();
+        inputs.push(987_654_321);
+        builder.fill_nodes(inputs.clone());
+        assert!(builder.check_constraints());
+        inputs[42] = 256;
+        builder.fill_nodes(inputs);
+        assert!(!builder.check_constraints());
+    }
+}
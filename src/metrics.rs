@@ -0,0 +1,38 @@
+//! Opt-in counters for performance investigations.
+//!
+//! Counting is disabled by default. Once enabled with `Builder::enable_metrics`, the builder
+//! counts its work until the counters are read with `Builder::metrics` or cleared with
+//! `Builder::reset_metrics`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! builder.enable_metrics();
+//! let x = builder.init();
+//! let x_squared = builder.mul(x.clone(), x);
+//! let one = builder.constant(1);
+//! let _ = builder.add(x_squared, one);
+//! builder.fill_nodes(vec![2]);
+//! builder.fill_nodes(vec![2]);
+//! builder.check_constraints();
+//!
+//! let metrics = builder.metrics().unwrap();
+//! assert_eq!(metrics.gates_created, 2);
+//! assert_eq!(metrics.evaluation_passes, 1);
+//! assert_eq!(metrics.cache_hits, 1);
+//! assert_eq!(metrics.constraints_checked, 2);
+//! ```
+
+/// Counts of the work done by a builder since metrics were enabled or last reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Metrics {
+    /// Addition and multiplication gates created.
+    pub gates_created: u64,
+    /// Constraints evaluated by `check_constraints` and `check_witness`.
+    pub constraints_checked: u64,
+    /// Calls to `fill_nodes` that evaluated the graph.
+    pub evaluation_passes: u64,
+    /// Calls to `fill_nodes` skipped because the graph was already filled with the same inputs.
+    pub cache_hits: u64,
+}
@@ -0,0 +1,141 @@
+//! Multilinear extensions.
+//!
+//! A vector of `2^n` values can be read as a function on the boolean hypercube `{0, 1}^n`. Its
+//! multilinear extension is the unique polynomial in `n` variables, of degree at most one in
+//! each, that agrees with it on the hypercube:
+//!
+//! `f̃(r_1, …, r_n) = Σ_{b ∈ {0,1}^n} f(b) Π_i (b_i r_i + (1 - b_i)(1 - r_i))`.
+//!
+//! It can be evaluated at any point of `F^n`, which is what sumcheck-based proof systems do
+//! with the witness. The value at index `i` sits at the point given by the bits of `i`, the
+//! first variable being the most significant bit. Fixing the first variable to `r` halves the
+//! table: `f̃(r, …) = (1 - r) f̃(0, …) + r f̃(1, …)`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let square = builder.mul(x.clone(), x);
+//! builder.output(square);
+//! builder.fill_nodes(vec![5]);
+//!
+//! // the witness [1, 5, 25] is padded to [1, 5, 25, 0]
+//! let field = Field::new(101);
+//! let mle = builder.witness_mle(field);
+//! assert_eq!(mle.num_vars(), 2);
+//! assert_eq!(mle.evaluate(&[1, 0]), 25);
+//! // halfway between index 0 and index 2
+//! assert_eq!(mle.evaluate(&[field.inv(2).unwrap(), 0]), 13);
+//! ```
+use crate::field::Field;
+use crate::Builder;
+
+/// The multilinear extension of a table of `2^n` values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultilinearExtension {
+    pub field: Field,
+    evaluations: Vec<u64>,
+}
+
+impl MultilinearExtension {
+    /// Creates the extension of `values`, padded with zeros to the next power of two.
+    pub fn new(mut values: Vec<u64>, field: Field) -> Self {
+        values.resize(values.len().max(1).next_power_of_two(), 0);
+        MultilinearExtension {
+            field,
+            evaluations: values
+                .into_iter()
+                .map(|value| field.reduce(value))
+                .collect(),
+        }
+    }
+
+    /// Returns the number of variables `n`.
+    pub fn num_vars(&self) -> usize {
+        self.evaluations.len().trailing_zeros() as usize
+    }
+
+    /// Returns the values on the hypercube, indexed by the bits of each point.
+    pub fn evaluations(&self) -> &[u64] {
+        &self.evaluations
+    }
+
+    /// Fixes the first variable to `r`, leaving an extension in one variable less.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no variables left.
+    pub fn fix_first(&self, r: u64) -> Self {
+        if self.num_vars() == 0 {
+            panic!("cannot fix a variable of a constant");
+        }
+        let field = self.field;
+        let (low, high) = self.evaluations.split_at(self.evaluations.len() / 2);
+        MultilinearExtension {
+            field,
+            evaluations: low
+                .iter()
+                .zip(high)
+                .map(|(low, high)| field.add(*low, field.mul(r, field.sub(*high, *low))))
+                .collect(),
+        }
+    }
+
+    /// Evaluates the extension at a point of `F^n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the point does not have one coordinate per variable.
+    pub fn evaluate(&self, point: &[u64]) -> u64 {
+        if point.len() != self.num_vars() {
+            panic!("expected one coordinate per variable");
+        }
+        point
+            .iter()
+            .fold(self.clone(), |mle, r| mle.fix_first(*r))
+            .evaluations[0]
+    }
+}
+
+impl Builder {
+    /// Returns the multilinear extension of the witness (see `Builder::witness`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit was not filled.
+    pub fn witness_mle(&self, field: Field) -> MultilinearExtension {
+        let witness = self
+            .witness()
+            .expect("the circuit must be filled before extending its witness");
+        MultilinearExtension::new(witness, field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agrees_with_sum_formula() {
+        let field = Field::new(97);
+        let mle = MultilinearExtension::new(vec![3, 1, 4, 1, 5, 9, 2, 6], field);
+        for (index, value) in mle.evaluations().iter().enumerate() {
+            let bits: Vec<u64> = (0..3).map(|i| (index >> (2 - i) & 1) as u64).collect();
+            assert_eq!(mle.evaluate(&bits), *value);
+        }
+        let point = [10, 20, 30];
+        let expected = (0..8).fold(0, |sum, index| {
+            let weight = (0..3).fold(1, |weight, i| {
+                let factor = match index >> (2 - i) & 1 {
+                    1 => point[i],
+                    _ => field.sub(1, point[i]),
+                };
+                field.mul(weight, factor)
+            });
+            field.add(sum, field.mul(weight, mle.evaluations()[index]))
+        });
+        assert_eq!(mle.evaluate(&point), expected);
+    }
+}
@@ -0,0 +1,174 @@
+//! Readable names for the wires of a circuit.
+//!
+//! Every node has a name that stays the same while the circuit grows: inputs are named
+//! `x0, x1, …` and constants `c0, c1, …` in order of creation, and every other node `w` followed
+//! by its id. `Builder::label` replaces the name of a node with one of your own. Names are used
+//! wherever the library prints wires: constraint dumps, DOT and code exports, log messages and
+//! errors.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let five = builder.constant(5);
+//! let square = builder.mul(x.clone(), x.clone());
+//! let result = builder.add(square.clone(), five.clone());
+//! builder.label(&square, "x_squared");
+//!
+//! assert_eq!(builder.name(&x), "x0");
+//! assert_eq!(builder.name(&five), "c0");
+//! assert_eq!(builder.name(&result), "w3");
+//! assert_eq!(builder.dump_constraints(), "x_squared = x0 * x0\nw3 = x_squared + 5\n");
+//! ```
+use crate::enums::Operation;
+use crate::replay::LoggedOp;
+use crate::{Builder, Node};
+
+/// Keywords of Rust and the parameter of `Builder::codegen_rust`, which labels cannot take
+/// without breaking the generated code.
+const RESERVED_RUST: &str = "as async await break const continue crate dyn else enum extern \
+    false fn for if impl in let loop match mod move mut pub ref return self Self static struct \
+    super trait true type unsafe use where while abstract become box do final gen macro \
+    override priv try typeof unsized virtual yield inputs";
+/// Keywords of Python and the names `Builder::to_sage` defines, likewise.
+const RESERVED_SAGE: &str = "False None True and assert class def del elif except finally from \
+    global import is lambda nonlocal not or pass raise with print F R I ZZ GF PolynomialRing \
+    outputs constraints";
+/// Operators and sorts of SMT-LIB used by `Builder::to_smtlib`, likewise.
+const RESERVED_SMTLIB: &str = "Int Bool div abs ite distinct exists forall";
+
+impl Builder {
+    /// Names a node, replacing its automatic name and any earlier label.
+    ///
+    /// # Arguments
+    ///
+    /// * `node`: The node to name.
+    /// * `name`: The new name, made of ASCII letters, digits and underscores and not starting
+    ///   with a digit, so that it is a valid identifier in every export.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name is not such an identifier, has the form of an automatic name like
+    /// `w5`, is a keyword or a name an export uses itself, such as `fn` or `inputs`, or already
+    /// names another node.
+    pub fn label(&mut self, node: &Node, name: &str) {
+        let is_identifier = name
+            .chars()
+            .next()
+            .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+            && name
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == '_');
+        if !is_identifier {
+            panic!("label {:?} is not an identifier", name);
+        }
+        if is_automatic_name(name) {
+            panic!("label {} could be mistaken for an automatic name", name);
+        }
+        let reserved = [RESERVED_RUST, RESERVED_SAGE, RESERVED_SMTLIB];
+        if reserved
+            .iter()
+            .any(|words| words.split_whitespace().any(|word| word == name))
+        {
+            panic!("label {} is reserved by the exports", name);
+        }
+        if let Some((other, _)) = self
+            .labels
+            .iter()
+            .find(|(id, label)| **id != node.id && *label == name)
+        {
+            panic!(
+                "label {} already names {}",
+                name,
+                self.automatic_name(*other)
+            );
+        }
+        self.labels.insert(node.id, name.to_string());
+        self.record_op(|| LoggedOp::Label {
+            node: node.id,
+            name: name.to_string(),
+        });
+    }
+
+    /// Returns the name of a node, its label if it has one, see the `names` module.
+    pub fn name(&self, node: &Node) -> String {
+        self.wire_name(node.id)
+    }
+
+    /// Returns the name of the node with this id, see `name`.
+    pub(crate) fn wire_name(&self, id: usize) -> String {
+        match self.labels.get(&id) {
+            Some(label) => label.clone(),
+            None => self.automatic_name(id),
+        }
+    }
+
+    /// Returns `x0, x1, …` for inputs, `c0, c1, …` for constants and `w` followed by the id for
+    /// every other node.
+    fn automatic_name(&self, id: usize) -> String {
+        let count = |operation: fn(&Operation) -> bool| {
+            self.full_graph[..id]
+                .iter()
+                .filter(|node| operation(&node.operation))
+                .count()
+        };
+        match self.full_graph[id].operation {
+            Operation::Input => format!("x{}", count(|op| *op == Operation::Input)),
+            Operation::Constant(_) => {
+                format!("c{}", count(|op| matches!(op, Operation::Constant(_))))
+            }
+            _ => format!("w{}", id),
+        }
+    }
+}
+
+/// Returns `true` for names of the form of `x0`, `c12` or `w5`.
+fn is_automatic_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some('x' | 'c' | 'w'))
+        && !chars.as_str().is_empty()
+        && chars.all(|char| char.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels_in_exports_and_rollback() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let checkpoint = builder.checkpoint();
+        let double = builder.add(x.clone(), x.clone());
+        builder.label(&double, "double");
+        builder.label(&double, "twice");
+        assert!(builder.to_dot().contains("label=\"twice (+)\""));
+        builder.rollback(checkpoint);
+
+        let square = builder.mul(x.clone(), x);
+        assert_eq!(builder.name(&square), "w1");
+        builder.label(&square, "twice");
+        assert_eq!(builder.dump_constraints(), "twice = x0 * x0\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "label w2 could be mistaken for an automatic name")]
+    fn test_automatic_names_are_reserved() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        builder.label(&x, "w2");
+    }
+
+    #[test]
+    fn test_keywords_are_reserved() {
+        for name in ["inputs", "fn", "let", "lambda", "ite"] {
+            let result = std::panic::catch_unwind(|| {
+                let mut builder = Builder::new();
+                let x = builder.init();
+                builder.label(&x, name);
+            });
+            assert!(result.is_err(), "{} was accepted", name);
+        }
+    }
+}
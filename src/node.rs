@@ -1,38 +1,37 @@
-use crate::enums::{CustomU64, ExprVal};
-use log::debug;
-use std::{
-    cell::RefCell,
-    fmt::{Display, Formatter},
-    rc::Rc,
-};
+use crate::builder::OVERFLOW;
+use crate::enums::{CustomU64, ExprVal, Operation};
+use crate::field::Field;
+use std::fmt::{Display, Formatter};
+use tracing::debug;
+
+/// The id of a node, which is its position in the builder's graph.
+pub type NodeId = usize;
 
 /// Represents a node in the arithmetic circuit.
 ///
 /// A node can either hold a specific value or be an expression
-/// based on other nodes. Each node can also have children,
-/// which are other nodes that contribute to its value or expression.
+/// based on other nodes. Each node records the operation that produced it
+/// and the ids of its children, which are other nodes that use it as an operand.
+///
+/// The `id` of a node is its position in the builder's graph. Since nodes can only
+/// be built from nodes that already exist, ids are always in evaluation order.
 ///
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Node {
+    pub id: usize,
     pub value: CustomU64,
-    pub children: Option<Vec<Rc<RefCell<Node>>>>,
+    pub operation: Operation,
+    pub children: Vec<usize>,
 }
 
+/// Shows the value of an evaluated node and `w` followed by the id otherwise. A node does not
+/// know the builder it belongs to; `Builder::name` gives the names of inputs, constants and
+/// labeled nodes.
 impl Display for Node {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match &self.value {
             CustomU64::Val(val) => write!(f, "{}", val),
-            CustomU64::Expr(expr) => match expr {
-                ExprVal::Add(val) => match val {
-                    Some(val) => write!(f, "Add(Unevaluated with val {})", val),
-                    None => write!(f, "Add(Unevaluated)"),
-                },
-                ExprVal::Mul(val) => match val {
-                    Some(val) => write!(f, "Mul(Unevaluated with val {})", val),
-                    None => write!(f, "Mul(Unevaluated))"),
-                },
-                ExprVal::Input => write!(f, "Input"),
-            },
+            CustomU64::Expr(_) => write!(f, "w{}", self.id),
         }
     }
 }
@@ -40,10 +39,15 @@ impl Display for Node {
 impl Node {
     /// Creates a new input node.
     ///
-    pub(crate) fn init() -> Self {
+    /// # Arguments
+    ///
+    /// * `id`: The position of the node in the graph.
+    pub(crate) fn init(id: usize) -> Self {
         Node {
+            id,
             value: CustomU64::Expr(ExprVal::Input),
-            children: Some(vec![]),
+            operation: Operation::Input,
+            children: vec![],
         }
     }
 
@@ -51,147 +55,104 @@ impl Node {
     ///
     /// # Arguments
     ///
+    /// * `id`: The position of the node in the graph.
     /// * `value`: The value to initialize the node with.
-    pub(crate) fn new(value: u64) -> Self {
+    pub(crate) fn new(id: usize, value: u64) -> Self {
         Node {
+            id,
             value: CustomU64::Val(value),
-            children: Some(vec![]),
-        }
-    }
-
-    /// Recursively evaluates the expressions of a node's children.
-    ///
-    ///
-    /// If a child node contains an addition (`Add`) or multiplication (`Mul`) expression
-    /// with a value, it is resolved to a definite value (`Val`).
-    ///
-    pub(crate) fn evaluate_children(&mut self) {
-        if let Some(children) = &mut self.children {
-            for child in children.iter_mut() {
-                let mut child = child.borrow_mut();
-                child.evaluate_children();
-                match &child.value {
-                    CustomU64::Expr(ExprVal::Add(Some(val))) => {
-                        child.value = CustomU64::Val(*val);
-                    }
-                    CustomU64::Expr(ExprVal::Mul(Some(val))) => {
-                        child.value = CustomU64::Val(*val);
-                    }
-                    _ => {
-                        panic!("An input node is a child. This is not possible.");
-                    }
-                }
-            }
+            operation: Operation::Constant(value),
+            children: vec![],
         }
     }
 
     /// Combines two nodes using addition.
     ///
     /// If either node represents an unevaluated expression,
-    /// the result will also be an unevaluated expression and new node will
-    /// be added as a child to the unevaluated node.
-    /// A constraint is also added during the addition process
+    /// the result will also be an unevaluated expression. The caller is
+    /// responsible for registering the new node as a child of `a` and `b`.
     ///
     /// # Arguments
     ///
+    /// * `id`: The position of the new node in the graph.
     /// * `a`: The first node.
     /// * `b`: The second node.
+    /// * `field`: The field to compute in, `None` for plain `u64` arithmetic.
     ///
     /// # Returns
     ///
     /// A new node representing the sum of the two input nodes.
-    pub(crate) fn add(mut a: Node, mut b: Node) -> Node {
-        let mut node = Node::default();
-        match (&a.value, &b.value) {
-            (CustomU64::Val(a_val), CustomU64::Val(b_val)) => {
-                node = Node::new(a_val + b_val);
-            }
-            (CustomU64::Val(a_val), CustomU64::Expr(_)) => {
-                node = Node {
-                    value: CustomU64::Expr(ExprVal::Add(Some(*a_val))),
-                    children: Some(vec![]),
-                };
-                if let Some(children) = &mut b.children {
-                    children.push(Rc::new(RefCell::new(node.clone())));
-                }
-            }
-            (CustomU64::Expr(_), CustomU64::Val(b_val)) => {
-                node = Node {
-                    value: CustomU64::Expr(ExprVal::Add(Some(*b_val))),
-                    children: Some(vec![]),
-                };
-                if let Some(children) = &mut a.children {
-                    children.push(Rc::new(RefCell::new(node.clone())));
-                }
-            }
-            (CustomU64::Expr(_), CustomU64::Expr(_)) => {
-                node = Node {
-                    value: CustomU64::Expr(ExprVal::Add(None)),
-                    children: Some(vec![]),
-                };
-                if let Some(children) = &mut a.children {
-                    children.push(Rc::new(RefCell::new(node.clone())));
-                }
-                if let Some(children) = &mut b.children {
-                    children.push(Rc::new(RefCell::new(node.clone())));
-                }
+    ///
+    /// # Panics
+    ///
+    /// Panics if, without a field, the sum of two values overflows a `u64`.
+    pub(crate) fn add(id: usize, a: &Node, b: &Node, field: Option<Field>) -> Node {
+        let value = match (&a.value, &b.value) {
+            (CustomU64::Val(a_val), CustomU64::Val(b_val)) => CustomU64::Val(match field {
+                Some(field) => field.add(*a_val, *b_val),
+                None => a_val.checked_add(*b_val).expect(OVERFLOW),
+            }),
+            (CustomU64::Val(val), CustomU64::Expr(_))
+            | (CustomU64::Expr(_), CustomU64::Val(val)) => {
+                CustomU64::Expr(ExprVal::Add(Some(*val)))
             }
-        }
-        debug!("add node: {} generated from {} and {}", node, a, b);
+            (CustomU64::Expr(_), CustomU64::Expr(_)) => CustomU64::Expr(ExprVal::Add(None)),
+        };
+        let node = Node {
+            id,
+            value,
+            operation: Operation::Add(a.id, b.id),
+            children: vec![],
+        };
+        debug!(gate = id, op = "add", a = a.id, b = b.id, value = %node, "gate created");
         node
     }
 
     /// Combines two nodes using multiplication.
     ///
     /// This method follows similar logic to `add`, but with multiplication.
-    /// A constraint is also added during the multiplication process
     ///
     /// # Arguments
     ///
+    /// * `id`: The position of the new node in the graph.
     /// * `a`: The first node.
     /// * `b`: The second node.
+    /// * `field`: The field to compute in, `None` for plain `u64` arithmetic.
     ///
     /// # Returns
     ///
     /// A new node representing the product of the two input nodes.
-    pub(crate) fn mul(mut a: Node, mut b: Node) -> Node {
-        let mut node = Node::default();
-        match (&a.value, &b.value) {
-            (CustomU64::Val(a_val), CustomU64::Val(b_val)) => {
-                node = Node::new(a_val * b_val);
-            }
-            (CustomU64::Val(a_val), CustomU64::Expr(_)) => {
-                node = Node {
-                    value: CustomU64::Expr(ExprVal::Mul(Some(*a_val))),
-                    children: Some(vec![]),
-                };
-                if let Some(children) = &mut b.children {
-                    children.push(Rc::new(RefCell::new(node.clone())));
-                }
-            }
-            (CustomU64::Expr(_), CustomU64::Val(b_val)) => {
-                node = Node {
-                    value: CustomU64::Expr(ExprVal::Mul(Some(*b_val))),
-                    children: Some(vec![]),
-                };
-                if let Some(children) = &mut a.children {
-                    children.push(Rc::new(RefCell::new(node.clone())));
-                }
-            }
-            (CustomU64::Expr(_), CustomU64::Expr(_)) => {
-                node = Node {
-                    value: CustomU64::Expr(ExprVal::Mul(None)),
-                    children: Some(vec![]),
-                };
-                if let Some(children) = &mut a.children {
-                    children.push(Rc::new(RefCell::new(node.clone())));
-                }
-                if let Some(children) = &mut b.children {
-                    children.push(Rc::new(RefCell::new(node.clone())));
-                }
+    ///
+    /// # Panics
+    ///
+    /// Panics if, without a field, the product of two values overflows a `u64`.
+    pub(crate) fn mul(id: usize, a: &Node, b: &Node, field: Option<Field>) -> Node {
+        let value = match (&a.value, &b.value) {
+            (CustomU64::Val(a_val), CustomU64::Val(b_val)) => CustomU64::Val(match field {
+                Some(field) => field.mul(*a_val, *b_val),
+                None => a_val.checked_mul(*b_val).expect(OVERFLOW),
+            }),
+            (CustomU64::Val(val), CustomU64::Expr(_))
+            | (CustomU64::Expr(_), CustomU64::Val(val)) => {
+                CustomU64::Expr(ExprVal::Mul(Some(*val)))
             }
-        }
-        debug!("mul node: {} generated from {} and {}", node.clone(), a, b);
+            (CustomU64::Expr(_), CustomU64::Expr(_)) => CustomU64::Expr(ExprVal::Mul(None)),
+        };
+        let node = Node {
+            id,
+            value,
+            operation: Operation::Mul(a.id, b.id),
+            children: vec![],
+        };
+        debug!(gate = id, op = "mul", a = a.id, b = b.id, value = %node, "gate created");
         node
     }
+
+    /// Returns the value of the node if it has been evaluated.
+    pub fn evaluated(&self) -> Option<u64> {
+        match self.value {
+            CustomU64::Val(val) => Some(val),
+            CustomU64::Expr(_) => None,
+        }
+    }
 }
@@ -0,0 +1,746 @@
+//! Optimization pipelines.
+//!
+//! A `PassManager` runs a list of optimization passes over a circuit, one after the other, and
+//! reports how much each of them shrank it. Passes can be registered in any order and more than
+//! once, which makes it easy to compare pipelines.
+//!
+//! A pass is anything implementing `OptimizationPass`: the built-in `Pass`es as well as passes
+//! shipped by other crates. It edits a `GraphView`, an editable copy of the graph, and the pass
+//! manager rebuilds the circuit from the edited view. A pass must keep the circuit equivalent: the
+//! result accepts the same inputs, in the same order and visibility, computes the same outputs
+//! and is satisfied exactly when the original circuit is.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::passes::{Pass, PassManager};
+//!
+//! // (2 + 3) * x + 0 * x, with an unused square of x
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let two = builder.constant(2);
+//! let three = builder.constant(3);
+//! let zero = builder.constant(0);
+//! let five = builder.add(two, three);
+//! let product = builder.mul(five, x.clone());
+//! let nothing = builder.mul(zero, x.clone());
+//! let _unused = builder.mul(x.clone(), x);
+//! let sum = builder.add(product, nothing);
+//! builder.output(sum);
+//!
+//! let mut manager = PassManager::new();
+//! manager.register(Pass::ConstantFolding);
+//! manager.register(Pass::Simplification);
+//! manager.register(Pass::DeadCodeElimination);
+//! let (optimized, stats) = manager.run(&builder);
+//! assert_eq!(optimized.evaluate(vec![4]), vec![20]);
+//! assert_eq!(stats[2].nodes_after, 3);
+//! assert_eq!(
+//!     stats[0].to_string(),
+//!     "constant folding: nodes 9 -> 9, constraints 5 -> 4"
+//! );
+//! ```
+use crate::enums::{Constraints, Operation};
+use crate::field::Field;
+use crate::hint::Hint;
+use crate::{Builder, Node, NodeId};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+use tracing::info;
+
+/// An optimization pass that can be registered with a `PassManager`.
+///
+/// # Example
+///
+/// ```
+/// use arithmetic_circuit::builder::Builder;
+/// use arithmetic_circuit::enums::Operation;
+/// use arithmetic_circuit::passes::{GraphView, OptimizationPass, Pass, PassManager};
+///
+/// /// Rewrites `x + x` to `2 * x`, for a backend where scaling by a constant is free.
+/// struct Doubling;
+///
+/// impl OptimizationPass for Doubling {
+///     fn name(&self) -> &str {
+///         "doubling"
+///     }
+///
+///     fn run(&self, graph: &mut GraphView) {
+///         for id in graph.topological_order() {
+///             if let Some(Operation::Add(a, b)) = graph.operation(id) {
+///                 if a == b {
+///                     let two = graph.add_node(Operation::Constant(2));
+///                     graph.set_operation(id, Operation::Mul(two, a));
+///                 }
+///             }
+///         }
+///     }
+/// }
+///
+/// let mut builder = Builder::new();
+/// let x = builder.init();
+/// let double = builder.add(x.clone(), x);
+/// builder.output(double);
+///
+/// let mut manager = PassManager::new();
+/// manager.register(Doubling);
+/// manager.register(Pass::CommonSubexpressionElimination);
+/// let (optimized, stats) = manager.run(&builder);
+/// assert_eq!(stats[0].pass, "doubling");
+/// assert_eq!(optimized.evaluate(vec![21]), vec![42]);
+/// ```
+pub trait OptimizationPass {
+    /// A short name identifying the pass in `PassStats`.
+    fn name(&self) -> &str;
+
+    /// Rewrites the graph in place, keeping the circuit equivalent.
+    fn run(&self, graph: &mut GraphView);
+}
+
+/// A built-in optimization pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    /// Replaces additions and multiplications of two constants by their result and drops
+    /// assertions between equal constants. Without a field, gates that would overflow a `u64`
+    /// are kept.
+    ConstantFolding,
+    /// Merges equal constants and gates computing the same operation on the same operands, and
+    /// drops duplicate assertions, like `Builder::dedup`.
+    CommonSubexpressionElimination,
+    /// Removes nodes that neither an output nor an equality assertion depends on. Inputs are
+    /// always kept.
+    DeadCodeElimination,
+    /// Rewrites `x + 0` and `x * 1` to `x`, and `x * 0` to `0`, and drops assertions between a
+    /// node and itself.
+    Simplification,
+}
+
+impl Display for Pass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl OptimizationPass for Pass {
+    fn name(&self) -> &str {
+        match self {
+            Pass::ConstantFolding => "constant folding",
+            Pass::CommonSubexpressionElimination => "common subexpression elimination",
+            Pass::DeadCodeElimination => "dead code elimination",
+            Pass::Simplification => "simplification",
+        }
+    }
+
+    fn run(&self, graph: &mut GraphView) {
+        match self {
+            Pass::ConstantFolding => fold_constants(graph),
+            Pass::CommonSubexpressionElimination => eliminate_common_subexpressions(graph),
+            Pass::DeadCodeElimination => eliminate_dead_code(graph),
+            Pass::Simplification => simplify(graph),
+        }
+    }
+}
+
+/// How much a single pass shrank a circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassStats {
+    /// The name of the pass.
+    pub pass: String,
+    pub nodes_before: usize,
+    pub nodes_after: usize,
+    pub constraints_before: usize,
+    pub constraints_after: usize,
+}
+
+impl Display for PassStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: nodes {} -> {}, constraints {} -> {}",
+            self.pass,
+            self.nodes_before,
+            self.nodes_after,
+            self.constraints_before,
+            self.constraints_after
+        )
+    }
+}
+
+/// An ordered list of passes, see the module documentation.
+#[derive(Clone)]
+pub struct PassManager {
+    passes: Vec<Rc<dyn OptimizationPass>>,
+}
+
+impl Default for PassManager {
+    /// Folds constants, simplifies, merges duplicates and finally removes what became unused.
+    fn default() -> Self {
+        let mut manager = PassManager::new();
+        manager.register(Pass::ConstantFolding);
+        manager.register(Pass::Simplification);
+        manager.register(Pass::CommonSubexpressionElimination);
+        manager.register(Pass::DeadCodeElimination);
+        manager
+    }
+}
+
+impl PassManager {
+    /// Creates a pass manager without any passes.
+    pub fn new() -> Self {
+        PassManager { passes: vec![] }
+    }
+
+    /// Appends a pass to the pipeline.
+    pub fn register(&mut self, pass: impl OptimizationPass + 'static) {
+        self.passes.push(Rc::new(pass));
+    }
+
+    /// Returns the names of the passes in the order they run.
+    pub fn pass_names(&self) -> Vec<&str> {
+        self.passes.iter().map(|pass| pass.name()).collect()
+    }
+
+    /// Runs every pass in order, each on the result of the previous one.
+    ///
+    /// Nodes returned by the original builder do not refer to the optimized circuit. Scopes,
+    /// constraint groups, range table lookups, the labels of nodes that remain, the field and the
+    /// logger carry over.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit`: The circuit to optimize, which is left unchanged.
+    ///
+    /// # Returns
+    ///
+    /// The optimized circuit and the statistics of every pass, in the order they ran.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a pass leaves a cycle or a use of a removed node in the graph.
+    pub fn run(&self, circuit: &Builder) -> (Builder, Vec<PassStats>) {
+        let mut current = circuit.clone();
+        let mut stats = Vec::with_capacity(self.passes.len());
+        for pass in &self.passes {
+            let mut graph = GraphView::new(&current);
+            pass.run(&mut graph);
+            let next = graph.into_circuit();
+            let pass_stats = PassStats {
+                pass: pass.name().to_string(),
+                nodes_before: current.full_graph.len(),
+                nodes_after: next.full_graph.len(),
+                constraints_before: current.constraints.len(),
+                constraints_after: next.constraints.len(),
+            };
+            info!(%pass_stats, "ran optimization pass");
+            stats.push(pass_stats);
+            current = next;
+        }
+        (current, stats)
+    }
+}
+
+/// An editable copy of a circuit's graph, handed to `OptimizationPass::run`.
+///
+/// Nodes keep their ids while the graph is edited. New nodes get the next free id, and
+/// operations may refer to nodes with larger ids: the circuit is rebuilt in topological order
+/// afterwards. Inputs cannot be changed or removed, so the rebuilt circuit has the same inputs.
+pub struct GraphView<'a> {
+    circuit: &'a Builder,
+    /// The operation of every node, `None` once removed.
+    operations: Vec<Option<Operation>>,
+    scopes: Vec<usize>,
+    /// The constraint group of every gate, see the `groups` module.
+    groups: Vec<Option<usize>>,
+    hint_inputs: Vec<Vec<NodeId>>,
+    equalities: Vec<(NodeId, NodeId)>,
+    /// The constraint group of every equality assertion.
+    equality_groups: Vec<Option<usize>>,
+    lookups: Vec<(NodeId, usize)>,
+    outputs: Vec<NodeId>,
+}
+
+impl<'a> GraphView<'a> {
+    pub(crate) fn new(circuit: &'a Builder) -> Self {
+        let mut groups = vec![None; circuit.full_graph.len()];
+        let mut equalities = vec![];
+        for (constraint, group) in circuit.constraints.iter().zip(&circuit.constraint_groups) {
+            match *constraint {
+                Constraints::Eq(a, b) => equalities.push(((a, b), *group)),
+                // a lowered zero assertion a * 1 = 0
+                Constraints::Mul(a, _, zero) if circuit.zero_assertion(constraint).is_some() => {
+                    equalities.push(((a, zero), *group))
+                }
+                Constraints::Add(_, _, out) | Constraints::Mul(_, _, out) => groups[out] = *group,
+            }
+        }
+        let (equalities, equality_groups) = equalities.into_iter().unzip();
+        GraphView {
+            circuit,
+            operations: circuit
+                .full_graph
+                .iter()
+                .map(|node| Some(node.operation))
+                .collect(),
+            scopes: circuit.node_scopes.clone(),
+            groups,
+            hint_inputs: circuit
+                .hints
+                .iter()
+                .map(|hint| hint.inputs.clone())
+                .collect(),
+            equalities,
+            equality_groups,
+            lookups: circuit.lookups.clone(),
+            outputs: circuit.outputs.clone(),
+        }
+    }
+
+    /// Returns the number of ids in use, including those of removed nodes.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Returns whether the graph has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Returns the field the circuit computes in, if any.
+    pub fn field(&self) -> Option<Field> {
+        self.circuit.field
+    }
+
+    /// Returns the operation of a node, `None` if it was removed.
+    pub fn operation(&self, id: NodeId) -> Option<Operation> {
+        self.operations[id]
+    }
+
+    /// Returns the value of a node if it is a constant.
+    pub fn constant_value(&self, id: NodeId) -> Option<u64> {
+        match self.operations[id] {
+            Some(Operation::Constant(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the nodes a node is computed from: the operands of a gate or the inputs of a hint.
+    pub fn operands(&self, id: NodeId) -> Vec<NodeId> {
+        match self.operations[id] {
+            Some(Operation::Add(a, b)) | Some(Operation::Mul(a, b)) => vec![a, b],
+            Some(Operation::Hint(index)) => self.hint_inputs[index].clone(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns the inputs of the circuit in order of creation.
+    pub fn inputs(&self) -> &[NodeId] {
+        &self.circuit.inputs
+    }
+
+    /// Returns the outputs of the circuit.
+    pub fn outputs(&self) -> &[NodeId] {
+        &self.outputs
+    }
+
+    /// Returns the equality assertions of the circuit.
+    pub fn equalities(&self) -> &[(NodeId, NodeId)] {
+        &self.equalities
+    }
+
+    /// Keeps only the equality assertions for which `keep` returns true.
+    pub fn retain_equalities(&mut self, mut keep: impl FnMut(&Self, NodeId, NodeId) -> bool) {
+        let equalities = std::mem::take(&mut self.equalities);
+        let groups = std::mem::take(&mut self.equality_groups);
+        (self.equalities, self.equality_groups) = equalities
+            .into_iter()
+            .zip(groups)
+            .filter(|((a, b), _)| keep(self, *a, *b))
+            .unzip();
+    }
+
+    /// Returns the wires checked against range tables, with the bits of each table, see the
+    /// `lookup` module.
+    pub fn lookups(&self) -> &[(NodeId, usize)] {
+        &self.lookups
+    }
+
+    /// Returns the ids of all nodes, every node after the nodes it is computed from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the graph has a cycle or uses a removed node.
+    pub fn topological_order(&self) -> Vec<NodeId> {
+        // 0: not visited, 1: on the current path, 2: done
+        let mut state = vec![0u8; self.len()];
+        let mut order = Vec::with_capacity(self.len());
+        for root in 0..self.len() {
+            if self.operations[root].is_none() {
+                continue;
+            }
+            let mut stack = vec![(root, false)];
+            while let Some((id, expanded)) = stack.pop() {
+                if expanded {
+                    state[id] = 2;
+                    order.push(id);
+                    continue;
+                }
+                match state[id] {
+                    1 => panic!("optimization pass created a cycle through node {}", id),
+                    2 => continue,
+                    _ => {}
+                }
+                state[id] = 1;
+                stack.push((id, true));
+                for operand in self.operands(id).into_iter().rev() {
+                    if self.operations[operand].is_none() {
+                        panic!("node {} was removed but node {} still uses it", operand, id);
+                    }
+                    stack.push((operand, false));
+                }
+            }
+        }
+        order
+    }
+
+    /// Changes the operation of a node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is an input or was removed, if the new operation is an input or a
+    /// hint, or if it refers to a node that does not exist.
+    pub fn set_operation(&mut self, id: NodeId, operation: Operation) {
+        if self.inputs().contains(&id) || self.operations[id].is_none() {
+            panic!("node {} is an input or was removed", id);
+        }
+        self.check_operation(&operation);
+        self.operations[id] = Some(operation);
+    }
+
+    /// Adds a new constant or gate in the root scope and no constraint group.
+    ///
+    /// # Returns
+    ///
+    /// The id of the new node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation is an input or a hint, or refers to a node that does not exist.
+    pub fn add_node(&mut self, operation: Operation) -> NodeId {
+        self.check_operation(&operation);
+        self.operations.push(Some(operation));
+        self.scopes.push(0);
+        self.groups.push(None);
+        self.operations.len() - 1
+    }
+
+    /// Makes every gate, hint, equality assertion, lookup and output using node `old` use `new`
+    /// instead.
+    pub fn replace_uses(&mut self, old: NodeId, new: NodeId) {
+        let replace = |id: &mut NodeId| {
+            if *id == old {
+                *id = new;
+            }
+        };
+        for operation in self.operations.iter_mut().flatten() {
+            if let Operation::Add(a, b) | Operation::Mul(a, b) = operation {
+                replace(a);
+                replace(b);
+            }
+        }
+        self.hint_inputs.iter_mut().flatten().for_each(replace);
+        for (a, b) in &mut self.equalities {
+            replace(a);
+            replace(b);
+        }
+        self.lookups.iter_mut().for_each(|(wire, _)| replace(wire));
+        self.outputs.iter_mut().for_each(replace);
+    }
+
+    /// Removes a node, which must not be used anymore once the pass finishes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is an input.
+    pub fn remove_node(&mut self, id: NodeId) {
+        if self.inputs().contains(&id) {
+            panic!("input {} cannot be removed", id);
+        }
+        self.operations[id] = None;
+    }
+
+    fn check_operation(&self, operation: &Operation) {
+        match *operation {
+            Operation::Input | Operation::Hint(_) => {
+                panic!("optimization passes cannot create inputs or hints")
+            }
+            Operation::Add(a, b) | Operation::Mul(a, b) => {
+                for operand in [a, b] {
+                    if self.operations.get(operand).copied().flatten().is_none() {
+                        panic!("node {} does not exist", operand);
+                    }
+                }
+            }
+            Operation::Constant(_) => {}
+        }
+    }
+
+    /// Rebuilds the circuit from the edited graph.
+    pub(crate) fn into_circuit(self) -> Builder {
+        let order = self.topological_order();
+        let mut circuit = self.circuit.empty_like();
+        circuit.groups = self.circuit.groups.clone();
+        circuit.disabled_groups = self.circuit.disabled_groups.clone();
+        let mut nodes: Vec<Option<Node>> = vec![None; self.len()];
+        // inputs first, so that they keep their order whatever the pass did
+        for id in self.inputs().iter().copied().chain(order) {
+            if nodes[id].is_some() {
+                continue;
+            }
+            circuit.current_scope = self.scopes[id];
+            circuit.current_group = self.groups[id];
+            let operand = |id: NodeId| nodes[id].clone().unwrap();
+            let node = match self.operations[id].unwrap() {
+                Operation::Input if self.circuit.private_inputs.contains(&id) => {
+                    circuit.init_private()
+                }
+                Operation::Input => circuit.init(),
+                Operation::Constant(value) => circuit.constant(value),
+                Operation::Add(a, b) => circuit.add(operand(a), operand(b)),
+                Operation::Mul(a, b) => circuit.mul(operand(a), operand(b)),
+                Operation::Hint(index) => circuit.push_hint(Hint {
+                    inputs: self.hint_inputs[index]
+                        .iter()
+                        .map(|id| operand(*id).id)
+                        .collect(),
+                    compute: self.circuit.hints[index].compute.clone(),
+                }),
+            };
+            nodes[id] = Some(node);
+        }
+        circuit.current_scope = 0;
+        for ((a, b), group) in self.equalities.iter().zip(&self.equality_groups) {
+            circuit.current_group = *group;
+            let (a, b) = (nodes[*a].clone().unwrap(), nodes[*b].clone().unwrap());
+            if !circuit.lower_equalities {
+                circuit.push_equality(a, b);
+            } else if circuit.full_graph[b.id].operation == Operation::Constant(0) {
+                // not assert_equal, which would lower already lowered assertions again
+                circuit.assert_zero(a);
+            } else {
+                circuit.assert_equal(a, b);
+            }
+        }
+        circuit.current_group = None;
+        for (wire, bits) in &self.lookups {
+            circuit.push_lookup(nodes[*wire].as_ref().unwrap().id, *bits);
+        }
+        for (id, label) in &self.circuit.labels {
+            if let Some(node) = &nodes[*id] {
+                circuit.labels.insert(node.id, label.clone());
+            }
+        }
+        for output in &self.outputs {
+            circuit.output(nodes[*output].clone().unwrap());
+        }
+        circuit
+    }
+}
+
+fn fold_constants(graph: &mut GraphView) {
+    for id in graph.topological_order() {
+        let (a, b, is_mul) = match graph.operation(id) {
+            Some(Operation::Add(a, b)) => (a, b, false),
+            Some(Operation::Mul(a, b)) => (a, b, true),
+            _ => continue,
+        };
+        if let (Some(x), Some(y)) = (graph.constant_value(a), graph.constant_value(b)) {
+            let value = match (graph.field(), is_mul) {
+                (Some(field), true) => Some(field.mul(x, y)),
+                (Some(field), false) => Some(field.add(x, y)),
+                (None, true) => x.checked_mul(y),
+                (None, false) => x.checked_add(y),
+            };
+            if let Some(value) = value {
+                graph.set_operation(id, Operation::Constant(value));
+            }
+        }
+    }
+    graph.retain_equalities(|graph, a, b| {
+        let constants = (graph.constant_value(a), graph.constant_value(b));
+        !matches!(constants, (Some(x), Some(y)) if x == y)
+    });
+}
+
+fn simplify(graph: &mut GraphView) {
+    for id in graph.topological_order() {
+        let replacement = match graph.operation(id) {
+            Some(Operation::Add(a, b)) => {
+                match (graph.constant_value(a), graph.constant_value(b)) {
+                    (Some(0), _) => Some(b),
+                    (_, Some(0)) => Some(a),
+                    _ => None,
+                }
+            }
+            Some(Operation::Mul(a, b)) => {
+                match (graph.constant_value(a), graph.constant_value(b)) {
+                    (Some(0), _) | (_, Some(0)) => {
+                        graph.set_operation(id, Operation::Constant(0));
+                        None
+                    }
+                    (Some(1), _) => Some(b),
+                    (_, Some(1)) => Some(a),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(replacement) = replacement {
+            graph.replace_uses(id, replacement);
+            graph.remove_node(id);
+        }
+    }
+    graph.retain_equalities(|_, a, b| a != b);
+}
+
+fn eliminate_common_subexpressions(graph: &mut GraphView) {
+    let mut constants: HashMap<u64, NodeId> = HashMap::new();
+    let mut gates: HashMap<(bool, NodeId, NodeId), NodeId> = HashMap::new();
+    for id in graph.topological_order() {
+        let existing = match graph.operation(id) {
+            Some(Operation::Constant(value)) => *constants.entry(value).or_insert(id),
+            Some(Operation::Add(a, b)) => *gates.entry((false, a.min(b), a.max(b))).or_insert(id),
+            Some(Operation::Mul(a, b)) => *gates.entry((true, a.min(b), a.max(b))).or_insert(id),
+            // hints may be impure, so they are never merged
+            _ => id,
+        };
+        if existing != id {
+            graph.replace_uses(id, existing);
+            graph.remove_node(id);
+        }
+    }
+    let mut seen = HashSet::new();
+    graph.retain_equalities(|_, a, b| a != b && seen.insert((a.min(b), a.max(b))));
+}
+
+fn eliminate_dead_code(graph: &mut GraphView) {
+    let mut live = vec![false; graph.len()];
+    let mut stack: Vec<NodeId> = graph.inputs().to_vec();
+    stack.extend(graph.outputs());
+    stack.extend(graph.equalities().iter().flat_map(|(a, b)| [*a, *b]));
+    stack.extend(graph.lookups().iter().map(|(wire, _)| *wire));
+    while let Some(id) = stack.pop() {
+        if !live[id] {
+            live[id] = true;
+            stack.extend(graph.operands(id));
+        }
+    }
+    for (id, live) in live.into_iter().enumerate() {
+        if !live && graph.operation(id).is_some() {
+            graph.remove_node(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{random_circuit, CircuitConfig, TestRng};
+
+    #[test]
+    fn test_default_pipeline_keeps_semantics() {
+        let mut rng = TestRng::new(423);
+        let config = CircuitConfig {
+            inputs: 1,
+            private_inputs: 1,
+            constants: 2,
+            max_value: 3,
+            assertions: 2,
+            ..CircuitConfig::default()
+        };
+        for _ in 0..30 {
+            let random = random_circuit(&mut rng, &config);
+            let (optimized, stats) = PassManager::default().run(&random.circuit);
+            assert_eq!(stats.len(), 4);
+            assert!(stats
+                .windows(2)
+                .all(|w| w[0].nodes_after == w[1].nodes_before));
+            let sets = vec![random.inputs, vec![1, 2]];
+            let results = random.circuit.fill_many(&sets);
+            for (result, optimized) in results.iter().zip(optimized.fill_many(&sets)) {
+                assert_eq!(result.outputs, optimized.outputs);
+                assert_eq!(result.satisfied, optimized.satisfied);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cycle through node 1")]
+    fn test_cycles_are_rejected() {
+        struct Loop;
+        impl OptimizationPass for Loop {
+            fn name(&self) -> &str {
+                "loop"
+            }
+            fn run(&self, graph: &mut GraphView) {
+                graph.set_operation(1, Operation::Add(0, 2));
+            }
+        }
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let double = builder.add(x.clone(), x.clone());
+        let _ = builder.add(double, x);
+        let mut manager = PassManager::new();
+        manager.register(Loop);
+        manager.run(&builder);
+    }
+
+    #[test]
+    fn test_folded_assertions_are_kept_when_violated() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let one = builder.constant(1);
+        let two = builder.constant(2);
+        let sum = builder.add(one.clone(), one);
+        builder.assert_equal(sum.clone(), two);
+        builder.assert_equal(sum, x);
+
+        let mut manager = PassManager::new();
+        manager.register(Pass::ConstantFolding);
+        let (folded, stats) = manager.run(&builder);
+        assert_eq!(stats[0].constraints_after, 1);
+        let results = folded.fill_many(&[vec![2], vec![3]]);
+        assert!(results[0].satisfied && !results[1].satisfied);
+    }
+
+    #[test]
+    fn test_lookups_labels_and_groups_carry_over() {
+        let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+        let mut table = builder.range_table(4);
+        let x = builder.init();
+        let zero = builder.constant(0);
+        let unused = builder.add(x.clone(), zero);
+        let shifted = builder.add_const(x, 0);
+        table.lookup(&shifted);
+        builder.label(&unused, "unused");
+        builder.label(&shifted, "shifted");
+        let challenge = builder.init();
+        builder.label(&challenge, "challenge");
+        builder.group("table", |b| table.finish(b, &challenge));
+        builder.disable_group("table");
+
+        let (mut optimized, _) = PassManager::default().run(&builder);
+        assert_eq!(optimized.lookups.len(), 1);
+        let (wire, bits) = optimized.lookups[0];
+        assert_eq!(
+            (optimized.full_graph[wire].operation, bits),
+            (Operation::Input, 4)
+        );
+        assert_eq!(optimized.skipped_groups(), vec!["table"]);
+        let labels: Vec<&String> = optimized.labels.values().collect();
+        assert_eq!(labels, vec!["challenge"]);
+        optimized.fill_nodes(vec![16, 5]);
+        assert!(optimized.check_constraints());
+        optimized.enable_group("table");
+        assert!(!optimized.check_constraints());
+    }
+}
@@ -0,0 +1,247 @@
+//! A toy KZG-style polynomial commitment with mock pairings.
+//!
+//! KZG commits to a polynomial `f` with a single group element `g^f(τ)`, computed from the
+//! powers `g^(τ^i)` of a trusted setup without knowing `τ`. To open the commitment at a point
+//! `z` the prover sends `y = f(z)` and a proof `π = g^q(τ)`, where `q(x) = (f(x) - y) / (x - z)`
+//! is a polynomial exactly when `f(z) = y`. The verifier checks `f(τ) - y = q(τ) (τ - z)` "in
+//! the exponent" with a pairing:
+//!
+//! `e(C / g^y, g) = e(π, g^τ / g^z)`.
+//!
+//! Real KZG uses elliptic curve groups with a bilinear pairing. This module works in the
+//! subgroup of quadratic residues modulo the safe prime `P = 2q + 1`, whose order `q` is about
+//! `2^24`. It has no pairing, so `pair` takes discrete logarithms by baby-step giant-step and
+//! multiplies them. That is only possible because the group is tiny, and it is exactly what
+//! makes the scheme insecure: anyone can recover `τ` from the setup. The API has the shape of
+//! KZG so the role of polynomial commitments in a proof system can be traced.
+//!
+//! ```
+//! use arithmetic_circuit::pcs::Srs;
+//! use arithmetic_circuit::polynomial::Polynomial;
+//!
+//! let srs = Srs::setup(4, 42);
+//! let polynomial = Polynomial::new(vec![7, 0, 3, 1]);
+//! let commitment = srs.commit(&polynomial);
+//!
+//! let opening = srs.open(&polynomial, 5);
+//! assert_eq!(opening.value, 7 + 3 * 25 + 125);
+//! assert!(srs.verify(&commitment, &opening));
+//! ```
+use crate::field::Field;
+use crate::hash::mix;
+use crate::polynomial::Polynomial;
+use std::collections::HashMap;
+
+/// The safe prime `P = 2q + 1` the group lives in.
+const MODULUS: u64 = 33_554_519;
+/// The prime order `q` of the group, also the modulus of the scalar field.
+const ORDER: u64 = 16_777_259;
+/// A generator of the quadratic residues modulo `MODULUS`.
+const GENERATOR: u64 = 4;
+
+/// A commitment `g^f(τ)` to a polynomial.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Commitment(pub u64);
+
+/// A claimed evaluation `f(point) = value` with its proof `g^q(τ)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opening {
+    pub point: u64,
+    pub value: u64,
+    pub proof: u64,
+}
+
+/// The structured reference string: the powers `g^(τ^i)` of a secret `τ`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Srs {
+    powers: Vec<u64>,
+}
+
+impl Srs {
+    /// Runs the trusted setup for polynomials of degree up to `max_degree`.
+    ///
+    /// `τ` is derived from `seed` and forgotten afterwards, as it would be in a real ceremony.
+    pub fn setup(max_degree: usize, seed: u64) -> Self {
+        let field = Srs::field();
+        let tau = (1..)
+            .map(|attempt| field.reduce(mix(seed ^ mix(attempt))))
+            .find(|tau| *tau > 1)
+            .unwrap();
+        let mut powers = Vec::with_capacity(max_degree + 1);
+        let mut tau_power = 1;
+        for _ in 0..=max_degree {
+            powers.push(exp(GENERATOR, tau_power));
+            tau_power = field.mul(tau_power, tau);
+        }
+        Srs { powers }
+    }
+
+    /// Returns the scalar field: polynomials, points and values live in `F_q`.
+    pub fn field() -> Field {
+        Field::new(ORDER)
+    }
+
+    pub fn max_degree(&self) -> usize {
+        self.powers.len() - 1
+    }
+
+    /// Commits to a polynomial by computing `Π (g^(τ^i))^(c_i) = g^f(τ)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the degree of the polynomial exceeds the setup.
+    pub fn commit(&self, polynomial: &Polynomial) -> Commitment {
+        if polynomial.degree() > self.max_degree() {
+            panic!("polynomial degree exceeds the size of the setup");
+        }
+        Commitment(
+            polynomial
+                .coefficients()
+                .iter()
+                .zip(&self.powers)
+                .fold(1, |product, (coefficient, power)| {
+                    mul(product, exp(*power, *coefficient))
+                }),
+        )
+    }
+
+    /// Evaluates the polynomial at `point` and proves the evaluation.
+    pub fn open(&self, polynomial: &Polynomial, point: u64) -> Opening {
+        let field = Srs::field();
+        let point = field.reduce(point);
+        let value = polynomial.evaluate(point, field);
+        let (quotient, _) = polynomial
+            .sub(&Polynomial::new(vec![value]), field)
+            .div_rem(&Polynomial::linear(point, field), field);
+        Opening {
+            point,
+            value,
+            proof: self.commit(&quotient).0,
+        }
+    }
+
+    /// Checks `e(C / g^y, g) = e(π, g^τ / g^z)`.
+    ///
+    /// Returns `false` without pairing if the commitment or the proof is not an element of the
+    /// group, or the point or the value is not an element of the scalar field.
+    pub fn verify(&self, commitment: &Commitment, opening: &Opening) -> bool {
+        if !is_element(commitment.0)
+            || !is_element(opening.proof)
+            || opening.value >= ORDER
+            || opening.point >= ORDER
+        {
+            return false;
+        }
+        let Some(tau) = self.powers.get(1) else {
+            // a setup for constants cannot open anything but constants
+            return exp(GENERATOR, opening.value) == commitment.0;
+        };
+        let shifted = mul(commitment.0, inverse(exp(GENERATOR, opening.value)));
+        let tau_minus_point = mul(*tau, inverse(exp(GENERATOR, opening.point)));
+        pair(shifted, GENERATOR) == pair(opening.proof, tau_minus_point)
+    }
+}
+
+/// The mock pairing `e(g^a, g^b) = g^(ab)`, computed by taking discrete logarithms.
+pub fn pair(a: u64, b: u64) -> u64 {
+    let field = Srs::field();
+    exp(GENERATOR, field.mul(discrete_log(a), discrete_log(b)))
+}
+
+/// Finds `x` with `g^x = element` by baby-step giant-step.
+///
+/// # Panics
+///
+/// Panics if `element` is not in the group.
+fn discrete_log(element: u64) -> u64 {
+    let steps = (ORDER as f64).sqrt().ceil() as u64;
+    let mut baby: HashMap<u64, u64> = HashMap::with_capacity(steps as usize);
+    let mut power = 1;
+    for j in 0..steps {
+        baby.entry(power).or_insert(j);
+        power = mul(power, GENERATOR);
+    }
+    // g^(-steps)
+    let giant = inverse(power);
+    let mut current = element % MODULUS;
+    for i in 0..steps {
+        if let Some(j) = baby.get(&current) {
+            return (i * steps + j) % ORDER;
+        }
+        current = mul(current, giant);
+    }
+    panic!("{} is not an element of the group", element);
+}
+
+/// Returns `true` if `x` is a quadratic residue modulo `MODULUS`, an element of the group.
+fn is_element(x: u64) -> bool {
+    !x.is_multiple_of(MODULUS) && exp(x, ORDER) == 1
+}
+
+fn mul(a: u64, b: u64) -> u64 {
+    a * b % MODULUS
+}
+
+fn exp(mut base: u64, mut exponent: u64) -> u64 {
+    let mut result = 1;
+    base %= MODULUS;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul(result, base);
+        }
+        base = mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+fn inverse(a: u64) -> u64 {
+    exp(a, MODULUS - 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairing_is_bilinear() {
+        let (a, b) = (exp(GENERATOR, 1234), exp(GENERATOR, 5678));
+        assert_eq!(pair(a, b), exp(GENERATOR, 1234 * 5678));
+        assert_eq!(pair(mul(a, a), b), mul(pair(a, b), pair(a, b)));
+    }
+
+    #[test]
+    fn test_wrong_openings_are_rejected() {
+        let srs = Srs::setup(3, 7);
+        let polynomial = Polynomial::new(vec![1, 2, 3]);
+        let commitment = srs.commit(&polynomial);
+        let opening = srs.open(&polynomial, 10);
+        assert!(srs.verify(&commitment, &opening));
+
+        let wrong_value = Opening {
+            value: opening.value + 1,
+            ..opening
+        };
+        assert!(!srs.verify(&commitment, &wrong_value));
+        let other = srs.commit(&Polynomial::new(vec![1, 2, 4]));
+        assert!(!srs.verify(&other, &opening));
+    }
+
+    #[test]
+    fn test_garbage_openings_are_rejected() {
+        let srs = Srs::setup(3, 7);
+        let polynomial = Polynomial::new(vec![1, 2, 3]);
+        let commitment = srs.commit(&polynomial);
+        let opening = srs.open(&polynomial, 10);
+        // 0, a multiple of the modulus and a non-residue are not in the group
+        for proof in [0, MODULUS, MODULUS - 1] {
+            assert!(!srs.verify(&commitment, &Opening { proof, ..opening }));
+            assert!(!srs.verify(&Commitment(proof), &opening));
+        }
+        let unreduced = Opening {
+            value: opening.value + ORDER,
+            ..opening
+        };
+        assert!(!srs.verify(&commitment, &unreduced));
+    }
+}
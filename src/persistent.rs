@@ -0,0 +1,235 @@
+//! An immutable builder whose versions share structure.
+//!
+//! Every operation on a `PersistentBuilder` leaves it untouched and returns a new version with
+//! the operation appended. The versions share everything they have in common, so keeping a
+//! version around costs one pointer and extending it a single allocation, however large the
+//! circuit. Exploring alternatives is then a matter of keeping the version to come back to,
+//! rather than cloning a `Builder` before every attempt.
+//!
+//! A version records operations like a `ReplayLog` and becomes an ordinary circuit with
+//! `to_builder`. Nodes are referred to by id.
+//!
+//! ```
+//! use arithmetic_circuit::persistent::PersistentBuilder;
+//!
+//! let base = PersistentBuilder::new();
+//! let (base, x) = base.init();
+//! let (base, square) = base.mul(x, x);
+//!
+//! // two ways to continue from the same base
+//! let (plus, sum) = base.add(square, x);
+//! let plus = plus.output(sum);
+//! let (times, product) = base.mul(square, x);
+//! let times = times.output(product);
+//!
+//! assert_eq!(plus.to_builder().evaluate(vec![3]), vec![12]);
+//! assert_eq!(times.to_builder().evaluate(vec![3]), vec![27]);
+//! assert_eq!(base.node_count(), 2);
+//! ```
+use crate::field::Field;
+use crate::replay::{LoggedOp, ReplayLog};
+use crate::{Builder, NodeId};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// One operation and the version it extends.
+struct Link {
+    op: LoggedOp,
+    parent: Option<Rc<Link>>,
+}
+
+impl Drop for Link {
+    /// Drops a chain of links no other version shares iteratively, since dropping it
+    /// recursively would overflow the stack for long chains.
+    fn drop(&mut self) {
+        let mut parent = self.parent.take();
+        while let Some(link) = parent {
+            parent = match Rc::try_unwrap(link) {
+                Ok(mut link) => link.parent.take(),
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+/// A version of a circuit, see the module documentation. Cloning it is cheap.
+#[derive(Clone, Default)]
+pub struct PersistentBuilder {
+    field: Option<Field>,
+    tip: Option<Rc<Link>>,
+    nodes: usize,
+    constraints: usize,
+}
+
+impl PersistentBuilder {
+    /// Creates an empty circuit over plain `u64` arithmetic.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty circuit computing in `field`.
+    pub fn new_in_field(field: Field) -> Self {
+        PersistentBuilder {
+            field: Some(field),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the number of nodes of this version.
+    pub fn node_count(&self) -> usize {
+        self.nodes
+    }
+
+    /// Returns the number of constraints of this version.
+    pub fn constraint_count(&self) -> usize {
+        self.constraints
+    }
+
+    /// Returns `true` if both versions are the same, not merely equal.
+    pub fn same_version(&self, other: &PersistentBuilder) -> bool {
+        match (&self.tip, &other.tip) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => self.field == other.field,
+            _ => false,
+        }
+    }
+
+    /// Returns a new version with an input.
+    pub fn init(&self) -> (Self, NodeId) {
+        self.push_node(LoggedOp::Input { private: false }, 0)
+    }
+
+    /// Returns a new version with a private input.
+    pub fn init_private(&self) -> (Self, NodeId) {
+        self.push_node(LoggedOp::Input { private: true }, 0)
+    }
+
+    /// Returns a new version with a constant.
+    pub fn constant(&self, value: u64) -> (Self, NodeId) {
+        self.push_node(LoggedOp::Constant(value), 0)
+    }
+
+    /// Returns a new version with the sum of two nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node is not part of this version.
+    pub fn add(&self, a: NodeId, b: NodeId) -> (Self, NodeId) {
+        self.check(&[a, b]);
+        self.push_node(LoggedOp::Add(a, b), 1)
+    }
+
+    /// Returns a new version with the product of two nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node is not part of this version.
+    pub fn mul(&self, a: NodeId, b: NodeId) -> (Self, NodeId) {
+        self.check(&[a, b]);
+        self.push_node(LoggedOp::Mul(a, b), 1)
+    }
+
+    /// Returns a new version with a hint, see `Builder::hint`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node is not part of this version.
+    pub fn hint(
+        &self,
+        inputs: &[NodeId],
+        compute: impl Fn(&[u64]) -> u64 + Send + Sync + 'static,
+    ) -> (Self, NodeId) {
+        self.check(inputs);
+        let op = LoggedOp::Hint {
+            inputs: inputs.to_vec(),
+            compute: Arc::new(compute),
+        };
+        self.push_node(op, 0)
+    }
+
+    /// Returns a new version asserting that two nodes are equal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node is not part of this version.
+    pub fn assert_equal(&self, a: NodeId, b: NodeId) -> Self {
+        self.check(&[a, b]);
+        self.push(LoggedOp::AssertEqual(a, b), 0, 1)
+    }
+
+    /// Returns a new version with `node` as an output.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is not part of this version.
+    pub fn output(&self, node: NodeId) -> Self {
+        self.check(&[node]);
+        self.push(LoggedOp::Output(node), 0, 0)
+    }
+
+    /// Builds the circuit of this version.
+    pub fn to_builder(&self) -> Builder {
+        let mut ops = vec![];
+        let mut link = self.tip.as_deref();
+        while let Some(current) = link {
+            ops.push(current.op.clone());
+            link = current.parent.as_deref();
+        }
+        ops.reverse();
+        Builder::replay(&ReplayLog::from_ops(self.field, ops))
+    }
+
+    fn check(&self, nodes: &[NodeId]) {
+        if let Some(node) = nodes.iter().find(|node| **node >= self.nodes) {
+            panic!("node {} is not part of this version", node);
+        }
+    }
+
+    fn push_node(&self, op: LoggedOp, constraints: usize) -> (Self, NodeId) {
+        (self.push(op, 1, constraints), self.nodes)
+    }
+
+    fn push(&self, op: LoggedOp, nodes: usize, constraints: usize) -> Self {
+        PersistentBuilder {
+            field: self.field,
+            tip: Some(Rc::new(Link {
+                op,
+                parent: self.tip.clone(),
+            })),
+            nodes: self.nodes + nodes,
+            constraints: self.constraints + constraints,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_are_independent() {
+        let (base, x) = PersistentBuilder::new_in_field(Field::new(13)).init();
+        let (with_hint, half) = base.hint(&[x], |values| values[0] / 2);
+        let (with_hint, double) = with_hint.add(half, half);
+        let checked = with_hint.assert_equal(double, x);
+
+        assert_eq!((base.node_count(), base.constraint_count()), (1, 0));
+        assert_eq!((checked.node_count(), checked.constraint_count()), (3, 2));
+        assert!(!checked.same_version(&with_hint));
+        assert!(checked.clone().same_version(&checked));
+
+        let mut circuit = checked.to_builder();
+        circuit.fill_nodes(vec![8]);
+        assert!(circuit.check_constraints());
+        assert_eq!(base.to_builder().constraints().count(), 0);
+    }
+
+    #[test]
+    fn test_long_chains_drop() {
+        let (mut version, x) = PersistentBuilder::new().init();
+        for _ in 0..200_000 {
+            version = version.add(x, x).0;
+        }
+        assert_eq!(version.node_count(), 200_001);
+    }
+}
@@ -0,0 +1,353 @@
+//! Symbolic expressions and their sum-of-monomials normal form.
+//!
+//! `Builder::expression` extracts what a node computes as an expression over the inputs of the
+//! circuit, and `normalize` multiplies it out into a sum of monomials with collected
+//! coefficients, terms sorted by decreasing degree and then lexicographically. Two expressions
+//! with the same normal form compute the same values, which `Builder::equivalent_outputs` uses
+//! to compare circuits built in different ways, and the normal form reads like the polynomial a
+//! textbook would write, in plain text or as LaTeX.
+//!
+//! Hints are opaque: they appear as variables of their own, named after their node. Over a field,
+//! coefficients are reduced, so equal normal forms mean equal values, but not conversely:
+//! `x^p` and `x` agree on every element of the field. Without a field, coefficients wrap around
+//! at `2^64`, which gives the same values as the circuit wherever it does not overflow.
+//! Multiplying out can take time exponential in the multiplicative depth.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//!
+//! let mut builder = Builder::new_in_field(Field::new(101));
+//! let x = builder.init();
+//! let y = builder.init();
+//! let sum = builder.add(x.clone(), y.clone());
+//! let square = builder.mul(sum.clone(), sum);
+//! let three = builder.constant(3);
+//! let result = builder.add(square, three);
+//!
+//! let normal = builder.normal_form(&result);
+//! assert_eq!(normal.to_string(), "x0^2 + 2*x0*x1 + x1^2 + 3");
+//! assert_eq!(normal.to_latex(), "x_{0}^{2} + 2 x_{0} x_{1} + x_{1}^{2} + 3");
+//! assert_eq!(normal.degree(), 2);
+//! ```
+use crate::enums::Operation;
+use crate::field::Field;
+use crate::{Builder, Node, NodeId};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+/// A leaf of an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Variable {
+    /// The input at this position among the inputs of the circuit.
+    Input(usize),
+    /// The hint with this node id.
+    Hint(NodeId),
+}
+
+impl Display for Variable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Variable::Input(position) => write!(f, "x{}", position),
+            Variable::Hint(id) => write!(f, "h{}", id),
+        }
+    }
+}
+
+/// What a node computes. Shared subexpressions are shared `Rc`s, so extracting the expression of
+/// a node takes time linear in the size of the circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Var(Variable),
+    Const(u64),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mul(Rc<Expr>, Rc<Expr>),
+}
+
+/// A product of variables, each with a positive exponent, sorted by variable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Monomial {
+    powers: Vec<(Variable, u32)>,
+}
+
+impl Monomial {
+    /// Returns the variables with their exponents, sorted by variable.
+    pub fn powers(&self) -> &[(Variable, u32)] {
+        &self.powers
+    }
+
+    pub fn degree(&self) -> u32 {
+        self.powers.iter().map(|(_, exponent)| exponent).sum()
+    }
+
+    fn mul(&self, other: &Monomial) -> Monomial {
+        let mut powers: BTreeMap<Variable, u32> = self.powers.iter().copied().collect();
+        for (variable, exponent) in &other.powers {
+            *powers.entry(*variable).or_default() += exponent;
+        }
+        Monomial {
+            powers: powers.into_iter().collect(),
+        }
+    }
+}
+
+impl Ord for Monomial {
+    /// Graded lexicographic order: by degree, then by the exponent of the first variable where
+    /// the two differ. Normal forms list their terms from the largest monomial down.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.degree().cmp(&other.degree()).then_with(|| {
+            let (mut a, mut b) = (self.powers.iter(), other.powers.iter());
+            loop {
+                match (a.next(), b.next()) {
+                    (Some(x), Some(y)) if x == y => continue,
+                    // the monomial with the smaller first variable has more of it
+                    (Some(x), Some(y)) if x.0 != y.0 => return y.0.cmp(&x.0),
+                    (Some(x), Some(y)) => return x.1.cmp(&y.1),
+                    (x, y) => return x.is_some().cmp(&y.is_some()),
+                }
+            }
+        })
+    }
+}
+
+impl PartialOrd for Monomial {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A polynomial as a sum of monomials with nonzero coefficients, largest monomial first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SumOfMonomials {
+    terms: Vec<(Monomial, u64)>,
+}
+
+impl SumOfMonomials {
+    /// Returns the monomials with their coefficients, largest monomial first.
+    pub fn terms(&self) -> &[(Monomial, u64)] {
+        &self.terms
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Returns the largest degree of a term, `0` for constants and the zero polynomial.
+    pub fn degree(&self) -> u32 {
+        self.terms
+            .first()
+            .map_or(0, |(monomial, _)| monomial.degree())
+    }
+
+    /// Writes the polynomial as LaTeX, with `x_{i}` for inputs and `h_{i}` for hints.
+    pub fn to_latex(&self) -> String {
+        self.render(
+            |variable| match variable {
+                Variable::Input(position) => format!("x_{{{}}}", position),
+                Variable::Hint(id) => format!("h_{{{}}}", id),
+            },
+            |base, exponent| format!("{}^{{{}}}", base, exponent),
+            " ",
+        )
+    }
+
+    fn render(
+        &self,
+        name: impl Fn(&Variable) -> String,
+        power: impl Fn(&str, u32) -> String,
+        times: &str,
+    ) -> String {
+        if self.terms.is_empty() {
+            return "0".to_string();
+        }
+        let terms: Vec<String> = self
+            .terms
+            .iter()
+            .map(|(monomial, coefficient)| {
+                let mut factors: Vec<String> = monomial
+                    .powers
+                    .iter()
+                    .map(|(variable, exponent)| match exponent {
+                        1 => name(variable),
+                        _ => power(&name(variable), *exponent),
+                    })
+                    .collect();
+                if *coefficient != 1 || factors.is_empty() {
+                    factors.insert(0, coefficient.to_string());
+                }
+                factors.join(times)
+            })
+            .collect();
+        terms.join(" + ")
+    }
+}
+
+impl Display for SumOfMonomials {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let text = self.render(
+            Variable::to_string,
+            |base, exponent| format!("{}^{}", base, exponent),
+            "*",
+        );
+        write!(f, "{}", text)
+    }
+}
+
+/// Terms under construction, without zero coefficients.
+type Terms = BTreeMap<Monomial, u64>;
+
+/// Multiplies out `expr` into its normal form, see the module documentation.
+///
+/// # Arguments
+///
+/// * `expr`: The expression, usually from `Builder::expression`.
+/// * `field`: The field to compute the coefficients in, `None` to wrap around at `2^64`.
+pub fn normalize(expr: &Expr, field: Option<Field>) -> SumOfMonomials {
+    let terms = normalize_shared(expr, field, &mut HashMap::new());
+    let mut terms: Vec<(Monomial, u64)> = terms.as_ref().clone().into_iter().collect();
+    terms.reverse();
+    SumOfMonomials { terms }
+}
+
+/// Normalizes every shared subexpression once, keyed by its address.
+fn normalize_shared(
+    expr: &Expr,
+    field: Option<Field>,
+    done: &mut HashMap<*const Expr, Rc<Terms>>,
+) -> Rc<Terms> {
+    if let Some(terms) = done.get(&(expr as *const Expr)) {
+        return terms.clone();
+    }
+    let reduce = |value: u64| field.map_or(value, |field| field.reduce(value));
+    let add = |a: u64, b: u64| field.map_or(a.wrapping_add(b), |field| field.add(a, b));
+    let mul = |a: u64, b: u64| field.map_or(a.wrapping_mul(b), |field| field.mul(a, b));
+    let mut terms = Terms::new();
+    let mut accumulate = |monomial: Monomial, coefficient: u64| {
+        let entry = terms.entry(monomial).or_insert(0);
+        *entry = add(*entry, coefficient);
+    };
+    match expr {
+        Expr::Var(variable) => accumulate(
+            Monomial {
+                powers: vec![(*variable, 1)],
+            },
+            1,
+        ),
+        Expr::Const(value) => accumulate(Monomial::default(), reduce(*value)),
+        Expr::Add(a, b) => {
+            let (a, b) = (
+                normalize_shared(a, field, done),
+                normalize_shared(b, field, done),
+            );
+            a.iter()
+                .chain(b.iter())
+                .for_each(|(monomial, coefficient)| accumulate(monomial.clone(), *coefficient));
+        }
+        Expr::Mul(a, b) => {
+            let (a, b) = (
+                normalize_shared(a, field, done),
+                normalize_shared(b, field, done),
+            );
+            for (x, c) in a.iter() {
+                for (y, d) in b.iter() {
+                    accumulate(x.mul(y), mul(*c, *d));
+                }
+            }
+        }
+    }
+    terms.retain(|_, coefficient| *coefficient != 0);
+    let terms = Rc::new(terms);
+    done.insert(expr as *const Expr, terms.clone());
+    terms
+}
+
+impl Builder {
+    /// Returns what `node` computes as an expression over the inputs and hints.
+    pub fn expression(&self, node: &Node) -> Rc<Expr> {
+        let mut positions = HashMap::new();
+        for (position, id) in self.inputs.iter().enumerate() {
+            positions.insert(*id, position);
+        }
+        let mut exprs: Vec<Rc<Expr>> = Vec::with_capacity(node.id + 1);
+        for current in &self.full_graph[..=node.id] {
+            let expr = match current.operation {
+                Operation::Input => Expr::Var(Variable::Input(positions[&current.id])),
+                Operation::Constant(value) => Expr::Const(value),
+                Operation::Add(a, b) => Expr::Add(exprs[a].clone(), exprs[b].clone()),
+                Operation::Mul(a, b) => Expr::Mul(exprs[a].clone(), exprs[b].clone()),
+                Operation::Hint(_) => Expr::Var(Variable::Hint(current.id)),
+            };
+            exprs.push(Rc::new(expr));
+        }
+        exprs.pop().unwrap()
+    }
+
+    /// Returns the normal form of what `node` computes, see the `poly` module.
+    pub fn normal_form(&self, node: &Node) -> SumOfMonomials {
+        normalize(&self.expression(node), self.field)
+    }
+
+    /// Returns `true` if both circuits compute in the same field, take as many inputs and have
+    /// outputs with the same normal forms, so they compute the same outputs.
+    ///
+    /// A `false` result does not prove the circuits different, see the `poly` module.
+    pub fn equivalent_outputs(&self, other: &Builder) -> bool {
+        self.field == other.field
+            && self.inputs.len() == other.inputs.len()
+            && self.outputs.len() == other.outputs.len()
+            && self.outputs.iter().zip(&other.outputs).all(|(a, b)| {
+                self.normal_form(&self.full_graph[*a]) == other.normal_form(&other.full_graph[*b])
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equivalent_circuits_and_cancellation() {
+        let field = Field::new(7);
+        // (x + y) * (x + 6y) = x^2 + 7xy + 6y^2 = x^2 + 6y^2 mod 7
+        let mut factored = Builder::new_in_field(field);
+        let (x, y) = (factored.init(), factored.init());
+        let six = factored.constant(6);
+        let six_y = factored.mul(six, y.clone());
+        let left = factored.add(x.clone(), y);
+        let right = factored.add(x, six_y);
+        let product = factored.mul(left, right);
+        factored.output(product);
+
+        let mut expanded = Builder::new_in_field(field);
+        let (x, y) = (expanded.init(), expanded.init());
+        let x_squared = expanded.mul(x.clone(), x);
+        let y_squared = expanded.mul(y.clone(), y);
+        let scaled = expanded.mul_const(y_squared, 13);
+        let sum = expanded.add(scaled, x_squared);
+        expanded.output(sum);
+
+        assert!(factored.equivalent_outputs(&expanded));
+        assert_eq!(
+            expanded.normal_form(&expanded.outputs()[0]).to_string(),
+            "x0^2 + 6*x1^2"
+        );
+
+        let zero = expanded.constant(7);
+        assert!(expanded.normal_form(&zero).is_zero());
+        let half = expanded.hint(&[&zero], |values| values[0] / 2);
+        let doubled = expanded.add(half.clone(), half);
+        assert_eq!(expanded.normal_form(&doubled).to_latex(), "2 h_{8}");
+    }
+
+    #[test]
+    fn test_repeated_squaring_is_shared() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let power = (0..20).fold(x, |acc, _| builder.mul(acc.clone(), acc));
+        let normal = builder.normal_form(&power);
+        assert_eq!(normal.terms().len(), 1);
+        assert_eq!(normal.degree() as u64, 1 << 20);
+    }
+}
@@ -0,0 +1,203 @@
+//! Univariate polynomials over a prime field.
+//!
+//! Coefficients are stored lowest degree first and every operation takes the `Field` it works
+//! in, mirroring how `Field` itself passes plain `u64` elements around.
+//!
+//! ```
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::polynomial::Polynomial;
+//!
+//! let field = Field::new(17);
+//! // the line through (1, 2) and (2, 4)
+//! let line = Polynomial::interpolate(&[1, 2], &[2, 4], field);
+//! assert_eq!(line, Polynomial::new(vec![0, 2]));
+//! assert_eq!(line.evaluate(5, field), 10);
+//! ```
+use crate::field::Field;
+
+/// A polynomial with coefficients in a prime field, lowest degree first.
+///
+/// Trailing zero coefficients are always trimmed, so the zero polynomial has no coefficients.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Polynomial {
+    coefficients: Vec<u64>,
+}
+
+impl Polynomial {
+    /// Creates a polynomial from its coefficients, lowest degree first.
+    pub fn new(mut coefficients: Vec<u64>) -> Self {
+        while coefficients.last() == Some(&0) {
+            coefficients.pop();
+        }
+        Polynomial { coefficients }
+    }
+
+    pub fn zero() -> Self {
+        Polynomial::default()
+    }
+
+    /// Creates the polynomial `x - root`.
+    pub fn linear(root: u64, field: Field) -> Self {
+        Polynomial::new(vec![field.neg(root), 1])
+    }
+
+    pub fn coefficients(&self) -> &[u64] {
+        &self.coefficients
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    /// Returns the degree of the polynomial. The zero polynomial is given degree 0.
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    /// Evaluates the polynomial at `x` using Horner's rule.
+    pub fn evaluate(&self, x: u64, field: Field) -> u64 {
+        self.coefficients.iter().rev().fold(0, |acc, coefficient| {
+            field.add(field.mul(acc, x), *coefficient)
+        })
+    }
+
+    pub fn add(&self, other: &Polynomial, field: Field) -> Polynomial {
+        let length = self.coefficients.len().max(other.coefficients.len());
+        Polynomial::new(
+            (0..length)
+                .map(|i| field.add(self.coefficient(i), other.coefficient(i)))
+                .collect(),
+        )
+    }
+
+    pub fn sub(&self, other: &Polynomial, field: Field) -> Polynomial {
+        self.add(&other.scale(field.neg(1), field), field)
+    }
+
+    /// Multiplies every coefficient by `factor`.
+    pub fn scale(&self, factor: u64, field: Field) -> Polynomial {
+        Polynomial::new(
+            self.coefficients
+                .iter()
+                .map(|coefficient| field.mul(*coefficient, factor))
+                .collect(),
+        )
+    }
+
+    pub fn mul(&self, other: &Polynomial, field: Field) -> Polynomial {
+        if self.is_zero() || other.is_zero() {
+            return Polynomial::zero();
+        }
+        let mut coefficients = vec![0; self.coefficients.len() + other.coefficients.len() - 1];
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in other.coefficients.iter().enumerate() {
+                coefficients[i + j] = field.add(coefficients[i + j], field.mul(*a, *b));
+            }
+        }
+        Polynomial::new(coefficients)
+    }
+
+    /// Divides by `divisor` using long division.
+    ///
+    /// # Returns
+    ///
+    /// The quotient and the remainder, whose degree is lower than the divisor's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divisor` is the zero polynomial.
+    pub fn div_rem(&self, divisor: &Polynomial, field: Field) -> (Polynomial, Polynomial) {
+        if divisor.is_zero() {
+            panic!("division by the zero polynomial");
+        }
+        let mut remainder = self.coefficients.clone();
+        let divisor_degree = divisor.degree();
+        if remainder.len() <= divisor_degree {
+            return (Polynomial::zero(), self.clone());
+        }
+        let lead_inverse = field.inv(divisor.coefficients[divisor_degree]).unwrap();
+        let mut quotient = vec![0; remainder.len() - divisor_degree];
+        for shift in (0..quotient.len()).rev() {
+            let factor = field.mul(remainder[shift + divisor_degree], lead_inverse);
+            quotient[shift] = factor;
+            for (i, coefficient) in divisor.coefficients.iter().enumerate() {
+                remainder[shift + i] =
+                    field.sub(remainder[shift + i], field.mul(factor, *coefficient));
+            }
+        }
+        (Polynomial::new(quotient), Polynomial::new(remainder))
+    }
+
+    /// Returns the unique polynomial of degree below `xs.len()` going through every `(xs[i], ys[i])`,
+    /// using Lagrange interpolation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lengths differ or the points in `xs` are not distinct in the field.
+    pub fn interpolate(xs: &[u64], ys: &[u64], field: Field) -> Polynomial {
+        if xs.len() != ys.len() {
+            panic!("interpolation needs as many values as points");
+        }
+        let mut result = Polynomial::zero();
+        for (i, (x_i, y_i)) in xs.iter().zip(ys).enumerate() {
+            if field.reduce(*y_i) == 0 {
+                continue;
+            }
+            let mut basis = Polynomial::new(vec![1]);
+            let mut denominator = 1;
+            for (j, x_j) in xs.iter().enumerate() {
+                if i != j {
+                    basis = basis.mul(&Polynomial::linear(*x_j, field), field);
+                    denominator = field.mul(denominator, field.sub(*x_i, *x_j));
+                }
+            }
+            let factor = field
+                .div(*y_i, denominator)
+                .expect("interpolation points must be distinct");
+            result = result.add(&basis.scale(factor, field), field);
+        }
+        result
+    }
+
+    /// Returns the polynomial `(x - r_1)(x - r_2)…` vanishing exactly on `roots`.
+    pub fn vanishing(roots: &[u64], field: Field) -> Polynomial {
+        roots.iter().fold(Polynomial::new(vec![1]), |acc, root| {
+            acc.mul(&Polynomial::linear(*root, field), field)
+        })
+    }
+
+    fn coefficient(&self, i: usize) -> u64 {
+        self.coefficients.get(i).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_div_rem() {
+        let field = Field::new(101);
+        let a = Polynomial::new(vec![3, 0, 5, 1]);
+        let b = Polynomial::new(vec![7, 2]);
+        let (quotient, remainder) = a.div_rem(&b, field);
+        assert!(remainder.degree() < b.degree() || remainder.is_zero());
+        assert_eq!(quotient.mul(&b, field).add(&remainder, field), a);
+    }
+
+    #[test]
+    fn test_interpolate_and_vanishing() {
+        let field = Field::new(101);
+        let xs = [1, 2, 3, 4];
+        let ys = [9, 0, 100, 42];
+        let p = Polynomial::interpolate(&xs, &ys, field);
+        assert!(p.degree() < 4);
+        for (x, y) in xs.iter().zip(ys) {
+            assert_eq!(p.evaluate(*x, field), y);
+        }
+        let z = Polynomial::vanishing(&xs, field);
+        assert_eq!(z.degree(), 4);
+        assert!(xs.iter().all(|x| z.evaluate(*x, field) == 0));
+        assert_ne!(z.evaluate(5, field), 0);
+    }
+}
@@ -0,0 +1,387 @@
+//! Per-scope timing of evaluations.
+//!
+//! `Builder::profile_fill` evaluates the circuit like `fill_nodes` while timing how long the nodes
+//! of every scope take, so the gadget dominating the evaluation of a large circuit can be found.
+//! Scopes are entered with `Builder::scope` or `Builder::push_scope`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let squares = builder.scope("squares", |b| {
+//!     (0..10).fold(x.clone(), |acc, _| b.mul(acc.clone(), acc))
+//! });
+//! let _ = builder.add(squares, x);
+//!
+//! let report = builder.profile_fill(vec![1]);
+//! let squares = report.scopes.iter().find(|s| s.scope == "squares").unwrap();
+//! assert_eq!(squares.nodes, 10);
+//! assert!(squares.time <= report.total);
+//! // one row per scope between the header and the total, slowest scope first
+//! let printed = report.to_string();
+//! let rows: Vec<&str> = printed.lines().collect();
+//! assert_eq!(rows.len(), 4);
+//! assert!(rows[0].starts_with("scope") && rows[3].starts_with("total"));
+//! assert!(rows.iter().any(|row| row.starts_with("<root>")));
+//! ```
+//!
+//! `Builder::profile_breakdown` combines the timing with the gates and cost of every scope, see
+//! the `cost` module, into a tree following the nesting of scopes. It prints as an indented
+//! table of totals, and `ScopeBreakdown::folded` renders it in the folded stack format read by
+//! flamegraph tools such as `inferno-flamegraph` or `flamegraph.pl`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::cost::CostModel;
+//! use arithmetic_circuit::profile::FlameMetric;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let y = builder.scope("mimc", |b| {
+//!     (0..2).fold(x.clone(), |acc, _| b.scope("round", |b| b.mul(acc.clone(), acc)))
+//! });
+//! builder.output(y);
+//!
+//! let tree = builder.profile_breakdown(vec![3], &CostModel::default());
+//! assert_eq!(tree.children[0].name, "mimc");
+//! assert_eq!(tree.total_cost(), 6);
+//! assert_eq!(tree.folded(FlameMetric::Gates), "<root>;mimc;round 2\n");
+//! // the last column is the evaluation time, which varies between runs
+//! let printed = tree.to_string();
+//! let rows: Vec<&str> = printed
+//!     .lines()
+//!     .map(|row| row.rsplit_once(' ').unwrap().0.trim_end())
+//!     .collect();
+//! assert_eq!(
+//!     rows,
+//!     vec![
+//!         "scope                             nodes    gates     cost",
+//!         "<root>                                3        2        6",
+//!         "  mimc                                2        2        6",
+//!         "    round                             2        2        6",
+//!     ]
+//! );
+//! ```
+use crate::cost::CostModel;
+use crate::Builder;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+use tracing::info_span;
+
+/// The time spent evaluating the nodes of a single scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeProfile {
+    /// The scope path, `""` for the root scope.
+    pub scope: String,
+    pub nodes: usize,
+    pub time: Duration,
+}
+
+/// The breakdown of an evaluation per scope, slowest scope first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileReport {
+    pub total: Duration,
+    pub scopes: Vec<ScopeProfile>,
+}
+
+/// The measurements of one scope and of the scopes nested in it, see `Builder::profile_breakdown`.
+///
+/// The fields count what was created in the scope itself; the `total_` methods include the
+/// nested scopes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeBreakdown {
+    /// The last component of the path, `"<root>"` for the root scope.
+    pub name: String,
+    /// The scope path, `""` for the root scope.
+    pub path: String,
+    pub nodes: usize,
+    /// The constraints attributed to the scope.
+    pub gates: usize,
+    pub cost: u64,
+    pub time: Duration,
+    /// The nested scopes, in order of creation.
+    pub children: Vec<ScopeBreakdown>,
+}
+
+/// The quantity a flamegraph of a `ScopeBreakdown` measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlameMetric {
+    Nodes,
+    Gates,
+    Cost,
+    /// Evaluation time in nanoseconds.
+    Time,
+}
+
+impl ScopeBreakdown {
+    fn new(name: &str, path: String) -> Self {
+        ScopeBreakdown {
+            name: name.to_string(),
+            path,
+            nodes: 0,
+            gates: 0,
+            cost: 0,
+            time: Duration::ZERO,
+            children: vec![],
+        }
+    }
+
+    /// Returns the breakdown of the scope at `path`, creating it and its parents if needed.
+    fn entry(&mut self, path: &str) -> &mut ScopeBreakdown {
+        let mut scope = self;
+        let mut prefix = String::new();
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(name);
+            let position = match scope.children.iter().position(|child| child.name == name) {
+                Some(position) => position,
+                None => {
+                    scope
+                        .children
+                        .push(ScopeBreakdown::new(name, prefix.clone()));
+                    scope.children.len() - 1
+                }
+            };
+            scope = &mut scope.children[position];
+        }
+        scope
+    }
+
+    pub fn total_nodes(&self) -> usize {
+        self.nodes + self.children.iter().map(Self::total_nodes).sum::<usize>()
+    }
+
+    pub fn total_gates(&self) -> usize {
+        self.gates + self.children.iter().map(Self::total_gates).sum::<usize>()
+    }
+
+    pub fn total_cost(&self) -> u64 {
+        self.cost + self.children.iter().map(Self::total_cost).sum::<u64>()
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.time + self.children.iter().map(Self::total_time).sum::<Duration>()
+    }
+
+    /// Renders the tree in the folded stack format of flamegraph tools: one line
+    /// `<root>;outer;inner value` per scope with a nonzero value of its own.
+    pub fn folded(&self, metric: FlameMetric) -> String {
+        let mut out = String::new();
+        self.fold_into(&mut Vec::new(), metric, &mut out);
+        out
+    }
+
+    fn fold_into<'a>(&'a self, stack: &mut Vec<&'a str>, metric: FlameMetric, out: &mut String) {
+        stack.push(&self.name);
+        let value = match metric {
+            FlameMetric::Nodes => self.nodes as u128,
+            FlameMetric::Gates => self.gates as u128,
+            FlameMetric::Cost => self.cost as u128,
+            FlameMetric::Time => self.time.as_nanos(),
+        };
+        if value > 0 {
+            out.push_str(&format!("{} {}\n", stack.join(";"), value));
+        }
+        for child in &self.children {
+            child.fold_into(stack, metric, out);
+        }
+        stack.pop();
+    }
+
+    fn write_rows(&self, f: &mut Formatter<'_>, depth: usize) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<30} {:>8} {:>8} {:>8} {:>12?}",
+            format!("{}{}", "  ".repeat(depth), self.name),
+            self.total_nodes(),
+            self.total_gates(),
+            self.total_cost(),
+            self.total_time()
+        )?;
+        self.children
+            .iter()
+            .try_for_each(|child| child.write_rows(f, depth + 1))
+    }
+}
+
+impl Display for ScopeBreakdown {
+    /// Prints one row per scope, indented by nesting, with the totals including nested scopes.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<30} {:>8} {:>8} {:>8} {:>12}",
+            "scope", "nodes", "gates", "cost", "time"
+        )?;
+        self.write_rows(f, 0)
+    }
+}
+
+impl Builder {
+    /// Evaluates the circuit like `profile_fill` and arranges the nodes, gates, cost and time of
+    /// every scope in a tree following the nesting of scopes.
+    ///
+    /// # Arguments
+    ///
+    /// * `input`: Values for the input nodes, as for `fill_nodes`.
+    /// * `model`: The cost of the gates, see `estimate_cost_with`.
+    ///
+    /// # Returns
+    ///
+    /// The breakdown of the root scope.
+    pub fn profile_breakdown(&mut self, input: Vec<u64>, model: &CostModel) -> ScopeBreakdown {
+        let profile = self.profile_fill(input);
+        let costs = self.estimate_cost_with(model);
+        let mut root = ScopeBreakdown::new("<root>", String::new());
+        for path in &self.scopes {
+            root.entry(path);
+        }
+        for scope in profile.scopes {
+            let entry = root.entry(&scope.scope);
+            entry.nodes = scope.nodes;
+            entry.time = scope.time;
+        }
+        for scope in costs.scopes {
+            let entry = root.entry(&scope.scope);
+            entry.gates = scope.adds + scope.muls + scope.equalities;
+            entry.cost = scope.cost;
+        }
+        root
+    }
+
+    /// Evaluates the circuit like `fill_nodes` and times the evaluation of every scope.
+    ///
+    /// Consecutive nodes of the same scope are timed together, so the measurement itself adds
+    /// little overhead. The cache of `fill_nodes` is bypassed and the graph is always evaluated.
+    ///
+    /// # Arguments
+    ///
+    /// * `input`: Values for the input nodes, as for `fill_nodes`.
+    ///
+    /// # Returns
+    ///
+    /// The time spent per scope, slowest scope first.
+    pub fn profile_fill(&mut self, input: Vec<u64>) -> ProfileReport {
+        let _span = info_span!("profile_fill", nodes = self.full_graph.len()).entered();
+        let start = Instant::now();
+        self.assign_inputs(&input);
+
+        let mut times = vec![Duration::ZERO; self.scopes.len()];
+        let mut counts = vec![0; self.scopes.len()];
+        let mut id = 0;
+        while id < self.full_graph.len() {
+            let scope = self.node_scopes[id];
+            let run_start = Instant::now();
+            while id < self.full_graph.len() && self.node_scopes[id] == scope {
+                self.evaluate_node(id);
+                counts[scope] += 1;
+                id += 1;
+            }
+            times[scope] += run_start.elapsed();
+        }
+        self.count(|metrics| metrics.evaluation_passes += 1);
+
+        let mut scopes: Vec<ScopeProfile> = (0..self.scopes.len())
+            .filter(|scope| counts[*scope] > 0)
+            .map(|scope| ScopeProfile {
+                scope: self.scopes[scope].clone(),
+                nodes: counts[scope],
+                time: times[scope],
+            })
+            .collect();
+        scopes.sort_by(|a, b| b.time.cmp(&a.time).then(b.nodes.cmp(&a.nodes)));
+        ProfileReport {
+            total: start.elapsed(),
+            scopes,
+        }
+    }
+}
+
+impl Display for ProfileReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<30} {:>8} {:>12} {:>7}",
+            "scope", "nodes", "time", "share"
+        )?;
+        for scope in &self.scopes {
+            let share = match self.total.as_nanos() {
+                0 => 0.0,
+                total => 100.0 * scope.time.as_nanos() as f64 / total as f64,
+            };
+            let name = match scope.scope.as_str() {
+                "" => "<root>",
+                name => name,
+            };
+            writeln!(
+                f,
+                "{:<30} {:>8} {:>12?} {:>6.1}%",
+                name, scope.nodes, scope.time, share
+            )?;
+        }
+        write!(f, "{:<30} {:>8} {:>12?}", "total", "", self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_groups_nested_scopes() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        builder.push_scope("outer");
+        let double = builder.add(x.clone(), x.clone());
+        let inner = builder.scope("inner", |b| b.mul(double.clone(), double.clone()));
+        let _ = builder.add(inner, x);
+        builder.pop_scope();
+
+        builder.enable_metrics();
+        let report = builder.profile_fill(vec![3]);
+        assert_eq!(builder.metrics().unwrap().evaluation_passes, 1);
+        let mut scopes: Vec<(String, usize)> = report
+            .scopes
+            .iter()
+            .map(|scope| (scope.scope.clone(), scope.nodes))
+            .collect();
+        scopes.sort();
+        assert_eq!(
+            scopes,
+            vec![
+                ("".to_string(), 1),
+                ("outer".to_string(), 2),
+                ("outer/inner".to_string(), 1)
+            ]
+        );
+        assert!(builder.check_constraints());
+    }
+
+    #[test]
+    fn test_breakdown_totals_include_nested_scopes() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        builder.push_scope("outer");
+        let double = builder.add(x.clone(), x.clone());
+        let _ = builder.scope("inner", |b| b.mul(double.clone(), double));
+        builder.pop_scope();
+
+        let tree = builder.profile_breakdown(vec![3], &CostModel::default());
+        let outer = &tree.children[0];
+        assert_eq!((outer.nodes, outer.gates, outer.cost), (1, 1, 1));
+        assert_eq!(
+            (outer.total_nodes(), outer.total_gates(), outer.total_cost()),
+            (2, 2, 4)
+        );
+        assert_eq!(outer.children[0].path, "outer/inner");
+        assert_eq!(tree.total_nodes(), 3);
+        assert_eq!(
+            tree.folded(FlameMetric::Cost),
+            "<root>;outer 1\n<root>;outer;inner 3\n"
+        );
+        let table = tree.to_string();
+        assert!(table.lines().nth(3).unwrap().starts_with("    inner "));
+    }
+}
@@ -0,0 +1,337 @@
+//! A toy prover/verifier split of a built circuit.
+//!
+//! Real proof systems let a prover convince a verifier that it knows private inputs satisfying
+//! a circuit without revealing them. This module only models who knows what: the "proof" is
+//! the full witness in the clear, so nothing is hidden, but the verifier never evaluates the
+//! circuit itself. It only checks the submitted witness against the circuit's constraints and
+//! the public inputs it was given. Both need the key for the circuit from `CircuitKey::setup`,
+//! see the `keys` module.
+//!
+//! The witness can also be kept hidden behind a `commitment`: the prover commits to it, the
+//! verifier challenges a single constraint and the prover only opens the wires that constraint
+//! refers to. Repeating this with random challenges convinces the verifier that every
+//! constraint holds with growing probability, without it ever seeing the whole witness.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::keys::CircuitKey;
+//! use arithmetic_circuit::protocol::{Prover, Verifier};
+//!
+//! // prove knowledge of `s` such that s * x + 5 is the output
+//! let mut builder = Builder::new();
+//! let s = builder.init_private();
+//! let x = builder.init();
+//! let s_x = builder.mul(s, x);
+//! let five = builder.constant(5);
+//! let out = builder.add(s_x, five);
+//! builder.output(out);
+//!
+//! let keys = CircuitKey::setup(&builder);
+//! let proof = Prover::new(&builder, &keys.prover_key, vec![3], vec![4]).prove();
+//! assert_eq!(proof.outputs, vec![17]);
+//! assert!(Verifier::new(&builder, &keys.verifier_key, vec![3]).verify(&proof));
+//! ```
+use crate::commitment::{Commitment, CommittedWitness, Opening};
+use crate::enums::Operation;
+use crate::keys::{CircuitProverKey, CircuitVerifierKey};
+use crate::Builder;
+use std::collections::HashMap;
+use tracing::info;
+
+/// What the prover sends to the verifier: the witness and the outputs it claims.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub witness: Vec<u64>,
+    pub outputs: Vec<u64>,
+}
+
+/// Holds the circuit together with every input, including the private ones.
+pub struct Prover<'a> {
+    circuit: &'a Builder,
+    public_inputs: Vec<u64>,
+    private_inputs: Vec<u64>,
+}
+
+impl<'a> Prover<'a> {
+    /// Creates a prover for `circuit`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit`: The circuit to prove. It is not modified.
+    /// * `key`: The prover key of the circuit.
+    /// * `public_inputs`: Values of the inputs created with `init`, in order.
+    /// * `private_inputs`: Values of the inputs created with `init_private`, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key was generated for a different circuit.
+    pub fn new(
+        circuit: &'a Builder,
+        key: &CircuitProverKey,
+        public_inputs: Vec<u64>,
+        private_inputs: Vec<u64>,
+    ) -> Self {
+        if !key.is_for(circuit) {
+            panic!("the prover key was generated for a different circuit");
+        }
+        Prover {
+            circuit,
+            public_inputs,
+            private_inputs,
+        }
+    }
+
+    /// Evaluates the circuit on the prover's inputs and produces a proof.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of public or private inputs does not match the circuit.
+    pub fn prove(&self) -> Proof {
+        if self.private_inputs.len() != self.circuit.private_inputs.len()
+            || self.public_inputs.len() + self.private_inputs.len() != self.circuit.inputs.len()
+        {
+            panic!("number of inputs supplied to the prover does not match the circuit");
+        }
+        let mut public = self.public_inputs.iter();
+        let mut private = self.private_inputs.iter();
+        let inputs = self
+            .circuit
+            .inputs
+            .iter()
+            .map(|id| match self.circuit.private_inputs.contains(id) {
+                true => *private.next().unwrap(),
+                false => *public.next().unwrap(),
+            })
+            .collect();
+
+        let mut circuit = self.circuit.clone();
+        circuit.fill_nodes(inputs);
+        let proof = Proof {
+            witness: circuit.witness().unwrap(),
+            outputs: circuit
+                .outputs()
+                .iter()
+                .map(|node| circuit.value(node).unwrap())
+                .collect(),
+        };
+        info!(
+            "prover produced proof with claimed outputs {:?}",
+            proof.outputs
+        );
+        proof
+    }
+
+    /// Commits to the witness instead of revealing it.
+    ///
+    /// Every value of the witness is committed to separately, so single wires can be opened.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed`: Source of the commitment salts.
+    pub fn commit(&self, seed: u64) -> CommittedWitness {
+        CommittedWitness::commit(self.prove().witness, 1, seed)
+    }
+
+    /// Answers a challenge by opening the wires the challenged constraint refers to.
+    ///
+    /// # Arguments
+    ///
+    /// * `committed`: The commitment produced by `commit`.
+    /// * `constraint`: Index of the challenged constraint, in order of creation.
+    pub fn open_constraint(&self, committed: &CommittedWitness, constraint: usize) -> Vec<Opening> {
+        let layout = self.circuit.witness_layout();
+        self.circuit.constraints[constraint]
+            .ids()
+            .iter()
+            .map(|id| committed.open_index(layout.iter().position(|w| w == id).unwrap() + 1))
+            .collect()
+    }
+}
+
+/// Holds only the circuit and the public inputs.
+pub struct Verifier<'a> {
+    circuit: &'a Builder,
+    public_inputs: Vec<u64>,
+}
+
+impl<'a> Verifier<'a> {
+    /// Creates a verifier for `circuit`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit`: The circuit the proof is checked against. Values stored in it are never read.
+    /// * `key`: The verifier key of the circuit.
+    /// * `public_inputs`: Values of the inputs created with `init`, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key was generated for a different circuit.
+    pub fn new(circuit: &'a Builder, key: &CircuitVerifierKey, public_inputs: Vec<u64>) -> Self {
+        if !key.is_for(circuit) {
+            panic!("the verifier key was generated for a different circuit");
+        }
+        Verifier {
+            circuit,
+            public_inputs,
+        }
+    }
+
+    /// Checks a proof submitted by a prover.
+    ///
+    /// The proof is accepted if the witness satisfies the circuit, its public section matches
+    /// the verifier's public inputs and the claimed outputs match the output wires of the witness.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the proof is accepted, otherwise `false`.
+    pub fn verify(&self, proof: &Proof) -> bool {
+        if !self.circuit.check_witness(&proof.witness) {
+            return false;
+        }
+        let public = self.circuit.public_inputs().len();
+        if self.public_inputs.len() != public || proof.witness[1..=public] != self.public_inputs[..]
+        {
+            info!("proof was made for different public inputs");
+            return false;
+        }
+        let layout = self.circuit.witness_layout();
+        let outputs: Vec<u64> = self
+            .circuit
+            .outputs
+            .iter()
+            .map(|id| proof.witness[layout.iter().position(|w| w == id).unwrap() + 1])
+            .collect();
+        if outputs != proof.outputs {
+            info!("claimed outputs do not match the witness");
+            return false;
+        }
+        info!("proof accepted");
+        true
+    }
+
+    /// Checks the answer to a challenge on a single constraint against a commitment.
+    ///
+    /// Every opened wire must match the commitment, constant wires must hold their constant and
+    /// public input wires the verifier's public input. Finally the constraint itself must hold
+    /// for the opened values.
+    ///
+    /// # Arguments
+    ///
+    /// * `commitment`: The commitment the prover sent before the challenge.
+    /// * `constraint`: Index of the challenged constraint, in order of creation.
+    /// * `openings`: The prover's answer, see `Prover::open_constraint`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the answer is accepted, otherwise `false`.
+    pub fn verify_opened_constraint(
+        &self,
+        commitment: &Commitment,
+        constraint: usize,
+        openings: &[Opening],
+    ) -> bool {
+        let Some(constraint) = self.circuit.constraints.get(constraint) else {
+            return false;
+        };
+        let layout = self.circuit.witness_layout();
+        let public = self.circuit.public_inputs();
+        let mut values = HashMap::new();
+        for id in constraint.ids() {
+            let index = layout.iter().position(|w| *w == id).unwrap() + 1;
+            let Some(value) = openings
+                .iter()
+                .find_map(|opening| commitment.value_at(opening, index))
+            else {
+                info!("wire {} was not opened correctly", id);
+                return false;
+            };
+            let expected = match self.circuit.full_graph[id].operation {
+                Operation::Constant(constant) => Some(constant),
+                _ => public
+                    .iter()
+                    .position(|p| *p == id)
+                    .and_then(|position| self.public_inputs.get(position).copied()),
+            };
+            if expected.is_some_and(|expected| expected != value) {
+                info!("wire {} does not hold its known value", id);
+                return false;
+            }
+            values.insert(id, value);
+        }
+        constraint.is_valid(self.circuit.field, |id| values.get(&id).copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::CircuitKey;
+
+    fn square_circuit() -> Builder {
+        // prove knowledge of a square root of the public input
+        let mut builder = Builder::new();
+        let root = builder.init_private();
+        let square = builder.init();
+        let root_squared = builder.mul(root.clone(), root);
+        builder.assert_equal(root_squared.clone(), square);
+        builder.output(root_squared);
+        builder
+    }
+
+    #[test]
+    fn test_honest_proof_is_accepted() {
+        let circuit = square_circuit();
+        let keys = CircuitKey::setup(&circuit);
+        let proof = Prover::new(&circuit, &keys.prover_key, vec![49], vec![7]).prove();
+        assert_eq!(proof.outputs, vec![49]);
+        assert!(Verifier::new(&circuit, &keys.verifier_key, vec![49]).verify(&proof));
+    }
+
+    #[test]
+    fn test_dishonest_proofs_are_rejected() {
+        let circuit = square_circuit();
+        let keys = CircuitKey::setup(&circuit);
+        let wrong_root = Prover::new(&circuit, &keys.prover_key, vec![49], vec![6]).prove();
+        assert!(!Verifier::new(&circuit, &keys.verifier_key, vec![49]).verify(&wrong_root));
+
+        let honest = Prover::new(&circuit, &keys.prover_key, vec![49], vec![7]).prove();
+        assert!(!Verifier::new(&circuit, &keys.verifier_key, vec![36]).verify(&honest));
+
+        let mut wrong_output = honest.clone();
+        wrong_output.outputs = vec![50];
+        assert!(!Verifier::new(&circuit, &keys.verifier_key, vec![49]).verify(&wrong_output));
+    }
+
+    #[test]
+    fn test_committed_spot_checks() {
+        let circuit = square_circuit();
+        let keys = CircuitKey::setup(&circuit);
+        let verifier = Verifier::new(&circuit, &keys.verifier_key, vec![49]);
+
+        let honest = Prover::new(&circuit, &keys.prover_key, vec![49], vec![7]);
+        let committed = honest.commit(1);
+        for constraint in 0..circuit.constraints.len() {
+            let openings = honest.open_constraint(&committed, constraint);
+            assert!(verifier.verify_opened_constraint(
+                &committed.commitment(),
+                constraint,
+                &openings
+            ));
+        }
+
+        // the equality constraint catches a prover that does not know a square root
+        let cheater = Prover::new(&circuit, &keys.prover_key, vec![49], vec![6]);
+        let committed = cheater.commit(1);
+        let openings = cheater.open_constraint(&committed, 1);
+        assert!(!verifier.verify_opened_constraint(&committed.commitment(), 1, &openings));
+    }
+
+    #[test]
+    #[should_panic(expected = "different circuit")]
+    fn test_keys_of_other_circuits_are_refused() {
+        let mut other = square_circuit();
+        let _ = other.init();
+        let keys = CircuitKey::setup(&other);
+        Verifier::new(&square_circuit(), &keys.verifier_key, vec![49]);
+    }
+}
@@ -0,0 +1,202 @@
+//! Quadratic arithmetic programs.
+//!
+//! A QAP turns the many rows of an R1CS into a single polynomial identity. Row `i` is assigned the
+//! point `x = i + 1`, and every column `j` of `A` becomes the polynomial `A_j(x)` interpolating the
+//! column's coefficients at these points (likewise for `B` and `C`). For a witness `w` let
+//!
+//! `A(x) = Σ w_j A_j(x)`, `B(x) = Σ w_j B_j(x)`, `C(x) = Σ w_j C_j(x)`.
+//!
+//! At the point of row `i`, `A(x) * B(x) - C(x)` is exactly the error of that row. So the witness
+//! satisfies every row if and only if `A(x) * B(x) - C(x)` vanishes on all points, that is if it is
+//! divisible by the target polynomial `Z(x) = (x - 1)(x - 2)…(x - m)`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::qap::Qap;
+//! use arithmetic_circuit::r1cs::R1cs;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let x_squared = builder.mul(x.clone(), x.clone());
+//! let _ = builder.mul(x_squared, x);
+//! builder.fill_nodes(vec![3]);
+//!
+//! let field = Field::new(97);
+//! let qap = Qap::from_r1cs(&R1cs::from_builder(&builder), field);
+//! let (_, remainder) = qap.quotient(&builder.witness().unwrap());
+//! assert!(remainder.is_zero());
+//! ```
+use crate::domain::Domain;
+use crate::fft;
+use crate::field::Field;
+use crate::polynomial::Polynomial;
+use crate::r1cs::{R1cs, Row};
+
+/// The QAP of an R1CS: one polynomial per witness variable for each of `A`, `B` and `C`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Qap {
+    pub field: Field,
+    /// The interpolation point of each R1CS row.
+    pub points: Vec<u64>,
+    pub a: Vec<Polynomial>,
+    pub b: Vec<Polynomial>,
+    pub c: Vec<Polynomial>,
+    /// The target polynomial `Z(x)`, vanishing on every point.
+    pub target: Polynomial,
+}
+
+impl Qap {
+    /// Interpolates the columns of an R1CS.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field has fewer elements than the R1CS has rows.
+    pub fn from_r1cs(r1cs: &R1cs, field: Field) -> Self {
+        if (r1cs.num_constraints() as u64) >= field.modulus() {
+            panic!("field is too small to give every constraint its own point");
+        }
+        let points: Vec<u64> = (1..=r1cs.num_constraints() as u64).collect();
+        let columns = |rows: &Vec<Row>| -> Vec<Polynomial> {
+            (0..r1cs.num_variables)
+                .map(|variable| {
+                    let values = column(rows, variable, field);
+                    Polynomial::interpolate(&points, &values, field)
+                })
+                .collect()
+        };
+        Qap {
+            field,
+            a: columns(&r1cs.a),
+            b: columns(&r1cs.b),
+            c: columns(&r1cs.c),
+            target: Polynomial::vanishing(&points, field),
+            points,
+        }
+    }
+
+    /// Interpolates the columns of an R1CS on the elements of a domain using the inverse NTT.
+    ///
+    /// Row `i` is assigned the `i`-th element of the domain. If the domain has more elements
+    /// than the R1CS has rows, the remaining points get an empty row, `0 * 0 = 0`, which every
+    /// witness satisfies. The target polynomial of a coset `s·<ω>` of size `n` is
+    /// `x^n - s^n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the domain has fewer elements than the R1CS has rows.
+    pub fn from_r1cs_on_domain(r1cs: &R1cs, domain: &Domain) -> Self {
+        if r1cs.num_constraints() > domain.size {
+            panic!("domain is too small to give every constraint its own point");
+        }
+        let field = domain.field;
+        let columns = |rows: &Vec<Row>| -> Vec<Polynomial> {
+            (0..r1cs.num_variables)
+                .map(|variable| {
+                    let mut values = column(rows, variable, field);
+                    values.resize(domain.size, 0);
+                    fft::interpolate(&values, domain)
+                })
+                .collect()
+        };
+        Qap {
+            field,
+            points: domain.elements().collect(),
+            a: columns(&r1cs.a),
+            b: columns(&r1cs.b),
+            c: columns(&r1cs.c),
+            target: domain.vanishing(),
+        }
+    }
+
+    /// Combines the column polynomials with the witness into `A(x)`, `B(x)` and `C(x)`.
+    pub fn combine(&self, witness: &[u64]) -> (Polynomial, Polynomial, Polynomial) {
+        let combine = |columns: &Vec<Polynomial>| {
+            columns
+                .iter()
+                .zip(witness)
+                .fold(Polynomial::zero(), |sum, (column, value)| {
+                    sum.add(&column.scale(*value, self.field), self.field)
+                })
+        };
+        (combine(&self.a), combine(&self.b), combine(&self.c))
+    }
+
+    /// Divides `A(x) * B(x) - C(x)` by the target polynomial.
+    ///
+    /// # Returns
+    ///
+    /// The quotient `H(x)` and the remainder, which is zero exactly when the witness
+    /// satisfies the R1CS.
+    pub fn quotient(&self, witness: &[u64]) -> (Polynomial, Polynomial) {
+        let (a, b, c) = self.combine(witness);
+        a.mul(&b, self.field)
+            .sub(&c, self.field)
+            .div_rem(&self.target, self.field)
+    }
+}
+
+/// Returns the coefficient of `variable` in every row.
+fn column(rows: &[Row], variable: usize, field: Field) -> Vec<u64> {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .filter(|(index, _)| *index == variable)
+                .fold(0, |sum, (_, coefficient)| field.add(sum, *coefficient))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+
+    #[test]
+    fn test_divisibility_matches_satisfaction() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let y = builder.init();
+        let product = builder.mul(x.clone(), y);
+        let four = builder.constant(4);
+        let sum = builder.add(product, four);
+        builder.assert_equal(sum, x);
+        let field = Field::new(101);
+        let r1cs = R1cs::from_builder(&builder);
+        let qap = Qap::from_r1cs(&r1cs, field);
+        assert_eq!(qap.target.degree(), r1cs.num_constraints());
+
+        // x * y + 4 == x holds for x = 4, y = 0
+        builder.fill_nodes(vec![4, 0]);
+        let witness = builder.witness().unwrap();
+        assert!(r1cs.is_satisfied(&witness, field));
+        assert!(qap.quotient(&witness).1.is_zero());
+
+        builder.fill_nodes(vec![4, 1]);
+        let witness = builder.witness().unwrap();
+        assert!(!r1cs.is_satisfied(&witness, field));
+        assert!(!qap.quotient(&witness).1.is_zero());
+    }
+
+    #[test]
+    fn test_domain_interpolation() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let cube = builder.mul(x.clone(), x.clone());
+        let cube = builder.mul(cube, x);
+        let five = builder.constant(5);
+        builder.add(cube, five);
+        let field = Field::new(97);
+        let r1cs = R1cs::from_builder(&builder);
+        let qap = Qap::from_r1cs_on_domain(&r1cs, &Domain::subgroup(field, 8));
+        assert!(qap
+            .points
+            .iter()
+            .all(|x| qap.target.evaluate(*x, field) == 0));
+
+        builder.fill_nodes(vec![3]);
+        assert!(qap.quotient(&builder.witness().unwrap()).1.is_zero());
+        builder.full_graph[2].value = crate::enums::CustomU64::Val(10);
+        assert!(!qap.quotient(&builder.witness().unwrap()).1.is_zero());
+    }
+}
@@ -0,0 +1,200 @@
+//! Rank-1 constraint systems.
+//!
+//! An R1CS is a list of constraints of the form `<A_i, w> * <B_i, w> = <C_i, w>` where `w` is the
+//! witness vector (see `Builder::witness`) and `A_i`, `B_i`, `C_i` are rows of coefficients.
+//! Every constraint of a `Builder` becomes one row:
+//!
+//! - `a + b = c` becomes `(w_a + w_b) * 1 = w_c`
+//! - `a * b = c` becomes `w_a * w_b = w_c`
+//! - `a == b` becomes `w_a * 1 = w_b`
+//!
+//! Constant nodes are part of the witness, so each of them also gets a row `w_k * 1 = k`
+//! pinning it to its value.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::r1cs::R1cs;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let five = builder.constant(5);
+//! let _ = builder.mul(x, five);
+//! builder.fill_nodes(vec![3]);
+//!
+//! let r1cs = R1cs::from_builder(&builder);
+//! assert_eq!(r1cs.num_constraints(), 2);
+//! assert!(r1cs.is_satisfied(&builder.witness().unwrap(), Field::new(97)));
+//! ```
+use crate::backend::Backend;
+use crate::field::Field;
+use crate::{Builder, NodeId};
+
+/// A sparse row of coefficients: pairs of witness index and coefficient.
+pub type Row = Vec<(usize, u64)>;
+
+/// A rank-1 constraint system over the witness layout of a `Builder`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct R1cs {
+    /// Length of the witness, including the leading `1`.
+    pub num_variables: usize,
+    /// Number of public inputs, which follow the leading `1` in the witness.
+    pub num_public: usize,
+    pub a: Vec<Row>,
+    pub b: Vec<Row>,
+    pub c: Vec<Row>,
+}
+
+impl R1cs {
+    /// Converts the constraints of a circuit into an R1CS.
+    ///
+    /// Constraint rows come first, in order of creation, followed by one row per constant node.
+    pub fn from_builder(builder: &Builder) -> Self {
+        let mut backend = R1csBackend::new(builder);
+        builder.emit(&mut backend);
+        backend.finish()
+    }
+
+    fn push_row(&mut self, a: Row, b: Row, c: Row) {
+        self.a.push(a);
+        self.b.push(b);
+        self.c.push(c);
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.a.len()
+    }
+
+    /// Checks whether row `i` holds for `witness` in `field`.
+    pub fn is_row_satisfied(&self, i: usize, witness: &[u64], field: Field) -> bool {
+        let a = dot(&self.a[i], witness, field);
+        let b = dot(&self.b[i], witness, field);
+        field.mul(a, b) == dot(&self.c[i], witness, field)
+    }
+
+    /// Checks whether every row holds for `witness` in `field`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the witness has the right length and satisfies every row, otherwise `false`.
+    pub fn is_satisfied(&self, witness: &[u64], field: Field) -> bool {
+        witness.len() == self.num_variables
+            && (0..self.num_constraints()).all(|i| self.is_row_satisfied(i, witness, field))
+    }
+}
+
+/// The backend emitting a circuit as an R1CS, see `R1cs::from_builder`.
+#[derive(Debug, Clone)]
+pub struct R1csBackend {
+    /// The position of every node in the witness, indexed by node id.
+    index: Vec<usize>,
+    r1cs: R1cs,
+}
+
+impl R1csBackend {
+    /// Creates an empty R1CS over the witness layout of `builder`.
+    pub fn new(builder: &Builder) -> Self {
+        let layout = builder.witness_layout();
+        let mut index = vec![0; builder.full_graph.len()];
+        layout
+            .iter()
+            .enumerate()
+            .for_each(|(position, id)| index[*id] = position + 1);
+        R1csBackend {
+            index,
+            r1cs: R1cs {
+                num_variables: layout.len() + 1,
+                num_public: builder.public_inputs().len(),
+                a: vec![],
+                b: vec![],
+                c: vec![],
+            },
+        }
+    }
+
+    /// Returns the rows emitted so far.
+    pub fn finish(self) -> R1cs {
+        self.r1cs
+    }
+}
+
+impl Backend for R1csBackend {
+    fn constant(&mut self, out: NodeId, value: u64) {
+        let out = self.index[out];
+        self.r1cs
+            .push_row(vec![(out, 1)], vec![(0, 1)], vec![(0, value)]);
+    }
+
+    fn add_gate(&mut self, a: NodeId, b: NodeId, out: NodeId) {
+        let (a, b, out) = (self.index[a], self.index[b], self.index[out]);
+        self.r1cs
+            .push_row(row(&[(a, 1), (b, 1)]), vec![(0, 1)], vec![(out, 1)]);
+    }
+
+    fn mul_gate(&mut self, a: NodeId, b: NodeId, out: NodeId) {
+        let (a, b, out) = (self.index[a], self.index[b], self.index[out]);
+        self.r1cs
+            .push_row(vec![(a, 1)], vec![(b, 1)], vec![(out, 1)]);
+    }
+
+    fn equality(&mut self, a: NodeId, b: NodeId) {
+        let (a, b) = (self.index[a], self.index[b]);
+        self.r1cs.push_row(vec![(a, 1)], vec![(0, 1)], vec![(b, 1)]);
+    }
+}
+
+/// Builds a row, merging entries that refer to the same witness index.
+fn row(entries: &[(usize, u64)]) -> Row {
+    let mut row: Row = vec![];
+    for (index, coefficient) in entries {
+        match row.iter_mut().find(|(i, _)| i == index) {
+            Some((_, existing)) => *existing += coefficient,
+            None => row.push((*index, *coefficient)),
+        }
+    }
+    row
+}
+
+/// Computes the inner product of a sparse row with the witness.
+pub(crate) fn dot(row: &Row, witness: &[u64], field: Field) -> u64 {
+    row.iter().fold(0, |sum, (index, coefficient)| {
+        field.add(sum, field.mul(*coefficient, witness[*index]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_match_constraints() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let double = builder.add(x.clone(), x.clone());
+        let square = builder.mul(x.clone(), x);
+        builder.assert_equal(double, square);
+        let r1cs = R1cs::from_builder(&builder);
+        assert_eq!(r1cs.num_variables, 4);
+        assert_eq!(r1cs.num_public, 1);
+        assert_eq!(r1cs.a[0], vec![(1, 2)]);
+        assert_eq!(r1cs.c[2], vec![(3, 1)]);
+
+        let field = Field::new(97);
+        builder.fill_nodes(vec![2]);
+        assert!(r1cs.is_satisfied(&builder.witness().unwrap(), field));
+        builder.fill_nodes(vec![3]);
+        assert!(!r1cs.is_satisfied(&builder.witness().unwrap(), field));
+    }
+
+    #[test]
+    fn test_constants_are_pinned() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let seven = builder.constant(7);
+        let _ = builder.add(x, seven);
+        let r1cs = R1cs::from_builder(&builder);
+        let field = Field::new(97);
+        assert!(r1cs.is_satisfied(&[1, 1, 7, 8], field));
+        assert!(!r1cs.is_satisfied(&[1, 1, 6, 7], field));
+    }
+}
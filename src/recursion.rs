@@ -0,0 +1,196 @@
+//! A verifier expressed as a circuit: a first step towards recursion.
+//!
+//! Recursive proof systems and incrementally verifiable computation (IVC) rest on one idea: the
+//! check a verifier performs is itself a computation, so it can be written as a circuit, and a
+//! proof for that circuit convinces anyone that the inner check passed. Repeating this folds a
+//! long chain of computations into a single statement.
+//!
+//! `RecursiveVerifier` builds the circuit for the simplest possible verifier, the one of
+//! `verifier::verify`. Its inputs are the witness of an inner constraint system, the public
+//! inputs as public inputs and every other value as private inputs, and it re-checks each inner
+//! constraint with gates and equality assertions of its own. The outer circuit is satisfied
+//! exactly when the inner witness is valid, and it is a circuit like any other, so the step can
+//! be repeated: the verifier of the verifier checks the witness of the outer circuit.
+//!
+//! Each level costs at least as much as the level below, since the outer circuit redoes all of
+//! the inner work. Real recursion verifies a succinct proof rather than the witness itself,
+//! which is what keeps each level small; this toy version shows the shape of the construction
+//! without the cryptography.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::recursion::RecursiveVerifier;
+//!
+//! // the inner circuit knows a square root of its public input
+//! let mut inner = Builder::new_in_field(Field::new(101));
+//! let y = inner.init();
+//! let root = inner.init_private();
+//! let square = inner.mul(root.clone(), root);
+//! inner.assert_equal(square, y);
+//! inner.fill_nodes(vec![49, 7]);
+//! let witness = inner.witness().unwrap();
+//!
+//! let verifier = RecursiveVerifier::new(&inner.constraint_system());
+//! assert!(verifier.check(&witness));
+//! assert!(!verifier.check(&[1, 49, 6, 36]));
+//! ```
+use crate::constraint_system::{Constraint, ConstraintSystem};
+use crate::evaluator::Evaluator;
+use crate::gadgets::to_bits;
+use crate::{Builder, Node};
+
+/// A circuit checking witnesses of a constraint system, see the module documentation.
+pub struct RecursiveVerifier {
+    circuit: Builder,
+    num_variables: usize,
+}
+
+impl RecursiveVerifier {
+    /// Builds the circuit that verifies witnesses of `system`.
+    ///
+    /// The outer circuit computes in the field of `system`. Its inputs are labeled `v1, v2, …`
+    /// after the positions of the inner witness they hold, and position `0` is the constant `1`.
+    ///
+    /// # Arguments
+    ///
+    /// * `system`: The inner constraints, for example from `Builder::constraint_system`.
+    pub fn new(system: &ConstraintSystem) -> Self {
+        let mut circuit = match system.field() {
+            Some(field) => Builder::new_in_field(field),
+            None => Builder::new(),
+        };
+        let mut wires: Vec<Node> = vec![circuit.constant(1)];
+        for position in 1..system.num_variables() {
+            let wire = match position <= system.num_public() {
+                true => circuit.init(),
+                false => circuit.init_private(),
+            };
+            circuit.label(&wire, &format!("v{}", position));
+            wires.push(wire);
+        }
+        for constraint in system.constraints() {
+            let wire = |position: usize| wires[position].clone();
+            match *constraint {
+                Constraint::Add { a, b, out } => {
+                    let sum = circuit.add(wire(a), wire(b));
+                    circuit.assert_equal(sum, wire(out));
+                }
+                Constraint::Mul { a, b, out } => {
+                    let product = circuit.mul(wire(a), wire(b));
+                    circuit.assert_equal(product, wire(out));
+                }
+                Constraint::Eq { a, b } => circuit.assert_equal(wire(a), wire(b)),
+                Constraint::Constant {
+                    wire: position,
+                    value,
+                } => {
+                    let constant = circuit.constant(value);
+                    circuit.assert_equal(wire(position), constant);
+                }
+                // every value is below 2^bits when the field or u64 is no larger
+                Constraint::Range { bits, .. }
+                    if 1u128 << bits.min(64)
+                        >= system
+                            .field()
+                            .map_or(1 << 64, |field| field.modulus().into()) => {}
+                Constraint::Range {
+                    wire: position,
+                    bits: 0,
+                } => circuit.assert_zero(wire(position)),
+                Constraint::Range {
+                    wire: position,
+                    bits,
+                } => {
+                    to_bits(&mut circuit, &wire(position), bits);
+                }
+            }
+        }
+        RecursiveVerifier {
+            circuit,
+            num_variables: system.num_variables(),
+        }
+    }
+
+    /// Returns the verifier circuit.
+    pub fn circuit(&self) -> &Builder {
+        &self.circuit
+    }
+
+    /// Returns the inputs of the verifier circuit for an inner witness: the witness without its
+    /// leading `1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the witness does not have the length of the inner witness.
+    pub fn inputs(&self, witness: &[u64]) -> Vec<u64> {
+        if witness.len() != self.num_variables {
+            panic!(
+                "expected an inner witness of {} values, found {}",
+                self.num_variables,
+                witness.len()
+            );
+        }
+        witness[1..].to_vec()
+    }
+
+    /// Evaluates the verifier circuit on an inner witness.
+    ///
+    /// # Returns
+    ///
+    /// `true` if every constraint of the verifier circuit holds, which is the case exactly when
+    /// the witness satisfies the inner system and starts with `1`, or `false` for a witness of
+    /// the wrong length.
+    pub fn check(&self, witness: &[u64]) -> bool {
+        witness.len() == self.num_variables
+            && witness[0] == 1
+            && Evaluator::new(&self.circuit)
+                .evaluate(&self.inputs(witness))
+                .satisfied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+    use crate::verifier::verify;
+
+    #[test]
+    fn test_verifier_of_the_verifier() {
+        let mut inner = Builder::new_in_field(Field::new(257));
+        let x = inner.init();
+        let secret = inner.init_private();
+        to_bits(&mut inner, &secret, 4);
+        let product = inner.mul(x, secret);
+        let seven = inner.constant(7);
+        let shifted = inner.add(product, seven);
+        inner.output(shifted);
+        inner.fill_nodes(vec![3, 5]);
+        let witness = inner.witness().unwrap();
+
+        let system = inner.constraint_system();
+        let level_one = RecursiveVerifier::new(&system);
+        assert!(level_one.check(&witness));
+
+        let mut forged = witness.clone();
+        let position = 1 + inner
+            .witness_layout()
+            .iter()
+            .position(|id| *id == 1)
+            .unwrap();
+        forged[position] = 20;
+        assert!(!verify(&system, &forged).is_valid());
+        assert!(!level_one.check(&forged));
+
+        let mut outer = level_one.circuit().clone();
+        outer.fill_nodes(level_one.inputs(&witness));
+        let outer_witness = outer.witness().unwrap();
+        let level_two = RecursiveVerifier::new(&outer.constraint_system());
+        assert!(level_two.check(&outer_witness));
+        assert!(
+            level_two.circuit().constraint_system().constraints().len()
+                > outer.constraint_system().constraints().len()
+        );
+    }
+}
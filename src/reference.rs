@@ -0,0 +1,160 @@
+//! A slow reference interpreter for differential testing.
+//!
+//! `evaluate` computes every node straight from its definition by recursing into its operands.
+//! It shares no code with `Builder::fill_nodes`, so comparing both with `compare` validates
+//! changes to the evaluator.
+//!
+//! ```
+//! use arithmetic_circuit::reference;
+//! use arithmetic_circuit::testing::{random_circuit, CircuitConfig, TestRng};
+//!
+//! let mut rng = TestRng::new(5);
+//! for _ in 0..20 {
+//!     let random = random_circuit(&mut rng, &CircuitConfig::default());
+//!     assert_eq!(reference::compare(&random.circuit, &random.inputs), Ok(()));
+//!     assert_eq!(reference::evaluate(&random.circuit, &random.inputs), random.outputs);
+//! }
+//! ```
+use crate::enums::Operation;
+use crate::Builder;
+
+/// A node whose value differs between the reference interpreter and `Builder::fill_nodes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mismatch {
+    pub node: usize,
+    /// The value computed by the reference interpreter.
+    pub expected: u64,
+    /// The value computed by `fill_nodes`, `None` if the node was left unevaluated.
+    pub actual: Option<u64>,
+}
+
+/// Evaluates the outputs of `circuit` on `inputs`.
+///
+/// # Arguments
+///
+/// * `circuit`: The circuit to evaluate. Values stored in it are never read.
+/// * `inputs`: Values for the input nodes, in order of creation.
+///
+/// # Panics
+///
+/// Panics if the number of inputs does not match the circuit or, without a field, if a value
+/// overflows a `u64`.
+pub fn evaluate(circuit: &Builder, inputs: &[u64]) -> Vec<u64> {
+    let values = evaluate_all(circuit, inputs);
+    circuit.outputs.iter().map(|id| values[*id]).collect()
+}
+
+/// Evaluates `circuit` with both the reference interpreter and `Builder::fill_nodes`.
+///
+/// # Returns
+///
+/// `Ok(())` if every node has the same value, otherwise the first node that differs.
+pub fn compare(circuit: &Builder, inputs: &[u64]) -> Result<(), Mismatch> {
+    let expected = evaluate_all(circuit, inputs);
+    let mut filled = circuit.clone();
+    filled.fill_nodes(inputs.to_vec());
+    match expected
+        .iter()
+        .enumerate()
+        .find(|(id, value)| filled.full_graph[*id].evaluated() != Some(**value))
+    {
+        Some((node, expected)) => Err(Mismatch {
+            node,
+            expected: *expected,
+            actual: filled.full_graph[node].evaluated(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Returns the value of every node, indexed by id.
+fn evaluate_all(circuit: &Builder, inputs: &[u64]) -> Vec<u64> {
+    if inputs.len() != circuit.inputs.len() {
+        panic!("number of input arguments supplied does not match number of inputs for function");
+    }
+    let mut memo = vec![None; circuit.full_graph.len()];
+    (0..circuit.full_graph.len())
+        .map(|id| value_of(circuit, inputs, id, &mut memo))
+        .collect()
+}
+
+/// Computes a node from its definition, remembering values so shared operands are only
+/// computed once.
+fn value_of(circuit: &Builder, inputs: &[u64], id: usize, memo: &mut [Option<u64>]) -> u64 {
+    if let Some(value) = memo[id] {
+        return value;
+    }
+    let value = match circuit.full_graph[id].operation {
+        Operation::Input => {
+            let position = circuit.inputs.iter().position(|input| *input == id);
+            inputs[position.expect("input node is registered as an input")]
+        }
+        Operation::Constant(value) => value,
+        Operation::Add(a, b) => {
+            let (a, b) = (
+                value_of(circuit, inputs, a, memo),
+                value_of(circuit, inputs, b, memo),
+            );
+            match circuit.field {
+                Some(field) => field.add(a, b),
+                None => a.checked_add(b).expect("addition overflows a u64"),
+            }
+        }
+        Operation::Mul(a, b) => {
+            let (a, b) = (
+                value_of(circuit, inputs, a, memo),
+                value_of(circuit, inputs, b, memo),
+            );
+            match circuit.field {
+                Some(field) => field.mul(a, b),
+                None => a.checked_mul(b).expect("multiplication overflows a u64"),
+            }
+        }
+        Operation::Hint(index) => {
+            let values: Vec<u64> = circuit
+                .hint_inputs(index)
+                .iter()
+                .map(|input| value_of(circuit, inputs, *input, memo))
+                .collect();
+            (circuit.hints[index].compute)(&values)
+        }
+    };
+    let value = match (circuit.field, circuit.full_graph[id].operation) {
+        (Some(field), Operation::Input | Operation::Hint(_)) => field.reduce(value),
+        _ => value,
+    };
+    memo[id] = Some(value);
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+    use crate::testing::{random_circuit, CircuitConfig, TestRng};
+
+    #[test]
+    fn test_reference_agrees_with_fill_nodes() {
+        let mut rng = TestRng::new(11);
+        let config = CircuitConfig {
+            depth: 8,
+            max_value: u64::MAX,
+            ..CircuitConfig::default()
+        };
+        for _ in 0..100 {
+            let random = random_circuit(&mut rng, &config);
+            assert_eq!(compare(&random.circuit, &random.inputs), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_reference_in_field() {
+        let mut builder = Builder::new_in_field(Field::new(7));
+        let x = builder.init();
+        let cube = builder.mul(x.clone(), x.clone());
+        let cube = builder.mul(cube, x);
+        builder.output(cube);
+        assert_eq!(evaluate(&builder, &[10]), vec![6]);
+        assert_eq!(compare(&builder, &[10]), Ok(()));
+    }
+}
@@ -0,0 +1,505 @@
+//! Recording the construction of a circuit and replaying it.
+//!
+//! After `Builder::record`, the builder appends every operation that changes the circuit to a
+//! `ReplayLog`: inputs, constants, gates, hints, assertions, outputs, range table lookups, scopes,
+//! groups and rollbacks. `Builder::replay` runs the log on a fresh builder and rebuilds an
+//! identical circuit, node for node. A log attached to a bug report reproduces the circuit
+//! without the program that built it, and replaying a prefix of it shows the circuit at any
+//! earlier point of its construction.
+//!
+//! Gadgets are recorded as the gates they create, so the log does not depend on gadget code that
+//! may have changed since. Hints are recorded with their closure, so a log lives as long as the
+//! program; its `Display` prints the operations for reading, not for parsing back.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! builder.record();
+//! let x = builder.init();
+//! let square = builder.scope("square", |b| b.mul(x.clone(), x.clone()));
+//! let _ = builder.add(square.clone(), x);
+//! builder.output(square);
+//! let log = builder.stop_recording().unwrap();
+//!
+//! let replayed = Builder::replay(&log);
+//! assert_eq!(replayed.fingerprint(), builder.fingerprint());
+//! assert_eq!(replayed.dump_constraints(), builder.dump_constraints());
+//! assert_eq!(Builder::replay(&log.prefix(3)).constraints().count(), 1);
+//! assert_eq!(
+//!     log.to_string(),
+//!     "input\npush_scope square\nmul 0 0\npop_scope\nadd 1 0\noutput 1\n"
+//! );
+//! ```
+//!
+//! The log also knows in which order nodes and assertions were created, which `Builder::undo`
+//! needs to take back the last few of them while building interactively.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::replay::UndoError;
+//!
+//! let mut builder = Builder::new();
+//! builder.record();
+//! let x = builder.init();
+//! let square = builder.mul(x.clone(), x.clone());
+//! builder.output(square.clone());
+//! let wrong = builder.add(square, x);
+//! builder.assert_equal(wrong.clone(), wrong);
+//!
+//! builder.undo(2).unwrap();
+//! assert_eq!(builder.constraints().count(), 1);
+//! // the square is an output, so it cannot be taken back
+//! assert_eq!(builder.undo(1), Err(UndoError::Used { node: 1 }));
+//! ```
+use crate::builder::Checkpoint;
+use crate::enums::Operation;
+use crate::field::Field;
+use crate::hint::{Hint, HintFn};
+use crate::{Builder, Node, NodeId};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use tracing::{debug, info};
+
+/// A single operation of a `ReplayLog`. Nodes are referred to by id.
+#[derive(Clone)]
+pub enum LoggedOp {
+    Input {
+        private: bool,
+    },
+    Constant(u64),
+    Add(NodeId, NodeId),
+    Mul(NodeId, NodeId),
+    Hint {
+        inputs: Vec<NodeId>,
+        compute: HintFn,
+    },
+    AssertEqual(NodeId, NodeId),
+    /// A lowered zero assertion, see `Builder::assert_zero`: the node and the constants `1`
+    /// and `0`.
+    AssertZero(NodeId, NodeId, NodeId),
+    Output(NodeId),
+    Lookup {
+        wire: NodeId,
+        bits: usize,
+    },
+    PushScope(String),
+    PopScope,
+    /// Entering a group, or returning to the enclosing one at the end of `Builder::group`.
+    Group(Option<String>),
+    DisableGroup(String),
+    EnableGroup(String),
+    LowerEqualities(bool),
+    Rollback(Checkpoint),
+    Label {
+        node: NodeId,
+        name: String,
+    },
+}
+
+impl Debug for LoggedOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for LoggedOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoggedOp::Input { private: false } => write!(f, "input"),
+            LoggedOp::Input { private: true } => write!(f, "private input"),
+            LoggedOp::Constant(value) => write!(f, "constant {}", value),
+            LoggedOp::Add(a, b) => write!(f, "add {} {}", a, b),
+            LoggedOp::Mul(a, b) => write!(f, "mul {} {}", a, b),
+            LoggedOp::Hint { inputs, .. } => write!(f, "hint {:?}", inputs),
+            LoggedOp::AssertEqual(a, b) => write!(f, "assert_equal {} {}", a, b),
+            LoggedOp::AssertZero(a, one, zero) => write!(f, "assert_zero {} {} {}", a, one, zero),
+            LoggedOp::Output(id) => write!(f, "output {}", id),
+            LoggedOp::Lookup { wire, bits } => write!(f, "lookup {} {}", wire, bits),
+            LoggedOp::PushScope(name) => write!(f, "push_scope {}", name),
+            LoggedOp::PopScope => write!(f, "pop_scope"),
+            LoggedOp::Group(Some(name)) => write!(f, "group {}", name),
+            LoggedOp::Group(None) => write!(f, "group none"),
+            LoggedOp::DisableGroup(name) => write!(f, "disable_group {}", name),
+            LoggedOp::EnableGroup(name) => write!(f, "enable_group {}", name),
+            LoggedOp::LowerEqualities(lower) => write!(f, "lower_equalities {}", lower),
+            LoggedOp::Rollback(checkpoint) => write!(f, "rollback {:?}", checkpoint),
+            LoggedOp::Label { node, name } => write!(f, "label {} {}", node, name),
+        }
+    }
+}
+
+/// The operations that built a circuit, see `Builder::record`.
+#[derive(Debug, Clone)]
+pub struct ReplayLog {
+    field: Option<Field>,
+    lower_equalities: bool,
+    ops: Vec<LoggedOp>,
+}
+
+impl ReplayLog {
+    /// Creates a log of `ops` for a builder computing in `field`.
+    pub(crate) fn from_ops(field: Option<Field>, ops: Vec<LoggedOp>) -> Self {
+        ReplayLog {
+            field,
+            lower_equalities: false,
+            ops,
+        }
+    }
+
+    /// Returns the operations, in the order they happened.
+    pub fn ops(&self) -> &[LoggedOp] {
+        &self.ops
+    }
+
+    /// Returns the log of the first `len` operations.
+    pub fn prefix(&self, len: usize) -> ReplayLog {
+        ReplayLog {
+            ops: self.ops[..len.min(self.ops.len())].to_vec(),
+            ..self.clone()
+        }
+    }
+}
+
+impl Display for ReplayLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(field) = self.field {
+            writeln!(f, "field {}", field.modulus())?;
+        }
+        self.ops.iter().try_for_each(|op| writeln!(f, "{}", op))
+    }
+}
+
+/// Why `Builder::undo` refused to take operations back. The builder is left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoError {
+    /// The builder is not recording, so the order of its operations is unknown.
+    NotRecording,
+    /// Fewer nodes and assertions were recorded than requested.
+    TooFew { requested: usize, available: usize },
+    /// A node to take back is an output or is looked up in a range table.
+    Used { node: NodeId },
+    /// The operations to take back reach past a rollback.
+    Rollback,
+}
+
+impl Display for UndoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UndoError::NotRecording => write!(f, "undo needs a recording builder"),
+            UndoError::TooFew {
+                requested,
+                available,
+            } => write!(
+                f,
+                "cannot undo {} operations, only {} were recorded",
+                requested, available
+            ),
+            UndoError::Used { node } => write!(f, "node {} is still used", node),
+            UndoError::Rollback => write!(f, "cannot undo past a rollback"),
+        }
+    }
+}
+
+impl Error for UndoError {}
+
+impl LoggedOp {
+    /// Returns `true` for the operations `undo` counts: creating a node or an assertion.
+    fn is_undoable(&self) -> bool {
+        matches!(
+            self,
+            LoggedOp::Input { .. }
+                | LoggedOp::Constant(_)
+                | LoggedOp::Add(..)
+                | LoggedOp::Mul(..)
+                | LoggedOp::Hint { .. }
+                | LoggedOp::AssertEqual(..)
+                | LoggedOp::AssertZero(..)
+        )
+    }
+}
+
+impl Builder {
+    /// Starts recording every operation into a `ReplayLog`, see the `replay` module.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the builder already has nodes, which the log could not reproduce.
+    pub fn record(&mut self) {
+        if !self.full_graph.is_empty() {
+            panic!("recording must start on an empty builder");
+        }
+        self.recording = Some(ReplayLog {
+            field: self.field,
+            lower_equalities: self.lower_equalities,
+            ops: vec![],
+        });
+    }
+
+    /// Returns the log recorded so far, `None` if the builder is not recording.
+    pub fn recording(&self) -> Option<&ReplayLog> {
+        self.recording.as_ref()
+    }
+
+    /// Stops recording and returns the log, `None` if the builder was not recording.
+    pub fn stop_recording(&mut self) -> Option<ReplayLog> {
+        self.recording.take()
+    }
+
+    /// Builds the circuit a log was recorded from.
+    ///
+    /// The result is not recording. Replaying a log only fails if it was not recorded by a
+    /// builder, in which case this panics like the builder method it calls.
+    pub fn replay(log: &ReplayLog) -> Builder {
+        let mut builder = match log.field {
+            Some(field) => Builder::new_in_field(field),
+            None => Builder::new(),
+        };
+        builder.lower_equalities = log.lower_equalities;
+        let node = |builder: &Builder, id: NodeId| builder.full_graph[id].clone();
+        for op in &log.ops {
+            match op {
+                LoggedOp::Input { private: false } => {
+                    builder.init();
+                }
+                LoggedOp::Input { private: true } => {
+                    builder.init_private();
+                }
+                LoggedOp::Constant(value) => {
+                    builder.constant(*value);
+                }
+                LoggedOp::Add(a, b) => {
+                    builder.add(node(&builder, *a), node(&builder, *b));
+                }
+                LoggedOp::Mul(a, b) => {
+                    builder.mul(node(&builder, *a), node(&builder, *b));
+                }
+                LoggedOp::Hint { inputs, compute } => {
+                    builder.push_hint(Hint {
+                        inputs: inputs.clone(),
+                        compute: compute.clone(),
+                    });
+                }
+                LoggedOp::AssertEqual(a, b) => {
+                    builder.push_equality(node(&builder, *a), node(&builder, *b))
+                }
+                LoggedOp::AssertZero(a, one, zero) => builder.push_zero_assertion(
+                    node(&builder, *a),
+                    node(&builder, *one),
+                    node(&builder, *zero),
+                ),
+                LoggedOp::Output(id) => builder.output(node(&builder, *id)),
+                LoggedOp::Lookup { wire, bits } => builder.push_lookup(*wire, *bits),
+                LoggedOp::PushScope(name) => builder.push_scope(name),
+                LoggedOp::PopScope => builder.pop_scope(),
+                LoggedOp::Group(name) => builder.set_group(name.as_deref()),
+                LoggedOp::DisableGroup(name) => builder.disable_group(name),
+                LoggedOp::EnableGroup(name) => builder.enable_group(name),
+                LoggedOp::LowerEqualities(lower) => builder.lower_equalities(*lower),
+                LoggedOp::Rollback(checkpoint) => builder.rollback(*checkpoint),
+                LoggedOp::Label { node: id, name } => builder.label(&node(&builder, *id), name),
+            }
+        }
+        info!(
+            ops = log.ops.len(),
+            nodes = builder.full_graph.len(),
+            "replayed log"
+        );
+        builder
+    }
+
+    /// Removes the last `n` nodes and equality assertions created, together with the constraints
+    /// of the removed gates, and drops them from the log.
+    ///
+    /// Anything depending on a removed node was created after it and is removed as well, except
+    /// outputs and range table lookups, which make the call fail instead. Scopes and groups
+    /// entered in between stay as they are.
+    ///
+    /// # Errors
+    ///
+    /// Fails without changing anything if the builder is not recording, fewer operations were
+    /// recorded since the last rollback, or a removed node is an output or looked up.
+    pub fn undo(&mut self, n: usize) -> Result<(), UndoError> {
+        let log = self.recording.as_ref().ok_or(UndoError::NotRecording)?;
+        // operations before a rollback may already have been taken back by it
+        let start = log
+            .ops
+            .iter()
+            .rposition(|op| matches!(op, LoggedOp::Rollback(_)))
+            .map_or(0, |index| index + 1);
+        let undone: Vec<usize> = (start..log.ops.len())
+            .rev()
+            .filter(|index| log.ops[*index].is_undoable())
+            .take(n)
+            .collect();
+        if undone.len() < n {
+            return Err(match start {
+                0 => UndoError::TooFew {
+                    requested: n,
+                    available: undone.len(),
+                },
+                _ => UndoError::Rollback,
+            });
+        }
+        let Some(&first) = undone.last() else {
+            return Ok(());
+        };
+        let removed =
+            |op: fn(&LoggedOp) -> bool| undone.iter().filter(|index| op(&log.ops[**index])).count();
+        let nodes = self.full_graph.len()
+            - removed(|op| {
+                op.is_undoable()
+                    && !matches!(op, LoggedOp::AssertEqual(..) | LoggedOp::AssertZero(..))
+            });
+        let constraints = self.constraints.len()
+            - removed(|op| {
+                matches!(
+                    op,
+                    LoggedOp::Add(..)
+                        | LoggedOp::Mul(..)
+                        | LoggedOp::AssertEqual(..)
+                        | LoggedOp::AssertZero(..)
+                )
+            });
+        let hints = self.hints.len() - removed(|op| matches!(op, LoggedOp::Hint { .. }));
+        for op in &log.ops[first..] {
+            match *op {
+                LoggedOp::Output(node) | LoggedOp::Lookup { wire: node, .. } if node >= nodes => {
+                    return Err(UndoError::Used { node })
+                }
+                _ => {}
+            }
+        }
+
+        let mut index = 0;
+        if let Some(log) = &mut self.recording {
+            // labels of removed nodes go with them
+            log.ops.retain(|op| {
+                index += 1;
+                !undone.contains(&(index - 1))
+                    && !matches!(op, LoggedOp::Label { node, .. } if *node >= nodes)
+            });
+        }
+        self.full_graph.truncate(nodes);
+        self.node_scopes.truncate(nodes);
+        self.labels.retain(|id, _| *id < nodes);
+        self.full_graph
+            .iter_mut()
+            .for_each(|node| node.children.retain(|child| *child < nodes));
+        self.constraints.truncate(constraints);
+        self.constraint_groups.truncate(constraints);
+        self.inputs.retain(|id| *id < nodes);
+        self.private_inputs.retain(|id| *id < nodes);
+        self.hints.truncate(hints);
+        self.last_fill = None;
+        debug!(n, nodes, constraints, "undid operations");
+        Ok(())
+    }
+
+    /// Appends an operation to the log if the builder is recording.
+    pub(crate) fn record_op(&mut self, op: impl FnOnce() -> LoggedOp) {
+        if let Some(log) = &mut self.recording {
+            log.ops.push(op());
+        }
+    }
+
+    /// Records the creation of a node from its operation.
+    pub(crate) fn record_node(&mut self, node: &Node) {
+        if self.recording.is_none() {
+            return;
+        }
+        let op = match node.operation {
+            Operation::Input => LoggedOp::Input {
+                private: self.private_inputs.last() == Some(&node.id),
+            },
+            Operation::Constant(value) => LoggedOp::Constant(value),
+            Operation::Add(a, b) => LoggedOp::Add(a, b),
+            Operation::Mul(a, b) => LoggedOp::Mul(a, b),
+            Operation::Hint(index) => LoggedOp::Hint {
+                inputs: self.hints[index].inputs.clone(),
+                compute: self.hints[index].compute.clone(),
+            },
+        };
+        self.record_op(|| op);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_reproduces_groups_hints_and_rollbacks() {
+        let mut builder = Builder::new_in_field(Field::new(101));
+        builder.record();
+        let x = builder.init();
+        let secret = builder.init_private();
+        builder.push_scope("unused");
+        builder.pop_scope();
+        let checkpoint = builder.checkpoint();
+        let discarded = builder.scope("discarded", |b| b.mul(x.clone(), x.clone()));
+        builder.label(&discarded, "square");
+        builder.rollback(checkpoint);
+        builder.lower_equalities(true);
+        let inverse = builder.group("inverse", |b| {
+            let inverse = b.hint(&[&x], |values| Field::new(101).inv(values[0]).unwrap_or(0));
+            let one = b.mul(x.clone(), inverse.clone());
+            let expected = b.constant(1);
+            b.scope("check", |b| b.assert_equal(one, expected));
+            inverse
+        });
+        builder.disable_group("inverse");
+        builder.label(&inverse, "inverse");
+        builder.output(inverse);
+        builder.output(secret);
+        let log = builder.stop_recording().unwrap();
+
+        let replayed = Builder::replay(&log);
+        assert_eq!(replayed.structure_words(), builder.structure_words());
+        assert_eq!(replayed.scopes, builder.scopes);
+        assert_eq!(replayed.node_scopes, builder.node_scopes);
+        assert_eq!(replayed.labels, builder.labels);
+        assert_eq!(replayed.groups, builder.groups);
+        assert_eq!(replayed.constraint_groups, builder.constraint_groups);
+        assert_eq!(replayed.skipped_groups(), vec!["inverse"]);
+        assert_eq!(replayed.private_inputs, builder.private_inputs);
+        assert!(replayed.lower_equalities);
+        assert_eq!(replayed.evaluate(vec![5, 7]), builder.evaluate(vec![5, 7]));
+    }
+
+    #[test]
+    fn test_undo_matches_never_building() {
+        let mut builder = Builder::new_in_field(Field::new(101));
+        builder.record();
+        let x = builder.init();
+        let y = builder.init_private();
+        let sum = builder.scope("sum", |b| b.add(x.clone(), y.clone()));
+        builder.output(sum.clone());
+        let expected = builder.session_text().unwrap();
+
+        let half = builder.hint(&[&sum], |values| values[0] / 2);
+        let z = builder.init_private();
+        builder.assert_equal(half.clone(), z.clone());
+        let _ = builder.mul(half, z);
+        builder.undo(4).unwrap();
+        assert_eq!(builder.session_text().unwrap(), expected);
+        let replayed = Builder::replay(builder.recording().unwrap());
+        assert_eq!(replayed.session_text().unwrap(), expected);
+        assert!(builder.validate().is_empty());
+        assert_eq!(
+            builder.undo(9),
+            Err(UndoError::TooFew {
+                requested: 9,
+                available: 3
+            })
+        );
+
+        let checkpoint = builder.checkpoint();
+        let _ = builder.constant(3);
+        builder.rollback(checkpoint);
+        let _ = builder.constant(4);
+        assert_eq!(builder.undo(2), Err(UndoError::Rollback));
+        assert_eq!(builder.undo(1), Ok(()));
+        assert_eq!(builder.full_graph.len(), 3);
+    }
+}
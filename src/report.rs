@@ -0,0 +1,306 @@
+//! Comparing the size of two versions of a circuit.
+//!
+//! A change to a gadget is judged by what it does to the circuits using it. `Builder::stats`
+//! condenses a circuit into counts of its gates, constraints and multiplicative depth, and
+//! `compare` lines up the stats of two builds into a summary, printed as a markdown table to
+//! paste into the description of a pull request. Where `Builder::diff` lists which gates changed,
+//! the comparison says how much.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::report;
+//!
+//! // x^4 by multiplying three times, then by squaring twice
+//! let mut old = Builder::new();
+//! let x = old.init();
+//! let power = (0..3).fold(x.clone(), |acc, _| old.mul(acc, x.clone()));
+//! old.output(power);
+//!
+//! let mut new = Builder::new();
+//! let x = new.init();
+//! let square = new.mul(x.clone(), x);
+//! let power = new.mul(square.clone(), square);
+//! new.output(power);
+//!
+//! let comparison = report::compare(&old.stats(), &new.stats());
+//! assert_eq!(comparison.delta("constraints"), Some(-1));
+//! assert_eq!(comparison.delta("mul depth"), Some(-1));
+//! assert_eq!(comparison.rows[0].name, "constraints");
+//! assert_eq!((comparison.rows[0].old, comparison.rows[0].new), (3, 2));
+//! assert!(!comparison.is_unchanged());
+//! assert_eq!(
+//!     comparison.to_string(),
+//!     "constraints: 3 -> 2 (-1 (-33.3%))\n\n\
+//!      | measure | old | new | change |\n\
+//!      |---|---:|---:|---:|\n\
+//!      | constraints | 3 | 2 | -1 (-33.3%) |\n\
+//!      | mul gates | 3 | 2 | -1 (-33.3%) |\n\
+//!      | mul depth | 3 | 2 | -1 (-33.3%) |\n\
+//!      | nodes | 4 | 3 | -1 (-25.0%) |"
+//! );
+//! ```
+use crate::builder::Checkpoint;
+use crate::enums::{Constraints, Operation};
+use crate::Builder;
+use std::fmt::{Display, Formatter};
+
+/// The size of a circuit, see `Builder::stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CircuitStats {
+    pub nodes: usize,
+    pub inputs: usize,
+    pub private_inputs: usize,
+    pub outputs: usize,
+    pub constants: usize,
+    pub adds: usize,
+    pub muls: usize,
+    pub hints: usize,
+    /// Equality assertions.
+    pub equalities: usize,
+    pub constraints: usize,
+    /// Wires looked up in range tables.
+    pub lookups: usize,
+    /// The largest multiplicative depth of any node, see `Builder::node_mul_depths`.
+    pub mul_depth: usize,
+}
+
+impl CircuitStats {
+    /// Returns the stats as named values, in the order of the comparison table.
+    fn entries(&self) -> [(&'static str, usize); 12] {
+        [
+            ("constraints", self.constraints),
+            ("mul gates", self.muls),
+            ("add gates", self.adds),
+            ("equalities", self.equalities),
+            ("lookups", self.lookups),
+            ("mul depth", self.mul_depth),
+            ("nodes", self.nodes),
+            ("constants", self.constants),
+            ("hints", self.hints),
+            ("inputs", self.inputs),
+            ("private inputs", self.private_inputs),
+            ("outputs", self.outputs),
+        ]
+    }
+}
+
+/// One measure of two circuits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatDelta {
+    pub name: &'static str,
+    pub old: usize,
+    pub new: usize,
+}
+
+impl StatDelta {
+    /// Returns `new - old`.
+    pub fn delta(&self) -> i64 {
+        self.new as i64 - self.old as i64
+    }
+}
+
+impl Display for StatDelta {
+    /// Prints the change with its percentage, `+3 (+25.0%)`, or `=` when unchanged.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.delta(), self.old) {
+            (0, _) => write!(f, "="),
+            (delta, 0) => write!(f, "{:+}", delta),
+            (delta, old) => write!(
+                f,
+                "{:+} ({:+.1}%)",
+                delta,
+                100.0 * delta as f64 / old as f64
+            ),
+        }
+    }
+}
+
+/// The changes between two circuits, see `compare`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    pub rows: Vec<StatDelta>,
+}
+
+impl Comparison {
+    /// Returns the change of the measure named like its row, `None` for an unknown name.
+    pub fn delta(&self, name: &str) -> Option<i64> {
+        self.rows
+            .iter()
+            .find(|row| row.name == name)
+            .map(StatDelta::delta)
+    }
+
+    /// Returns `true` if no measure changed.
+    pub fn is_unchanged(&self) -> bool {
+        self.rows.iter().all(|row| row.delta() == 0)
+    }
+}
+
+impl Display for Comparison {
+    /// Prints a one line summary followed by a markdown table of every measure that changed.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let constraints = self.rows[0];
+        if self.is_unchanged() {
+            return write!(f, "circuit size unchanged: {} constraints", constraints.new);
+        }
+        writeln!(
+            f,
+            "constraints: {} -> {} ({})\n",
+            constraints.old, constraints.new, constraints
+        )?;
+        writeln!(f, "| measure | old | new | change |")?;
+        write!(f, "|---|---:|---:|---:|")?;
+        for row in self.rows.iter().filter(|row| row.delta() != 0) {
+            write!(
+                f,
+                "\n| {} | {} | {} | {} |",
+                row.name, row.old, row.new, row
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the stats of two builds of a circuit.
+///
+/// # Arguments
+///
+/// * `old`: The stats before the change.
+/// * `new`: The stats after the change.
+pub fn compare(old: &CircuitStats, new: &CircuitStats) -> Comparison {
+    Comparison {
+        rows: old
+            .entries()
+            .into_iter()
+            .zip(new.entries())
+            .map(|((name, old), (_, new))| StatDelta { name, old, new })
+            .collect(),
+    }
+}
+
+impl Builder {
+    /// Counts the gates, constraints and depth of the circuit, see the `report` module.
+    pub fn stats(&self) -> CircuitStats {
+        self.stats_after(0, 0, 0, 0)
+    }
+
+    /// Counts what was added to the circuit since `checkpoint` was taken, so that continuations
+    /// of a common prefix can be compared, see `Builder::fork`.
+    ///
+    /// The multiplicative depth is the largest depth of a new node, measured from the inputs of
+    /// the whole circuit.
+    pub fn stats_since(&self, checkpoint: &Checkpoint) -> CircuitStats {
+        self.stats_after(
+            checkpoint.nodes,
+            checkpoint.constraints,
+            checkpoint.outputs,
+            checkpoint.lookups,
+        )
+    }
+
+    /// Counts what was added since the builder was forked, or the whole circuit if it was not.
+    pub fn stats_since_fork(&self) -> CircuitStats {
+        match &self.fork_point {
+            Some(checkpoint) => self.stats_since(checkpoint),
+            None => self.stats(),
+        }
+    }
+
+    fn stats_after(
+        &self,
+        nodes: usize,
+        constraints: usize,
+        outputs: usize,
+        lookups: usize,
+    ) -> CircuitStats {
+        // a rollback or undo may have removed part of the prefix since
+        let nodes = nodes.min(self.full_graph.len());
+        let constraints = constraints.min(self.constraints.len());
+        let mut stats = CircuitStats {
+            nodes: self.full_graph.len() - nodes,
+            private_inputs: self
+                .private_inputs
+                .iter()
+                .filter(|id| **id >= nodes)
+                .count(),
+            outputs: self.outputs.len().saturating_sub(outputs),
+            constraints: self.constraints.len() - constraints,
+            lookups: self.lookups.len().saturating_sub(lookups),
+            mul_depth: self.node_mul_depths()[nodes..]
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or(0),
+            ..CircuitStats::default()
+        };
+        for node in &self.full_graph[nodes..] {
+            match node.operation {
+                Operation::Constant(_) => stats.constants += 1,
+                Operation::Add(..) => stats.adds += 1,
+                Operation::Mul(..) => stats.muls += 1,
+                Operation::Hint(_) => stats.hints += 1,
+                Operation::Input => stats.inputs += 1,
+            }
+        }
+        stats.inputs -= stats.private_inputs;
+        stats.equalities = self.constraints[constraints..]
+            .iter()
+            .filter(|constraint| matches!(constraint, Constraints::Eq(..)))
+            .count();
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comparison_lists_changed_measures() {
+        let mut old = Builder::new();
+        let x = old.init();
+        let _ = old.mul(x.clone(), x);
+
+        let mut new = old.clone();
+        let secret = new.init_private();
+        let sum = new.add(secret.clone(), secret.clone());
+        new.assert_equal(sum, secret);
+
+        let comparison = compare(&old.stats(), &new.stats());
+        assert_eq!(
+            comparison.to_string(),
+            "constraints: 1 -> 3 (+2 (+200.0%))\n\n\
+             | measure | old | new | change |\n\
+             |---|---:|---:|---:|\n\
+             | constraints | 1 | 3 | +2 (+200.0%) |\n\
+             | add gates | 0 | 1 | +1 |\n\
+             | equalities | 0 | 1 | +1 |\n\
+             | nodes | 2 | 4 | +2 (+100.0%) |\n\
+             | private inputs | 0 | 1 | +1 |"
+        );
+        assert!(compare(&old.stats(), &old.stats()).is_unchanged());
+    }
+
+    #[test]
+    fn test_forks_compare_their_continuations() {
+        let mut base = Builder::new();
+        let x = base.init();
+        let square = base.mul(x.clone(), x.clone());
+
+        let mut naive = base.fork();
+        let power = (0..2).fold(square.clone(), |acc, _| naive.mul(acc, x.clone()));
+        naive.output(power);
+        let mut squaring = base.fork();
+        let power = squaring.mul(square.clone(), square);
+        squaring.output(power);
+
+        assert_eq!(base.stats().nodes, 2);
+        let (naive, squaring) = (naive.stats_since_fork(), squaring.stats_since_fork());
+        assert_eq!((naive.muls, naive.outputs, naive.mul_depth), (2, 1, 3));
+        assert_eq!(
+            (squaring.muls, squaring.outputs, squaring.mul_depth),
+            (1, 1, 2)
+        );
+        assert_eq!(compare(&naive, &squaring).delta("constraints"), Some(-1));
+    }
+}
@@ -0,0 +1,107 @@
+//! Checking all constraints at once with a random linear combination.
+//!
+//! A verifier facing many checks `d_1 = 0, d_2 = 0, …, d_m = 0` can pick a random challenge
+//! `r` and check the single equation `Σ r^i d_i = 0` instead. If every `d_i` is zero the sum is
+//! zero. Otherwise the sum is a nonzero polynomial of degree below `m` in `r`, which has fewer
+//! than `m` roots, so a random `r` from a field of size `p` catches the failure except with
+//! probability below `m / p`. Proof systems use this to batch constraints, openings and whole
+//! proofs.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::transcript::Transcript;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let y = builder.init();
+//! let square = builder.mul(x.clone(), x);
+//! builder.assert_equal(square, y);
+//!
+//! // the challenge must be chosen after the witness is fixed
+//! builder.fill_nodes(vec![4, 16]);
+//! let mut transcript = Transcript::new("rlc");
+//! transcript.absorb("witness", &builder.witness().unwrap());
+//! let challenge = transcript.squeeze("challenge");
+//! assert!(builder.check_constraints_rlc(challenge));
+//!
+//! builder.fill_nodes(vec![4, 15]);
+//! assert!(!builder.check_constraints_rlc(challenge));
+//! ```
+use crate::enums::Constraints;
+use crate::field::Field;
+use crate::Builder;
+use tracing::info;
+
+/// The field used for circuits without one, the prime `2^64 - 2^32 + 1`.
+const DEFAULT_MODULUS: u64 = 18_446_744_069_414_584_321;
+
+impl Builder {
+    /// Checks the random linear combination `Σ r^i d_i = 0` of all constraints, where `d_i` is
+    /// the difference between the two sides of constraint `i`.
+    ///
+    /// The combination is computed in the circuit's field. Circuits without a field use the
+    /// prime `2^64 - 2^32 + 1`, so for them the check shows that the constraints hold modulo
+    /// that prime.
+    ///
+    /// # Arguments
+    ///
+    /// * `challenge`: The random `r`, chosen after the values of the nodes are fixed.
+    ///
+    /// # Returns
+    ///
+    /// `false` if any node of a constraint is unevaluated or the combination is not zero.
+    pub fn check_constraints_rlc(&self, challenge: u64) -> bool {
+        let field = self.field.unwrap_or_else(|| Field::new(DEFAULT_MODULUS));
+        let r = field.reduce(challenge);
+        let mut sum = 0;
+        let mut power = 1;
+        for constraint in &self.constraints {
+            let Some(difference) = self.difference(constraint, field) else {
+                return false;
+            };
+            sum = field.add(sum, field.mul(power, difference));
+            power = field.mul(power, r);
+        }
+        info!(combination = sum, "checked random linear combination");
+        sum == 0
+    }
+
+    /// Returns the left side minus the right side of a constraint, or `None` if a node is
+    /// unevaluated.
+    fn difference(&self, constraint: &Constraints, field: Field) -> Option<u64> {
+        let value = |id: usize| self.full_graph[id].evaluated().map(|v| field.reduce(v));
+        Some(match *constraint {
+            Constraints::Add(a, b, c) => field.sub(field.add(value(a)?, value(b)?), value(c)?),
+            Constraints::Mul(a, b, c) => field.sub(field.mul(value(a)?, value(b)?), value(c)?),
+            Constraints::Eq(a, b) => field.sub(value(a)?, value(b)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agrees_with_check_constraints() {
+        let mut builder = Builder::new_in_field(Field::new(10_007));
+        let x = builder.init();
+        let y = builder.init();
+        let product = builder.mul(x.clone(), y.clone());
+        let sum = builder.add(product, x);
+        builder.assert_equal(sum, y);
+        for (inputs, holds) in [(vec![0, 0], true), (vec![1, 2], false)] {
+            builder.fill_nodes(inputs);
+            assert_eq!(builder.check_constraints(), holds);
+            for challenge in [3, 1234, 9999] {
+                assert_eq!(builder.check_constraints_rlc(challenge), holds);
+            }
+        }
+
+        // unevaluated nodes fail the check
+        let mut builder = Builder::new();
+        let x = builder.init();
+        builder.mul(x.clone(), x);
+        assert!(!builder.check_constraints_rlc(1));
+    }
+}
@@ -0,0 +1,113 @@
+//! Export of circuits as SageMath scripts.
+//!
+//! `Builder::to_sage` writes a script that recomputes the circuit symbolically in a computer
+//! algebra system. The inputs and hints become the generators of a polynomial ring over the
+//! circuit's field, or over the integers without a field, and every gate becomes a polynomial
+//! in them, so the script prints the polynomial of every output. Each equality assertion
+//! `a == b` becomes the polynomial `a - b`; together they generate the ideal `I` whose variety
+//! holds exactly the satisfying inputs.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//!
+//! let mut builder = Builder::new_in_field(Field::new(97));
+//! let x = builder.init();
+//! let y = builder.init();
+//! let five = builder.constant(5);
+//! let x_y = builder.mul(x, y.clone());
+//! let sum = builder.add(x_y, five);
+//! builder.assert_equal(sum.clone(), y);
+//! builder.output(sum);
+//!
+//! assert_eq!(
+//!     builder.to_sage(),
+//!     "\
+//! F = GF(97)
+//! R = PolynomialRing(F, ['x0', 'x1'])
+//! x0, x1 = R.gens()
+//! c0 = R(5)
+//! w3 = x0 * x1
+//! w4 = w3 + c0
+//! outputs = [w4]
+//! constraints = [w4 - x1]
+//! I = R.ideal(constraints)
+//! print(outputs)
+//! print(I)
+//! "
+//! );
+//! ```
+use crate::enums::{Constraints, Operation};
+use crate::Builder;
+
+impl Builder {
+    /// Returns a SageMath script defining the field, the ring of the inputs and hints, every
+    /// node as a polynomial, the outputs and the ideal of the equality assertions.
+    pub fn to_sage(&self) -> String {
+        let mut script = match self.field {
+            Some(field) => format!("F = GF({})\n", field.modulus()),
+            None => "F = ZZ\n".to_string(),
+        };
+        let generators: Vec<String> = self
+            .full_graph
+            .iter()
+            .filter(|node| matches!(node.operation, Operation::Input | Operation::Hint(_)))
+            .map(|node| self.wire_name(node.id))
+            .collect();
+        if generators.is_empty() {
+            script.push_str("R = PolynomialRing(F, 'z')\n");
+        } else {
+            let quoted: Vec<String> = generators.iter().map(|g| format!("'{}'", g)).collect();
+            script.push_str(&format!(
+                "R = PolynomialRing(F, [{}])\n{}{} = R.gens()\n",
+                quoted.join(", "),
+                generators.join(", "),
+                // a single generator still needs unpacking from the tuple
+                if generators.len() == 1 { "," } else { "" }
+            ));
+        }
+        for node in &self.full_graph {
+            let value = match node.operation {
+                Operation::Input | Operation::Hint(_) => continue,
+                Operation::Constant(value) => format!("R({})", value),
+                Operation::Add(a, b) => format!("{} + {}", self.wire_name(a), self.wire_name(b)),
+                Operation::Mul(a, b) => format!("{} * {}", self.wire_name(a), self.wire_name(b)),
+            };
+            script.push_str(&format!("{} = {}\n", self.wire_name(node.id), value));
+        }
+        let outputs: Vec<String> = self.outputs.iter().map(|id| self.wire_name(*id)).collect();
+        let constraints: Vec<String> = self
+            .constraints
+            .iter()
+            .filter_map(|constraint| match *constraint {
+                Constraints::Eq(a, b) => {
+                    Some(format!("{} - {}", self.wire_name(a), self.wire_name(b)))
+                }
+                _ => self.zero_assertion(constraint).map(|a| self.wire_name(a)),
+            })
+            .collect();
+        script.push_str(&format!(
+            "outputs = [{}]\nconstraints = [{}]\nI = R.ideal(constraints)\nprint(outputs)\nprint(I)\n",
+            outputs.join(", "),
+            constraints.join(", ")
+        ));
+        script
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hints_are_generators() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let root = builder.hint(&[&x], |_| 0);
+        let square = builder.mul(root.clone(), root);
+        builder.assert_equal(square, x);
+        let script = builder.to_sage();
+        assert!(script.starts_with("F = ZZ\nR = PolynomialRing(F, ['x0', 'w1'])\n"));
+        assert!(script.contains("constraints = [w2 - x0]\n"));
+    }
+}
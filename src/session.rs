@@ -0,0 +1,458 @@
+//! Saving a builder to a file and resuming later.
+//!
+//! `Builder::save_session` writes the whole state of a builder to a text file and
+//! `Builder::load_session` restores it, so a circuit built up over a long interactive session
+//! can be picked up again where it was left. Everything a builder records survives the round
+//! trip: the graph with the values of an evaluation in progress, the constraints and their
+//! groups, inputs and outputs, scopes, labels, range table lookups, the constraint budget and the
+//! field.
+//!
+//! Hints are Rust closures and cannot be written to a file, so circuits with hints are refused.
+//! Loggers, observers and metrics belong to the running program rather than to the circuit and
+//! are not saved either; attach them again after loading.
+//!
+//! The file lists one record per line: the settings, then every node in order of id with its
+//! scope, value, operation and children, then the constraints, inputs, outputs, lookups and
+//! labels.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let square = builder.scope("square", |b| b.mul(x.clone(), x));
+//! builder.output(square.clone());
+//! builder.fill_nodes(vec![3]);
+//!
+//! let path = std::env::temp_dir().join("arithmetic_circuit_session_doc.txt");
+//! builder.save_session(&path).unwrap();
+//! let mut resumed = Builder::load_session(&path).unwrap();
+//! assert_eq!(resumed.value(&square), Some(9));
+//! assert_eq!(resumed.scope_of(&square), "square");
+//! let _ = resumed.add(square.clone(), square);
+//! assert_eq!(resumed.evaluate(vec![4]), vec![16]);
+//! # std::fs::remove_file(path).unwrap();
+//! ```
+use crate::budget::{BudgetMode, ConstraintBudget};
+use crate::enums::{Constraints, CustomU64, ExprVal, Operation};
+use crate::field::{is_prime, Field};
+use crate::validate::Violation;
+use crate::{Builder, Node};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+/// The first line of every session file.
+const HEADER: &str = "arithmetic-circuit session 1";
+
+/// Why a session could not be saved or loaded.
+#[derive(Debug)]
+pub enum SessionError {
+    /// The file could not be read or written.
+    Io(std::io::Error),
+    /// The circuit has a hint, which cannot be saved.
+    Hint { node: usize },
+    /// A line of the file is not a valid record.
+    Parse { line: usize, reason: String },
+    /// The records do not form a well-formed circuit.
+    Invalid(Vec<Violation>),
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::Io(error) => write!(f, "could not access the session file: {}", error),
+            SessionError::Hint { node } => {
+                write!(f, "node {} is a hint, which cannot be saved", node)
+            }
+            SessionError::Parse { line, reason } => write!(f, "line {}: {}", line, reason),
+            SessionError::Invalid(violations) => {
+                write!(f, "the session is not a valid circuit: {}", violations[0])
+            }
+        }
+    }
+}
+
+impl Error for SessionError {}
+
+impl From<std::io::Error> for SessionError {
+    fn from(error: std::io::Error) -> Self {
+        SessionError::Io(error)
+    }
+}
+
+impl Builder {
+    /// Writes the state of the builder to a file, see the `session` module.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the circuit has a hint or the file cannot be written.
+    pub fn save_session(&self, path: impl AsRef<Path>) -> Result<(), SessionError> {
+        std::fs::write(path.as_ref(), self.session_text()?)?;
+        info!(path = %path.as_ref().display(), nodes = self.full_graph.len(), "saved session");
+        Ok(())
+    }
+
+    /// Restores a builder saved with `save_session`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read, is not a session file or does not describe a
+    /// well-formed circuit.
+    pub fn load_session(path: impl AsRef<Path>) -> Result<Builder, SessionError> {
+        let builder = Builder::from_session_text(&std::fs::read_to_string(path.as_ref())?)?;
+        info!(path = %path.as_ref().display(), nodes = builder.full_graph.len(), "loaded session");
+        Ok(builder)
+    }
+
+    pub(crate) fn session_text(&self) -> Result<String, SessionError> {
+        let mut lines = vec![HEADER.to_string()];
+        if let Some(field) = self.field {
+            lines.push(format!("field {}", field.modulus()));
+        }
+        if self.lower_equalities {
+            lines.push("lower-equalities".to_string());
+        }
+        lines.extend(
+            self.scopes[1..]
+                .iter()
+                .map(|scope| format!("scope {}", scope)),
+        );
+        lines.extend(self.groups.iter().map(|group| format!("group {}", group)));
+        lines.extend(
+            self.disabled_groups
+                .iter()
+                .map(|group| format!("disabled {}", group)),
+        );
+        if let Some(budget) = self.budget {
+            let mode = match budget.mode {
+                BudgetMode::Warn => "warn",
+                BudgetMode::Strict => "strict",
+            };
+            lines.push(format!("budget {} {}", budget.limit, mode));
+        }
+        for node in &self.full_graph {
+            let operation = match node.operation {
+                Operation::Input => "input".to_string(),
+                Operation::Constant(value) => format!("constant {}", value),
+                Operation::Add(a, b) => format!("add {} {}", a, b),
+                Operation::Mul(a, b) => format!("mul {} {}", a, b),
+                Operation::Hint(_) => return Err(SessionError::Hint { node: node.id }),
+            };
+            let value = match &node.value {
+                CustomU64::Val(value) => value.to_string(),
+                CustomU64::Expr(ExprVal::Input) => "input".to_string(),
+                CustomU64::Expr(ExprVal::Add(None)) => "add".to_string(),
+                CustomU64::Expr(ExprVal::Add(Some(value))) => format!("add:{}", value),
+                CustomU64::Expr(ExprVal::Mul(None)) => "mul".to_string(),
+                CustomU64::Expr(ExprVal::Mul(Some(value))) => format!("mul:{}", value),
+            };
+            let children: Vec<String> = node.children.iter().map(usize::to_string).collect();
+            lines.push(format!(
+                "node {} scope {} value {} {} children {}",
+                node.id,
+                self.node_scopes[node.id],
+                value,
+                operation,
+                children.join(" ")
+            ));
+        }
+        for (constraint, group) in self.constraints.iter().zip(&self.constraint_groups) {
+            let mut line = match *constraint {
+                Constraints::Add(a, b, c) => format!("constraint add {} {} {}", a, b, c),
+                Constraints::Mul(a, b, c) => format!("constraint mul {} {} {}", a, b, c),
+                Constraints::Eq(a, b) => format!("constraint eq {} {}", a, b),
+            };
+            if let Some(group) = group {
+                line.push_str(&format!(" group {}", group));
+            }
+            lines.push(line);
+        }
+        lines.extend(self.inputs.iter().map(|id| format!("input {}", id)));
+        lines.extend(
+            self.private_inputs
+                .iter()
+                .map(|id| format!("private {}", id)),
+        );
+        lines.extend(self.outputs.iter().map(|id| format!("output {}", id)));
+        lines.extend(
+            self.lookups
+                .iter()
+                .map(|(wire, bits)| format!("lookup {} {}", wire, bits)),
+        );
+        lines.extend(
+            self.labels
+                .iter()
+                .map(|(id, label)| format!("label {} {}", id, label)),
+        );
+        lines.push(format!("current-scope {}", self.current_scope));
+        if let Some(group) = self.current_group {
+            lines.push(format!("current-group {}", group));
+        }
+        if let Some(input) = &self.last_fill {
+            let input: Vec<String> = input.iter().map(u64::to_string).collect();
+            lines.push(format!("filled {}", input.join(" ")).trim_end().to_string());
+        }
+        Ok(lines.join("\n") + "\n")
+    }
+
+    fn from_session_text(text: &str) -> Result<Builder, SessionError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line));
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => return Err(parse_error(1, "not a session file")),
+        }
+        let mut builder = Builder::new();
+        let mut last_fill = None;
+        for (number, line) in lines {
+            let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let mut record = Record {
+                line: number,
+                tokens: rest.split_whitespace(),
+            };
+            match keyword {
+                "field" => {
+                    let modulus: u64 = record.number()?;
+                    if !is_prime(modulus) {
+                        return Err(parse_error(number, "the field modulus is not a prime"));
+                    }
+                    builder.field = Some(Field::new(modulus));
+                }
+                "lower-equalities" => builder.lower_equalities = true,
+                "scope" => builder.scopes.push(rest.to_string()),
+                "group" => builder.groups.push(rest.to_string()),
+                "disabled" => builder.disabled_groups.push(rest.to_string()),
+                "budget" => {
+                    let limit = record.number()?;
+                    let mode = match record.word()? {
+                        "warn" => BudgetMode::Warn,
+                        "strict" => BudgetMode::Strict,
+                        _ => return Err(parse_error(number, "unknown budget mode")),
+                    };
+                    builder.budget = Some(ConstraintBudget { limit, mode });
+                }
+                "node" => {
+                    let (node, scope) =
+                        record.node(builder.full_graph.len(), builder.scopes.len())?;
+                    builder.node_scopes.push(scope);
+                    builder.full_graph.push(node);
+                }
+                "constraint" => {
+                    let constraint = match record.word()? {
+                        "add" => {
+                            Constraints::Add(record.number()?, record.number()?, record.number()?)
+                        }
+                        "mul" => {
+                            Constraints::Mul(record.number()?, record.number()?, record.number()?)
+                        }
+                        "eq" => Constraints::Eq(record.number()?, record.number()?),
+                        _ => return Err(parse_error(number, "unknown constraint")),
+                    };
+                    let group = match record.tokens.next() {
+                        Some("group") => Some(record.index(builder.groups.len())?),
+                        Some(_) => return Err(parse_error(number, "expected group")),
+                        None => None,
+                    };
+                    builder.constraints.push(constraint);
+                    builder.constraint_groups.push(group);
+                }
+                "input" => builder.inputs.push(record.number()?),
+                "private" => builder.private_inputs.push(record.number()?),
+                "output" => builder.outputs.push(record.number()?),
+                "lookup" => {
+                    let wire = record.index(builder.full_graph.len())?;
+                    builder.lookups.push((wire, record.number()?));
+                }
+                "label" => {
+                    let id = record.index(builder.full_graph.len())?;
+                    builder.labels.insert(id, record.word()?.to_string());
+                }
+                "current-scope" => builder.current_scope = record.index(builder.scopes.len())?,
+                "current-group" => {
+                    builder.current_group = Some(record.index(builder.groups.len())?)
+                }
+                "filled" => {
+                    last_fill = Some(
+                        record
+                            .tokens
+                            .map(|token| parse_number(number, token))
+                            .collect::<Result<Vec<u64>, _>>()?,
+                    )
+                }
+                _ => return Err(parse_error(number, &format!("unknown record {}", keyword))),
+            }
+        }
+        let violations = builder.validate();
+        if !violations.is_empty() {
+            return Err(SessionError::Invalid(violations));
+        }
+        builder.last_fill = last_fill;
+        Ok(builder)
+    }
+}
+
+/// The tokens of one line after its keyword.
+struct Record<'a> {
+    line: usize,
+    tokens: std::str::SplitWhitespace<'a>,
+}
+
+impl<'a> Record<'a> {
+    fn word(&mut self) -> Result<&'a str, SessionError> {
+        self.tokens
+            .next()
+            .ok_or_else(|| parse_error(self.line, "unexpected end of line"))
+    }
+
+    fn number<T: FromStr>(&mut self) -> Result<T, SessionError> {
+        let token = self.word()?;
+        parse_number(self.line, token)
+    }
+
+    /// Parses an index that must be smaller than `len`.
+    fn index(&mut self, len: usize) -> Result<usize, SessionError> {
+        let index = self.number()?;
+        if index >= len {
+            return Err(parse_error(
+                self.line,
+                &format!("{} is out of range", index),
+            ));
+        }
+        Ok(index)
+    }
+
+    fn keyword(&mut self, expected: &str) -> Result<(), SessionError> {
+        match self.word()? {
+            word if word == expected => Ok(()),
+            word => Err(parse_error(
+                self.line,
+                &format!("expected {}, found {}", expected, word),
+            )),
+        }
+    }
+
+    /// Parses a node with the given id and its scope, which must be smaller than `scopes`.
+    fn node(&mut self, id: usize, scopes: usize) -> Result<(Node, usize), SessionError> {
+        if self.number::<usize>()? != id {
+            return Err(parse_error(
+                self.line,
+                "nodes must be listed in order of id",
+            ));
+        }
+        self.keyword("scope")?;
+        let scope = self.index(scopes)?;
+        self.keyword("value")?;
+        let value = match self.word()? {
+            "input" => CustomU64::Expr(ExprVal::Input),
+            "add" => CustomU64::Expr(ExprVal::Add(None)),
+            "mul" => CustomU64::Expr(ExprVal::Mul(None)),
+            value => match value.split_once(':') {
+                Some(("add", value)) => {
+                    CustomU64::Expr(ExprVal::Add(Some(parse_number(self.line, value)?)))
+                }
+                Some(("mul", value)) => {
+                    CustomU64::Expr(ExprVal::Mul(Some(parse_number(self.line, value)?)))
+                }
+                _ => CustomU64::Val(parse_number(self.line, value)?),
+            },
+        };
+        let operation = match self.word()? {
+            "input" => Operation::Input,
+            "constant" => Operation::Constant(self.number()?),
+            "add" => Operation::Add(self.number()?, self.number()?),
+            "mul" => Operation::Mul(self.number()?, self.number()?),
+            _ => return Err(parse_error(self.line, "unknown operation")),
+        };
+        self.keyword("children")?;
+        let line = self.line;
+        let children = self
+            .tokens
+            .by_ref()
+            .map(|token| parse_number(line, token))
+            .collect::<Result<Vec<usize>, _>>()?;
+        let node = Node {
+            id,
+            value,
+            operation,
+            children,
+        };
+        Ok((node, scope))
+    }
+}
+
+fn parse_number<T: FromStr>(line: usize, token: &str) -> Result<T, SessionError> {
+    token
+        .parse()
+        .map_err(|_| parse_error(line, &format!("{} is not a number", token)))
+}
+
+fn parse_error(line: usize, reason: &str) -> SessionError {
+    SessionError::Parse {
+        line,
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(builder: &Builder) -> Builder {
+        Builder::from_session_text(&builder.session_text().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_keeps_the_whole_state() {
+        let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+        builder.set_constraint_budget(100);
+        let x = builder.init();
+        let secret = builder.init_private();
+        let product = builder.group("products", |b| {
+            b.scope("product", |b| b.mul(x.clone(), secret.clone()))
+        });
+        builder.disable_group("products");
+        builder.assert_equal(product.clone(), x.clone());
+        // range tables divide with hints, so record the lookup directly
+        builder.lookups.push((secret.id, 4));
+        builder.push_scope("open");
+        builder.label(&product, "product");
+        builder.output(product);
+        builder.fill_nodes(vec![3, 1]);
+
+        let resumed = round_trip(&builder);
+        assert_eq!(
+            resumed.session_text().unwrap(),
+            builder.session_text().unwrap()
+        );
+        assert_eq!(resumed.last_fill, builder.last_fill);
+        assert_eq!(resumed.skipped_groups(), vec!["products"]);
+        assert!(resumed.check_constraints());
+    }
+
+    #[test]
+    fn test_hints_and_broken_files_are_refused() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let half = builder.hint(&[&x], |values| values[0] / 2);
+        assert!(matches!(
+            builder.session_text(),
+            Err(SessionError::Hint { node }) if node == half.id
+        ));
+
+        let text = format!("{}\nnode 0 scope 0 value add add 0 0 children\n", HEADER);
+        assert!(matches!(
+            Builder::from_session_text(&text),
+            Err(SessionError::Invalid(_))
+        ));
+        let text = format!("{}\nnode 0 scope 3 value input input children\n", HEADER);
+        assert!(matches!(
+            Builder::from_session_text(&text),
+            Err(SessionError::Parse { line: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,244 @@
+//! A mock trusted setup ceremony.
+//!
+//! Pairing-based proof systems such as Groth16 need a structured reference string: powers
+//! `τ^0, τ^1, …, τ^d` of a secret field element `τ`, generated once per circuit. Whoever knows
+//! `τ` (the "toxic waste") can forge proofs, which is why `τ` is produced in a ceremony where
+//! every participant multiplies in a secret of their own. `τ` stays unknown as long as a single
+//! participant destroys their secret.
+//!
+//! Real keys hide the powers of `τ` inside elliptic-curve points. Here they are plain field
+//! elements and the verifying key even stores `τ` itself, so the keys only demonstrate the shape
+//! of a setup: they are derived from the circuit, sized for it, and useless for any other circuit.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::setup::Ceremony;
+//!
+//! let mut square = Builder::new();
+//! let x = square.init();
+//! let _ = square.mul(x.clone(), x);
+//!
+//! let mut ceremony = Ceremony::new(&square, Field::new(97));
+//! ceremony.contribute(5);
+//! ceremony.contribute(11);
+//! let (proving_key, verifying_key) = ceremony.finish();
+//! assert!(proving_key.is_for(&square) && verifying_key.is_for(&square));
+//!
+//! // a key generated for one circuit cannot be reused for another
+//! let mut cube = Builder::new();
+//! let x = cube.init();
+//! let x_squared = cube.mul(x.clone(), x.clone());
+//! let _ = cube.mul(x_squared, x);
+//! assert!(!proving_key.is_for(&cube));
+//! ```
+use crate::field::Field;
+use crate::hash::{hash_words, mix};
+use crate::r1cs::R1cs;
+use crate::Builder;
+use tracing::info;
+
+/// Returns the digest of a circuit's shape that keys are bound to.
+pub(crate) fn circuit_digest(circuit: &Builder) -> u64 {
+    hash_words(&circuit.structure_words())
+}
+
+/// The public key material a prover needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvingKey {
+    circuit_digest: u64,
+    field: Field,
+    powers_of_tau: Vec<u64>,
+}
+
+/// The key material a verifier needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyingKey {
+    circuit_digest: u64,
+    field: Field,
+    tau: u64,
+}
+
+/// A multi-party ceremony producing the keys of a single circuit.
+pub struct Ceremony {
+    circuit_digest: u64,
+    field: Field,
+    degree: usize,
+    tau: u64,
+    contributions: usize,
+}
+
+impl Ceremony {
+    /// Starts a ceremony for `circuit` over `field`.
+    ///
+    /// The keys will hold enough powers of `τ` to evaluate polynomials with one
+    /// coefficient per row of the circuit's R1CS, plus one.
+    pub fn new(circuit: &Builder, field: Field) -> Self {
+        let circuit_digest = circuit_digest(circuit);
+        Ceremony {
+            circuit_digest,
+            field,
+            degree: R1cs::from_builder(circuit).num_constraints(),
+            // the circuit digest seeds τ so that different circuits never share a structure
+            tau: field.reduce(circuit_digest).max(1),
+            contributions: 0,
+        }
+    }
+
+    /// Multiplies a participant's secret into `τ`.
+    ///
+    /// The participant is expected to destroy `secret` afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `secret` is zero in the field, which would make `τ` zero for everyone.
+    pub fn contribute(&mut self, secret: u64) {
+        if self.field.reduce(secret) == 0 {
+            panic!("a contribution to the ceremony must be non-zero in the field");
+        }
+        self.tau = self.field.mul(self.tau, secret);
+        self.contributions += 1;
+        info!("ceremony received contribution #{}", self.contributions);
+    }
+
+    /// Ends the ceremony and derives the keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if nobody contributed.
+    pub fn finish(self) -> (ProvingKey, VerifyingKey) {
+        if self.contributions == 0 {
+            panic!("a ceremony needs at least one contribution");
+        }
+        let powers_of_tau = (0..=self.degree)
+            .map(|power| self.field.pow(self.tau, power as u64))
+            .collect();
+        (
+            ProvingKey {
+                circuit_digest: self.circuit_digest,
+                field: self.field,
+                powers_of_tau,
+            },
+            VerifyingKey {
+                circuit_digest: self.circuit_digest,
+                field: self.field,
+                tau: self.tau,
+            },
+        )
+    }
+}
+
+/// Runs a ceremony with a single participant whose secret is derived from `seed`.
+pub fn setup(circuit: &Builder, field: Field, seed: u64) -> (ProvingKey, VerifyingKey) {
+    let mut ceremony = Ceremony::new(circuit, field);
+    let secret = (1..)
+        .map(|attempt| field.reduce(mix(seed ^ attempt)))
+        .find(|secret| *secret != 0)
+        .unwrap();
+    ceremony.contribute(secret);
+    ceremony.finish()
+}
+
+impl ProvingKey {
+    /// Returns `true` if the key was generated for `circuit`.
+    pub fn is_for(&self, circuit: &Builder) -> bool {
+        self.circuit_digest == circuit_digest(circuit)
+    }
+
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Returns `τ^0, τ^1, …, τ^d` where `d` is the number of rows of the circuit's R1CS.
+    pub fn powers_of_tau(&self) -> &[u64] {
+        &self.powers_of_tau
+    }
+
+    /// Evaluates the polynomial with the given coefficients (lowest degree first) at `τ`
+    /// without knowing `τ`, using only the powers in the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the polynomial has a higher degree than the key supports.
+    pub fn evaluate_at_tau(&self, coefficients: &[u64]) -> u64 {
+        if coefficients.len() > self.powers_of_tau.len() {
+            panic!(
+                "proving key supports polynomials of degree {}, got degree {}",
+                self.powers_of_tau.len() - 1,
+                coefficients.len() - 1
+            );
+        }
+        coefficients
+            .iter()
+            .zip(&self.powers_of_tau)
+            .fold(0, |sum, (coefficient, power)| {
+                self.field.add(sum, self.field.mul(*coefficient, *power))
+            })
+    }
+}
+
+impl VerifyingKey {
+    /// Returns `true` if the key was generated for `circuit`.
+    pub fn is_for(&self, circuit: &Builder) -> bool {
+        self.circuit_digest == circuit_digest(circuit)
+    }
+
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Returns the secret evaluation point. Only a mock key can afford to expose it.
+    pub fn tau(&self) -> u64 {
+        self.tau
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circuit(squarings: usize) -> Builder {
+        let mut builder = Builder::new();
+        let mut x = builder.init();
+        for _ in 0..squarings {
+            x = builder.mul(x.clone(), x);
+        }
+        builder
+    }
+
+    #[test]
+    fn test_keys_are_bound_to_the_circuit() {
+        let field = Field::new(101);
+        let (proving_key, verifying_key) = setup(&circuit(2), field, 7);
+        assert_eq!(proving_key.powers_of_tau().len(), 3);
+        assert_eq!(proving_key.powers_of_tau()[1], verifying_key.tau());
+        assert!(proving_key.is_for(&circuit(2)));
+        assert!(!proving_key.is_for(&circuit(3)));
+        assert!(!verifying_key.is_for(&circuit(1)));
+    }
+
+    #[test]
+    fn test_keys_are_bound_to_the_field() {
+        let mut in_field = Builder::new_in_field(Field::new(101));
+        let x = in_field.init();
+        let _ = in_field.mul(x.clone(), x);
+        let (proving_key, _) = setup(&circuit(1), Field::new(101), 7);
+        assert!(!proving_key.is_for(&in_field));
+    }
+
+    #[test]
+    fn test_evaluate_at_tau() {
+        let field = Field::new(101);
+        let (proving_key, verifying_key) = setup(&circuit(2), field, 7);
+        let tau = verifying_key.tau();
+        let expected = field.add(3, field.mul(tau, field.mul(tau, 2)));
+        assert_eq!(proving_key.evaluate_at_tau(&[3, 0, 2]), expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_key_too_small_for_polynomial() {
+        let (proving_key, _) = setup(&circuit(1), Field::new(101), 7);
+        proving_key.evaluate_at_tau(&[1, 2, 3]);
+    }
+}
@@ -0,0 +1,106 @@
+//! Export of the constraint system to SMT-LIB2.
+//!
+//! `Builder::to_smtlib` writes the circuit as a script for SMT solvers such as Z3 or CVC5. Every
+//! input, gate and hint becomes an integer constant named like the wires in
+//! `Builder::dump_constraints`, bounded to the values a wire can take: `[0, p)` over a field and
+//! `[0, 2^64)` otherwise. Every constraint becomes an assertion, with the reduction modulo `p`
+//! spelled out over a field. Without a field gates must not overflow, as in `check_constraints`.
+//!
+//! A solver answering `sat` has found a satisfying witness, and its model names the value of
+//! every wire. To prove that no witness violates a property, add the negated property as an
+//! assertion before `(check-sat)`: `unsat` means the property always holds. Hints are not
+//! constrained beyond their range, exactly as in the circuit.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//!
+//! let mut builder = Builder::new_in_field(Field::new(97));
+//! let x = builder.init();
+//! let square = builder.mul(x.clone(), x);
+//! let nine = builder.constant(9);
+//! builder.assert_equal(square, nine);
+//!
+//! assert_eq!(
+//!     builder.to_smtlib(),
+//!     "\
+//! (set-logic QF_NIA)
+//! (declare-const x0 Int)
+//! (assert (and (<= 0 x0) (< x0 97)))
+//! (declare-const w1 Int)
+//! (assert (and (<= 0 w1) (< w1 97)))
+//! (assert (= w1 (mod (* x0 x0) 97)))
+//! (assert (= w1 9))
+//! (check-sat)
+//! (get-model)
+//! "
+//! );
+//! ```
+use crate::enums::{Constraints, Operation};
+use crate::Builder;
+
+impl Builder {
+    /// Returns an SMT-LIB2 script asserting every constraint of the circuit, followed by
+    /// `(check-sat)` and `(get-model)`.
+    pub fn to_smtlib(&self) -> String {
+        let mut script = self.smtlib_assertions();
+        script.push_str("(check-sat)\n(get-model)\n");
+        script
+    }
+
+    /// Returns the logic, declarations and assertions of the script, without any commands.
+    pub(crate) fn smtlib_assertions(&self) -> String {
+        let bound = match self.field {
+            Some(field) => field.modulus().to_string(),
+            None => (1u128 << 64).to_string(),
+        };
+        let mut script = "(set-logic QF_NIA)\n".to_string();
+        for node in &self.full_graph {
+            if let Operation::Constant(_) = node.operation {
+                continue;
+            }
+            let name = self.wire_name(node.id);
+            script.push_str(&format!("(declare-const {} Int)\n", name));
+            script.push_str(&format!(
+                "(assert (and (<= 0 {}) (< {} {})))\n",
+                name, name, bound
+            ));
+        }
+        for constraint in &self.constraints {
+            let (result, value) = match *constraint {
+                Constraints::Add(a, b, c) => (c, self.smtlib_gate("+", a, b)),
+                Constraints::Mul(a, b, c) => (c, self.smtlib_gate("*", a, b)),
+                Constraints::Eq(a, b) => (a, self.term(b)),
+            };
+            script.push_str(&format!("(assert (= {} {}))\n", self.term(result), value));
+        }
+        script
+    }
+
+    /// Returns the term of a gate, reduced modulo the field's prime if there is one.
+    fn smtlib_gate(&self, op: &str, a: usize, b: usize) -> String {
+        let term = format!("({} {} {})", op, self.term(a), self.term(b));
+        match self.field {
+            Some(field) => format!("(mod {} {})", term, field.modulus()),
+            None => term,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hints_are_declared_without_u64_overflow() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let half = builder.hint(&[&x], |values| values[0] / 2);
+        let double = builder.add(half.clone(), half);
+        builder.assert_equal(double, x);
+        let script = builder.to_smtlib();
+        assert!(script.contains("(declare-const w1 Int)\n"));
+        assert!(script.contains("(< x0 18446744073709551616)"));
+        assert!(script.contains("(assert (= w2 (+ w1 w1)))\n(assert (= w2 x0))\n"));
+    }
+}
@@ -0,0 +1,264 @@
+//! Solving circuits for unknown inputs.
+//!
+//! `Builder::solve` takes the inputs that are known and propagates values through the
+//! constraints in every direction: a sum with a known result and one known operand determines
+//! the other operand, a product determines an operand if the other one is invertible, and an
+//! equality copies a value. This recovers the inputs of linear or invertible chains, for
+//! example the preimage of `y = 3x + 5`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init_private();
+//! let y = builder.init();
+//! let three = builder.constant(3);
+//! let five = builder.constant(5);
+//! let three_x = builder.mul(three, x);
+//! let sum = builder.add(three_x, five);
+//! builder.assert_equal(sum, y);
+//!
+//! assert_eq!(builder.solve(&[None, Some(26)]), Ok(vec![7, 26]));
+//! ```
+use crate::enums::{Constraints, Operation};
+use crate::Builder;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use tracing::info;
+
+/// Why `Builder::solve` could not determine the inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// The constraints do not determine these inputs, given by position in order of creation.
+    Underdetermined { inputs: Vec<usize> },
+    /// The constraints contradict each other; the constraint with this index cannot hold.
+    Overdetermined { constraint: usize },
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Underdetermined { inputs } => {
+                write!(
+                    f,
+                    "inputs {:?} are not determined by the constraints",
+                    inputs
+                )
+            }
+            SolveError::Overdetermined { constraint } => {
+                write!(f, "constraint {} contradicts the others", constraint)
+            }
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+impl Builder {
+    /// Computes the missing inputs from the known ones by propagating values through the
+    /// constraints.
+    ///
+    /// Propagation is local, so inputs that are only determined by solving several constraints
+    /// at once, such as a square root, are reported as underdetermined.
+    ///
+    /// # Arguments
+    ///
+    /// * `known_inputs`: One entry per input in order of creation, its value or `None` if it is
+    ///   unknown.
+    ///
+    /// # Returns
+    ///
+    /// The values of all inputs in order of creation, ready for `fill_nodes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of entries does not match the number of inputs.
+    pub fn solve(&self, known_inputs: &[Option<u64>]) -> Result<Vec<u64>, SolveError> {
+        if known_inputs.len() != self.inputs.len() {
+            panic!(
+                "number of input arguments supplied does not match number of inputs for function"
+            );
+        }
+        let mut values: Vec<Option<u64>> = self
+            .full_graph
+            .iter()
+            .map(|node| match node.operation {
+                Operation::Constant(value) => Some(value),
+                _ => None,
+            })
+            .collect();
+        for (position, id) in self.inputs.iter().enumerate() {
+            values[*id] = known_inputs[position].map(|value| self.reduce(value));
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            // hints are computed as soon as the nodes they read are known
+            for (id, node) in self.full_graph.iter().enumerate() {
+                if let (None, Operation::Hint(index)) = (values[id], node.operation) {
+                    let known: Option<Vec<u64>> = self
+                        .hint_inputs(index)
+                        .iter()
+                        .map(|id| values[*id])
+                        .collect();
+                    if let Some(known) = known {
+                        values[id] = Some(self.reduce((self.hints[index].compute)(&known)));
+                        changed = true;
+                    }
+                }
+            }
+            for (index, constraint) in self.constraints.iter().enumerate() {
+                let ids = constraint.ids();
+                let known: Vec<Option<u64>> = ids.iter().map(|id| values[*id]).collect();
+                if known.iter().all(Option::is_some) {
+                    if !constraint.is_valid(self.field, |id| values[id]) {
+                        info!(constraint = index, "constraints contradict each other");
+                        return Err(SolveError::Overdetermined { constraint: index });
+                    }
+                    continue;
+                }
+                let Some((id, value)) = self.propagate(constraint, &known) else {
+                    continue;
+                };
+                match value {
+                    Some(value) => values[ids[id]] = Some(value),
+                    None => return Err(SolveError::Overdetermined { constraint: index }),
+                }
+                changed = true;
+            }
+        }
+
+        let unknown: Vec<usize> = (0..self.inputs.len())
+            .filter(|position| values[self.inputs[*position]].is_none())
+            .collect();
+        if !unknown.is_empty() {
+            info!(?unknown, "inputs are not determined by the constraints");
+            return Err(SolveError::Underdetermined { inputs: unknown });
+        }
+        Ok(self.inputs.iter().map(|id| values[*id].unwrap()).collect())
+    }
+
+    /// Derives one unknown wire of a constraint from the known ones.
+    ///
+    /// # Returns
+    ///
+    /// `None` if nothing can be derived, otherwise the position of the derived wire within the
+    /// constraint together with its value, or `None` as value if no value can satisfy it.
+    fn propagate(
+        &self,
+        constraint: &Constraints,
+        known: &[Option<u64>],
+    ) -> Option<(usize, Option<u64>)> {
+        let field = self.field;
+        match (constraint, known) {
+            (Constraints::Eq(..), [Some(a), None]) => Some((1, Some(*a))),
+            (Constraints::Eq(..), [None, Some(b)]) => Some((0, Some(*b))),
+            (Constraints::Add(..), [Some(a), Some(b), None]) => Some((
+                2,
+                match field {
+                    Some(field) => Some(field.add(*a, *b)),
+                    None => a.checked_add(*b),
+                },
+            )),
+            (Constraints::Add(..), [Some(a), None, Some(c)]) => Some((1, difference(self, *c, *a))),
+            (Constraints::Add(..), [None, Some(b), Some(c)]) => Some((0, difference(self, *c, *b))),
+            (Constraints::Mul(..), [Some(a), Some(b), None]) => Some((
+                2,
+                match field {
+                    Some(field) => Some(field.mul(*a, *b)),
+                    None => a.checked_mul(*b),
+                },
+            )),
+            (Constraints::Mul(..), [Some(a), None, Some(c)]) => {
+                quotient(self, *c, *a).map(|value| (1, value))
+            }
+            (Constraints::Mul(..), [None, Some(b), Some(c)]) => {
+                quotient(self, *c, *b).map(|value| (0, value))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Solves `x + subtrahend = minuend` for `x`.
+fn difference(circuit: &Builder, minuend: u64, subtrahend: u64) -> Option<u64> {
+    match circuit.field {
+        Some(field) => Some(field.sub(minuend, subtrahend)),
+        None => minuend.checked_sub(subtrahend),
+    }
+}
+
+/// Solves `x * divisor = dividend` for `x`.
+///
+/// Returns `None` if a zero divisor leaves `x` undetermined, `Some(None)` if there is no
+/// solution.
+fn quotient(circuit: &Builder, dividend: u64, divisor: u64) -> Option<Option<u64>> {
+    match (divisor, dividend) {
+        (0, 0) => None,
+        (0, _) => Some(None),
+        _ => Some(match circuit.field {
+            Some(field) => field.div(dividend, divisor),
+            None => match dividend % divisor {
+                0 => Some(dividend / divisor),
+                _ => None,
+            },
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_solve_in_field() {
+        // 4x + y = 3 over F_11, with y known
+        let mut builder = Builder::new_in_field(Field::new(11));
+        let x = builder.init();
+        let y = builder.init();
+        let four = builder.constant(4);
+        let three = builder.constant(3);
+        let four_x = builder.mul(four, x);
+        let sum = builder.add(four_x, y);
+        builder.assert_equal(sum, three);
+
+        let inputs = builder.solve(&[None, Some(5)]).unwrap();
+        assert_eq!(inputs, vec![5, 5]);
+        let mut filled = builder.clone();
+        filled.fill_nodes(inputs);
+        assert!(filled.check_constraints());
+    }
+
+    #[test]
+    fn test_under_and_overdetermined() {
+        // x * x = y cannot be solved for x by propagation
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let y = builder.init();
+        let square = builder.mul(x.clone(), x);
+        builder.assert_equal(square, y);
+        assert_eq!(
+            builder.solve(&[None, Some(9)]),
+            Err(SolveError::Underdetermined { inputs: vec![0] })
+        );
+        assert_eq!(builder.solve(&[Some(3), None]), Ok(vec![3, 9]));
+        assert_eq!(
+            builder.solve(&[Some(3), Some(8)]),
+            Err(SolveError::Overdetermined { constraint: 1 })
+        );
+
+        // 2x = 7 has no solution in the integers
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let two = builder.constant(2);
+        let seven = builder.constant(7);
+        let double = builder.mul(two, x);
+        builder.assert_equal(double, seven);
+        assert_eq!(
+            builder.solve(&[None]),
+            Err(SolveError::Overdetermined { constraint: 0 })
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Compressed sparse row matrices of the R1CS view of a circuit.
+//!
+//! `Builder::constraint_matrices` returns the `A`, `B` and `C` matrices of the circuit's R1CS
+//! (see the `r1cs` module) in CSR form: the nonzero entries of all rows are stored back to back,
+//! with an offset per row marking where it starts. Rows can be iterated without allocating and
+//! matrix-vector products run over the nonzero entries only.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let y = builder.init();
+//! let product = builder.mul(x, y);
+//! builder.fill_nodes(vec![3, 4]);
+//!
+//! let matrices = builder.constraint_matrices();
+//! let witness = builder.witness().unwrap();
+//! let field = Field::new(97);
+//! let (a, b, c) = (
+//!     matrices.a.mul_vec(&witness, field),
+//!     matrices.b.mul_vec(&witness, field),
+//!     matrices.c.mul_vec(&witness, field),
+//! );
+//! assert_eq!(a[0] * b[0], c[0]);
+//! assert_eq!(matrices.c.row(0).collect::<Vec<_>>(), vec![(3, 1)]);
+//! ```
+use crate::field::Field;
+use crate::r1cs::{R1cs, Row};
+use crate::Builder;
+
+/// A sparse matrix in compressed sparse row form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrMatrix {
+    /// Number of columns, the length of the witness.
+    pub num_cols: usize,
+    /// Start of every row in `columns` and `values`, followed by the number of entries.
+    pub row_offsets: Vec<usize>,
+    /// Column of every nonzero entry, row by row.
+    pub columns: Vec<usize>,
+    /// Value of every nonzero entry, row by row.
+    pub values: Vec<u64>,
+}
+
+impl CsrMatrix {
+    /// Packs rows of (column, value) pairs into a matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows`: The entries of every row. Entries with value zero are dropped.
+    /// * `num_cols`: The number of columns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an entry lies outside of the columns.
+    pub fn from_rows(rows: &[Row], num_cols: usize) -> Self {
+        let mut matrix = CsrMatrix {
+            num_cols,
+            row_offsets: vec![0],
+            columns: vec![],
+            values: vec![],
+        };
+        for row in rows {
+            for (column, value) in row.iter().filter(|(_, value)| *value != 0) {
+                if *column >= num_cols {
+                    panic!(
+                        "entry in column {} of a matrix with {} columns",
+                        column, num_cols
+                    );
+                }
+                matrix.columns.push(*column);
+                matrix.values.push(*value);
+            }
+            matrix.row_offsets.push(matrix.columns.len());
+        }
+        matrix
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.row_offsets.len() - 1
+    }
+
+    /// Returns the number of nonzero entries.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns the (column, value) pairs of the nonzero entries of row `i`.
+    pub fn row(&self, i: usize) -> impl Iterator<Item = (usize, u64)> + '_ {
+        let range = self.row_offsets[i]..self.row_offsets[i + 1];
+        self.columns[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.values[range].iter().copied())
+    }
+
+    /// Multiplies the matrix with a vector in `field`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the vector does not match the number of columns.
+    pub fn mul_vec(&self, vector: &[u64], field: Field) -> Vec<u64> {
+        if vector.len() != self.num_cols {
+            panic!("vector length does not match the number of columns");
+        }
+        (0..self.num_rows())
+            .map(|i| {
+                self.row(i).fold(0, |sum, (column, value)| {
+                    field.add(sum, field.mul(value, vector[column]))
+                })
+            })
+            .collect()
+    }
+}
+
+/// The `A`, `B` and `C` matrices of an R1CS, with one row per constraint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintMatrices {
+    /// Number of public inputs, which follow the leading `1` in the witness.
+    pub num_public: usize,
+    pub a: CsrMatrix,
+    pub b: CsrMatrix,
+    pub c: CsrMatrix,
+}
+
+impl Builder {
+    /// Returns the R1CS of the circuit as sparse matrices over its witness layout.
+    ///
+    /// Rows are in the same order as in `R1cs::from_builder`.
+    pub fn constraint_matrices(&self) -> ConstraintMatrices {
+        let r1cs = R1cs::from_builder(self);
+        ConstraintMatrices {
+            num_public: r1cs.num_public,
+            a: CsrMatrix::from_rows(&r1cs.a, r1cs.num_variables),
+            b: CsrMatrix::from_rows(&r1cs.b, r1cs.num_variables),
+            c: CsrMatrix::from_rows(&r1cs.c, r1cs.num_variables),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r1cs::dot;
+
+    #[test]
+    fn test_matches_r1cs_rows() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let y = builder.init_private();
+        let two = builder.constant(2);
+        let sum = builder.add(x.clone(), x.clone());
+        let product = builder.mul(sum, y);
+        let scaled = builder.mul(product, two);
+        builder.assert_equal(scaled, x);
+        builder.fill_nodes(vec![0, 5]);
+
+        let r1cs = R1cs::from_builder(&builder);
+        let matrices = builder.constraint_matrices();
+        let witness = builder.witness().unwrap();
+        let field = Field::new(101);
+        for (rows, matrix) in [
+            (&r1cs.a, &matrices.a),
+            (&r1cs.b, &matrices.b),
+            (&r1cs.c, &matrices.c),
+        ] {
+            assert_eq!(matrix.num_rows(), r1cs.num_constraints());
+            let expected: Vec<u64> = rows.iter().map(|row| dot(row, &witness, field)).collect();
+            assert_eq!(matrix.mul_vec(&witness, field), expected);
+        }
+        // x + x is merged into one entry with coefficient 2
+        assert_eq!(matrices.a.row(0).collect::<Vec<_>>(), vec![(1, 2)]);
+    }
+}
@@ -0,0 +1,317 @@
+//! Partial evaluation of circuits.
+//!
+//! `Builder::specialize` fixes some of the inputs of a circuit and folds every node that only
+//! depends on fixed inputs and constants into a constant. What remains is a smaller circuit over
+//! the other inputs, which shows how much of a circuit fixing its public parameters removes.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! // a * x^2 + b
+//! let mut builder = Builder::new();
+//! let a = builder.init();
+//! let b = builder.init();
+//! let x = builder.init();
+//! let x_squared = builder.mul(x.clone(), x);
+//! let a_x_squared = builder.mul(a, x_squared);
+//! let result = builder.add(a_x_squared, b);
+//! builder.output(result);
+//!
+//! // fixing a = 1 and b = 0 leaves x^2
+//! let specialized = builder.specialize(&[Some(1), Some(0), None]);
+//! assert_eq!(specialized.evaluate(vec![7]), vec![49]);
+//!
+//! let mut expected = Builder::new();
+//! let x = expected.init();
+//! let x_squared = expected.mul(x.clone(), x);
+//! expected.output(x_squared);
+//! assert_eq!(specialized.fingerprint(), expected.fingerprint());
+//! ```
+use crate::builder::OVERFLOW;
+use crate::enums::{Constraints, Operation};
+use crate::hint::Hint;
+use crate::{Builder, Node};
+use std::collections::HashMap;
+use tracing::info;
+
+/// A node of the original circuit during specialization.
+#[derive(Clone)]
+enum Folded {
+    /// The value is known from the fixed inputs and constants.
+    Known(u64),
+    /// The value depends on inputs that are not fixed.
+    Node(Node),
+}
+
+/// Builds the specialized circuit, creating every constant only once.
+struct Specializer {
+    circuit: Builder,
+    constants: HashMap<u64, Node>,
+}
+
+impl Specializer {
+    fn node(&mut self, folded: &Folded) -> Node {
+        match folded {
+            Folded::Node(node) => node.clone(),
+            Folded::Known(value) => match self.constants.get(value) {
+                Some(node) => node.clone(),
+                None => {
+                    let node = self.circuit.constant(*value);
+                    self.constants.insert(*value, node.clone());
+                    node
+                }
+            },
+        }
+    }
+}
+
+impl Builder {
+    /// Fixes some inputs and constant-folds the circuit.
+    ///
+    /// Gates with only known operands are evaluated, additions of zero and multiplications by
+    /// zero or one are simplified. Equality assertions between known values are dropped if they
+    /// hold and kept as an assertion between two constants otherwise, so the specialized circuit
+    /// is unsatisfiable exactly when the original one is for the fixed inputs. Constraint groups,
+    /// range table lookups and the labels of nodes that are not folded carry over.
+    ///
+    /// # Arguments
+    ///
+    /// * `partial_inputs`: One entry per input in order of creation, the value to fix it to or
+    ///   `None` to keep it as an input.
+    ///
+    /// # Returns
+    ///
+    /// A new circuit whose inputs are the inputs left unfixed, in their original order and
+    /// visibility, with the same outputs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of entries does not match the number of inputs or, without a field,
+    /// if folding overflows a `u64`.
+    pub fn specialize(&self, partial_inputs: &[Option<u64>]) -> Builder {
+        if partial_inputs.len() != self.inputs.len() {
+            panic!(
+                "number of input arguments supplied does not match number of inputs for function"
+            );
+        }
+        let mut specializer = Specializer {
+            circuit: match self.field {
+                Some(field) => Builder::new_in_field(field),
+                None => Builder::new(),
+            },
+            constants: HashMap::new(),
+        };
+        specializer.circuit.lower_equalities = self.lower_equalities;
+        specializer.circuit.groups = self.groups.clone();
+        specializer.circuit.disabled_groups = self.disabled_groups.clone();
+        let mut gate_groups = vec![None; self.full_graph.len()];
+        for (constraint, group) in self.constraints.iter().zip(&self.constraint_groups) {
+            if let Constraints::Add(_, _, out) | Constraints::Mul(_, _, out) = *constraint {
+                if self.zero_assertion(constraint).is_none() {
+                    gate_groups[out] = *group;
+                }
+            }
+        }
+        let mut folded: Vec<Folded> = Vec::with_capacity(self.full_graph.len());
+        for node in &self.full_graph {
+            specializer.circuit.current_group = gate_groups[node.id];
+            let result = match node.operation {
+                Operation::Input => {
+                    let position = self.inputs.iter().position(|id| *id == node.id).unwrap();
+                    match partial_inputs[position] {
+                        Some(value) => Folded::Known(self.reduce(value)),
+                        None if self.private_inputs.contains(&node.id) => {
+                            Folded::Node(specializer.circuit.init_private())
+                        }
+                        None => Folded::Node(specializer.circuit.init()),
+                    }
+                }
+                Operation::Constant(value) => Folded::Known(value),
+                Operation::Add(a, b) => match (&folded[a], &folded[b]) {
+                    (Folded::Known(a), Folded::Known(b)) => Folded::Known(match self.field {
+                        Some(field) => field.add(*a, *b),
+                        None => a.checked_add(*b).expect(OVERFLOW),
+                    }),
+                    (Folded::Known(0), other) | (other, Folded::Known(0)) => other.clone(),
+                    (a, b) => {
+                        let (a, b) = (a.clone(), b.clone());
+                        let (a, b) = (specializer.node(&a), specializer.node(&b));
+                        Folded::Node(specializer.circuit.add(a, b))
+                    }
+                },
+                Operation::Mul(a, b) => match (&folded[a], &folded[b]) {
+                    (Folded::Known(a), Folded::Known(b)) => Folded::Known(match self.field {
+                        Some(field) => field.mul(*a, *b),
+                        None => a.checked_mul(*b).expect(OVERFLOW),
+                    }),
+                    (Folded::Known(0), _) | (_, Folded::Known(0)) => Folded::Known(0),
+                    (Folded::Known(1), other) | (other, Folded::Known(1)) => other.clone(),
+                    (a, b) => {
+                        let (a, b) = (a.clone(), b.clone());
+                        let (a, b) = (specializer.node(&a), specializer.node(&b));
+                        Folded::Node(specializer.circuit.mul(a, b))
+                    }
+                },
+                Operation::Hint(index) => {
+                    let hint = &self.hints[index];
+                    let known: Option<Vec<u64>> = hint
+                        .inputs
+                        .iter()
+                        .map(|id| match folded[*id] {
+                            Folded::Known(value) => Some(value),
+                            Folded::Node(_) => None,
+                        })
+                        .collect();
+                    match known {
+                        Some(values) => Folded::Known(self.reduce((hint.compute)(&values))),
+                        None => {
+                            let inputs = hint.inputs.iter().map(|id| folded[*id].clone());
+                            let inputs: Vec<usize> =
+                                inputs.map(|input| specializer.node(&input).id).collect();
+                            Folded::Node(specializer.circuit.push_hint(Hint {
+                                inputs,
+                                compute: hint.compute.clone(),
+                            }))
+                        }
+                    }
+                }
+            };
+            folded.push(result);
+        }
+
+        for (constraint, group) in self.constraints.iter().zip(&self.constraint_groups) {
+            specializer.circuit.current_group = *group;
+            if let Constraints::Eq(a, b) = *constraint {
+                if let (Folded::Known(x), Folded::Known(y)) = (&folded[a], &folded[b]) {
+                    if x == y {
+                        continue;
+                    }
+                }
+                let (a, b) = (specializer.node(&folded[a]), specializer.node(&folded[b]));
+                specializer.circuit.assert_equal(a, b);
+            } else if let Some(a) = self.zero_assertion(constraint) {
+                if matches!(folded[a], Folded::Known(0)) {
+                    continue;
+                }
+                let a = specializer.node(&folded[a]);
+                let one = specializer.node(&Folded::Known(1));
+                let zero = specializer.node(&Folded::Known(0));
+                specializer.circuit.push_zero_assertion(a, one, zero);
+            }
+        }
+        specializer.circuit.current_group = None;
+        // a folded wire is still range checked, as a constant
+        for (wire, bits) in &self.lookups {
+            let wire = specializer.node(&folded[*wire]);
+            specializer.circuit.push_lookup(wire.id, *bits);
+        }
+        for (id, label) in &self.labels {
+            if let Folded::Node(node) = &folded[*id] {
+                specializer
+                    .circuit
+                    .labels
+                    .entry(node.id)
+                    .or_insert_with(|| label.clone());
+            }
+        }
+        for output in &self.outputs {
+            let node = specializer.node(&folded[*output]);
+            specializer.circuit.output(node);
+        }
+        info!(
+            nodes = self.full_graph.len(),
+            remaining = specializer.circuit.full_graph.len(),
+            "specialized circuit"
+        );
+        specializer.circuit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    fn test_specialized_circuit_agrees_with_original() {
+        // (p + x) * (q * y) with p = 2 and q = 3 fixed
+        let mut builder = Builder::new();
+        let p = builder.init();
+        let x = builder.init_private();
+        let q = builder.init();
+        let y = builder.init();
+        let left = builder.add(p, x);
+        let right = builder.mul(q, y);
+        let product = builder.mul(left, right);
+        builder.output(product);
+
+        let specialized = builder.specialize(&[Some(2), None, Some(3), None]);
+        assert_eq!(specialized.private_inputs.len(), 1);
+        for (x, y) in [(0, 0), (1, 5), (7, 2)] {
+            assert_eq!(
+                specialized.evaluate(vec![x, y]),
+                builder.evaluate(vec![2, x, 3, y])
+            );
+        }
+    }
+
+    #[test]
+    fn test_failing_assertion_is_kept() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let y = builder.init();
+        let five = builder.constant(5);
+        builder.assert_equal(x, five.clone());
+        let sum = builder.add(y, five);
+        builder.output(sum);
+
+        let satisfied = builder.specialize(&[Some(5), None]);
+        assert!(satisfied
+            .constraints
+            .iter()
+            .all(|c| !matches!(c, Constraints::Eq(..))));
+        let mut violated = builder.specialize(&[Some(4), None]);
+        violated.fill_nodes(vec![1]);
+        assert!(!violated.check_constraints());
+    }
+
+    #[test]
+    #[should_panic(expected = "gate overflows a u64")]
+    fn test_folding_checks_overflow() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let _ = builder.mul(x.clone(), x);
+        builder.specialize(&[Some(1 << 32)]);
+    }
+
+    #[test]
+    fn test_lookups_labels_and_groups_carry_over() {
+        let mut builder = Builder::new_in_field(Field::new(1_000_000_007));
+        let mut table = builder.range_table(4);
+        let p = builder.init();
+        let x = builder.init();
+        let sum = builder.add(p.clone(), x.clone());
+        table.lookup(&p);
+        table.lookup(&x);
+        builder.label(&p, "p");
+        builder.label(&sum, "sum");
+        let challenge = builder.init();
+        builder.group("table", |b| table.finish(b, &challenge));
+        builder.disable_group("table");
+
+        let mut specialized = builder.specialize(&[Some(3), None, None]);
+        assert_eq!(specialized.lookups.len(), 2);
+        let labels: Vec<&String> = specialized.labels.values().collect();
+        assert_eq!(labels, vec!["sum"]);
+        assert_eq!(specialized.skipped_groups(), vec!["table"]);
+        specialized.enable_group("table");
+        specialized.fill_nodes(vec![5, 7]);
+        assert!(specialized.check_constraints());
+        // the fixed p = 20 is out of range
+        let mut specialized = builder.specialize(&[Some(20), None, None]);
+        specialized.fill_nodes(vec![5, 7]);
+        assert!(specialized.check_constraints());
+        specialized.enable_group("table");
+        assert!(!specialized.check_constraints());
+    }
+}
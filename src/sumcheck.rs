@@ -0,0 +1,334 @@
+//! A toy sumcheck protocol over the constraints of a circuit.
+//!
+//! The sumcheck protocol convinces a verifier of the value of `Σ_{b ∈ {0,1}^n} g(b)` for a
+//! polynomial `g` in `n` variables while the verifier evaluates `g` only once. In round `i` the
+//! prover sends the univariate polynomial `g_i(X)`, the sum with the first `i - 1` variables
+//! fixed to earlier challenges, the `i`-th variable left free and the rest summed over `{0, 1}`.
+//! The verifier checks `g_i(0) + g_i(1)` against the running claim, picks a random challenge
+//! `r_i` and continues with the claim `g_i(r_i)`. After the last round the claim must equal
+//! `g(r_1, …, r_n)`, which the verifier queries directly.
+//!
+//! The polynomial summed here is derived from the R1CS of the circuit, as in Spartan:
+//!
+//! `g(b) = eq(τ, b) · (Ã(b) · B̃(b) - C̃(b))`
+//!
+//! where `Ã`, `B̃`, `C̃` are the multilinear extensions (see `mle`) of the vectors `A·w`, `B·w`
+//! and `C·w` and `eq(τ, b)` is the extension of the indicator of `b = τ`. Every satisfied row
+//! contributes zero, and for a random `τ` the sum is zero only if every row is satisfied, except
+//! with small probability. Each `g_i` has degree at most 3 and is sent as its values at
+//! `0, 1, 2, 3`. In this toy the verifier's final query is answered from the witness itself; a
+//! real system answers it with a polynomial commitment.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::sumcheck::{ConstraintPolynomial, Prover, Verifier};
+//! use arithmetic_circuit::transcript::Transcript;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let cube = builder.mul(x.clone(), x.clone());
+//! let cube = builder.mul(cube, x);
+//! builder.output(cube);
+//! builder.fill_nodes(vec![3]);
+//!
+//! let field = Field::new(2_147_483_647);
+//! let mut transcript = Transcript::new("sumcheck");
+//! let polynomial = ConstraintPolynomial::new(&builder, field, &mut transcript);
+//! let mut prover = Prover::new(&polynomial);
+//! let mut verifier = Verifier::new(polynomial.num_vars(), prover.claimed_sum(), field, transcript);
+//! assert_eq!(prover.claimed_sum(), 0);
+//!
+//! while !verifier.is_done() {
+//!     let message = prover.message();
+//!     let challenge = verifier.receive(&message).expect("an honest prover is accepted");
+//!     prover.fix(challenge);
+//! }
+//! assert!(verifier.finish(&polynomial));
+//! ```
+use crate::field::Field;
+use crate::mle::MultilinearExtension;
+use crate::polynomial::Polynomial;
+use crate::transcript::Transcript;
+use crate::Builder;
+use tracing::debug;
+
+/// The degree of every round polynomial: `eq · (a · b - c)` is a product of three multilinear
+/// factors.
+const DEGREE: u64 = 3;
+
+/// The polynomial `eq(τ, b) · (Ã(b) · B̃(b) - C̃(b))` of a filled circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintPolynomial {
+    pub field: Field,
+    /// The random point `τ`, one coordinate per variable.
+    pub tau: Vec<u64>,
+    eq: MultilinearExtension,
+    a: MultilinearExtension,
+    b: MultilinearExtension,
+    c: MultilinearExtension,
+}
+
+impl ConstraintPolynomial {
+    /// Builds the polynomial from the R1CS and witness of a circuit, squeezing `τ` from the
+    /// transcript.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit was not filled.
+    pub fn new(circuit: &Builder, field: Field, transcript: &mut Transcript) -> Self {
+        let witness: Vec<u64> = circuit
+            .witness()
+            .expect("the circuit must be filled before running sumcheck")
+            .iter()
+            .map(|value| field.reduce(*value))
+            .collect();
+        let matrices = circuit.constraint_matrices();
+        let a = MultilinearExtension::new(matrices.a.mul_vec(&witness, field), field);
+        let b = MultilinearExtension::new(matrices.b.mul_vec(&witness, field), field);
+        let c = MultilinearExtension::new(matrices.c.mul_vec(&witness, field), field);
+
+        transcript.absorb_circuit(circuit);
+        let num_vars = a.num_vars();
+        let tau: Vec<u64> = (0..num_vars)
+            .map(|_| transcript.squeeze_below("sumcheck tau", field.modulus()))
+            .collect();
+        let eq = (0..1usize << num_vars)
+            .map(|index| {
+                tau.iter().enumerate().fold(1, |product, (i, t)| {
+                    let factor = match index >> (num_vars - 1 - i) & 1 {
+                        1 => *t,
+                        _ => field.sub(1, *t),
+                    };
+                    field.mul(product, factor)
+                })
+            })
+            .collect();
+        ConstraintPolynomial {
+            field,
+            tau,
+            eq: MultilinearExtension::new(eq, field),
+            a,
+            b,
+            c,
+        }
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.eq.num_vars()
+    }
+
+    /// Evaluates the polynomial at a point of `F^n`, the verifier's final query.
+    pub fn evaluate(&self, point: &[u64]) -> u64 {
+        let field = self.field;
+        combine(
+            field,
+            self.eq.evaluate(point),
+            self.a.evaluate(point),
+            self.b.evaluate(point),
+            self.c.evaluate(point),
+        )
+    }
+}
+
+/// Computes `eq · (a · b - c)`.
+fn combine(field: Field, eq: u64, a: u64, b: u64, c: u64) -> u64 {
+    field.mul(eq, field.sub(field.mul(a, b), c))
+}
+
+/// The prover's message of one round: the values of `g_i` at `0, 1, 2, 3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundMessage {
+    pub evaluations: Vec<u64>,
+}
+
+/// The prover, holding the tables of the polynomial with the variables fixed so far.
+#[derive(Debug, Clone)]
+pub struct Prover {
+    polynomial: ConstraintPolynomial,
+}
+
+impl Prover {
+    pub fn new(polynomial: &ConstraintPolynomial) -> Self {
+        Prover {
+            polynomial: polynomial.clone(),
+        }
+    }
+
+    /// Returns the sum of the polynomial over the hypercube, zero for a satisfying witness.
+    pub fn claimed_sum(&self) -> u64 {
+        let ConstraintPolynomial {
+            field, eq, a, b, c, ..
+        } = &self.polynomial;
+        (0..eq.evaluations().len()).fold(0, |sum, i| {
+            let value = combine(
+                *field,
+                eq.evaluations()[i],
+                a.evaluations()[i],
+                b.evaluations()[i],
+                c.evaluations()[i],
+            );
+            field.add(sum, value)
+        })
+    }
+
+    /// Computes the message of the current round.
+    ///
+    /// # Panics
+    ///
+    /// Panics if every variable has been fixed.
+    pub fn message(&self) -> RoundMessage {
+        let ConstraintPolynomial {
+            field, eq, a, b, c, ..
+        } = &self.polynomial;
+        let field = *field;
+        if eq.num_vars() == 0 {
+            panic!("every round of the sumcheck protocol has been run");
+        }
+        let half = eq.evaluations().len() / 2;
+        // the value of a table at the free variable x, for the remaining variables at index i
+        let at = |table: &MultilinearExtension, i: usize, x: u64| {
+            let (low, high) = (table.evaluations()[i], table.evaluations()[half + i]);
+            field.add(low, field.mul(x, field.sub(high, low)))
+        };
+        let evaluations = (0..=DEGREE)
+            .map(|x| {
+                (0..half).fold(0, |sum, i| {
+                    let value = combine(field, at(eq, i, x), at(a, i, x), at(b, i, x), at(c, i, x));
+                    field.add(sum, value)
+                })
+            })
+            .collect();
+        RoundMessage { evaluations }
+    }
+
+    /// Fixes the current variable to the verifier's challenge.
+    pub fn fix(&mut self, challenge: u64) {
+        let polynomial = &mut self.polynomial;
+        polynomial.eq = polynomial.eq.fix_first(challenge);
+        polynomial.a = polynomial.a.fix_first(challenge);
+        polynomial.b = polynomial.b.fix_first(challenge);
+        polynomial.c = polynomial.c.fix_first(challenge);
+    }
+}
+
+/// The verifier, tracking the running claim and the challenges sent so far.
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    field: Field,
+    num_rounds: usize,
+    claim: u64,
+    challenges: Vec<u64>,
+    transcript: Transcript,
+}
+
+impl Verifier {
+    /// Creates a verifier for the claim that the polynomial sums to `claimed_sum`.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_rounds`: The number of variables of the polynomial.
+    /// * `claimed_sum`: The sum the prover claims.
+    /// * `field`: The field to compute in.
+    /// * `transcript`: Source of the challenges. Every message is absorbed into it.
+    pub fn new(num_rounds: usize, claimed_sum: u64, field: Field, transcript: Transcript) -> Self {
+        Verifier {
+            field,
+            num_rounds,
+            claim: field.reduce(claimed_sum),
+            challenges: vec![],
+            transcript,
+        }
+    }
+
+    /// Returns `true` once a message has been received for every round.
+    pub fn is_done(&self) -> bool {
+        self.challenges.len() == self.num_rounds
+    }
+
+    pub fn challenges(&self) -> &[u64] {
+        &self.challenges
+    }
+
+    /// Checks the message of the current round against the running claim.
+    ///
+    /// # Returns
+    ///
+    /// The challenge for the next round, or `None` if the message is rejected.
+    pub fn receive(&mut self, message: &RoundMessage) -> Option<u64> {
+        let field = self.field;
+        if self.is_done() || message.evaluations.len() != DEGREE as usize + 1 {
+            return None;
+        }
+        let sum = field.add(message.evaluations[0], message.evaluations[1]);
+        if sum != self.claim {
+            debug!(
+                round = self.challenges.len(),
+                "g(0) + g(1) = {} but the claim is {}", sum, self.claim
+            );
+            return None;
+        }
+        self.transcript
+            .absorb("sumcheck round", &message.evaluations);
+        let challenge = self
+            .transcript
+            .squeeze_below("sumcheck challenge", field.modulus());
+        let points: Vec<u64> = (0..=DEGREE).collect();
+        self.claim = Polynomial::interpolate(&points, &message.evaluations, field)
+            .evaluate(challenge, field);
+        self.challenges.push(challenge);
+        Some(challenge)
+    }
+
+    /// Runs the final check: the last claim must be the value of the polynomial at the
+    /// challenges.
+    pub fn finish(&self, polynomial: &ConstraintPolynomial) -> bool {
+        self.is_done() && polynomial.evaluate(&self.challenges) == self.claim
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circuit(x: u64, y: u64) -> Builder {
+        // x * x == y
+        let mut builder = Builder::new();
+        let x_node = builder.init();
+        let y_node = builder.init();
+        let square = builder.mul(x_node.clone(), x_node);
+        builder.assert_equal(square, y_node);
+        builder.fill_nodes(vec![x, y]);
+        builder
+    }
+
+    #[test]
+    fn test_false_claim_is_rejected() {
+        let field = Field::new(2_147_483_647);
+        let builder = circuit(3, 10);
+        let mut transcript = Transcript::new("sumcheck");
+        let polynomial = ConstraintPolynomial::new(&builder, field, &mut transcript);
+        let prover = Prover::new(&polynomial);
+        assert_ne!(prover.claimed_sum(), 0);
+
+        // claiming the sum is zero fails in the first round...
+        let mut verifier = Verifier::new(polynomial.num_vars(), 0, field, transcript.clone());
+        assert_eq!(verifier.receive(&prover.message()), None);
+
+        // ...and shifting the message to match the claim fails at the end
+        let mut prover = Prover::new(&polynomial);
+        let mut verifier = Verifier::new(polynomial.num_vars(), 0, field, transcript);
+        let mut message = prover.message();
+        let shift = field.neg(prover.claimed_sum());
+        message.evaluations[0] = field.add(message.evaluations[0], shift);
+        prover.fix(verifier.receive(&message).unwrap());
+        while !verifier.is_done() {
+            let challenge = verifier.receive(&prover.message());
+            match challenge {
+                Some(challenge) => prover.fix(challenge),
+                None => return,
+            }
+        }
+        assert!(!verifier.finish(&polynomial));
+    }
+}
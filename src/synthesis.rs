@@ -0,0 +1,196 @@
+//! Synthesizing a circuit twice: once for its shape, once for a witness.
+//!
+//! Proof systems in the style of bellman or arkworks describe a circuit as a type whose
+//! `synthesize` method adds its gates and constraints. The method runs twice. During setup the
+//! circuit carries no values, and synthesis only fixes the shape: which gates exist and how they
+//! are wired, from which keys are derived. When proving, the same method runs again on a circuit
+//! that carries the values, and this second pass produces the witness.
+//!
+//! `synthesize` is the first pass and `generate_witness` the second. The second pass checks that
+//! it built exactly the shape of the first one: a circuit whose gates depend on its values would
+//! produce witnesses that do not fit the keys of the setup, which is a classic bug.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::synthesis::{generate_witness, synthesize, Circuit};
+//!
+//! /// Knowledge of `x` with `x^3 + x + 5 = y` for a public `y`.
+//! struct Cubic {
+//!     x: Option<u64>,
+//!     y: Option<u64>,
+//! }
+//!
+//! impl Circuit for Cubic {
+//!     fn input_values(&self) -> Option<Vec<u64>> {
+//!         Some(vec![self.y?, self.x?])
+//!     }
+//!
+//!     fn synthesize(&self, builder: &mut Builder) {
+//!         let y = builder.init();
+//!         let x = builder.init_private();
+//!         let square = builder.mul(x.clone(), x.clone());
+//!         let cube = builder.mul(square, x.clone());
+//!         let sum = builder.add(cube, x);
+//!         let five = builder.constant(5);
+//!         let result = builder.add(sum, five);
+//!         builder.assert_equal(result, y);
+//!     }
+//! }
+//!
+//! // setup: no values, only the shape
+//! let shape = synthesize(&Cubic { x: None, y: None });
+//! assert_eq!(shape.constraints().count(), 5);
+//!
+//! // proving: the same circuit with values
+//! let result = generate_witness(&shape, &Cubic { x: Some(3), y: Some(35) }).unwrap();
+//! assert!(result.satisfied);
+//! ```
+use crate::batch::WitnessResult;
+use crate::evaluator::Evaluator;
+use crate::field::Field;
+use crate::Builder;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use tracing::info;
+
+/// A circuit that can be synthesized with or without values.
+pub trait Circuit {
+    /// The field the circuit computes in, `None` for plain `u64` arithmetic.
+    fn field(&self) -> Option<Field> {
+        None
+    }
+
+    /// The values of the inputs in order of creation, `None` when the circuit carries no values.
+    fn input_values(&self) -> Option<Vec<u64>>;
+
+    /// Adds the inputs, gates and constraints of the circuit to an empty builder. What is added
+    /// must not depend on the values the circuit carries.
+    fn synthesize(&self, builder: &mut Builder);
+}
+
+/// Why `generate_witness` failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SynthesisError {
+    /// The circuit carries no values to compute a witness from.
+    MissingValues,
+    /// The circuit carries a different number of values than it has inputs.
+    InputCount { expected: usize, found: usize },
+    /// Synthesizing with values built a different circuit than the shape.
+    ShapeMismatch,
+}
+
+impl Display for SynthesisError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SynthesisError::MissingValues => write!(f, "the circuit carries no values"),
+            SynthesisError::InputCount { expected, found } => {
+                write!(f, "expected {} input values, found {}", expected, found)
+            }
+            SynthesisError::ShapeMismatch => {
+                write!(f, "the circuit built with values differs from its shape")
+            }
+        }
+    }
+}
+
+impl Error for SynthesisError {}
+
+/// Runs the first pass: synthesizes the shape of a circuit, without evaluating anything.
+///
+/// # Arguments
+///
+/// * `circuit`: The circuit, whose values are ignored and usually absent.
+///
+/// # Returns
+///
+/// A builder holding the gates and constraints, with no values assigned.
+pub fn synthesize(circuit: &impl Circuit) -> Builder {
+    let mut builder = match circuit.field() {
+        Some(field) => Builder::new_in_field(field),
+        None => Builder::new(),
+    };
+    circuit.synthesize(&mut builder);
+    info!(
+        nodes = builder.full_graph.len(),
+        constraints = builder.constraints.len(),
+        "synthesized circuit shape"
+    );
+    builder
+}
+
+/// Runs the second pass: synthesizes the circuit again and computes its witness from the values
+/// it carries.
+///
+/// # Arguments
+///
+/// * `shape`: The result of `synthesize` at setup.
+/// * `circuit`: The circuit carrying the input values.
+///
+/// # Returns
+///
+/// The witness, laid out like `Builder::witness` for the shape, the outputs and whether every
+/// constraint holds.
+///
+/// # Errors
+///
+/// Fails if the circuit carries no or the wrong number of values, or if the circuit it builds
+/// differs from the shape.
+pub fn generate_witness(
+    shape: &Builder,
+    circuit: &impl Circuit,
+) -> Result<WitnessResult, SynthesisError> {
+    let values = circuit
+        .input_values()
+        .ok_or(SynthesisError::MissingValues)?;
+    let rebuilt = synthesize(circuit);
+    if rebuilt.field != shape.field || rebuilt.structure_words() != shape.structure_words() {
+        return Err(SynthesisError::ShapeMismatch);
+    }
+    if values.len() != rebuilt.inputs.len() {
+        return Err(SynthesisError::InputCount {
+            expected: rebuilt.inputs.len(),
+            found: values.len(),
+        });
+    }
+    Ok(Evaluator::new(&rebuilt).evaluate(&values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Multiplies its input by itself as often as its value says, so its shape depends on it.
+    struct ValueDependent {
+        x: Option<u64>,
+    }
+
+    impl Circuit for ValueDependent {
+        fn input_values(&self) -> Option<Vec<u64>> {
+            self.x.map(|x| vec![x])
+        }
+
+        fn synthesize(&self, builder: &mut Builder) {
+            let x = builder.init();
+            let mut power = x.clone();
+            for _ in 0..self.x.unwrap_or(1) {
+                power = builder.mul(power, x.clone());
+            }
+            builder.output(power);
+        }
+    }
+
+    #[test]
+    fn test_value_dependent_shapes_are_rejected() {
+        let shape = synthesize(&ValueDependent { x: None });
+        let result = generate_witness(&shape, &ValueDependent { x: Some(1) }).unwrap();
+        assert_eq!(result.outputs, vec![1]);
+        assert_eq!(
+            generate_witness(&shape, &ValueDependent { x: Some(2) }),
+            Err(SynthesisError::ShapeMismatch)
+        );
+        assert_eq!(
+            generate_witness(&shape, &ValueDependent { x: None }),
+            Err(SynthesisError::MissingValues)
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Gadgets defined once over parameters.
+//!
+//! Many gadgets come in families: a range check for every width, a hash for every number of
+//! rounds. A `Template` defines such a family once, as a function from parameters to a
+//! sub-circuit with inputs and outputs, and `Template::instantiate` places an instance with
+//! concrete parameters into a builder through `Builder::call`. The sub-circuit of each set of
+//! parameters is built once and cached, so a gadget used a thousand times with the same width is
+//! constructed once and copied after that.
+//!
+//! Instances are created in a scope named after the template and its parameters, so reports
+//! such as `profile_breakdown` and `estimate_cost` attribute their gates to the instance.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::template::Template;
+//!
+//! // x^n by repeated multiplication, for a parameter n
+//! let power = Template::new("power", |b: &mut Builder, n: &u32| {
+//!     let x = b.init();
+//!     let mut result = x.clone();
+//!     for _ in 1..*n {
+//!         result = b.mul(result, x.clone());
+//!     }
+//!     b.output(result);
+//! });
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let cube = power.instantiate(&mut builder, &3, vec![x.clone()]).remove(0);
+//! let ninth = power.instantiate(&mut builder, &3, vec![cube]).remove(0);
+//! let square = power.instantiate(&mut builder, &2, vec![x]).remove(0);
+//! builder.output(ninth.clone());
+//! builder.output(square.clone());
+//!
+//! assert_eq!(builder.evaluate(vec![2]), vec![512, 4]);
+//! assert_eq!(power.cached_instances(), 2);
+//! assert_eq!(builder.scope_of(&square), "power(2)");
+//! ```
+use crate::field::Field;
+use crate::{Builder, Node};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::rc::Rc;
+use tracing::debug;
+
+/// Builds the sub-circuit of a template into an empty builder for the given parameters.
+pub type TemplateFn<P> = dyn Fn(&mut Builder, &P);
+
+/// The sub-circuits of a template built so far, by parameters and field.
+type Cache<P> = RefCell<HashMap<(P, Option<Field>), Rc<Builder>>>;
+
+/// A family of gadgets over parameters of type `P`, see the module documentation.
+pub struct Template<P> {
+    name: String,
+    build: Rc<TemplateFn<P>>,
+    cache: Cache<P>,
+}
+
+impl<P: Clone + Eq + Hash + Debug> Template<P> {
+    /// Defines a template.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name of the scope instances are created in, followed by the parameters.
+    /// * `build`: Adds the inputs, gates and outputs of the gadget for some parameters to an
+    ///   empty builder. It must only depend on the parameters, since its result is reused.
+    pub fn new(name: &str, build: impl Fn(&mut Builder, &P) + 'static) -> Self {
+        Template {
+            name: name.to_string(),
+            build: Rc::new(build),
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the sub-circuit for `params` in `field`, building it on first use.
+    pub fn circuit(&self, params: &P, field: Option<Field>) -> Rc<Builder> {
+        let key = (params.clone(), field);
+        if let Some(circuit) = self.cache.borrow().get(&key) {
+            return circuit.clone();
+        }
+        let mut circuit = match field {
+            Some(field) => Builder::new_in_field(field),
+            None => Builder::new(),
+        };
+        (self.build)(&mut circuit, params);
+        debug!(template = %self.name, params = ?params, gates = circuit.full_graph.len(), "built template instance");
+        let circuit = Rc::new(circuit);
+        self.cache.borrow_mut().insert(key, circuit.clone());
+        circuit
+    }
+
+    /// Adds an instance of the template to `builder`.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder`: The builder to add the instance to. The sub-circuit is built in its field.
+    /// * `params`: The parameters of the instance.
+    /// * `inputs`: Nodes of `builder` to use as the inputs of the instance, in order.
+    ///
+    /// # Returns
+    ///
+    /// The nodes corresponding to the outputs of the instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of nodes does not match the number of inputs of the instance.
+    pub fn instantiate(&self, builder: &mut Builder, params: &P, inputs: Vec<Node>) -> Vec<Node> {
+        let circuit = self.circuit(params, builder.field());
+        let params = format!("{:?}", params);
+        // tuples print their own parentheses
+        let scope = match params.starts_with('(') {
+            true => format!("{}{}", self.name, params),
+            false => format!("{}({})", self.name, params),
+        };
+        builder.scope(&scope, |b| b.call(&circuit, inputs))
+    }
+
+    /// Returns the number of sub-circuits built and cached so far.
+    pub fn cached_instances(&self) -> usize {
+        self.cache.borrow().len()
+    }
+
+    /// Drops every cached sub-circuit.
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_instances_are_cached_per_parameters_and_field() {
+        let builds = Rc::new(Cell::new(0));
+        let counter = builds.clone();
+        // a width-bit decomposition repeated for some rounds
+        let template = Template::new(
+            "bits",
+            move |b: &mut Builder, (width, rounds): &(usize, usize)| {
+                counter.set(counter.get() + 1);
+                let x = b.init();
+                for _ in 0..*rounds {
+                    let bits = crate::gadgets::to_bits(b, &x, *width);
+                    b.output(bits[0].clone());
+                }
+            },
+        );
+
+        let field = Field::new(101);
+        let mut builder = Builder::new_in_field(field);
+        let x = builder.init();
+        for _ in 0..3 {
+            template.instantiate(&mut builder, &(4, 2), vec![x.clone()]);
+        }
+        let low = template
+            .instantiate(&mut builder, &(3, 1), vec![x])
+            .remove(0);
+        assert_eq!(builds.get(), 2);
+        assert_eq!(builder.scope_of(&low), "bits(3, 1)");
+
+        template.circuit(&(4, 2), None);
+        assert_eq!((builds.get(), template.cached_instances()), (3, 3));
+        template.clear_cache();
+        assert_eq!(template.cached_instances(), 0);
+
+        builder.fill_nodes(vec![5]);
+        assert!(builder.check_constraints());
+        assert_eq!(builder.value(&low), Some(1));
+    }
+}
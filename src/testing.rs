@@ -0,0 +1,307 @@
+//! Random circuits for fuzzing and differential testing.
+//!
+//! `random_circuit` builds an arbitrary circuit together with inputs that satisfy it and the
+//! outputs it should produce, computed independently of `Builder::fill_nodes`. The shape of the
+//! circuit is controlled by a `CircuitConfig`, the randomness by a seeded `TestRng`, so a failing
+//! case can be reproduced from its seed.
+//!
+//! ```
+//! use arithmetic_circuit::testing::{random_circuit, CircuitConfig, TestRng};
+//!
+//! let mut rng = TestRng::new(7);
+//! for _ in 0..10 {
+//!     let mut random = random_circuit(&mut rng, &CircuitConfig::default());
+//!     random.circuit.fill_nodes(random.inputs.clone());
+//!     assert!(random.circuit.check_constraints());
+//!     assert_eq!(random.circuit.evaluate(random.inputs), random.outputs);
+//! }
+//! ```
+use crate::hash::mix;
+use crate::{Builder, Node};
+use std::fmt::{Debug, Formatter};
+
+#[cfg(feature = "proptest")]
+pub mod strategies;
+
+/// A small seeded random number generator (splitmix64). It is not suitable for cryptography.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRng {
+    state: u64,
+}
+
+impl TestRng {
+    pub fn new(seed: u64) -> Self {
+        TestRng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mix(self.state)
+    }
+
+    /// Returns a value in `0..bound`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bound` is zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            panic!("bound must be positive");
+        }
+        self.next_u64() % bound
+    }
+
+    /// Returns `true` with a probability of `percent` percent.
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+/// The shape of the circuits generated by `random_circuit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitConfig {
+    /// Number of public inputs.
+    pub inputs: usize,
+    /// Number of private inputs, created after the public ones.
+    pub private_inputs: usize,
+    /// Number of constants available to the first layer of gates.
+    pub constants: usize,
+    /// Number of gate layers. Every gate uses a node of the previous layer, so this is the
+    /// depth of the circuit.
+    pub depth: usize,
+    /// Number of gates per layer.
+    pub width: usize,
+    /// Percentage of gates that are multiplications, the rest are additions.
+    pub mul_percent: u64,
+    /// Maximum number of gates using the same node as an operand.
+    pub max_fan_out: usize,
+    /// Inputs and constants are drawn from `0..=max_value`.
+    pub max_value: u64,
+    /// Number of gates additionally asserted equal to a constant holding their value.
+    pub assertions: usize,
+}
+
+impl Default for CircuitConfig {
+    fn default() -> Self {
+        CircuitConfig {
+            inputs: 2,
+            private_inputs: 1,
+            constants: 2,
+            depth: 4,
+            width: 3,
+            mul_percent: 50,
+            max_fan_out: 3,
+            max_value: 10,
+            assertions: 1,
+        }
+    }
+}
+
+/// A generated circuit together with a satisfying assignment.
+#[derive(Clone)]
+pub struct RandomCircuit {
+    pub circuit: Builder,
+    /// Values for all inputs in order of creation, as expected by `Builder::fill_nodes`.
+    pub inputs: Vec<u64>,
+    /// The values the outputs take for `inputs`.
+    pub outputs: Vec<u64>,
+}
+
+impl Debug for RandomCircuit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let gates: Vec<String> = (0..self.circuit.full_graph.len())
+            .map(|id| self.circuit.describe_gate(id))
+            .collect();
+        f.debug_struct("RandomCircuit")
+            .field("gates", &gates)
+            .field("inputs", &self.inputs)
+            .field("outputs", &self.outputs)
+            .finish()
+    }
+}
+
+/// Generates a random circuit and inputs satisfying it.
+///
+/// Gates whose value would overflow a `u64` for the chosen inputs use the other operation
+/// instead, or multiply by a new constant `1` if both overflow, so the circuit can always be
+/// evaluated on `inputs`. The gates of the last layer are the outputs.
+///
+/// # Arguments
+///
+/// * `rng`: Source of randomness.
+/// * `config`: The shape of the circuit.
+///
+/// # Panics
+///
+/// Panics if the config has no inputs and constants, a depth or width of zero, or a maximum
+/// fan-out of zero.
+pub fn random_circuit(rng: &mut TestRng, config: &CircuitConfig) -> RandomCircuit {
+    if config.inputs + config.private_inputs + config.constants == 0
+        || config.depth == 0
+        || config.width == 0
+        || config.max_fan_out == 0
+    {
+        panic!("circuit config does not allow any gates");
+    }
+    let mut generator = Generator {
+        rng,
+        config,
+        circuit: Builder::new(),
+        nodes: vec![],
+        values: vec![],
+        fan_out: vec![],
+    };
+
+    let mut inputs = vec![];
+    for index in 0..config.inputs + config.private_inputs {
+        let node = match index < config.inputs {
+            true => generator.circuit.init(),
+            false => generator.circuit.init_private(),
+        };
+        let value = generator.value();
+        inputs.push(value);
+        generator.push(node, value);
+    }
+    (0..config.constants).for_each(|_| {
+        generator.constant();
+    });
+
+    let mut layer: Vec<usize> = (0..generator.nodes.len()).collect();
+    for _ in 0..config.depth {
+        let earlier = generator.nodes.len();
+        layer = (0..config.width)
+            .map(|_| generator.gate(&layer, earlier))
+            .collect();
+    }
+
+    let first_gate = config.inputs + config.private_inputs + config.constants;
+    let gates: Vec<usize> = (first_gate..generator.nodes.len()).collect();
+    for _ in 0..config.assertions {
+        let gate = generator.rng.pick(&gates);
+        let constant = generator.circuit.constant(generator.values[gate]);
+        let node = generator.nodes[gate].clone();
+        generator.circuit.assert_equal(node, constant);
+    }
+    for index in &layer {
+        let node = generator.nodes[*index].clone();
+        generator.circuit.output(node);
+    }
+
+    RandomCircuit {
+        outputs: layer.iter().map(|index| generator.values[*index]).collect(),
+        circuit: generator.circuit,
+        inputs,
+    }
+}
+
+/// The state of `random_circuit`. Nodes are tracked together with their value for the chosen
+/// inputs and the number of gates using them.
+struct Generator<'a> {
+    rng: &'a mut TestRng,
+    config: &'a CircuitConfig,
+    circuit: Builder,
+    nodes: Vec<Node>,
+    values: Vec<u64>,
+    fan_out: Vec<usize>,
+}
+
+impl Generator<'_> {
+    fn push(&mut self, node: Node, value: u64) -> usize {
+        self.nodes.push(node);
+        self.values.push(value);
+        self.fan_out.push(0);
+        self.nodes.len() - 1
+    }
+
+    /// Returns a value in `0..=max_value`.
+    fn value(&mut self) -> u64 {
+        match self.config.max_value.checked_add(1) {
+            Some(bound) => self.rng.below(bound),
+            None => self.rng.next_u64(),
+        }
+    }
+
+    fn constant(&mut self) -> usize {
+        let value = self.value();
+        let node = self.circuit.constant(value);
+        self.push(node, value)
+    }
+
+    /// Picks one of `candidates` that can still be used as an operand, or a new constant if
+    /// there is none.
+    fn operand(&mut self, candidates: impl Iterator<Item = usize>) -> usize {
+        let available: Vec<usize> = candidates
+            .filter(|index| self.fan_out[*index] < self.config.max_fan_out)
+            .collect();
+        let operand = match available.is_empty() {
+            true => self.constant(),
+            false => self.rng.pick(&available),
+        };
+        self.fan_out[operand] += 1;
+        operand
+    }
+
+    /// Creates a gate using a node of the previous layer and any node created before the
+    /// current layer.
+    fn gate(&mut self, previous: &[usize], earlier: usize) -> usize {
+        let a = self.operand(previous.iter().copied());
+        let b = self.operand(0..earlier);
+        let (x, y) = (self.values[a], self.values[b]);
+        let (a, b) = (self.nodes[a].clone(), self.nodes[b].clone());
+        let mul = self.rng.chance(self.config.mul_percent);
+        let (node, value) = match (x.checked_mul(y), x.checked_add(y)) {
+            (Some(product), _) if mul => (self.circuit.mul(a, b), product),
+            (_, Some(sum)) => (self.circuit.add(a, b), sum),
+            (Some(product), None) => (self.circuit.mul(a, b), product),
+            (None, None) => {
+                let one = self.circuit.constant(1);
+                (self.circuit.mul(a, one), x)
+            }
+        };
+        self.push(node, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_circuits_are_satisfied_by_their_inputs() {
+        let mut rng = TestRng::new(1);
+        let config = CircuitConfig {
+            depth: 12,
+            width: 5,
+            mul_percent: 80,
+            max_value: u64::MAX,
+            ..CircuitConfig::default()
+        };
+        for _ in 0..50 {
+            let mut random = random_circuit(&mut rng, &config);
+            random.circuit.fill_nodes(random.inputs.clone());
+            assert!(random.circuit.check_constraints());
+            assert_eq!(random.circuit.outputs().len(), config.width);
+            assert_eq!(random.circuit.evaluate(random.inputs), random.outputs);
+        }
+    }
+
+    #[test]
+    fn test_fan_out_is_bounded() {
+        let mut rng = TestRng::new(2);
+        let config = CircuitConfig {
+            max_fan_out: 1,
+            assertions: 0,
+            ..CircuitConfig::default()
+        };
+        let random = random_circuit(&mut rng, &config);
+        assert!(random
+            .circuit
+            .full_graph
+            .iter()
+            .all(|node| node.children.len() <= 1));
+    }
+}
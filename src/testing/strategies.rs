@@ -0,0 +1,102 @@
+//! proptest strategies for circuits and their inputs, enabled by the `proptest` feature.
+//!
+//! `inputs` generates values for the inputs of a gadget, so a property like "the constraints
+//! hold for every input" is a few lines of test code:
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::testing::strategies;
+//! use proptest::prelude::*;
+//!
+//! fn square_gadget() -> Builder {
+//!     let mut builder = Builder::new();
+//!     let x = builder.init();
+//!     let square = builder.mul(x.clone(), x.clone());
+//!     let expected = builder.mul(x.clone(), x);
+//!     builder.assert_equal(square, expected);
+//!     builder
+//! }
+//!
+//! proptest!(|(inputs in strategies::inputs(&square_gadget(), 1 << 16))| {
+//!     let mut gadget = square_gadget();
+//!     gadget.fill_nodes(inputs);
+//!     prop_assert!(gadget.check_constraints());
+//! });
+//! ```
+use crate::testing::{random_circuit, CircuitConfig, RandomCircuit, TestRng};
+use crate::Builder;
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+/// Generates values in `0..=max_value` for every input of `circuit`, in order of creation.
+pub fn inputs(circuit: &Builder, max_value: u64) -> impl Strategy<Value = Vec<u64>> {
+    vec(0..=max_value, circuit.inputs.len())
+}
+
+/// Generates circuit configs of up to 6 layers of up to 4 gates. Shrinking moves towards
+/// smaller circuits.
+pub fn configs() -> impl Strategy<Value = CircuitConfig> {
+    (
+        (0..3usize, 0..3usize, 0..3usize),
+        (1..=6usize, 1..=4usize),
+        (0..=100u64, 1..=4usize, 0..3usize),
+    )
+        .prop_filter(
+            "a circuit needs an input or a constant",
+            |((inputs, private_inputs, constants), _, _)| inputs + private_inputs + constants > 0,
+        )
+        .prop_map(
+            |(
+                (inputs, private_inputs, constants),
+                (depth, width),
+                (mul_percent, max_fan_out, assertions),
+            )| {
+                CircuitConfig {
+                    inputs,
+                    private_inputs,
+                    constants,
+                    depth,
+                    width,
+                    mul_percent,
+                    max_fan_out,
+                    assertions,
+                    ..CircuitConfig::default()
+                }
+            },
+        )
+}
+
+/// Generates circuits of the given shape, see `random_circuit`.
+pub fn circuits(config: CircuitConfig) -> impl Strategy<Value = RandomCircuit> {
+    any::<u64>().prop_map(move |seed| random_circuit(&mut TestRng::new(seed), &config))
+}
+
+/// Generates circuits of any shape produced by `configs`.
+pub fn any_circuit() -> impl Strategy<Value = RandomCircuit> {
+    configs().prop_flat_map(circuits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn three_inputs() -> Builder {
+        random_circuit(&mut TestRng::new(3), &CircuitConfig::default()).circuit
+    }
+
+    proptest! {
+        #[test]
+        fn test_generated_circuits_are_satisfied(random in any_circuit()) {
+            let mut circuit = random.circuit.clone();
+            circuit.fill_nodes(random.inputs.clone());
+            prop_assert!(circuit.check_constraints());
+            prop_assert_eq!(circuit.evaluate(random.inputs), random.outputs);
+        }
+
+        #[test]
+        fn test_inputs_match_the_circuit(inputs in inputs(&three_inputs(), 5)) {
+            prop_assert_eq!(inputs.len(), 3);
+            prop_assert!(inputs.iter().all(|value| *value <= 5));
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! A Fiat–Shamir transcript simulation.
+//!
+//! In an interactive protocol the verifier sends random challenges after seeing the prover's
+//! messages. The Fiat–Shamir transformation removes the interaction: both parties feed every
+//! message into a transcript and derive the challenges by hashing it. A prover cannot choose
+//! its messages after the challenge because the challenge depends on them.
+//!
+//! The hash used here is deliberately plain and offers no security, see `hash`.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::transcript::Transcript;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let _ = builder.mul(x.clone(), x);
+//!
+//! // the prover and the verifier absorb the same messages in the same order...
+//! let mut prover = Transcript::new("example");
+//! prover.absorb_circuit(&builder);
+//! prover.absorb("public inputs", &[3]);
+//! let mut verifier = Transcript::new("example");
+//! verifier.absorb_circuit(&builder);
+//! verifier.absorb("public inputs", &[3]);
+//!
+//! // ...and therefore agree on the challenges without talking to each other
+//! assert_eq!(prover.squeeze("alpha"), verifier.squeeze("alpha"));
+//! ```
+use crate::hash::{hash_str, hash_words};
+use crate::Builder;
+use tracing::debug;
+
+/// Absorbs protocol messages and squeezes pseudo-random challenges from them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    state: u64,
+}
+
+impl Transcript {
+    /// Creates an empty transcript.
+    ///
+    /// # Arguments
+    ///
+    /// * `label`: Separates transcripts of different protocols, so their challenges differ.
+    pub fn new(label: &str) -> Self {
+        Transcript {
+            state: hash_words(&[hash_str(label)]),
+        }
+    }
+
+    /// Absorbs a labelled message into the transcript.
+    ///
+    /// # Arguments
+    ///
+    /// * `label`: Names the message. The same values under a different label give other challenges.
+    /// * `values`: The message.
+    pub fn absorb(&mut self, label: &str, values: &[u64]) {
+        let mut words = vec![self.state, hash_str(label), values.len() as u64];
+        words.extend_from_slice(values);
+        self.state = hash_words(&words);
+        debug!("transcript absorbed {}: {:?}", label, values);
+    }
+
+    /// Absorbs the shape of a circuit: its operations, constraints, private inputs and outputs.
+    ///
+    /// This binds every later challenge to the circuit being proven.
+    pub fn absorb_circuit(&mut self, circuit: &Builder) {
+        self.absorb("circuit", &circuit.structure_words());
+    }
+
+    /// Squeezes a challenge out of the transcript.
+    ///
+    /// Squeezing also updates the transcript, so two squeezes in a row give different challenges.
+    pub fn squeeze(&mut self, label: &str) -> u64 {
+        let challenge = hash_words(&[self.state, hash_str(label)]);
+        self.state = hash_words(&[self.state, challenge]);
+        debug!("transcript squeezed {}: {}", label, challenge);
+        challenge
+    }
+
+    /// Squeezes a challenge in the range `0..bound`, e.g. an element of a field or an index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bound` is zero.
+    pub fn squeeze_below(&mut self, label: &str, bound: u64) -> u64 {
+        if bound == 0 {
+            panic!("cannot squeeze a challenge below zero");
+        }
+        self.squeeze(label) % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_challenges_depend_on_messages() {
+        let mut a = Transcript::new("test");
+        let mut b = Transcript::new("test");
+        a.absorb("message", &[1, 2, 3]);
+        b.absorb("message", &[1, 2, 4]);
+        assert_ne!(a.clone().squeeze("c"), b.squeeze("c"));
+
+        let first = a.squeeze("c");
+        let second = a.squeeze("c");
+        assert_ne!(first, second);
+        assert!(a.squeeze_below("index", 10) < 10);
+    }
+
+    #[test]
+    fn test_challenges_depend_on_circuit() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let x_squared = builder.mul(x.clone(), x.clone());
+        let mut before = Transcript::new("test");
+        before.absorb_circuit(&builder);
+
+        let _ = builder.add(x_squared, x);
+        let mut after = Transcript::new("test");
+        after.absorb_circuit(&builder);
+        assert_ne!(before.squeeze("c"), after.squeeze("c"));
+    }
+}
@@ -0,0 +1,201 @@
+//! Truth tables of boolean circuits.
+//!
+//! A circuit over bits encodes logic in arithmetic: `a AND b` is `a * b`, `a OR b` is
+//! `a + b - a * b` and `a XOR b` is `a + b - 2 * a * b`, and `x * x = x` forces `x` to be a bit.
+//! `Builder::truth_table` evaluates such a circuit on every combination of bits and tabulates the
+//! outputs and whether the constraints hold, which shows the encoding at work. The table prints
+//! as markdown, with rows counting up in binary and the first input as the most significant bit.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::gadgets::assert_bit;
+//!
+//! let mut builder = Builder::new_in_field(Field::new(101));
+//! let a = builder.init();
+//! let b = builder.init();
+//! assert_bit(&mut builder, &a);
+//! assert_bit(&mut builder, &b);
+//! let and = builder.mul(a.clone(), b.clone());
+//! let sum = builder.add(a, b);
+//! let or = builder.sub(sum, and.clone());
+//! builder.label(&and, "both");
+//! builder.label(&or, "either");
+//! builder.output(and);
+//! builder.output(or);
+//!
+//! let table = builder.truth_table().unwrap();
+//! assert_eq!(
+//!     table.to_string(),
+//!     "| x0 | x1 | both | either | satisfied |\n\
+//!      |---|---|---|---|---|\n\
+//!      | 0 | 0 | 0 | 0 | yes |\n\
+//!      | 0 | 1 | 0 | 1 | yes |\n\
+//!      | 1 | 0 | 0 | 1 | yes |\n\
+//!      | 1 | 1 | 1 | 1 | yes |\n"
+//! );
+//! ```
+use crate::builder::EXHAUSTIVE_LIMIT;
+use crate::enums::{Constraints, Operation};
+use crate::evaluator::Evaluator;
+use crate::{Builder, NodeId};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// One combination of input bits and what the circuit makes of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruthRow {
+    /// The input bits, in order of creation.
+    pub inputs: Vec<u64>,
+    /// The values of the outputs, in the order they were registered.
+    pub outputs: Vec<u64>,
+    /// Whether every constraint holds.
+    pub satisfied: bool,
+}
+
+/// The truth table of a circuit, see `Builder::truth_table`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruthTable {
+    /// The names of the inputs, see the `names` module.
+    pub inputs: Vec<String>,
+    /// The names of the outputs.
+    pub outputs: Vec<String>,
+    /// One row per combination of input bits, counting up in binary.
+    pub rows: Vec<TruthRow>,
+}
+
+impl Display for TruthTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let columns = self.inputs.len() + self.outputs.len() + 1;
+        let header: Vec<&str> = self
+            .inputs
+            .iter()
+            .chain(&self.outputs)
+            .map(String::as_str)
+            .chain(["satisfied"])
+            .collect();
+        writeln!(f, "| {} |", header.join(" | "))?;
+        writeln!(f, "|{}", "---|".repeat(columns))?;
+        for row in &self.rows {
+            let cells: Vec<String> = row
+                .inputs
+                .iter()
+                .chain(&row.outputs)
+                .map(u64::to_string)
+                .chain([if row.satisfied { "yes" } else { "no" }.to_string()])
+                .collect();
+            writeln!(f, "| {} |", cells.join(" | "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Why `Builder::truth_table` could not tabulate a circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TruthTableError {
+    /// The input with this name is not constrained to be a bit.
+    NotBoolean { input: String },
+    /// The circuit has more inputs than `EXHAUSTIVE_LIMIT` combinations allow.
+    TooManyInputs { inputs: usize },
+}
+
+impl Display for TruthTableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TruthTableError::NotBoolean { input } => {
+                write!(f, "input {} is not constrained to be a bit", input)
+            }
+            TruthTableError::TooManyInputs { inputs } => write!(
+                f,
+                "{} inputs exceed the limit of {} combinations",
+                inputs, EXHAUSTIVE_LIMIT
+            ),
+        }
+    }
+}
+
+impl Error for TruthTableError {}
+
+impl Builder {
+    /// Evaluates the circuit on every combination of input bits.
+    ///
+    /// # Returns
+    ///
+    /// The table of outputs and constraint satisfaction, see the `truth_table` module.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an input is not constrained to be a bit by `x * x == x`, as asserted by
+    /// `gadgets::assert_bit` and `BoolWire::new`, or if there are more than `EXHAUSTIVE_LIMIT`
+    /// combinations. Equalities lowered with `lower_equalities` are not recognized.
+    pub fn truth_table(&self) -> Result<TruthTable, TruthTableError> {
+        if let Some(input) = self.inputs.iter().find(|id| !self.is_bit_constrained(**id)) {
+            return Err(TruthTableError::NotBoolean {
+                input: self.wire_name(*input),
+            });
+        }
+        let count = self.inputs.len();
+        if count >= 64 || 1u64 << count > EXHAUSTIVE_LIMIT {
+            return Err(TruthTableError::TooManyInputs { inputs: count });
+        }
+        let evaluator = Evaluator::new(self);
+        let rows = (0..1u64 << count)
+            .map(|combination| {
+                let inputs: Vec<u64> = (0..count)
+                    .map(|position| (combination >> (count - 1 - position)) & 1)
+                    .collect();
+                let result = evaluator.evaluate(&inputs);
+                TruthRow {
+                    inputs,
+                    outputs: result.outputs,
+                    satisfied: result.satisfied,
+                }
+            })
+            .collect();
+        Ok(TruthTable {
+            inputs: self.inputs.iter().map(|id| self.wire_name(*id)).collect(),
+            outputs: self.outputs.iter().map(|id| self.wire_name(*id)).collect(),
+            rows,
+        })
+    }
+
+    /// Returns `true` if some constraint asserts `node * node == node`.
+    fn is_bit_constrained(&self, node: NodeId) -> bool {
+        let is_square = |id: NodeId| self.full_graph[id].operation == Operation::Mul(node, node);
+        self.constraints.iter().any(|constraint| match *constraint {
+            Constraints::Eq(a, b) => (a == node && is_square(b)) || (b == node && is_square(a)),
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Field;
+    use crate::gadgets::assert_bit;
+
+    #[test]
+    fn test_unsatisfied_rows_and_unconstrained_inputs() {
+        let mut builder = Builder::new_in_field(Field::new(7));
+        let a = builder.init();
+        let b = builder.init();
+        assert_bit(&mut builder, &b);
+        let nand_input = builder.mul(a.clone(), b);
+        let zero = builder.constant(0);
+        builder.assert_equal(nand_input, zero);
+        assert_eq!(
+            builder.truth_table(),
+            Err(TruthTableError::NotBoolean {
+                input: "x0".to_string()
+            })
+        );
+
+        assert_bit(&mut builder, &a);
+        let table = builder.truth_table().unwrap();
+        let satisfied: Vec<bool> = table.rows.iter().map(|row| row.satisfied).collect();
+        assert_eq!(satisfied, vec![true, true, true, false]);
+        assert!(table.outputs.is_empty());
+        assert!(table.to_string().ends_with("| 1 | 1 | no |\n"));
+    }
+}
@@ -0,0 +1,340 @@
+//! Well-formedness checks of the graph.
+//!
+//! Circuits built through the `Builder` API are always well formed. Graphs that were edited by
+//! hand or assembled from other data can break the invariants evaluation relies on, and
+//! `Builder::validate` lists every broken invariant instead of failing somewhere in
+//! `fill_nodes`. Graphs whose gates depend on each other in a cycle cannot be evaluated at all;
+//! `Builder::check_acyclic` rejects them with an error naming the wires on the cycle.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let square = builder.mul(x.clone(), x);
+//! builder.output(square);
+//! assert!(builder.validate().is_empty());
+//! ```
+use crate::enums::Operation;
+use crate::Builder;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// A broken invariant of the graph. Nodes are given by id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The node at `position` in the graph has a different id.
+    IdMismatch { position: usize, id: usize },
+    /// A gate uses an operand that is not in the graph.
+    DanglingOperand { node: usize, operand: usize },
+    /// A node lists a child that is not in the graph.
+    DanglingChild { node: usize, child: usize },
+    /// A node lists a child that does not use it as an operand.
+    UnrelatedChild { node: usize, child: usize },
+    /// A gate is missing from the children of one of its operands.
+    MissingChild { node: usize, child: usize },
+    /// A gate uses an operand with a larger id, so evaluating in order of id fails.
+    OperandAfterNode { node: usize, operand: usize },
+    /// The gates depend on each other in a cycle, listed in order of dependency.
+    Cycle { nodes: Vec<usize> },
+    /// A constraint refers to a node that is not in the graph.
+    DanglingConstraint { constraint: usize, node: usize },
+    /// An output is not in the graph.
+    DanglingOutput { node: usize },
+    /// An input node is not registered as an input.
+    UnregisteredInput { node: usize },
+    /// A registered input, or private input, is not an input node.
+    NotAnInput { node: usize },
+    /// A node is registered as an input more than once.
+    DuplicateInput { node: usize },
+    /// A private input is not registered as an input.
+    UnregisteredPrivateInput { node: usize },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::IdMismatch { position, id } => {
+                write!(f, "node at position {} has id {}", position, id)
+            }
+            Violation::DanglingOperand { node, operand } => {
+                write!(f, "node {} uses missing operand {}", node, operand)
+            }
+            Violation::DanglingChild { node, child } => {
+                write!(f, "node {} lists missing child {}", node, child)
+            }
+            Violation::UnrelatedChild { node, child } => {
+                write!(
+                    f,
+                    "node {} lists child {} which does not use it",
+                    node, child
+                )
+            }
+            Violation::MissingChild { node, child } => {
+                write!(f, "node {} does not list its child {}", node, child)
+            }
+            Violation::OperandAfterNode { node, operand } => {
+                write!(f, "node {} uses operand {} created after it", node, operand)
+            }
+            Violation::Cycle { nodes } => write!(f, "nodes {:?} form a cycle", nodes),
+            Violation::DanglingConstraint { constraint, node } => {
+                write!(
+                    f,
+                    "constraint {} refers to missing node {}",
+                    constraint, node
+                )
+            }
+            Violation::DanglingOutput { node } => write!(f, "output {} is missing", node),
+            Violation::UnregisteredInput { node } => {
+                write!(f, "input node {} is not registered as an input", node)
+            }
+            Violation::NotAnInput { node } => {
+                write!(f, "node {} is registered as an input but is not one", node)
+            }
+            Violation::DuplicateInput { node } => {
+                write!(f, "node {} is registered as an input twice", node)
+            }
+            Violation::UnregisteredPrivateInput { node } => {
+                write!(f, "private input {} is not registered as an input", node)
+            }
+        }
+    }
+}
+
+/// A cyclic dependency between gates, found by `Builder::check_acyclic`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError {
+    /// Names of the wires on the cycle, each depending on the next and the last on the first.
+    pub wires: Vec<String>,
+}
+
+impl Display for CycleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "cyclic dependency between wires: {} -> {}",
+            self.wires.join(" -> "),
+            self.wires[0]
+        )
+    }
+}
+
+impl Error for CycleError {}
+
+impl Builder {
+    /// Checks that no gate depends on itself, directly or through other gates.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use arithmetic_circuit::builder::Builder;
+    /// let mut builder = Builder::new();
+    /// let x = builder.init();
+    /// let y = builder.init();
+    /// let sum = builder.add(x, y);
+    /// builder.output(sum);
+    /// assert!(builder.check_acyclic().is_ok());
+    /// ```
+    pub fn check_acyclic(&self) -> Result<(), CycleError> {
+        match self.find_cycle() {
+            None => Ok(()),
+            Some(nodes) => Err(CycleError {
+                wires: nodes.into_iter().map(|id| self.wire_name(id)).collect(),
+            }),
+        }
+    }
+
+    /// Checks the invariants of the graph.
+    ///
+    /// # Returns
+    ///
+    /// Every broken invariant, empty if the graph is well formed.
+    pub fn validate(&self) -> Vec<Violation> {
+        let count = self.full_graph.len();
+        let exists = |id: usize| id < count;
+        let mut violations = vec![];
+        for (position, node) in self.full_graph.iter().enumerate() {
+            if node.id != position {
+                violations.push(Violation::IdMismatch {
+                    position,
+                    id: node.id,
+                });
+            }
+            for child in &node.children {
+                if !exists(*child) {
+                    violations.push(Violation::DanglingChild {
+                        node: position,
+                        child: *child,
+                    });
+                } else if !self
+                    .operands(&self.full_graph[*child].operation)
+                    .contains(&position)
+                {
+                    violations.push(Violation::UnrelatedChild {
+                        node: position,
+                        child: *child,
+                    });
+                }
+            }
+            for operand in self.operands(&node.operation) {
+                if !exists(operand) {
+                    violations.push(Violation::DanglingOperand {
+                        node: position,
+                        operand,
+                    });
+                    continue;
+                }
+                if !self.full_graph[operand].children.contains(&position) {
+                    violations.push(Violation::MissingChild {
+                        node: operand,
+                        child: position,
+                    });
+                }
+                if operand >= position {
+                    violations.push(Violation::OperandAfterNode {
+                        node: position,
+                        operand,
+                    });
+                }
+            }
+        }
+        if let Some(nodes) = self.find_cycle() {
+            violations.push(Violation::Cycle { nodes });
+        }
+
+        for (constraint, ids) in self.constraints.iter().map(|c| c.ids()).enumerate() {
+            ids.into_iter().filter(|id| !exists(*id)).for_each(|node| {
+                violations.push(Violation::DanglingConstraint { constraint, node })
+            });
+        }
+        self.outputs
+            .iter()
+            .filter(|id| !exists(**id))
+            .for_each(|node| violations.push(Violation::DanglingOutput { node: *node }));
+
+        for (position, id) in self.inputs.iter().enumerate() {
+            if !exists(*id) || self.full_graph[*id].operation != Operation::Input {
+                violations.push(Violation::NotAnInput { node: *id });
+            }
+            if self.inputs[..position].contains(id) {
+                violations.push(Violation::DuplicateInput { node: *id });
+            }
+        }
+        self.full_graph
+            .iter()
+            .filter(|node| node.operation == Operation::Input && !self.inputs.contains(&node.id))
+            .for_each(|node| violations.push(Violation::UnregisteredInput { node: node.id }));
+        self.private_inputs
+            .iter()
+            .filter(|id| !self.inputs.contains(id))
+            .for_each(|node| violations.push(Violation::UnregisteredPrivateInput { node: *node }));
+        violations
+    }
+
+    /// Looks for gates that depend on each other in a cycle.
+    ///
+    /// # Returns
+    ///
+    /// The ids of the nodes on a cycle, each using the next one as an operand and the last one
+    /// using the first, or `None` if the graph is acyclic. Operands outside of the graph are
+    /// ignored.
+    pub(crate) fn find_cycle(&self) -> Option<Vec<usize>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            Unvisited,
+            OnPath,
+            Done,
+        }
+        let count = self.full_graph.len();
+        let mut state = vec![State::Unvisited; count];
+        for start in 0..count {
+            if state[start] != State::Unvisited {
+                continue;
+            }
+            // depth first search with an explicit stack of (node, next operand to visit)
+            let mut path: Vec<(usize, usize)> = vec![(start, 0)];
+            state[start] = State::OnPath;
+            while let Some((node, next)) = path.last_mut() {
+                let node = *node;
+                let operands = self.operands(&self.full_graph[node].operation);
+                let Some(operand) = operands.get(*next).copied() else {
+                    state[node] = State::Done;
+                    path.pop();
+                    continue;
+                };
+                *next += 1;
+                if operand >= count {
+                    continue;
+                }
+                match state[operand] {
+                    State::Unvisited => {
+                        state[operand] = State::OnPath;
+                        path.push((operand, 0));
+                    }
+                    State::OnPath => {
+                        let from = path.iter().position(|(id, _)| *id == operand).unwrap();
+                        return Some(path[from..].iter().map(|(id, _)| *id).collect());
+                    }
+                    State::Done => {}
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the ids of the operands of an operation, the nodes a hint reads for hints.
+    pub(crate) fn operands(&self, operation: &Operation) -> Vec<usize> {
+        match *operation {
+            Operation::Add(a, b) | Operation::Mul(a, b) => vec![a, b],
+            Operation::Hint(index) => self
+                .hints
+                .get(index)
+                .map_or(vec![], |hint| hint.inputs.clone()),
+            Operation::Input | Operation::Constant(_) => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Constraints;
+
+    #[test]
+    fn test_broken_graph() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let y = builder.init();
+        let sum = builder.add(x.clone(), y.clone());
+        let product = builder.mul(sum.clone(), x);
+        builder.output(product.clone());
+
+        // make the sum depend on the product
+        builder.full_graph[sum.id].operation = Operation::Add(product.id, y.id);
+        builder.full_graph[product.id].children.push(sum.id);
+        builder.constraints.push(Constraints::Eq(0, 9));
+        builder.inputs.pop();
+
+        let violations = builder.validate();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::UnrelatedChild { node: 0, child: 2 },
+                Violation::OperandAfterNode {
+                    node: 2,
+                    operand: 3
+                },
+                Violation::Cycle { nodes: vec![2, 3] },
+                Violation::DanglingConstraint {
+                    constraint: 2,
+                    node: 9
+                },
+                Violation::UnregisteredInput { node: 1 },
+            ]
+        );
+        assert_eq!(
+            builder.check_acyclic().unwrap_err().to_string(),
+            "cyclic dependency between wires: w2 -> w3 -> w2"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Dividing by vanishing polynomials.
+//!
+//! A polynomial `P(x)` takes the value `t` on every point of a set `S` exactly when `P(x) - t` is
+//! divisible by the vanishing polynomial `Z(x) = Π_{s ∈ S} (x - s)`. This is why proof systems
+//! can check many constraints at once by checking one divisibility: the QAP of a circuit (see
+//! the `qap` module) encodes the error of every constraint as the value of
+//! `A(x) * B(x) - C(x)` at the constraint's point, so the witness satisfies the circuit exactly
+//! when the division by `Z(x)` leaves no remainder. `Builder::constraint_quotient` carries out
+//! this division and, when it fails, reports which constraints are to blame.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//!
+//! // y == x * x + 1
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let y = builder.init();
+//! let one = builder.constant(1);
+//! let square = builder.mul(x.clone(), x);
+//! let sum = builder.add(square, one);
+//! builder.assert_equal(sum, y);
+//!
+//! let field = Field::new(101);
+//! builder.fill_nodes(vec![3, 10]);
+//! assert!(builder.constraint_quotient(field).is_exact());
+//!
+//! builder.fill_nodes(vec![3, 11]);
+//! let quotient = builder.constraint_quotient(field);
+//! assert!(!quotient.is_exact());
+//! // the equality assertion is the third constraint
+//! assert_eq!(quotient.failing, vec![2]);
+//! ```
+use crate::field::Field;
+use crate::polynomial::Polynomial;
+use crate::qap::Qap;
+use crate::r1cs::R1cs;
+use crate::Builder;
+
+/// The result of dividing `P(x) - target` by the vanishing polynomial of a set of points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quotient {
+    pub quotient: Polynomial,
+    pub remainder: Polynomial,
+    /// Positions of the points where `P(x)` differs from the target.
+    pub failing: Vec<usize>,
+}
+
+impl Quotient {
+    /// Returns `true` if the division leaves no remainder, so `P(x)` takes the target value on
+    /// every point.
+    pub fn is_exact(&self) -> bool {
+        self.remainder.is_zero()
+    }
+}
+
+/// Divides `P(x) - target` by the vanishing polynomial of `points`.
+///
+/// # Arguments
+///
+/// * `polynomial`: The polynomial `P(x)`.
+/// * `target`: The value `P(x)` should take on every point.
+/// * `points`: The points, distinct elements of the field.
+/// * `field`: The field to compute in.
+pub fn divide_by_vanishing(
+    polynomial: &Polynomial,
+    target: u64,
+    points: &[u64],
+    field: Field,
+) -> Quotient {
+    let shifted = polynomial.sub(&Polynomial::new(vec![field.reduce(target)]), field);
+    let (quotient, remainder) = shifted.div_rem(&Polynomial::vanishing(points, field), field);
+    let failing = points
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| shifted.evaluate(**x, field) != 0)
+        .map(|(position, _)| position)
+        .collect();
+    Quotient {
+        quotient,
+        remainder,
+        failing,
+    }
+}
+
+impl Builder {
+    /// Divides `A(x) * B(x) - C(x)` of the circuit's QAP by its target polynomial.
+    ///
+    /// The points of the QAP are the rows of `R1cs::from_builder`: the constraints in order of
+    /// creation, followed by one row per constant. The failing positions of the result are
+    /// these rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit was not filled, or the field has fewer elements than the R1CS has
+    /// rows.
+    pub fn constraint_quotient(&self, field: Field) -> Quotient {
+        let witness: Vec<u64> = self
+            .witness()
+            .expect("the circuit must be filled before computing its quotient")
+            .iter()
+            .map(|value| field.reduce(*value))
+            .collect();
+        let qap = Qap::from_r1cs(&R1cs::from_builder(self), field);
+        let (a, b, c) = qap.combine(&witness);
+        divide_by_vanishing(&a.mul(&b, field).sub(&c, field), 0, &qap.points, field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remainder_reports_points() {
+        let field = Field::new(101);
+        // x^2 is 4 at -2 and 2, but not at 3
+        let square = Polynomial::new(vec![0, 0, 1]);
+        let exact = divide_by_vanishing(&square, 4, &[2, 99], field);
+        assert!(exact.is_exact());
+        assert_eq!(exact.quotient, Polynomial::new(vec![1]));
+
+        let inexact = divide_by_vanishing(&square, 4, &[2, 3, 99], field);
+        assert!(!inexact.is_exact());
+        assert_eq!(inexact.failing, vec![1]);
+    }
+}
@@ -0,0 +1,151 @@
+//! Checking witnesses without a builder.
+//!
+//! A verifier does not need to know how a circuit was built, only its constraints. `verify`
+//! checks a witness vector against a `ConstraintSystem`, typically parsed from a file written by
+//! whoever built the circuit, and reports every constraint the witness violates together with the
+//! public inputs it claims, so a program that only verifies needs nothing but the text of the
+//! system and the witness.
+//!
+//! ```
+//! use arithmetic_circuit::constraint_system::{ConstraintSystem, VerifyError};
+//! use arithmetic_circuit::verifier::verify;
+//!
+//! // x * x = w2 and w2 = 9, over the witness [1, x, w2, 9]
+//! let system: ConstraintSystem = "variables 4\npublic 1\nmul 1 1 2\neq 2 3\nconst 3 9\n"
+//!     .parse()
+//!     .unwrap();
+//!
+//! let result = verify(&system, &[1, 3, 9, 9]);
+//! assert!(result.is_valid());
+//! assert_eq!(result.public_inputs, vec![3]);
+//!
+//! let result = verify(&system, &[1, 2, 4, 8]);
+//! assert_eq!(
+//!     result.errors,
+//!     vec![VerifyError::Violated(1), VerifyError::Violated(2)]
+//! );
+//! assert_eq!(
+//!     result.to_string(),
+//!     "invalid witness\nconstraint 1 does not hold: eq 2 3\nconstraint 2 does not hold: const 3 9\n"
+//! );
+//! ```
+use crate::constraint_system::{ConstraintSystem, VerifyError};
+use std::fmt::{Display, Formatter};
+
+/// The outcome of `verify`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationResult {
+    /// The public inputs the witness claims, empty if the witness has the wrong length.
+    pub public_inputs: Vec<u64>,
+    /// Every reason the witness is rejected, in order, empty if it is valid.
+    pub errors: Vec<VerifyError>,
+    /// The constraints of `errors`, to describe them.
+    system: ConstraintSystem,
+}
+
+impl VerificationResult {
+    /// Returns `true` if the witness satisfies the constraint system.
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the public inputs of a valid witness.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first reason the witness is rejected.
+    pub fn into_result(self) -> Result<Vec<u64>, VerifyError> {
+        match self.errors.first() {
+            Some(error) => Err(*error),
+            None => Ok(self.public_inputs),
+        }
+    }
+}
+
+impl Display for VerificationResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_valid() {
+            return writeln!(f, "valid witness, public inputs {:?}", self.public_inputs);
+        }
+        writeln!(f, "invalid witness")?;
+        for error in &self.errors {
+            match error {
+                VerifyError::Violated(index) => {
+                    writeln!(f, "{}: {}", error, self.system.constraints()[*index])?
+                }
+                _ => writeln!(f, "{}", error)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks `witness` against every constraint of `system`.
+///
+/// Unlike `ConstraintSystem::verify`, which stops at the first violated constraint, this checks
+/// all of them.
+///
+/// # Arguments
+///
+/// * `system`: The constraints, over a witness laid out like `Builder::witness`.
+/// * `witness`: The values: a leading `1`, the public inputs, then every other wire.
+///
+/// # Returns
+///
+/// The public inputs and every reason the witness is rejected. A witness of the wrong length is
+/// not checked any further.
+pub fn verify(system: &ConstraintSystem, witness: &[u64]) -> VerificationResult {
+    let mut result = VerificationResult {
+        public_inputs: vec![],
+        errors: vec![],
+        system: system.clone(),
+    };
+    if witness.len() != system.num_variables() {
+        result.errors.push(VerifyError::Length {
+            expected: system.num_variables(),
+            found: witness.len(),
+        });
+        return result;
+    }
+    if witness[0] != 1 {
+        result.errors.push(VerifyError::One);
+    }
+    result.public_inputs = witness[1..=system.num_public()].to_vec();
+    result
+        .errors
+        .extend(system.violations(witness).map(VerifyError::Violated));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Builder;
+    use crate::field::Field;
+
+    #[test]
+    fn test_agrees_with_the_builder() {
+        let mut builder = Builder::new_in_field(Field::new(97));
+        let x = builder.init();
+        let secret = builder.init_private();
+        let product = builder.mul(x.clone(), secret);
+        let twelve = builder.constant(12);
+        builder.assert_equal(product, twelve);
+        builder.fill_nodes(vec![3, 4]);
+        let witness = builder.witness().unwrap();
+
+        let system: ConstraintSystem = builder.constraint_system().to_string().parse().unwrap();
+        assert_eq!(verify(&system, &witness).into_result(), Ok(vec![3]));
+
+        let mut forged = witness.clone();
+        forged[0] = 2;
+        forged[1] = 5;
+        let result = verify(&system, &forged);
+        assert_eq!(result.errors[0], VerifyError::One);
+        assert!(result.errors.len() > 1);
+        assert!(matches!(
+            verify(&system, &witness[1..]).errors[..],
+            [VerifyError::Length { .. }]
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+//! Walking the graph in evaluation order.
+//!
+//! `Builder::iter_topo` yields every node after its operands, and `Builder::visit` calls a
+//! `Visitor` once per node with a callback for each kind of gate. Analyses and exporters can be
+//! written on top of them without access to the builder's internals.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::visit::Visitor;
+//! use arithmetic_circuit::NodeId;
+//!
+//! // prints every node as an expression over the inputs
+//! #[derive(Default)]
+//! struct Printer {
+//!     expressions: Vec<String>,
+//! }
+//!
+//! impl Visitor for Printer {
+//!     fn visit_input(&mut self, id: NodeId, _private: bool) {
+//!         self.expressions.push(format!("x{}", id));
+//!     }
+//!     fn visit_constant(&mut self, _id: NodeId, value: u64) {
+//!         self.expressions.push(value.to_string());
+//!     }
+//!     fn visit_add(&mut self, _id: NodeId, a: NodeId, b: NodeId) {
+//!         let expression = format!("({} + {})", self.expressions[a], self.expressions[b]);
+//!         self.expressions.push(expression);
+//!     }
+//!     fn visit_mul(&mut self, _id: NodeId, a: NodeId, b: NodeId) {
+//!         let expression = format!("{} * {}", self.expressions[a], self.expressions[b]);
+//!         self.expressions.push(expression);
+//!     }
+//! }
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let one = builder.constant(1);
+//! let sum = builder.add(x.clone(), one);
+//! let product = builder.mul(sum, x);
+//!
+//! let mut printer = Printer::default();
+//! builder.visit(&mut printer);
+//! assert_eq!(printer.expressions[product.id], "(x0 + 1) * x0");
+//! ```
+use crate::enums::Operation;
+use crate::{Builder, Node, NodeId};
+
+/// Callbacks for every kind of node, called by `Builder::visit` in evaluation order.
+///
+/// All callbacks do nothing by default, so a visitor only implements the ones it needs.
+pub trait Visitor {
+    /// Called for an input, `private` if it is not part of the public statement.
+    fn visit_input(&mut self, _id: NodeId, _private: bool) {}
+
+    /// Called for a constant.
+    fn visit_constant(&mut self, _id: NodeId, _value: u64) {}
+
+    /// Called for an addition gate with operands `a` and `b`.
+    fn visit_add(&mut self, _id: NodeId, _a: NodeId, _b: NodeId) {}
+
+    /// Called for a multiplication gate with operands `a` and `b`.
+    fn visit_mul(&mut self, _id: NodeId, _a: NodeId, _b: NodeId) {}
+
+    /// Called for a hint reading the nodes `inputs`.
+    fn visit_hint(&mut self, _id: NodeId, _inputs: &[NodeId]) {}
+}
+
+impl Builder {
+    /// Returns an iterator over all nodes in evaluation order, every node after its operands.
+    pub fn iter_topo(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.full_graph.iter().map(|node| (node.id, node))
+    }
+
+    /// Calls the visitor's callback for every node in evaluation order.
+    ///
+    /// # Arguments
+    ///
+    /// * `visitor`: The visitor to call.
+    pub fn visit(&self, visitor: &mut impl Visitor) {
+        for (id, node) in self.iter_topo() {
+            match node.operation {
+                Operation::Input => visitor.visit_input(id, self.private_inputs.contains(&id)),
+                Operation::Constant(value) => visitor.visit_constant(id, value),
+                Operation::Add(a, b) => visitor.visit_add(id, a, b),
+                Operation::Mul(a, b) => visitor.visit_mul(id, a, b),
+                Operation::Hint(index) => visitor.visit_hint(id, self.hint_inputs(index)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operands_are_visited_first() {
+        struct Order {
+            seen: Vec<NodeId>,
+        }
+        impl Visitor for Order {
+            fn visit_input(&mut self, id: NodeId, private: bool) {
+                assert_eq!(private, id == 1);
+                self.seen.push(id);
+            }
+            fn visit_mul(&mut self, id: NodeId, a: NodeId, b: NodeId) {
+                assert!(self.seen.contains(&a) && self.seen.contains(&b));
+                self.seen.push(id);
+            }
+        }
+
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let y = builder.init_private();
+        let product = builder.mul(x, y);
+        builder.mul(product.clone(), product);
+
+        let mut order = Order { seen: vec![] };
+        builder.visit(&mut order);
+        assert_eq!(order.seen, vec![0, 1, 2, 3]);
+        assert_eq!(builder.iter_topo().count(), 4);
+    }
+}
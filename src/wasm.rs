@@ -0,0 +1,227 @@
+//! Export of a circuit as a WebAssembly module.
+//!
+//! `Builder::to_wasm` compiles the circuit (see the `compile` module) and encodes the program as
+//! a standalone `.wasm` binary without any imports. The module exports a single function
+//! `evaluate` taking one `i64` per input, in order of creation, and returning one `i64` per
+//! output. Registers of the program become locals of the function.
+//!
+//! ```
+//! use arithmetic_circuit::builder::Builder;
+//!
+//! let mut builder = Builder::new();
+//! let x = builder.init();
+//! let square = builder.mul(x.clone(), x);
+//! builder.output(square);
+//!
+//! let module = builder.to_wasm();
+//! assert_eq!(&module[..4], b"\0asm");
+//! // std::fs::write("square.wasm", module).unwrap();
+//! ```
+//!
+//! In a browser or in node the module can then be used like this:
+//!
+//! ```js
+//! const { instance } = await WebAssembly.instantiate(bytes);
+//! instance.exports.evaluate(7n); // 49n
+//! ```
+use crate::compile::{CompiledCircuit, Instruction};
+use crate::Builder;
+
+const I64: u8 = 0x7e;
+
+impl Builder {
+    /// Compiles the computation of the outputs into a WebAssembly module.
+    ///
+    /// Without a field, values wrap around on overflow instead of panicking like `fill_nodes`.
+    ///
+    /// # Returns
+    ///
+    /// The bytes of the module.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit is over a field whose prime does not fit into 32 bits, since
+    /// products would then overflow a 64 bit WebAssembly integer before being reduced, or if an
+    /// output depends on a hint, since hints run Rust code the module cannot call.
+    pub fn to_wasm(&self) -> Vec<u8> {
+        self.compile().to_wasm()
+    }
+}
+
+impl CompiledCircuit {
+    /// Encodes the program as a WebAssembly module, see `Builder::to_wasm`.
+    pub fn to_wasm(&self) -> Vec<u8> {
+        if self.field.is_some_and(|field| field.modulus() >= 1 << 32) {
+            panic!("WebAssembly export only supports fields with a prime below 2^32");
+        }
+        if self
+            .instructions
+            .iter()
+            .any(|instruction| matches!(instruction, Instruction::Hint { .. }))
+        {
+            panic!("circuits with hints cannot be exported to WebAssembly");
+        }
+        // the parameters are locals 0..inputs, the registers follow them
+        let register = |r: usize| r + self.inputs;
+        let mut code = vec![];
+        for instruction in &self.instructions {
+            let dst = match *instruction {
+                Instruction::Load { dst, input } => {
+                    local_get(&mut code, input);
+                    self.reduce(&mut code);
+                    dst
+                }
+                Instruction::Const { dst, value } => {
+                    code.push(0x42); // i64.const
+                    signed(&mut code, value as i64);
+                    dst
+                }
+                Instruction::Add { dst, a, b } | Instruction::Mul { dst, a, b } => {
+                    local_get(&mut code, register(a));
+                    local_get(&mut code, register(b));
+                    code.push(match instruction {
+                        Instruction::Add { .. } => 0x7c, // i64.add
+                        _ => 0x7e,                       // i64.mul
+                    });
+                    self.reduce(&mut code);
+                    dst
+                }
+                Instruction::Hint { .. } => unreachable!("hints are rejected above"),
+            };
+            code.push(0x21); // local.set
+            unsigned(&mut code, register(dst) as u64);
+        }
+        self.outputs
+            .iter()
+            .for_each(|output| local_get(&mut code, register(*output)));
+        code.push(0x0b); // end
+
+        let mut function_type = vec![0x01, 0x60];
+        vector(&mut function_type, &vec![I64; self.inputs]);
+        vector(&mut function_type, &vec![I64; self.outputs.len()]);
+
+        let mut body = vec![];
+        match self.registers {
+            0 => body.push(0x00),
+            registers => {
+                body.push(0x01);
+                unsigned(&mut body, registers as u64);
+                body.push(I64);
+            }
+        }
+        body.extend(code);
+        let mut code_section = vec![0x01];
+        unsigned(&mut code_section, body.len() as u64);
+        code_section.extend(body);
+
+        let mut export = vec![0x01];
+        vector(&mut export, b"evaluate");
+        export.extend([0x00, 0x00]); // function 0
+
+        let mut module = b"\0asm".to_vec();
+        module.extend([0x01, 0x00, 0x00, 0x00]);
+        section(&mut module, 1, &function_type);
+        section(&mut module, 3, &[0x01, 0x00]);
+        section(&mut module, 7, &export);
+        section(&mut module, 10, &code_section);
+        module
+    }
+
+    /// Reduces the value on top of the stack if the circuit is over a field.
+    fn reduce(&self, code: &mut Vec<u8>) {
+        if let Some(field) = self.field {
+            code.push(0x42); // i64.const
+            signed(code, field.modulus() as i64);
+            code.push(0x82); // i64.rem_u
+        }
+    }
+}
+
+fn local_get(code: &mut Vec<u8>, local: usize) {
+    code.push(0x20);
+    unsigned(code, local as u64);
+}
+
+/// Appends a vector of single byte items, prefixed with its length.
+fn vector(out: &mut Vec<u8>, items: &[u8]) {
+    unsigned(out, items.len() as u64);
+    out.extend(items);
+}
+
+fn section(out: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    out.push(id);
+    unsigned(out, contents.len() as u64);
+    out.extend(contents);
+}
+
+/// Appends `value` in unsigned LEB128.
+fn unsigned(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        match value {
+            0 => return out.push(byte),
+            _ => out.push(byte | 0x80),
+        }
+    }
+}
+
+/// Appends `value` in signed LEB128.
+fn signed(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let sign_bit_clear = byte & 0x40 == 0;
+        if (value == 0 && sign_bit_clear) || (value == -1 && !sign_bit_clear) {
+            return out.push(byte);
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leb128() {
+        let encode = |value: i64| {
+            let mut out = vec![];
+            signed(&mut out, value);
+            out
+        };
+        assert_eq!(encode(5), vec![0x05]);
+        assert_eq!(encode(64), vec![0xc0, 0x00]);
+        assert_eq!(encode(-1), vec![0x7f]);
+        assert_eq!(encode(u64::MAX as i64), vec![0x7f]);
+        let mut out = vec![];
+        unsigned(&mut out, 624485);
+        assert_eq!(out, vec![0xe5, 0x8e, 0x26]);
+    }
+
+    #[test]
+    fn test_square_module() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let square = builder.mul(x.clone(), x);
+        builder.output(square);
+        let expected: Vec<u8> = [
+            &b"\0asm\x01\0\0\0"[..],
+            // type section: (func (param i64) (result i64))
+            &[0x01, 0x06, 0x01, 0x60, 0x01, 0x7e, 0x01, 0x7e],
+            // function section
+            &[0x03, 0x02, 0x01, 0x00],
+            // export section: "evaluate" is function 0
+            &[0x07, 0x0c, 0x01, 0x08],
+            b"evaluate",
+            &[0x00, 0x00],
+            // code section: one i64 local, r0 = x0, r0 = r0 * r0, return r0
+            &[0x0a, 0x13, 0x01, 0x11, 0x01, 0x01, 0x7e],
+            &[0x20, 0x00, 0x21, 0x01],
+            &[0x20, 0x01, 0x20, 0x01, 0x7e, 0x21, 0x01],
+            &[0x20, 0x01, 0x0b],
+        ]
+        .concat();
+        assert_eq!(builder.to_wasm(), expected);
+    }
+}
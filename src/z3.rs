@@ -0,0 +1,159 @@
+//! Soundness checks with the Z3 solver.
+//!
+//! With the `z3` feature, `Builder::prove_assertion` hands the script of `Builder::to_smtlib`
+//! to a `z3` executable found on the `PATH`, together with the negation of a property over the
+//! wires. Z3 either proves that every witness satisfying the constraints has the property, or
+//! returns a counterexample witness, mapped back to the nodes of the circuit. The feature adds
+//! no dependencies, only the executable is needed at runtime.
+//!
+//! A typical use is finding underconstrained circuits: if the output of a gadget should be
+//! determined by its inputs, assert that it equals the intended value and let Z3 look for a
+//! witness where it does not.
+//!
+//! ```no_run
+//! use arithmetic_circuit::builder::Builder;
+//! use arithmetic_circuit::field::Field;
+//! use arithmetic_circuit::z3::Z3Result;
+//!
+//! // the square root is hinted, and -3 is a second root of 9
+//! let mut builder = Builder::new_in_field(Field::new(97));
+//! let root = builder.hint(&[], |_| 3);
+//! let square = builder.mul(root.clone(), root);
+//! let nine = builder.constant(9);
+//! builder.assert_equal(square, nine);
+//!
+//! match builder.prove_assertion("(= w0 3)").unwrap() {
+//!     Z3Result::Sat(model) => assert_eq!(model[0], (0, 94)),
+//!     other => panic!("expected a counterexample, got {:?}", other),
+//! }
+//! ```
+use crate::{Builder, NodeId};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tracing::info;
+
+/// The answer of Z3 to `Builder::prove_assertion`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Z3Result {
+    /// A witness satisfies the constraints but violates the assertion: the value of every
+    /// input, gate and hint, by node id.
+    Sat(Vec<(NodeId, u64)>),
+    /// Every witness satisfying the constraints satisfies the assertion.
+    Unsat,
+    /// Z3 gave up, e.g. because the nonlinear arithmetic was too hard.
+    Unknown,
+}
+
+/// Why Z3 could not be asked.
+#[derive(Debug)]
+pub enum Z3Error {
+    /// The `z3` executable could not be run.
+    Io(std::io::Error),
+    /// Z3 answered with something other than a result, usually a syntax error in the assertion.
+    Solver(String),
+}
+
+impl Display for Z3Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Z3Error::Io(error) => write!(f, "could not run z3: {}", error),
+            Z3Error::Solver(output) => write!(f, "z3 failed: {}", output),
+        }
+    }
+}
+
+impl Error for Z3Error {}
+
+impl Builder {
+    /// Asks Z3 whether every witness satisfying the constraints satisfies `assertion`.
+    ///
+    /// # Arguments
+    ///
+    /// * `assertion`: An SMT-LIB2 boolean term over the wire names of `to_smtlib`, such as
+    ///   `(= w4 9)`.
+    ///
+    /// # Returns
+    ///
+    /// `Unsat` if the assertion always holds, or a counterexample.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the `z3` executable cannot be run or rejects the script.
+    pub fn prove_assertion(&self, assertion: &str) -> Result<Z3Result, Z3Error> {
+        let mut script = self.smtlib_assertions();
+        // get-model fails unless the answer is sat, but only the model's output is read then
+        script.push_str(&format!(
+            "(assert (not {}))\n(check-sat)\n(get-model)\n",
+            assertion
+        ));
+        let output = run_z3(&script)?;
+        let result = match output.lines().next().map(str::trim) {
+            Some("unsat") => Z3Result::Unsat,
+            Some("unknown") => Z3Result::Unknown,
+            Some("sat") => Z3Result::Sat(self.parse_model(&output)?),
+            _ => return Err(Z3Error::Solver(output)),
+        };
+        info!(?result, "z3 answered");
+        Ok(result)
+    }
+
+    /// Maps the `define-fun`s of a model back to node ids, sorted by id.
+    fn parse_model(&self, output: &str) -> Result<Vec<(NodeId, u64)>, Z3Error> {
+        let spaced = output.replace('(', " ( ").replace(')', " ) ");
+        let tokens: Vec<&str> = spaced.split_whitespace().collect();
+        let mut model = vec![];
+        for (i, token) in tokens.iter().enumerate() {
+            if *token != "define-fun" {
+                continue;
+            }
+            // define-fun <name> ( ) Int <value>
+            let (Some(name), Some(value)) = (tokens.get(i + 1), tokens.get(i + 5)) else {
+                return Err(Z3Error::Solver(output.to_string()));
+            };
+            let id = (0..self.full_graph.len()).find(|id| self.wire_name(*id) == *name);
+            match (id, value.parse()) {
+                (Some(id), Ok(value)) => model.push((id, value)),
+                _ => return Err(Z3Error::Solver(output.to_string())),
+            }
+        }
+        model.sort_unstable();
+        Ok(model)
+    }
+}
+
+/// Runs `z3` on a script and returns its output.
+fn run_z3(script: &str) -> Result<String, Z3Error> {
+    let mut child = Command::new("z3")
+        .args(["-in", "-smt2"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(Z3Error::Io)?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .map_err(Z3Error::Io)?;
+    let output = child.wait_with_output().map_err(Z3Error::Io)?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_model() {
+        let mut builder = Builder::new();
+        let x = builder.init();
+        let two = builder.constant(2);
+        let _ = builder.mul(x, two);
+        let output =
+            "sat\n(\n  (define-fun w2 () Int\n    14)\n  (define-fun x0 () Int\n    7)\n)\n";
+        assert_eq!(builder.parse_model(output).unwrap(), vec![(0, 7), (2, 14)]);
+        assert!(builder.parse_model("(define-fun y9 () Int 1)").is_err());
+    }
+}